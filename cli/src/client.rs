@@ -0,0 +1,70 @@
+//! Connection helpers for talking to the background daemon.
+
+use std::fs::OpenOptions;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reverse_ssh_core::{config::paths, ipc::IpcClient, CoreError};
+
+/// How long to wait for an auto-spawned daemon to start listening
+const SPAWN_TIMEOUT: Duration = Duration::from_secs(5);
+const SPAWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Connect to a running daemon, returning `None` if none is running.
+pub async fn connect() -> Result<Option<IpcClient>> {
+    match IpcClient::connect(paths::socket_file()).await {
+        Ok(client) => Ok(Some(client)),
+        Err(CoreError::DaemonNotRunning) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Connect to the daemon, spawning it in the background if it is not running.
+pub async fn connect_or_spawn() -> Result<IpcClient> {
+    if let Some(client) = connect().await? {
+        return Ok(client);
+    }
+
+    let log_path = paths::daemon_log_file();
+    std::fs::create_dir_all(paths::data_dir())?;
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .context("Failed to open daemon log file")?;
+
+    let exe = std::env::current_exe().context("Failed to locate rssh executable")?;
+    let mut child = Command::new(exe)
+        .arg("daemon")
+        .arg("run")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        // Own process group so Ctrl+C in this terminal does not reach the daemon
+        .process_group(0)
+        .spawn()
+        .context("Failed to spawn daemon")?;
+
+    let deadline = tokio::time::Instant::now() + SPAWN_TIMEOUT;
+    loop {
+        if let Some(client) = connect().await? {
+            return Ok(client);
+        }
+        if let Some(status) = child.try_wait()? {
+            anyhow::bail!(
+                "Daemon exited during startup ({}). See {}",
+                status,
+                log_path.display()
+            );
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!(
+                "Timed out waiting for daemon to start. See {}",
+                log_path.display()
+            );
+        }
+        tokio::time::sleep(SPAWN_POLL_INTERVAL).await;
+    }
+}
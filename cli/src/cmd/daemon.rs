@@ -0,0 +1,121 @@
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use reverse_ssh_core::{
+    config::{has_plaintext_passwords, init_config, migrate_plaintext_passwords, paths},
+    ipc::IpcServer,
+    metrics::{exporter, Metrics},
+    storage::SecretStore,
+    supervisor::SessionManager,
+};
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::client;
+
+/// Run the daemon in the foreground until it is told to shut down.
+///
+/// With `metrics_addr`, Prometheus metrics are also served over HTTP.
+pub async fn run(metrics_addr: Option<SocketAddr>) -> Result<()> {
+    let config = init_config()?;
+    let secrets = config.secrets.clone();
+
+    let (mut manager, handle) = SessionManager::new(config);
+
+    // The socket doubles as the single-instance lock: bind it before init
+    // restores sessions, so a second daemon fails without starting any
+    let server = IpcServer::bind(paths::socket_file(), handle.clone())?;
+
+    // Move plaintext profile passwords into the secret store before init
+    // restores sessions, so none reads a profile file while it is rewritten
+    let migration = tokio::task::spawn_blocking(move || {
+        if !has_plaintext_passwords() {
+            return;
+        }
+        match SecretStore::open(&secrets).and_then(|mut store| migrate_plaintext_passwords(&mut store)) {
+            Ok(moved) => {
+                for moved in moved {
+                    println!("Moved the password in {} to secret '{}'", moved.path.display(), moved.secret);
+                }
+            }
+            Err(e) => eprintln!("Profiles have plaintext passwords that cannot be moved to the secret store: {}", e),
+        }
+    });
+    migration.await?;
+
+    // Subscribe before init so restored sessions are counted
+    let metrics = metrics_addr.map(|_| Metrics::spawn(handle.subscribe()));
+    manager.init().await?;
+
+    let metrics_listener = match metrics_addr {
+        Some(addr) => Some(
+            TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("Failed to bind metrics listener on {}", addr))?,
+        ),
+        None => None,
+    };
+
+    println!("Daemon listening on {} (PID {})", server.path().display(), std::process::id());
+
+    if let (Some(listener), Some(metrics)) = (metrics_listener, metrics) {
+        println!("Serving metrics on http://{}/metrics", listener.local_addr()?);
+
+        let handle = handle.clone();
+        tokio::spawn(async move {
+            if let Err(e) = exporter::serve(listener, metrics, handle).await {
+                eprintln!("Metrics listener error: {}", e);
+            }
+        });
+    }
+
+    let manager_task = tokio::spawn(async move {
+        if let Err(e) = manager.run().await {
+            eprintln!("Session manager error: {}", e);
+        }
+    });
+
+    let mut terminate = signal(SignalKind::terminate())?;
+
+    tokio::select! {
+        result = server.run() => result?,
+        _ = tokio::signal::ctrl_c() => {
+            println!("Received interrupt, stopping all sessions...");
+            handle.shutdown().await?;
+        }
+        _ = terminate.recv() => {
+            println!("Received SIGTERM, stopping all sessions...");
+            handle.shutdown().await?;
+        }
+    }
+
+    let _ = manager_task.await;
+    println!("Daemon stopped.");
+    Ok(())
+}
+
+/// Ask a running daemon to stop all sessions and exit.
+pub async fn stop() -> Result<()> {
+    match client::connect().await? {
+        Some(mut client) => {
+            client.shutdown().await?;
+            println!("Daemon stopped.");
+        }
+        None => println!("Daemon is not running."),
+    }
+    Ok(())
+}
+
+/// Report whether a daemon is running.
+pub async fn status() -> Result<()> {
+    match client::connect().await? {
+        Some(mut client) => {
+            let sessions = client.status().await?;
+            println!("Daemon is running (PID {})", client.daemon_pid());
+            println!("Socket:   {}", paths::socket_file().display());
+            println!("Sessions: {}", sessions.len());
+        }
+        None => println!("Daemon is not running."),
+    }
+    Ok(())
+}
@@ -1,8 +1,4 @@
-use anyhow::{Result, Context};
-use reverse_ssh_core::{
-    config::init_config,
-    supervisor::SessionManager,
-};
+use anyhow::{bail, Result, Context};
 use uuid::Uuid;
 
 pub async fn run(session_id: String) -> Result<()> {
@@ -10,24 +6,45 @@ pub async fn run(session_id: String) -> Result<()> {
     let id = Uuid::parse_str(&session_id)
         .context("Invalid session ID format")?;
 
-    let config = init_config()?;
+    println!("Stopping session '{}'...", session_id);
+
+    #[cfg(unix)]
+    {
+        let Some(mut client) = crate::client::connect().await? else {
+            bail!("Failed to stop session: daemon is not running");
+        };
 
-    // Create and initialize session manager
-    let (mut manager, handle) = SessionManager::new(config);
-    manager.init().await?;
+        match client.stop(id).await {
+            Ok(_) => println!("Session stopped."),
+            Err(e) => eprintln!("Failed to stop session: {}", e),
+        }
+    }
 
-    // Run manager in background to process commands
-    tokio::spawn(async move {
-        let _ = manager.run().await;
-    });
+    #[cfg(not(unix))]
+    {
+        use reverse_ssh_core::{config::init_config, supervisor::SessionManager};
 
-    println!("Stopping session '{}'...", session_id);
+        let config = init_config()?;
+
+        // Create and initialize session manager
+        let (mut manager, handle) = SessionManager::new(config);
+        // Short-lived manager: neither restore nor record sessions
+        manager.set_persist_state(false);
+        manager.set_record_sessions(false);
+        manager.init().await?;
+
+        // Run manager in background to process commands
+        tokio::spawn(async move {
+            let _ = manager.run().await;
+        });
+
+        match handle.stop(id).await {
+            Ok(_) => println!("Session stopped."),
+            Err(e) => eprintln!("Failed to stop session: {}", e),
+        }
 
-    match handle.stop(id).await {
-        Ok(_) => println!("Session stopped."),
-        Err(e) => eprintln!("Failed to stop session: {}", e),
+        handle.shutdown().await?;
     }
 
-    handle.shutdown().await?;
     Ok(())
 }
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use chrono::{Local, Utc};
+use reverse_ssh_core::config::paths;
+use reverse_ssh_core::storage::{history, HistoryReport, HistoryStore};
+
+use crate::output::OutputFormat;
+
+pub async fn run(profile: String, window: String, limit: usize, format: OutputFormat) -> Result<()> {
+    let length = history::parse_window(&window)?;
+    let to = Utc::now();
+    let from = to - chrono::Duration::from_std(length)?;
+
+    // Without a database, JSON output is still a report: an empty one
+    let path = paths::history_file();
+    let store = match (path.exists(), format) {
+        (true, _) => HistoryStore::open(&path)?,
+        (false, OutputFormat::Json) => HistoryStore::open_in_memory()?,
+        (false, OutputFormat::Human) => {
+            println!("No session history recorded yet.");
+            return Ok(());
+        }
+    };
+
+    let report = store.report(&profile, from, to)?;
+
+    match format {
+        OutputFormat::Human => print_report(&report, &window, limit),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+fn print_report(report: &HistoryReport, window: &str, limit: usize) {
+    println!("History for '{}' over the last {}", report.profile_name, window);
+    println!();
+
+    match report.uptime_percent {
+        Some(uptime) => println!(
+            "Uptime:          {:.2}% ({} connected of {} running)",
+            uptime,
+            format_duration(report.connected_secs),
+            format_duration(report.active_secs)
+        ),
+        None => println!("Uptime:          - (no session ran)"),
+    }
+    println!("Outages:         {}", report.outages);
+    match report.mean_time_to_reconnect_secs {
+        Some(secs) => println!("Mean reconnect:  {}", format_duration(secs.round() as u64)),
+        None => println!("Mean reconnect:  -"),
+    }
+
+    if report.entries.is_empty() || limit == 0 {
+        return;
+    }
+
+    println!();
+    println!("Recent events:");
+    let skip = report.entries.len().saturating_sub(limit);
+    for entry in &report.entries[skip..] {
+        let session = entry.session_id.to_string();
+        print!(
+            "  {}  [{}]  {:<13}",
+            entry.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            &session[..8],
+            entry.kind.to_string()
+        );
+        match &entry.reason {
+            Some(reason) => println!("  {}", reason),
+            None => println!(),
+        }
+    }
+}
+
+/// `1d 2h`, `3h 4m`, `5m 6s` or `7s`
+fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes, seconds) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
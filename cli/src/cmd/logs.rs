@@ -1,9 +1,14 @@
-use anyhow::{Result, Context};
+use anyhow::{bail, Context, Result};
 use reverse_ssh_core::config::paths;
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-pub async fn run(session_id: Option<String>, follow: bool, lines: usize) -> Result<()> {
+/// How often to check a followed log for new lines
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub async fn run(session_id: Option<String>, profile: Option<String>, follow: bool, lines: usize) -> Result<()> {
     let logs_dir = paths::logs_dir();
 
     if !logs_dir.exists() {
@@ -11,55 +16,124 @@ pub async fn run(session_id: Option<String>, follow: bool, lines: usize) -> Resu
         return Ok(());
     }
 
-    if let Some(id) = session_id {
-        // Show logs for specific session
-        let log_file = logs_dir.join(format!("{}.log", id));
+    if let Some(name) = profile {
+        let log_file = paths::profile_log_file(&name);
         if log_file.exists() {
             show_log_file(&log_file, lines, follow).await?;
         } else {
-            println!("No logs found for session: {}", id);
+            println!("No logs found for profile: {}", name);
         }
-    } else {
-        // List available log files
-        let entries = fs::read_dir(&logs_dir)
-            .context("Failed to read logs directory")?;
-
-        let mut log_files: Vec<_> = entries
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map(|ext| ext == "log").unwrap_or(false))
-            .collect();
-
-        if log_files.is_empty() {
-            println!("No log files found.");
-            return Ok(());
+    } else if let Some(id) = session_id {
+        match find_session_log(&logs_dir, &id)? {
+            Some(log_file) => show_log_file(&log_file, lines, follow).await?,
+            None => println!("No logs found for session: {}", id),
         }
+    } else {
+        list_log_files(&logs_dir)?;
+    }
 
-        // Sort by modification time (newest first)
-        log_files.sort_by(|a, b| {
-            let time_a = a.metadata().and_then(|m| m.modified()).ok();
-            let time_b = b.metadata().and_then(|m| m.modified()).ok();
-            time_b.cmp(&time_a)
-        });
-
-        println!("Available log files:");
-        for entry in log_files {
-            let path = entry.path();
-            let name = path.file_stem().unwrap_or_default().to_string_lossy();
-            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
-            println!("  {} ({} bytes)", name, size);
+    Ok(())
+}
+
+/// Find a session log by full ID or unambiguous ID prefix
+fn find_session_log(logs_dir: &Path, id: &str) -> Result<Option<PathBuf>> {
+    let exact = logs_dir.join(format!("{}.log", id));
+    if exact.exists() {
+        return Ok(Some(exact));
+    }
+
+    let matches: Vec<PathBuf> = log_files_in(logs_dir)?
+        .into_iter()
+        .filter(|path| file_stem(path).starts_with(id))
+        .collect();
+
+    match matches.len() {
+        0 => Ok(None),
+        1 => Ok(matches.into_iter().next()),
+        n => bail!("Session ID prefix '{}' matches {} log files; use more characters", id, n),
+    }
+}
+
+fn list_log_files(logs_dir: &Path) -> Result<()> {
+    let session_logs = log_files_in(logs_dir)?;
+    let profile_logs_dir = paths::profile_logs_dir();
+    let profile_logs = if profile_logs_dir.exists() {
+        log_files_in(&profile_logs_dir)?
+    } else {
+        Vec::new()
+    };
+
+    if session_logs.is_empty() && profile_logs.is_empty() {
+        println!("No log files found.");
+        return Ok(());
+    }
+
+    if !session_logs.is_empty() {
+        println!("Session logs:");
+        print_log_files(&session_logs);
+    }
+
+    if !profile_logs.is_empty() {
+        if !session_logs.is_empty() {
+            println!();
         }
-        println!("\nUse 'rssh logs <session-id>' to view a specific log.");
+        println!("Profile logs:");
+        print_log_files(&profile_logs);
     }
 
+    println!("\nUse 'rssh logs <session-id>' or 'rssh logs --profile <name>' to view a log.");
     Ok(())
 }
 
-async fn show_log_file(path: &std::path::Path, lines: usize, follow: bool) -> Result<()> {
+/// `.log` files in a directory, newest first
+fn log_files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir)
+        .context("Failed to read logs directory")?;
+
+    let mut log_files: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().map(|ext| ext == "log").unwrap_or(false))
+        .collect();
+
+    // Sort by modification time (newest first)
+    log_files.sort_by(|a, b| {
+        let time_a = a.metadata().and_then(|m| m.modified()).ok();
+        let time_b = b.metadata().and_then(|m| m.modified()).ok();
+        time_b.cmp(&time_a)
+    });
+
+    Ok(log_files)
+}
+
+fn print_log_files(files: &[PathBuf]) {
+    for path in files {
+        let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+        println!("  {} ({} bytes)", file_stem(path), size);
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+async fn show_log_file(path: &Path, lines: usize, follow: bool) -> Result<()> {
     let file = fs::File::open(path)
         .context("Failed to open log file")?;
-    let reader = BufReader::new(file);
-
-    let all_lines: Vec<String> = reader.lines().filter_map(|l| l.ok()).collect();
+    let mut reader = BufReader::new(file);
+
+    let mut all_lines = Vec::new();
+    let mut position = 0;
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        let read = reader.read_line(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        position += read as u64;
+        all_lines.push(buf.trim_end_matches(['\n', '\r']).to_string());
+    }
     let start = all_lines.len().saturating_sub(lines);
 
     for line in all_lines.iter().skip(start) {
@@ -68,26 +142,66 @@ async fn show_log_file(path: &std::path::Path, lines: usize, follow: bool) -> Re
 
     if follow {
         println!("--- Following log (Ctrl+C to stop) ---");
-        // In a real implementation, we'd use notify or similar for file watching
-        // For now, just poll the file
-        use std::time::Duration;
-        let mut last_pos = all_lines.len();
+        follow_log(path, reader, position).await?;
+    }
 
-        loop {
-            tokio::time::sleep(Duration::from_millis(500)).await;
+    Ok(())
+}
 
-            let file = fs::File::open(path)?;
-            let reader = BufReader::new(file);
-            let current_lines: Vec<String> = reader.lines().filter_map(|l| l.ok()).collect();
+/// Print lines as they are appended, reopening the file when it is rotated or truncated
+async fn follow_log(path: &Path, mut reader: BufReader<fs::File>, mut position: u64) -> Result<()> {
+    let mut identity = file_identity(reader.get_ref());
+    let mut partial = String::new();
+
+    loop {
+        let read = reader.read_line(&mut partial)?;
+        if read > 0 {
+            position += read as u64;
+            // Only print complete lines; the writer may be mid-line
+            if partial.ends_with('\n') {
+                println!("{}", partial.trim_end_matches(['\n', '\r']));
+                partial.clear();
+            }
+            continue;
+        }
 
-            if current_lines.len() > last_pos {
-                for line in current_lines.iter().skip(last_pos) {
-                    println!("{}", line);
+        tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+
+        match fs::metadata(path) {
+            Ok(metadata) => {
+                let replaced = file_identity_of(&metadata) != identity;
+                if replaced || metadata.len() < position {
+                    // Rotated away or truncated: start again from the top of the current file
+                    let file = fs::File::open(path)
+                        .context("Failed to reopen log file")?;
+                    identity = file_identity(&file);
+                    reader = BufReader::new(file);
+                    position = 0;
+                    partial.clear();
+                } else {
+                    // Clear the EOF state so new data is picked up
+                    reader.seek(SeekFrom::Start(position))?;
                 }
-                last_pos = current_lines.len();
             }
+            // Mid-rotation the file may briefly not exist
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("Failed to check log file"),
         }
     }
+}
 
-    Ok(())
+#[cfg(unix)]
+fn file_identity_of(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Without inode numbers, rotation is only noticed as truncation
+#[cfg(not(unix))]
+fn file_identity_of(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn file_identity(file: &fs::File) -> Option<(u64, u64)> {
+    file.metadata().ok().and_then(|m| file_identity_of(&m))
 }
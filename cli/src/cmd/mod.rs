@@ -2,4 +2,9 @@ pub mod up;
 pub mod down;
 pub mod status;
 pub mod logs;
+pub mod history;
+pub mod notify;
 pub mod profile;
+pub mod secret;
+#[cfg(unix)]
+pub mod daemon;
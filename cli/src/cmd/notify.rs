@@ -0,0 +1,37 @@
+use anyhow::{bail, Result};
+use reverse_ssh_core::config::init_config;
+use reverse_ssh_core::notify::{Notification, Notifier};
+
+pub async fn run_test(webhook: Option<String>, profile: String) -> Result<()> {
+    let config = init_config()?;
+    if config.webhooks.is_empty() {
+        bail!("No webhooks configured; add a [[webhooks]] entry to config.toml");
+    }
+
+    let notifier = Notifier::new(&config.webhooks)?;
+    let targets: Vec<_> = notifier
+        .webhooks()
+        .iter()
+        .filter(|w| webhook.as_ref().is_none_or(|name| &w.config.name == name))
+        .collect();
+    if targets.is_empty() {
+        bail!("Webhook '{}' not found", webhook.unwrap_or_default());
+    }
+
+    let notification = Notification::test(&profile);
+    let mut failed = 0;
+    for target in targets {
+        match notifier.send(target, &notification).await {
+            Ok(()) => println!("✓ {}: delivered", target.config.name),
+            Err(e) => {
+                println!("✗ {}: {}", target.config.name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} webhook(s) failed", failed);
+    }
+    Ok(())
+}
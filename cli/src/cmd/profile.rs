@@ -1,12 +1,68 @@
 use anyhow::{Result, Context};
+use clap::ValueEnum;
 use reverse_ssh_core::{
-    config::{load_profiles, paths, save_profile, delete_profile},
-    types::{Profile, TunnelSpec, AuthMethod},
+    config::{
+        export_host_block, import_profiles, lint_profiles, load_profiles, load_raw_profile, paths, save_profile,
+        delete_profile, ensure_not_extended, SshConfigFile,
+    },
+    types::{
+        BackendKind, Diagnostic, FailoverStrategy, HealthCheckKind, JumpHost, Profile, ProfileHooks, RelayEndpoint, TunnelEndpoint,
+        TunnelKind, TunnelSpec, AuthMethod,
+    },
 };
 use uuid::Uuid;
 
 use crate::output::OutputFormat;
 
+/// Failover strategy as given on the command line
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum FailoverArg {
+    Ordered,
+    RoundRobin,
+    LowestLatency,
+}
+
+impl From<FailoverArg> for FailoverStrategy {
+    fn from(arg: FailoverArg) -> Self {
+        match arg {
+            FailoverArg::Ordered => FailoverStrategy::Ordered,
+            FailoverArg::RoundRobin => FailoverStrategy::RoundRobin,
+            FailoverArg::LowestLatency => FailoverStrategy::LowestLatency,
+        }
+    }
+}
+
+/// Fallback server settings from `profile add` / `profile edit`
+#[derive(Debug, Clone, Default)]
+pub struct FailoverOptions {
+    /// `host[:port]` specs replacing the fallbacks when not empty
+    pub fallbacks: Vec<String>,
+    /// Remove all fallbacks
+    pub clear_fallbacks: bool,
+    pub strategy: Option<FailoverArg>,
+    pub fail_back_after: Option<u64>,
+}
+
+impl FailoverOptions {
+    fn apply(self, profile: &mut Profile) -> Result<()> {
+        if self.clear_fallbacks {
+            profile.fallback_hosts.clear();
+        } else if !self.fallbacks.is_empty() {
+            profile.fallback_hosts = self.fallbacks
+                .iter()
+                .map(|f| parse_fallback_spec(f))
+                .collect::<Result<Vec<_>>>()?;
+        }
+        if let Some(strategy) = self.strategy {
+            profile.failover_strategy = strategy.into();
+        }
+        if let Some(secs) = self.fail_back_after {
+            profile.fail_back_after_secs = secs;
+        }
+        Ok(())
+    }
+}
+
 pub async fn run_list(format: OutputFormat) -> Result<()> {
     let profiles = load_profiles()?;
 
@@ -22,11 +78,17 @@ pub async fn run_list(format: OutputFormat) -> Result<()> {
             for profile in &profiles {
                 println!("  [{}]", profile.name);
                 println!("    Host: {}@{}:{}", profile.user, profile.host, profile.port);
+                if !profile.jump_hosts.is_empty() {
+                    let hops: Vec<String> = profile.jump_hosts.iter().map(JumpHost::destination).collect();
+                    println!("    Via:  {}", hops.join(" -> "));
+                }
+                if !profile.fallback_hosts.is_empty() {
+                    let fallbacks: Vec<String> = profile.fallback_hosts.iter().map(ToString::to_string).collect();
+                    println!("    Fallbacks: {} ({})", fallbacks.join(", "), profile.failover_strategy);
+                }
                 println!("    Tunnels: {}", profile.tunnels.len());
                 for tunnel in &profile.tunnels {
-                    println!("      -R {}:{}:{}:{}", 
-                        tunnel.remote_bind, tunnel.remote_port, 
-                        tunnel.local_host, tunnel.local_port);
+                    println!("      {}", tunnel);
                 }
                 println!();
             }
@@ -40,26 +102,90 @@ pub async fn run_list(format: OutputFormat) -> Result<()> {
     Ok(())
 }
 
-pub async fn run_show(name: String, format: OutputFormat) -> Result<()> {
+pub async fn run_show(name: String, format: OutputFormat, resolved: bool) -> Result<()> {
     let profiles = load_profiles()?;
 
     let profile = profiles.iter()
         .find(|p| p.name == name)
         .context(format!("Profile '{}' not found", name))?;
 
+    // Without --resolved, a profile that extends another shows only its own settings
+    if let (Some(base), false) = (&profile.extends, resolved) {
+        let raw = load_raw_profile(&profile.name)?;
+        match format {
+            OutputFormat::Human => {
+                println!("Profile: {} (extends '{}')\n", profile.name, base);
+                for line in toml::to_string_pretty(&raw)?.lines() {
+                    println!("  {}", line);
+                }
+                println!("\nUse --resolved to see the effective profile.");
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&raw)?);
+            }
+        }
+        return Ok(());
+    }
+
     match format {
         OutputFormat::Human => {
             println!("Profile: {}\n", profile.name);
             println!("  ID:       {}", profile.id);
+            if let Some(base) = &profile.extends {
+                println!("  Extends:  {}", base);
+            }
             println!("  Host:     {}", profile.host);
             println!("  Port:     {}", profile.port);
             println!("  User:     {}", profile.user);
             println!("  Auth:     {}", format_auth(&profile.auth));
+            println!("  Backend:  {}", profile.backend);
+            if !profile.jump_hosts.is_empty() {
+                println!("\n  Jump hosts:");
+                for hop in &profile.jump_hosts {
+                    println!("    {}", hop);
+                }
+            }
+            if !profile.fallback_hosts.is_empty() {
+                println!("\n  Fallback hosts ({}):", profile.failover_strategy);
+                for endpoint in &profile.fallback_hosts {
+                    println!("    {}", endpoint);
+                }
+                match profile.fail_back_after_secs {
+                    0 => println!("    Fail-back: off"),
+                    secs => println!("    Fail-back: after {}s", secs),
+                }
+            }
             println!("\n  Tunnels:");
             for tunnel in &profile.tunnels {
-                println!("    -R {}:{}:{}:{}", 
-                    tunnel.remote_bind, tunnel.remote_port,
-                    tunnel.local_host, tunnel.local_port);
+                println!("    {}", tunnel);
+                if let Some(check) = &tunnel.health_check {
+                    print!("      Health check: {} every {}s", check.kind, check.interval_secs);
+                    if check.kind == HealthCheckKind::Http {
+                        print!(" (GET {} -> {})", check.path, check.expected_status);
+                    }
+                    match check.reconnect_after {
+                        0 => println!(),
+                        n => println!(", reconnect after {} failures", n),
+                    }
+                }
+            }
+            if let Some(policy) = &profile.reconnect {
+                println!(
+                    "\n  Reconnect: {}s to {}s, x{}, jitter {}",
+                    policy.initial_delay_secs, policy.max_delay_secs, policy.multiplier, policy.jitter
+                );
+                if policy.reset_after_stable_secs > 0 {
+                    println!("    Reset after {}s connected", policy.reset_after_stable_secs);
+                }
+                if policy.give_up_after_secs > 0 {
+                    println!("    Give up after {}s", policy.give_up_after_secs);
+                }
+            }
+            if !profile.hooks.is_empty() {
+                println!("\n  Hooks:");
+                for (point, hook) in profile.hooks.iter() {
+                    println!("    {:<16} {:?} (timeout {}s)", point.to_string(), hook.command, hook.timeout_secs);
+                }
             }
             if !profile.extra_options.is_empty() {
                 println!("\n  Extra SSH options: {:?}", profile.extra_options);
@@ -74,6 +200,7 @@ pub async fn run_show(name: String, format: OutputFormat) -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run_add(
     name: String,
     host: String,
@@ -81,6 +208,8 @@ pub async fn run_add(
     port: Option<u16>,
     tunnels: Vec<String>,
     key_file: Option<String>,
+    jumps: Vec<String>,
+    failover: FailoverOptions,
 ) -> Result<()> {
     let profiles = load_profiles()?;
 
@@ -96,30 +225,45 @@ pub async fn run_add(
         anyhow::bail!("At least one tunnel specification is required. Use --tunnel <remote_port>:<local_port>");
     }
 
+    let jump_hosts = jumps.iter()
+        .map(|j| parse_jump_spec(j))
+        .collect::<Result<Vec<_>>>()?;
+
     let auth = if let Some(key) = key_file {
         AuthMethod::KeyFile { path: key }
     } else {
         AuthMethod::Agent
     };
 
-    let profile = Profile {
+    let mut profile = Profile {
         id: Uuid::new_v4(),
         name: name.clone(),
+        extends: None,
         host,
         port: port.unwrap_or(22),
         user,
         auth,
         tunnels: parsed_tunnels,
+        jump_hosts,
+        fallback_hosts: Vec::new(),
+        failover_strategy: FailoverStrategy::default(),
+        fail_back_after_secs: 0,
+        backend: BackendKind::default(),
         keepalive_interval: 20,
         keepalive_count: 3,
         auto_reconnect: true,
         max_reconnect_attempts: 0,
+        reconnect: None,
+        hooks: ProfileHooks::default(),
         extra_options: std::collections::HashMap::new(),
         ssh_path: None,
         known_hosts_file: None,
         identity_file: None,
+        password: None,
     };
+    failover.apply(&mut profile)?;
 
+    check_profile(&profile, &profiles)?;
     save_profile(&profile)?;
 
     println!("Profile '{}' created successfully.", name);
@@ -142,6 +286,108 @@ pub async fn run_remove(name: String) -> Result<()> {
     Ok(())
 }
 
+pub async fn run_import(path: Option<std::path::PathBuf>, hosts: Vec<String>) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => SshConfigFile::default_path().context("Could not determine the home directory")?,
+    };
+
+    let config = SshConfigFile::load(&path)?;
+    let default_user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok();
+    let import = import_profiles(&config, &hosts, default_user.as_deref())?;
+
+    for warning in &import.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let existing = load_profiles()?;
+    let mut created = 0;
+    for profile in &import.profiles {
+        if existing.iter().any(|p| p.name == profile.name) {
+            println!("Skipped '{}': a profile with that name already exists.", profile.name);
+            continue;
+        }
+        save_profile(profile)?;
+        println!("Imported '{}' ({} tunnel(s)).", profile.name, profile.tunnels.len());
+        created += 1;
+    }
+
+    if import.profiles.is_empty() {
+        println!("No hosts with port forwards found in {}.", path.display());
+    } else {
+        println!("{} profile(s) imported into {}", created, paths::profiles_dir().display());
+    }
+
+    Ok(())
+}
+
+pub async fn run_export(name: String) -> Result<()> {
+    let profiles = load_profiles()?;
+
+    let profile = profiles.iter()
+        .find(|p| p.name == name)
+        .context(format!("Profile '{}' not found.", name))?;
+
+    print!("{}", export_host_block(profile));
+
+    Ok(())
+}
+
+/// Print the profile's warnings and refuse it if it has errors
+fn check_profile(profile: &Profile, others: &[Profile]) -> Result<()> {
+    let (errors, warnings): (Vec<Diagnostic>, Vec<Diagnostic>) =
+        profile.validate_among(others).into_iter().partition(Diagnostic::is_error);
+
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    if !errors.is_empty() {
+        let lines: Vec<String> = errors.iter().map(|e| format!("  {}", e)).collect();
+        anyhow::bail!("Profile '{}' is invalid:\n{}", profile.name, lines.join("\n"));
+    }
+    Ok(())
+}
+
+/// Check every profile, or only the one named
+pub async fn run_lint(name: Option<String>, format: OutputFormat) -> Result<()> {
+    let mut lints = lint_profiles()?;
+    if let Some(name) = &name {
+        lints.retain(|lint| &lint.name == name);
+        if lints.is_empty() {
+            anyhow::bail!("Profile '{}' not found.", name);
+        }
+    }
+
+    match format {
+        OutputFormat::Human => {
+            if lints.is_empty() {
+                println!("No profiles configured.");
+                return Ok(());
+            }
+            for lint in &lints {
+                if lint.diagnostics.is_empty() {
+                    println!("{}: ok", lint.name);
+                    continue;
+                }
+                println!("{} ({}):", lint.name, lint.path.display());
+                for diagnostic in &lint.diagnostics {
+                    println!("  {}: {}", diagnostic.severity, diagnostic);
+                }
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&lints)?);
+        }
+    }
+
+    let invalid = lints.iter().filter(|lint| lint.has_errors()).count();
+    if invalid > 0 {
+        anyhow::bail!("{} profile(s) have errors", invalid);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn run_edit(
     name: String,
     new_name: Option<String>,
@@ -152,6 +398,9 @@ pub async fn run_edit(
     key_file: Option<String>,
     password: bool,
     agent: bool,
+    jumps: Vec<String>,
+    no_jump: bool,
+    failover: FailoverOptions,
 ) -> Result<()> {
     let profiles = load_profiles()?;
 
@@ -187,6 +436,17 @@ pub async fn run_edit(
         updated.tunnels = parsed_tunnels;
     }
 
+    if no_jump {
+        updated.jump_hosts.clear();
+    } else if !jumps.is_empty() {
+        updated.jump_hosts = jumps
+            .iter()
+            .map(|j| parse_jump_spec(j))
+            .collect::<Result<Vec<_>>>()?;
+    }
+
+    failover.apply(&mut updated)?;
+
     // Auth selection precedence:
     // 1) --password
     // 2) --key
@@ -205,6 +465,11 @@ pub async fn run_edit(
         anyhow::bail!("Profile '{}' already exists.", updated.name);
     }
 
+    check_profile(&updated, &profiles)?;
+    if updated.name != name {
+        ensure_not_extended(&name)?;
+    }
+
     // Save updated profile first; if renamed, delete the old file.
     save_profile(&updated)?;
     if updated.name != name {
@@ -218,57 +483,166 @@ pub async fn run_edit(
 }
 
 fn parse_tunnel_spec(spec: &str) -> Result<TunnelSpec> {
-    // Format: remote_port:local_host:local_port
-    // Or: remote_port:local_port (defaults local_host to localhost)
-    let parts: Vec<&str> = spec.split(':').collect();
-
-    match parts.len() {
-        2 => {
-            let remote_port: u16 = parts[0].parse()
-                .context("Invalid remote port")?;
-            let local_port: u16 = parts[1].parse()
-                .context("Invalid local port")?;
-            Ok(TunnelSpec {
-                remote_bind: "localhost".to_string(),
-                remote_port,
-                local_host: "localhost".to_string(),
-                local_port,
-            })
+    // Optional kind prefix: R: (default), L:, D:, RD:
+    let (kind, rest) = match spec.split_once(':') {
+        Some(("R", rest)) => (TunnelKind::Remote, rest),
+        Some(("L", rest)) => (TunnelKind::Local, rest),
+        Some(("D", rest)) => (TunnelKind::Dynamic, rest),
+        Some(("RD", rest)) => (TunnelKind::RemoteDynamic, rest),
+        _ => (TunnelKind::Remote, spec),
+    };
+    let parts: Vec<&str> = rest.split(':').collect();
+
+    match kind {
+        TunnelKind::Remote => parse_remote_tunnel(&parts),
+        TunnelKind::Local => parse_local_tunnel(&parts),
+        TunnelKind::Dynamic => {
+            let (bind, port) = parse_bind_port(&parts, "D:[local_bind:]local_port")?;
+            let mut tunnel = TunnelSpec::dynamic(port);
+            tunnel.local_host = bind;
+            Ok(tunnel)
         }
-        3 => {
-            let remote_port: u16 = parts[0].parse()
-                .context("Invalid remote port")?;
-            let local_host = parts[1].to_string();
-            let local_port: u16 = parts[2].parse()
-                .context("Invalid local port")?;
-            Ok(TunnelSpec {
-                remote_bind: "localhost".to_string(),
-                remote_port,
-                local_host,
-                local_port,
-            })
+        TunnelKind::RemoteDynamic => {
+            let (bind, port) = parse_bind_port(&parts, "RD:[remote_bind:]remote_port")?;
+            let mut tunnel = TunnelSpec::remote_dynamic(port);
+            tunnel.remote_bind = bind;
+            Ok(tunnel)
+        }
+    }
+}
+
+fn parse_remote_tunnel(parts: &[&str]) -> Result<TunnelSpec> {
+    // Format: [remote_bind:]remote_port:[local_host:]local_port
+    // Either side may be a Unix socket path instead, e.g. /run/remote.sock:/var/run/docker.sock
+    let (listen, target) = parse_endpoint_pair(parts).context(
+        "Invalid tunnel spec format. Use:\n  \
+         remote_port:local_port\n  \
+         remote_port:local_host:local_port\n  \
+         remote_bind:remote_port:local_host:local_port\n  \
+         L:[local_bind:]local_port:remote_host:remote_port\n  \
+         D:[local_bind:]local_port\n  \
+         RD:[remote_bind:]remote_port\n\
+         Any host:port of -R/-L specs may be replaced by an absolute socket path.",
+    )?;
+
+    Ok(TunnelSpec::new(0, 0)
+        .with_remote_endpoint(listen)
+        .with_local_endpoint(target))
+}
+
+fn parse_local_tunnel(parts: &[&str]) -> Result<TunnelSpec> {
+    // Format: L:[local_bind:]local_port:remote_host:remote_port
+    // Either side may be a Unix socket path instead, e.g. L:/tmp/pg.sock:/run/postgresql/.s.PGSQL.5432
+    let (listen, target) = parse_endpoint_pair(parts).context(
+        "Invalid local tunnel spec. Use: L:[local_bind:]local_port:remote_host:remote_port",
+    )?;
+
+    Ok(TunnelSpec::local(0, "localhost", 0)
+        .with_local_endpoint(listen)
+        .with_remote_endpoint(target))
+}
+
+/// Split `listen:target` where each side is `[host:]port` or an absolute socket path.
+fn parse_endpoint_pair(parts: &[&str]) -> Result<(TunnelEndpoint, TunnelEndpoint)> {
+    let is_socket = |part: &str| part.starts_with('/');
+    let n = parts.len();
+    anyhow::ensure!(n >= 2, "expected a listening and a target endpoint");
+
+    let target_len = if is_socket(parts[n - 1]) {
+        1
+    } else if is_socket(parts[0]) || n == 2 {
+        n - 1
+    } else {
+        2
+    };
+    let (listen, target) = parts.split_at(n - target_len);
+
+    Ok((parse_endpoint(listen, "listening")?, parse_endpoint(target, "target")?))
+}
+
+fn parse_endpoint(parts: &[&str], side: &str) -> Result<TunnelEndpoint> {
+    match parts {
+        [path] if path.starts_with('/') => Ok(TunnelEndpoint::Socket(path.to_string())),
+        [port] => Ok(TunnelEndpoint::Tcp {
+            host: "localhost".to_string(),
+            port: port.parse().with_context(|| format!("Invalid {} port", side))?,
+        }),
+        [host, port] => Ok(TunnelEndpoint::Tcp {
+            host: host.to_string(),
+            port: port.parse().with_context(|| format!("Invalid {} port", side))?,
+        }),
+        _ => anyhow::bail!("Invalid {} endpoint '{}'", side, parts.join(":")),
+    }
+}
+
+/// Parse `[bind:]port` for the SOCKS kinds; the bind address defaults to localhost.
+fn parse_bind_port(parts: &[&str], usage: &str) -> Result<(String, u16)> {
+    match parts {
+        [port] => Ok(("localhost".to_string(), port.parse().context("Invalid port")?)),
+        [bind, port] => Ok((bind.to_string(), port.parse().context("Invalid port")?)),
+        _ => anyhow::bail!("Invalid tunnel spec. Use: {}", usage),
+    }
+}
+
+/// Parse `[user@]host[:port][,key=PATH][,known_hosts=PATH]`; IPv6 hosts go in brackets.
+fn parse_jump_spec(spec: &str) -> Result<JumpHost> {
+    let usage = "Invalid jump host. Use: [user@]host[:port][,key=PATH][,known_hosts=PATH]";
+    let mut fields = spec.split(',');
+    let destination = fields.next().unwrap_or_default();
+
+    let (user, host_port) = match destination.rsplit_once('@') {
+        Some((user, rest)) => (Some(user.to_string()), rest),
+        None => (None, destination),
+    };
+
+    let (host, port) = if let Some(rest) = host_port.strip_prefix('[') {
+        let (host, after) = rest.split_once(']').context(usage)?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match host_port.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
         }
-        4 => {
-            let remote_bind = parts[0].to_string();
-            let remote_port: u16 = parts[1].parse()
-                .context("Invalid remote port")?;
-            let local_host = parts[2].to_string();
-            let local_port: u16 = parts[3].parse()
-                .context("Invalid local port")?;
-            Ok(TunnelSpec {
-                remote_bind,
-                remote_port,
-                local_host,
-                local_port,
-            })
+    };
+
+    let mut hop = JumpHost::new(host);
+    hop.user = user;
+    if let Some(port) = port {
+        hop.port = port.parse().context("Invalid jump host port")?;
+    }
+
+    for field in fields {
+        match field.split_once('=') {
+            Some(("key", path)) => hop.auth = AuthMethod::KeyFile { path: path.to_string() },
+            Some(("known_hosts", path)) => hop.known_hosts_file = Some(path.to_string()),
+            _ => anyhow::bail!("{} (unknown option '{}')", usage, field),
         }
-        _ => anyhow::bail!(
-            "Invalid tunnel spec format. Use:\n  \
-             remote_port:local_port\n  \
-             remote_port:local_host:local_port\n  \
-             remote_bind:remote_port:local_host:local_port"
-        ),
     }
+
+    hop.validate()?;
+    Ok(hop)
+}
+
+/// Parse `host[:port]`; IPv6 hosts go in brackets.
+fn parse_fallback_spec(spec: &str) -> Result<RelayEndpoint> {
+    let (host, port) = if let Some(rest) = spec.strip_prefix('[') {
+        let (host, after) = rest.split_once(']').context("Invalid fallback host. Use: host[:port]")?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match spec.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (spec, None),
+        }
+    };
+
+    let port = match port {
+        Some(port) => port.parse().context("Invalid fallback host port")?,
+        None => 22,
+    };
+
+    let endpoint = RelayEndpoint::new(host, port);
+    endpoint.validate()?;
+    Ok(endpoint)
 }
 
 fn format_auth(auth: &AuthMethod) -> String {
@@ -0,0 +1,120 @@
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use reverse_ssh_core::config::{has_plaintext_passwords, init_config, load_profiles, migrate_plaintext_passwords, SECRET_PREFIX};
+use reverse_ssh_core::storage::{validate_secret_name, SecretStore, PASSPHRASE_ENV};
+use reverse_ssh_core::CoreError;
+
+use crate::output::OutputFormat;
+
+/// Open the configured secret store, asking on the terminal for a vault
+/// passphrase not given in the environment
+pub fn open_store() -> Result<SecretStore> {
+    let config = init_config()?;
+    Ok(SecretStore::open_with(&config.secrets, &prompt_passphrase)?)
+}
+
+fn prompt_passphrase(path: &Path, new: bool) -> reverse_ssh_core::Result<String> {
+    let no_terminal = |_| {
+        CoreError::SecretStore(format!(
+            "vault {} is locked and there is no terminal to ask for its passphrase; set {}",
+            path.display(),
+            PASSPHRASE_ENV
+        ))
+    };
+
+    if !new {
+        return rpassword::prompt_password(format!("Passphrase for {}: ", path.display())).map_err(no_terminal);
+    }
+    eprintln!("Creating a secret vault at {}", path.display());
+    let passphrase = rpassword::prompt_password("New vault passphrase: ").map_err(no_terminal)?;
+    if rpassword::prompt_password("Repeat the passphrase: ").map_err(no_terminal)? != passphrase {
+        return Err(CoreError::SecretStore("the passphrases do not match".to_string()));
+    }
+    Ok(passphrase)
+}
+
+pub async fn run_set(name: String) -> Result<()> {
+    validate_secret_name(&name)?;
+
+    // Prompt on a terminal; otherwise take the value from stdin so it stays out of shell history
+    let value = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(format!("Value for '{}': ", name))?
+    } else {
+        let mut value = String::new();
+        std::io::stdin().read_to_string(&mut value)?;
+        let trimmed = value.strip_suffix('\n').map(|v| v.strip_suffix('\r').unwrap_or(v));
+        trimmed.unwrap_or(&value).to_string()
+    };
+    if value.is_empty() {
+        bail!("The secret value is empty");
+    }
+
+    let mut store = open_store()?;
+    store.set(&name, &value)?;
+    println!("Secret '{}' saved to {}.", name, store.describe());
+    println!("Refer to it in a profile as \"{}{}\".", SECRET_PREFIX, name);
+    Ok(())
+}
+
+pub async fn run_get(name: String) -> Result<()> {
+    let store = open_store()?;
+    match store.get(&name)? {
+        Some(value) => println!("{}", value),
+        None => bail!("Secret '{}' not found in {}", name, store.describe()),
+    }
+    Ok(())
+}
+
+pub async fn run_remove(name: String) -> Result<()> {
+    let mut store = open_store()?;
+    if !store.remove(&name)? {
+        bail!("Secret '{}' not found in {}", name, store.describe());
+    }
+    println!("Secret '{}' removed.", name);
+
+    let reference = format!("{}{}", SECRET_PREFIX, name);
+    for profile in load_profiles()? {
+        if profile.password.as_deref() == Some(reference.as_str()) {
+            eprintln!("Warning: profile '{}' still refers to it", profile.name);
+        }
+    }
+    Ok(())
+}
+
+pub async fn run_migrate() -> Result<()> {
+    if !has_plaintext_passwords() {
+        println!("No profile or template has a password in plain text.");
+        return Ok(());
+    }
+
+    let mut store = open_store()?;
+    for moved in migrate_plaintext_passwords(&mut store)? {
+        println!("Moved the password in {} to secret '{}'.", moved.path.display(), moved.secret);
+    }
+    Ok(())
+}
+
+pub async fn run_list(format: OutputFormat) -> Result<()> {
+    let store = open_store()?;
+    let names = store.names()?;
+
+    match format {
+        OutputFormat::Human => {
+            if names.is_empty() {
+                println!("No secrets in {}.", store.describe());
+                println!("Add one with: rssh secret set <name>");
+                return Ok(());
+            }
+            println!("Secrets in {}:\n", store.describe());
+            for name in &names {
+                println!("  {}", name);
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&names)?);
+        }
+    }
+    Ok(())
+}
@@ -1,25 +1,11 @@
 use anyhow::Result;
-use reverse_ssh_core::{
-    config::init_config,
-    supervisor::SessionManager,
-    types::SessionStatus,
-};
+use reverse_ssh_core::types::{Session, SessionStatus};
 use uuid::Uuid;
 
 use crate::output::OutputFormat;
 
 pub async fn run(session_id: Option<String>, format: OutputFormat) -> Result<()> {
-    let config = init_config()?;
-
-    let (mut manager, handle) = SessionManager::new(config);
-    manager.init().await?;
-
-    // Run manager briefly
-    tokio::spawn(async move {
-        let _ = manager.run().await;
-    });
-
-    let sessions = handle.status().await?;
+    let sessions = fetch_sessions().await?;
 
     if let Some(id_str) = session_id {
         let id = Uuid::parse_str(&id_str)?;
@@ -39,20 +25,81 @@ pub async fn run(session_id: Option<String>, format: OutputFormat) -> Result<()>
         }
     }
 
-    handle.shutdown().await?;
     Ok(())
 }
 
-fn print_session(session: &reverse_ssh_core::types::Session, format: &OutputFormat) {
+/// Ask the daemon for its sessions; no daemon means no sessions.
+#[cfg(unix)]
+async fn fetch_sessions() -> Result<Vec<Session>> {
+    match crate::client::connect().await? {
+        Some(mut client) => Ok(client.status().await?),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn fetch_sessions() -> Result<Vec<Session>> {
+    use reverse_ssh_core::{config::init_config, supervisor::SessionManager};
+
+    let config = init_config()?;
+
+    let (mut manager, handle) = SessionManager::new(config);
+    // Short-lived manager: neither restore nor record sessions
+    manager.set_persist_state(false);
+    manager.set_record_sessions(false);
+    manager.init().await?;
+
+    // Run manager briefly
+    tokio::spawn(async move {
+        let _ = manager.run().await;
+    });
+
+    let sessions = handle.status().await?;
+    handle.shutdown().await?;
+    Ok(sessions)
+}
+
+fn print_session(session: &Session, format: &OutputFormat) {
     match format {
         OutputFormat::Human => {
             println!("Session ID: {}", session.id);
             println!("Profile:    {}", session.profile_name);
             println!("Status:     {}", format_status(&session.status));
             println!("Started:    {}", session.started_at.format("%Y-%m-%d %H:%M:%S"));
+            if let Some(endpoint) = &session.active_endpoint {
+                println!("Server:     {}", endpoint);
+            }
             if let Some(pid) = session.pid {
                 println!("PID:        {}", pid);
             }
+            if !session.tunnels.is_empty() {
+                println!("Tunnels:");
+                for tunnel in &session.tunnels {
+                    match tunnel.allocated_port {
+                        Some(port) => print!("  {} (allocated port {})", tunnel.effective_spec(), port),
+                        None if tunnel.wants_allocated_port() => {
+                            print!("  {} (waiting for allocated port)", tunnel.spec)
+                        }
+                        None => print!("  {}", tunnel.spec),
+                    }
+                    if let Some(traffic) = &tunnel.traffic {
+                        print!(
+                            " ({} sent, {} received, {} connections)",
+                            format_bytes(traffic.bytes_sent),
+                            format_bytes(traffic.bytes_received),
+                            traffic.channels
+                        );
+                    }
+                    match &tunnel.health {
+                        Some(health) if health.healthy => println!(" [healthy]"),
+                        Some(health) => println!(
+                            " [unhealthy: {}]",
+                            health.last_error.as_deref().unwrap_or("health check failed")
+                        ),
+                        None => println!(),
+                    }
+                }
+            }
         }
         OutputFormat::Json => {
             let json = serde_json::json!({
@@ -61,6 +108,13 @@ fn print_session(session: &reverse_ssh_core::types::Session, format: &OutputForm
                 "status": format_status(&session.status),
                 "started_at": session.started_at.to_rfc3339(),
                 "pid": session.pid,
+                "server": session.active_endpoint.as_ref().map(ToString::to_string),
+                "tunnels": session.tunnels.iter().map(|t| serde_json::json!({
+                    "forward": t.effective_spec().to_string(),
+                    "allocated_port": t.allocated_port,
+                    "health": t.health,
+                    "traffic": t.traffic,
+                })).collect::<Vec<_>>(),
             });
             println!("{}", serde_json::to_string_pretty(&json).unwrap());
         }
@@ -71,8 +125,24 @@ fn format_status(status: &SessionStatus) -> &'static str {
     match status {
         SessionStatus::Starting => "starting",
         SessionStatus::Connected => "connected",
+        SessionStatus::Degraded => "degraded",
         SessionStatus::Reconnecting => "reconnecting",
         SessionStatus::Stopped => "stopped",
         SessionStatus::Failed => "failed",
+        SessionStatus::TimedOut => "timed out",
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
     }
+    format!("{:.1} {}", value, UNITS[unit])
 }
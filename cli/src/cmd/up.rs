@@ -1,18 +1,17 @@
 use anyhow::{Result, Context};
 use reverse_ssh_core::{
-    config::{init_config, load_profiles},
-    supervisor::SessionManager,
-    types::SessionStatus,
+    config::{init_config, load_profiles, SECRET_PREFIX},
+    supervisor::StartSessionOptions,
+    types::{AuthMethod, Event, Profile, SessionStatus},
 };
 use tokio::signal;
-use std::time::Duration;
-use tokio::time;
+use uuid::Uuid;
 
-pub async fn run(name: String) -> Result<()> {
+pub async fn run(name: String, detach: bool) -> Result<()> {
     // Initialize config and load profiles
     let config = init_config()?;
     let profiles = load_profiles()?;
-    
+
     // Find profile by name
     let profile = profiles.iter()
         .find(|p| p.name == name)
@@ -21,10 +20,115 @@ pub async fn run(name: String) -> Result<()> {
 
     println!("Starting profile '{}' ({})", name, profile.host);
 
+    // The daemon does not share our environment, so hand over SSHPASS explicitly.
+    // A password in the secret store is read here too, where a vault
+    // passphrase can be asked for.
+    let options = StartSessionOptions {
+        password: match profile.auth {
+            AuthMethod::Password => match std::env::var("SSHPASS") {
+                Ok(password) => Some(password),
+                Err(_) => stored_password(&profile)?,
+            },
+            _ => None,
+        },
+        sshpass_path: None,
+    };
+
+    #[cfg(unix)]
+    {
+        // The daemon loads its own copy of the configuration
+        drop(config);
+        run_with_daemon(profile, options, detach).await
+    }
+
+    #[cfg(not(unix))]
+    {
+        // Without a daemon the session cannot outlive this process
+        let _ = detach;
+        run_in_process(config, profile, options).await
+    }
+}
+
+/// The password a profile refers to with `secret://<name>`, if it does
+fn stored_password(profile: &Profile) -> Result<Option<String>> {
+    let Some(name) = profile.password.as_deref().and_then(|p| p.strip_prefix(SECRET_PREFIX)) else {
+        return Ok(None);
+    };
+    let store = crate::cmd::secret::open_store()?;
+    let password = store
+        .get(name)?
+        .with_context(|| format!("Secret '{}' for profile '{}' not found in {}", name, profile.name, store.describe()))?;
+    Ok(Some(password))
+}
+
+/// Start the session in the (possibly auto-spawned) daemon and follow its events
+#[cfg(unix)]
+async fn run_with_daemon(profile: Profile, options: StartSessionOptions, detach: bool) -> Result<()> {
+    let client = crate::client::connect_or_spawn().await?;
+    let daemon_pid = client.daemon_pid();
+
+    // Subscribe before starting so no early events are missed
+    let mut events = client.subscribe().await?;
+
+    // The subscribed connection only carries events; commands need a second
+    // one, to the same daemon
+    let mut client = crate::client::connect()
+        .await?
+        .filter(|client| client.daemon_pid() == daemon_pid)
+        .context("The daemon stopped or was replaced while starting the session")?;
+
+    let session_id = client.start(profile, options).await?;
+
+    if detach {
+        println!("Session started (ID: {}).", session_id);
+        println!("Use 'rssh status' to check it and 'rssh down {}' to stop it.", session_id);
+        return Ok(());
+    }
+
+    println!("Session started (ID: {}). Press Ctrl+C to stop.", session_id);
+
+    loop {
+        tokio::select! {
+            _ = signal::ctrl_c() => {
+                println!("\nReceived Ctrl+C, stopping...");
+                client.stop(session_id).await?;
+                println!("Stopped.");
+                break;
+            }
+            event = events.next() => {
+                match event? {
+                    Some(event) => {
+                        if !print_event(session_id, &event) {
+                            break;
+                        }
+                    }
+                    None => {
+                        println!("Daemon shut down.");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a private session manager for the lifetime of this command
+#[cfg(not(unix))]
+async fn run_in_process(
+    config: reverse_ssh_core::config::AppConfig,
+    profile: Profile,
+    options: StartSessionOptions,
+) -> Result<()> {
+    use reverse_ssh_core::supervisor::SessionManager;
+
     // Create and initialize session manager
     let (mut manager, handle) = SessionManager::new(config);
+    // Record this session, but neither restore nor overwrite persisted sessions
+    manager.set_persist_state(false);
     manager.init().await?;
-    
+
     // Run manager in background
     tokio::spawn(async move {
         if let Err(e) = manager.run().await {
@@ -33,7 +137,7 @@ pub async fn run(name: String) -> Result<()> {
     });
 
     // Start session
-    let session_id = handle.start(profile).await?;
+    let session_id = handle.start_with_options(profile, options).await?;
     println!("Session started (ID: {}). Press Ctrl+C to stop.", session_id);
 
     // Subscribe to events for monitoring
@@ -50,31 +154,7 @@ pub async fn run(name: String) -> Result<()> {
             }
             event = events.recv() => {
                 if let Ok(event) = event {
-                    match event {
-                        reverse_ssh_core::types::Event::SessionConnected { profile_name, .. } => {
-                            println!("Session '{}' connected", profile_name);
-                        }
-                        reverse_ssh_core::types::Event::SessionDisconnected { profile_name, reason, .. } => {
-                            println!("Session '{}' disconnected: {:?}", profile_name, reason);
-                        }
-                        reverse_ssh_core::types::Event::SessionFailed { profile_name, error, .. } => {
-                            eprintln!("Session '{}' failed: {}", profile_name, error);
-                            break;
-                        }
-                        reverse_ssh_core::types::Event::SessionReconnecting { profile_name, attempt, max_attempts, .. } => {
-                            let max = if max_attempts == 0 { "unlimited".to_string() } else { max_attempts.to_string() };
-                            println!("Session '{}' reconnecting (attempt {}/{})", profile_name, attempt, max);
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            _ = time::sleep(Duration::from_secs(30)) => {
-                // Periodic status check
-                let sessions = handle.status().await?;
-                if let Some(session) = sessions.iter().find(|s| s.id == session_id) {
-                    if session.status == SessionStatus::Failed {
-                        eprintln!("Session failed: {:?}", session.last_error);
+                    if !print_event(session_id, &event) {
                         break;
                     }
                 }
@@ -86,3 +166,39 @@ pub async fn run(name: String) -> Result<()> {
     handle.shutdown().await?;
     Ok(())
 }
+
+/// Print a session event. Returns `false` once the session has ended.
+fn print_event(session_id: Uuid, event: &Event) -> bool {
+    match event {
+        Event::SessionConnected { session_id: id, profile_name, .. } if *id == session_id => {
+            println!("Session '{}' connected", profile_name);
+        }
+        Event::SessionDisconnected { session_id: id, profile_name, reason, .. } if *id == session_id => {
+            println!("Session '{}' disconnected: {:?}", profile_name, reason);
+        }
+        Event::SessionFailed { session_id: id, profile_name, error, .. } if *id == session_id => {
+            eprintln!("Session '{}' failed: {}", profile_name, error);
+            return false;
+        }
+        Event::TunnelPortAllocated { session_id: id, profile_name, tunnel_index, allocated_port, .. } if *id == session_id => {
+            println!("Session '{}' tunnel {}: server allocated port {}", profile_name, tunnel_index + 1, allocated_port);
+        }
+        Event::SessionReconnecting { session_id: id, profile_name, attempt, max_attempts, .. } if *id == session_id => {
+            let max = if *max_attempts == 0 { "unlimited".to_string() } else { max_attempts.to_string() };
+            println!("Session '{}' reconnecting (attempt {}/{})", profile_name, attempt, max);
+        }
+        Event::SessionEndpointChanged { session_id: id, profile_name, previous: Some(previous), endpoint, reason, .. }
+            if *id == session_id =>
+        {
+            println!("Session '{}' switching from {} to {} ({})", profile_name, previous, endpoint, reason);
+        }
+        Event::SessionStatusChanged { session_id: id, profile_name, new_status, .. }
+            if *id == session_id && matches!(new_status, SessionStatus::Stopped | SessionStatus::Failed) =>
+        {
+            println!("Session '{}' {}", profile_name, new_status);
+            return false;
+        }
+        _ => {}
+    }
+    true
+}
@@ -1,9 +1,12 @@
 use clap::{Parser, Subcommand};
 use anyhow::Result;
 
+#[cfg(unix)]
+pub mod client;
 pub mod cmd;
 pub mod output;
 
+use cmd::profile::{FailoverArg, FailoverOptions};
 use output::OutputFormat;
 
 #[derive(Parser)]
@@ -20,6 +23,10 @@ enum Commands {
     Up {
         /// Profile name
         profile: String,
+
+        /// Return once the session is started instead of following it
+        #[arg(short, long)]
+        detach: bool,
     },
     /// Stop a reverse SSH tunnel
     Down {
@@ -38,8 +45,12 @@ enum Commands {
     },
     /// View logs for a session
     Logs {
-        /// Session ID (optional, lists all if not provided)
+        /// Session ID or ID prefix (optional, lists all if not provided)
         session: Option<String>,
+
+        /// Show the log for a profile across all of its sessions
+        #[arg(short, long, conflicts_with = "session")]
+        profile: Option<String>,
         
         /// Follow logs
         #[arg(short, long)]
@@ -49,11 +60,100 @@ enum Commands {
         #[arg(short = 'n', long, default_value = "50")]
         lines: usize,
     },
+    /// Show a profile's connection history with uptime and outages
+    History {
+        /// Profile name
+        profile: String,
+
+        /// How far back to look, e.g. 24h, 7d or 2w
+        #[arg(short, long, default_value = "7d")]
+        window: String,
+
+        /// Number of recent events to list
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "human")]
+        format: OutputFormat,
+    },
+    /// Send webhook notifications
+    Notify {
+        #[command(subcommand)]
+        action: NotifyAction,
+    },
     /// Manage profiles
     Profile {
         #[command(subcommand)]
         action: ProfileAction,
     },
+    /// Manage secrets that profiles refer to as secret://<name>
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+    /// Manage the background daemon that owns all sessions
+    #[cfg(unix)]
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
+    },
+}
+
+#[cfg(unix)]
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Run the daemon in the foreground
+    Run {
+        /// Serve Prometheus metrics at http://ADDR/metrics, e.g. 127.0.0.1:9184
+        #[arg(long, value_name = "ADDR")]
+        metrics_addr: Option<std::net::SocketAddr>,
+    },
+    /// Stop all sessions and shut the daemon down
+    Stop,
+    /// Show whether the daemon is running
+    Status,
+}
+
+#[derive(Subcommand)]
+enum NotifyAction {
+    /// Send a test notification to the configured webhooks
+    Test {
+        /// Only send to the webhook with this name
+        #[arg(short, long)]
+        webhook: Option<String>,
+
+        /// Profile name to put in the notification
+        #[arg(short, long, default_value = "test")]
+        profile: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretAction {
+    /// Store a secret, read from the terminal or stdin
+    Set {
+        /// Secret name
+        name: String,
+    },
+    /// Print a secret
+    Get {
+        /// Secret name
+        name: String,
+    },
+    /// Remove a secret
+    Rm {
+        /// Secret name
+        name: String,
+    },
+    /// Move plaintext passwords in profiles and templates into the secret store
+    Migrate,
+    /// List secret names
+    List {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "human")]
+        format: OutputFormat,
+    },
 }
 
 #[derive(Subcommand)]
@@ -72,6 +172,10 @@ enum ProfileAction {
         /// Output format
         #[arg(short, long, value_enum, default_value = "human")]
         format: OutputFormat,
+
+        /// Show the effective profile, with the settings it inherits through `extends`
+        #[arg(long)]
+        resolved: bool,
     },
     /// Add a new profile
     Add {
@@ -90,13 +194,44 @@ enum ProfileAction {
         #[arg(short, long)]
         port: Option<u16>,
         
-        /// Tunnel specifications (format: remote_port:local_host:local_port)
+        /// Tunnel specifications (repeatable)
+        ///
+        /// Format:
+        /// - remote_port:local_port
+        /// - remote_port:local_host:local_port
+        /// - remote_bind:remote_port:local_host:local_port
+        /// - L:[local_bind:]local_port:remote_host:remote_port
+        /// - D:[local_bind:]local_port
+        /// - RD:[remote_bind:]remote_port
+        ///
+        /// Any host:port of a -R or -L spec may be an absolute Unix socket path,
+        /// e.g. /run/docker.sock:/var/run/docker.sock
         #[arg(short, long)]
         tunnel: Vec<String>,
         
         /// Path to SSH key file
         #[arg(short, long)]
         key: Option<String>,
+
+        /// Jump host to hop through (repeatable, first hop first)
+        ///
+        /// Format: [user@]host[:port][,key=PATH][,known_hosts=PATH]
+        #[arg(short = 'J', long = "jump")]
+        jump: Vec<String>,
+
+        /// Fallback server used when the host is unavailable (repeatable)
+        ///
+        /// Format: host[:port]
+        #[arg(long)]
+        fallback: Vec<String>,
+
+        /// How the server is picked among the host and its fallbacks
+        #[arg(long, value_enum, default_value = "ordered")]
+        failover: FailoverArg,
+
+        /// Return to the host once it has been reachable this many seconds (0 = never)
+        #[arg(long, default_value_t = 0)]
+        fail_back_after: u64,
     },
     /// Edit an existing profile
     Edit {
@@ -125,6 +260,12 @@ enum ProfileAction {
         /// - remote_port:local_port
         /// - remote_port:local_host:local_port
         /// - remote_bind:remote_port:local_host:local_port
+        /// - L:[local_bind:]local_port:remote_host:remote_port
+        /// - D:[local_bind:]local_port
+        /// - RD:[remote_bind:]remote_port
+        ///
+        /// Any host:port of a -R or -L spec may be an absolute Unix socket path,
+        /// e.g. /run/docker.sock:/var/run/docker.sock
         #[arg(short, long)]
         tunnel: Vec<String>,
 
@@ -139,12 +280,73 @@ enum ProfileAction {
         /// Force SSH agent auth
         #[arg(long)]
         agent: bool,
+
+        /// Replace jump hosts with these (repeatable, first hop first)
+        ///
+        /// Format: [user@]host[:port][,key=PATH][,known_hosts=PATH]
+        #[arg(short = 'J', long = "jump")]
+        jump: Vec<String>,
+
+        /// Remove all jump hosts
+        #[arg(long, conflicts_with = "jump")]
+        no_jump: bool,
+
+        /// Replace fallback servers with these (repeatable)
+        ///
+        /// Format: host[:port]
+        #[arg(long)]
+        fallback: Vec<String>,
+
+        /// Remove all fallback servers
+        #[arg(long, conflicts_with = "fallback")]
+        no_fallback: bool,
+
+        /// How the server is picked among the host and its fallbacks
+        #[arg(long, value_enum)]
+        failover: Option<FailoverArg>,
+
+        /// Return to the host once it has been reachable this many seconds (0 = never)
+        #[arg(long)]
+        fail_back_after: Option<u64>,
     },
     /// Remove a profile
     Remove {
         /// Profile name
         name: String,
     },
+    /// Create profiles from OpenSSH client configuration
+    ///
+    /// Every Host with a LocalForward, RemoteForward or DynamicForward
+    /// becomes a profile named after it. Existing profiles are left alone.
+    Import {
+        /// ssh_config file to read (default: ~/.ssh/config)
+        #[arg(long, value_name = "PATH", num_args = 0..=1, required = true)]
+        from_ssh_config: Option<Option<std::path::PathBuf>>,
+
+        /// Only import this Host (repeatable)
+        #[arg(long)]
+        host: Vec<String>,
+    },
+    /// Check profiles for errors and likely problems
+    ///
+    /// Exits with an error when any profile is invalid; warnings alone do not fail.
+    Lint {
+        /// Only check this profile
+        name: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "human")]
+        format: OutputFormat,
+    },
+    /// Print a profile in another format
+    Export {
+        /// Profile name
+        name: String,
+
+        /// Print an ssh_config Host block
+        #[arg(long, required = true)]
+        ssh_config: bool,
+    },
 }
 
 #[tokio::main]
@@ -152,8 +354,8 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Up { profile } => {
-            cmd::up::run(profile).await?;
+        Commands::Up { profile, detach } => {
+            cmd::up::run(profile, detach).await?;
         }
         Commands::Down { session_id } => {
             cmd::down::run(session_id).await?;
@@ -161,26 +363,75 @@ async fn main() -> Result<()> {
         Commands::Status { session, format } => {
             cmd::status::run(session, format).await?;
         }
-        Commands::Logs { session, follow, lines } => {
-            cmd::logs::run(session, follow, lines).await?;
+        Commands::Logs { session, profile, follow, lines } => {
+            cmd::logs::run(session, profile, follow, lines).await?;
+        }
+        Commands::History { profile, window, limit, format } => {
+            cmd::history::run(profile, window, limit, format).await?;
+        }
+        Commands::Notify { action } => {
+            match action {
+                NotifyAction::Test { webhook, profile } => cmd::notify::run_test(webhook, profile).await?,
+            }
         }
         Commands::Profile { action } => {
             match action {
                 ProfileAction::List { format } => {
                     cmd::profile::run_list(format).await?;
                 }
-                ProfileAction::Show { name, format } => {
-                    cmd::profile::run_show(name, format).await?;
+                ProfileAction::Show { name, format, resolved } => {
+                    cmd::profile::run_show(name, format, resolved).await?;
                 }
-                ProfileAction::Add { name, host, user, port, tunnel, key } => {
-                    cmd::profile::run_add(name, host, user, port, tunnel, key).await?;
+                ProfileAction::Add { name, host, user, port, tunnel, key, jump, fallback, failover, fail_back_after } => {
+                    let failover = FailoverOptions {
+                        fallbacks: fallback,
+                        clear_fallbacks: false,
+                        strategy: Some(failover),
+                        fail_back_after: Some(fail_back_after),
+                    };
+                    cmd::profile::run_add(name, host, user, port, tunnel, key, jump, failover).await?;
                 }
-                ProfileAction::Edit { name, new_name, host, user, port, tunnel, key, password, agent } => {
-                    cmd::profile::run_edit(name, new_name, host, user, port, tunnel, key, password, agent).await?;
+                ProfileAction::Edit {
+                    name, new_name, host, user, port, tunnel, key, password, agent, jump, no_jump,
+                    fallback, no_fallback, failover, fail_back_after,
+                } => {
+                    let failover = FailoverOptions {
+                        fallbacks: fallback,
+                        clear_fallbacks: no_fallback,
+                        strategy: failover,
+                        fail_back_after,
+                    };
+                    cmd::profile::run_edit(name, new_name, host, user, port, tunnel, key, password, agent, jump, no_jump, failover).await?;
                 }
                 ProfileAction::Remove { name } => {
                     cmd::profile::run_remove(name).await?;
                 }
+                ProfileAction::Import { from_ssh_config, host } => {
+                    cmd::profile::run_import(from_ssh_config.flatten(), host).await?;
+                }
+                ProfileAction::Lint { name, format } => {
+                    cmd::profile::run_lint(name, format).await?;
+                }
+                ProfileAction::Export { name, ssh_config: _ } => {
+                    cmd::profile::run_export(name).await?;
+                }
+            }
+        }
+        Commands::Secret { action } => {
+            match action {
+                SecretAction::Set { name } => cmd::secret::run_set(name).await?,
+                SecretAction::Get { name } => cmd::secret::run_get(name).await?,
+                SecretAction::Rm { name } => cmd::secret::run_remove(name).await?,
+                SecretAction::Migrate => cmd::secret::run_migrate().await?,
+                SecretAction::List { format } => cmd::secret::run_list(format).await?,
+            }
+        }
+        #[cfg(unix)]
+        Commands::Daemon { action } => {
+            match action {
+                DaemonAction::Run { metrics_addr } => cmd::daemon::run(metrics_addr).await?,
+                DaemonAction::Stop => cmd::daemon::stop().await?,
+                DaemonAction::Status => cmd::daemon::status().await?,
             }
         }
     }
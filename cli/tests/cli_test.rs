@@ -26,3 +26,579 @@ fn test_cli_status() {
         .assert()
         .success();
 }
+
+#[cfg(unix)]
+#[test]
+fn test_cli_daemon_status_not_running() {
+    // Point the socket at an empty runtime dir so no real daemon is found
+    let runtime_dir = std::env::temp_dir().join(format!("rssh-test-{}", std::process::id()));
+    let mut cmd = Command::cargo_bin("rssh").unwrap();
+    cmd.args(["daemon", "status"])
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Daemon is not running"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_down_daemon_not_running() {
+    let runtime_dir = std::env::temp_dir().join(format!("rssh-test-down-{}", std::process::id()));
+    let mut cmd = Command::cargo_bin("rssh").unwrap();
+    cmd.args(["down", "550e8400-e29b-41d4-a716-446655440000"])
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("daemon is not running"));
+}
+
+#[test]
+fn test_cli_profile_add_tunnel_specs() {
+    // Isolate config and data so the user's profiles are untouched
+    let home = std::env::temp_dir().join(format!("rssh-test-tunnels-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    rssh()
+        .args(["profile", "add", "kinds", "--host", "example.com", "--user", "me"])
+        .args(["--tunnel", "8080:3000"])
+        .args(["--tunnel", "L:5432:db.internal:5432"])
+        .args(["--tunnel", "D:127.0.0.1:1080"])
+        .args(["--tunnel", "RD:1081"])
+        .args(["--tunnel", "/run/docker.sock:/var/run/docker.sock"])
+        .args(["--tunnel", "L:15432:/run/postgresql/.s.PGSQL.5432"])
+        .assert()
+        .success();
+
+    rssh()
+        .args(["profile", "show", "kinds"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-R localhost:8080:localhost:3000"))
+        .stdout(predicate::str::contains("-L localhost:5432:db.internal:5432"))
+        .stdout(predicate::str::contains("-D 127.0.0.1:1080"))
+        .stdout(predicate::str::contains("-R localhost:1081"))
+        .stdout(predicate::str::contains("-R /run/docker.sock:/var/run/docker.sock"))
+        .stdout(predicate::str::contains("-L localhost:15432:/run/postgresql/.s.PGSQL.5432"));
+
+    rssh()
+        .args(["profile", "add", "bad", "--host", "example.com", "--user", "me"])
+        .args(["--tunnel", "L:5432"])
+        .assert()
+        .failure();
+
+    rssh()
+        .args(["profile", "add", "bad", "--host", "example.com", "--user", "me"])
+        .args(["--tunnel", "D:/tmp/socks.sock"])
+        .assert()
+        .failure();
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_logs_profile_and_session_prefix() {
+    let home = std::env::temp_dir().join(format!("rssh-test-logs-{}", std::process::id()));
+    let logs_dir = home.join("data").join("reverse-ssh-interface").join("logs");
+    std::fs::create_dir_all(logs_dir.join("profiles")).unwrap();
+    std::fs::write(
+        logs_dir.join("f47ac10b-58cc-4372-a567-0e02b2c3d479.log"),
+        "[2026-01-15 10:30:00] [INFO] Connected\n",
+    )
+    .unwrap();
+    std::fs::write(
+        logs_dir.join("profiles").join("web_server.log"),
+        "[2026-01-15 10:30:00] [INFO] [f47ac10b] Connected\n",
+    )
+    .unwrap();
+
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    rssh()
+        .args(["logs", "--profile", "web server"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[f47ac10b] Connected"));
+
+    rssh()
+        .args(["logs", "f47ac10b"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[INFO] Connected"));
+
+    rssh()
+        .arg("logs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Profile logs:"))
+        .stdout(predicate::str::contains("web_server"));
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_profile_jump_hosts() {
+    let home = std::env::temp_dir().join(format!("rssh-test-jumps-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    rssh()
+        .args(["profile", "add", "hops", "--host", "relay.internal", "--user", "me"])
+        .args(["--tunnel", "8080:3000"])
+        .args(["--jump", "ops@bastion.example.com"])
+        .args(["--jump", "[fd00::5]:2222,key=/keys/inner,known_hosts=/keys/known_hosts"])
+        .assert()
+        .success();
+
+    rssh()
+        .args(["profile", "show", "hops"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ops@bastion.example.com"))
+        .stdout(predicate::str::contains("[fd00::5]:2222 (key /keys/inner) (known_hosts /keys/known_hosts)"));
+
+    rssh()
+        .args(["profile", "edit", "hops", "--jump", "bad host"])
+        .assert()
+        .failure();
+
+    rssh()
+        .args(["profile", "edit", "hops", "--no-jump"])
+        .assert()
+        .success();
+
+    rssh()
+        .args(["profile", "show", "hops"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Jump hosts").not());
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_profile_fallback_hosts() {
+    let home = std::env::temp_dir().join(format!("rssh-test-fallbacks-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    rssh()
+        .args(["profile", "add", "relays", "--host", "relay1.example.com", "--user", "me"])
+        .args(["--tunnel", "8080:3000"])
+        .args(["--fallback", "relay2.example.com", "--fallback", "[fd00::7]:2222"])
+        .args(["--failover", "round-robin", "--fail-back-after", "300"])
+        .assert()
+        .success();
+
+    rssh()
+        .args(["profile", "show", "relays"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fallback hosts (round_robin)"))
+        .stdout(predicate::str::contains("relay2.example.com:22"))
+        .stdout(predicate::str::contains("[fd00::7]:2222"))
+        .stdout(predicate::str::contains("Fail-back: after 300s"));
+
+    rssh()
+        .args(["profile", "edit", "relays", "--fallback", "bad host"])
+        .assert()
+        .failure();
+
+    rssh()
+        .args(["profile", "edit", "relays", "--no-fallback"])
+        .assert()
+        .success();
+
+    rssh()
+        .args(["profile", "show", "relays"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fallback hosts").not());
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_history() {
+    use reverse_ssh_core::storage::{HistoryEntry, HistoryKind, HistoryStore};
+
+    let home = std::env::temp_dir().join(format!("rssh-test-history-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    rssh()
+        .args(["history", "web"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No session history recorded yet"));
+
+    let output = rssh().args(["history", "web", "--format", "json"]).output().unwrap();
+    assert!(output.status.success());
+    let empty: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(empty["outages"], 0);
+    assert_eq!(empty["entries"], serde_json::json!([]));
+
+    let store = HistoryStore::open(home.join("data").join("reverse-ssh-interface").join("history.db")).unwrap();
+    let session_id = uuid::Uuid::new_v4();
+    let now = chrono::Utc::now();
+    for (minutes_ago, kind, reason) in [
+        (90, HistoryKind::Connected, None),
+        (60, HistoryKind::Disconnected, Some("Connection reset by peer")),
+        (57, HistoryKind::Connected, None),
+    ] {
+        store
+            .insert(&HistoryEntry {
+                timestamp: now - chrono::Duration::minutes(minutes_ago),
+                session_id,
+                profile_name: "web".to_string(),
+                kind,
+                reason: reason.map(String::from),
+            })
+            .unwrap();
+    }
+
+    rssh()
+        .args(["history", "web", "--window", "2h"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Outages:         1"))
+        .stdout(predicate::str::contains("Mean reconnect:  3m 0s"))
+        .stdout(predicate::str::contains("Connection reset by peer"));
+
+    rssh()
+        .args(["history", "web", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"outages\": 1"));
+
+    rssh()
+        .args(["history", "web", "--window", "forever"])
+        .assert()
+        .failure();
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_notify_test() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let home = std::env::temp_dir().join(format!("rssh-test-notify-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    rssh()
+        .args(["notify", "test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No webhooks configured"));
+
+    // A stand-in webhook receiver that accepts one request
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let receiver = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            if line.trim_end().is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        (&stream).write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+        String::from_utf8(body).unwrap()
+    });
+
+    let config_dir = home.join("config").join("reverse-ssh-interface");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "[[webhooks]]\nname = \"ops\"\nurl = \"http://{}/hook\"\ntemplate = {{ text = \"{{{{message}}}}\" }}\n",
+            addr
+        ),
+    )
+    .unwrap();
+
+    rssh()
+        .args(["notify", "test", "--webhook", "chat"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Webhook 'chat' not found"));
+
+    rssh()
+        .args(["notify", "test", "--profile", "web"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ops: delivered"));
+
+    let body = receiver.join().unwrap();
+    assert!(body.contains("Test notification from rssh for 'web'"));
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_profile_ssh_config_import_export() {
+    let home = std::env::temp_dir().join(format!("rssh-test-ssh-config-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    std::fs::create_dir_all(home.join(".ssh/conf.d")).unwrap();
+    std::fs::write(
+        home.join(".ssh/config"),
+        "Host *\n    User tunnel\n\nInclude conf.d/*.conf\n\nHost plain\n    HostName plain.example.com\n",
+    )
+    .unwrap();
+    std::fs::write(
+        home.join(".ssh/conf.d/web.conf"),
+        "Host web\n    HostName relay.example.com\n    Port 2222\n    RemoteForward 8080 localhost:3000\n    ForwardX11 yes\n",
+    )
+    .unwrap();
+
+    rssh()
+        .args(["profile", "import", "--from-ssh-config"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 'web' (1 tunnel(s))"))
+        .stdout(predicate::str::contains("plain").not())
+        .stderr(predicate::str::contains("'forwardx11' is not imported"));
+
+    // Importing again leaves the existing profile alone
+    rssh()
+        .args(["profile", "import", "--from-ssh-config", "--host", "web"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped 'web'"));
+
+    rssh()
+        .args(["profile", "export", "web", "--ssh-config"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Host web\n"))
+        .stdout(predicate::str::contains("    HostName relay.example.com\n"))
+        .stdout(predicate::str::contains("    Port 2222\n"))
+        .stdout(predicate::str::contains("    RemoteForward localhost:8080 localhost:3000\n"));
+
+    rssh()
+        .args(["profile", "import", "--from-ssh-config", "--host", "plain"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has no port forwards"));
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_profile_show_resolved() {
+    let home = std::env::temp_dir().join(format!("rssh-test-extends-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    let profiles_dir = home.join("config").join("reverse-ssh-interface").join("profiles");
+    std::fs::create_dir_all(profiles_dir.join("templates")).unwrap();
+    std::fs::write(
+        profiles_dir.join("templates").join("device.toml"),
+        "host = \"relay.example.com\"\nuser = \"device\"\n\n[[tunnels]]\nremote_port = 0\nlocal_port = 22\n",
+    )
+    .unwrap();
+    std::fs::write(profiles_dir.join("sensor-7.toml"), "name = \"sensor-7\"\nextends = \"device\"\nport = 2222\n").unwrap();
+
+    rssh()
+        .args(["profile", "show", "sensor-7"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Profile: sensor-7 (extends 'device')"))
+        .stdout(predicate::str::contains("port = 2222"))
+        .stdout(predicate::str::contains("relay.example.com").not());
+
+    rssh()
+        .args(["profile", "show", "sensor-7", "--resolved"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Extends:  device"))
+        .stdout(predicate::str::contains("Host:     relay.example.com"))
+        .stdout(predicate::str::contains("Port:     2222"));
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_secret_vault() {
+    let home = std::env::temp_dir().join(format!("rssh-test-secrets-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"))
+            .env("RSSH_VAULT_PASSPHRASE", "correct horse");
+        cmd
+    };
+
+    // Use the vault even where an OS keyring is available
+    let config_dir = home.join("config").join("reverse-ssh-interface");
+    std::fs::create_dir_all(config_dir.join("profiles")).unwrap();
+    std::fs::write(config_dir.join("config.toml"), "[secrets]\nbackend = \"vault\"\n").unwrap();
+
+    let mut set = rssh();
+    set.args(["secret", "set", "relay-password"]);
+    assert_cmd::Command::from_std(set)
+        .write_stdin("hunter2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"secret://relay-password\""));
+
+    rssh()
+        .args(["secret", "get", "relay-password"])
+        .assert()
+        .success()
+        .stdout("hunter2\n");
+
+    rssh()
+        .args(["secret", "get", "relay-password"])
+        .env("RSSH_VAULT_PASSPHRASE", "battery staple")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("wrong passphrase"));
+
+    // Loading profiles leaves a plaintext password alone; migrating moves it into the vault
+    let plaintext = "name = \"edge\"\nhost = \"relay.example.com\"\nuser = \"tunnel\"\nauth = \"password\"\npassword = \"swordfish\"\ntunnels = []\n";
+    std::fs::write(config_dir.join("profiles").join("edge.toml"), plaintext).unwrap();
+    rssh().args(["profile", "list"]).assert().success();
+    assert_eq!(std::fs::read_to_string(config_dir.join("profiles").join("edge.toml")).unwrap(), plaintext);
+    rssh()
+        .args(["secret", "migrate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("to secret 'edge-password'"));
+    let saved = std::fs::read_to_string(config_dir.join("profiles").join("edge.toml")).unwrap();
+    assert!(saved.contains("password = \"secret://edge-password\""), "{}", saved);
+
+    rssh()
+        .args(["secret", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  edge-password\n  relay-password\n"));
+
+    rssh().args(["secret", "rm", "relay-password"]).assert().success();
+    rssh()
+        .args(["secret", "rm", "relay-password"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Secret 'relay-password' not found"));
+
+    let vault = std::fs::read_to_string(home.join("data").join("reverse-ssh-interface").join("secrets.vault")).unwrap();
+    assert!(!vault.contains("swordfish") && !vault.contains("edge-password"));
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn test_cli_profile_lint() {
+    let home = std::env::temp_dir().join(format!("rssh-test-lint-{}", std::process::id()));
+    let rssh = || {
+        let mut cmd = Command::cargo_bin("rssh").unwrap();
+        cmd.env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_DATA_HOME", home.join("data"));
+        cmd
+    };
+
+    rssh()
+        .args(["profile", "add", "web", "--host", "relay.example.com", "--user", "tunnel"])
+        .args(["--tunnel", "8080:3000"])
+        .assert()
+        .success();
+
+    // Warnings are shown but do not stop the profile being saved
+    rssh()
+        .args(["profile", "add", "api", "--host", "relay.example.com", "--user", "tunnel"])
+        .args(["--tunnel", "8080:4000", "--key", "/nonexistent/id_ed25519"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: auth.path: key file '/nonexistent/id_ed25519' does not exist"))
+        .stderr(predicate::str::contains("profile 'web' also listens on port 8080"));
+
+    rssh()
+        .args(["profile", "add", "bad", "--host", "https://relay.example.com", "--user", "tunnel"])
+        .args(["--tunnel", "8081:3000"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("host: 'https://relay.example.com' is a URL"));
+
+    rssh()
+        .args(["profile", "edit", "web", "--port", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("port: must not be 0"));
+
+    rssh()
+        .args(["profile", "lint", "web"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("warning: tunnels[0].remote_port: profile 'api'"));
+
+    // A profile broken by hand is reported, and fails the lint
+    let profiles_dir = home.join("config").join("reverse-ssh-interface").join("profiles");
+    std::fs::write(
+        profiles_dir.join("broken.toml"),
+        "name = \"broken\"\nhost = \"relay.example.com\"\nuser = \"tunnel\"\ntunnels = []\n\n[extra_options]\nLocalCommand = \"touch /tmp/x\"\n",
+    )
+    .unwrap();
+
+    let output = rssh().args(["profile", "lint", "--format", "json"]).assert().failure().get_output().stdout.clone();
+    let lints: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let broken = lints.as_array().unwrap().iter().find(|lint| lint["name"] == "broken").unwrap();
+    assert_eq!(broken["diagnostics"][0]["severity"], "error");
+    assert_eq!(broken["diagnostics"][0]["field"], "extra_options.LocalCommand");
+
+    let _ = std::fs::remove_dir_all(&home);
+}
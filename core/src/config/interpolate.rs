@@ -0,0 +1,206 @@
+//! `${VAR}`, `${VAR:-default}` and `secret://<name>` references in profiles.
+//!
+//! Profile files keep their references; they are resolved only when a
+//! session starts, so a file can be shared between machines and checked in
+//! without the values it refers to.
+
+use serde_json::Value;
+
+use crate::error::{CoreError, Result};
+use crate::types::Profile;
+
+/// Prefix of a string field that names a secret
+pub const SECRET_PREFIX: &str = "secret://";
+
+/// Top-level fields left as written
+///
+/// Hook commands are programs and arguments that may be shell scripts, where
+/// `${VAR:-default}` is the shell's to expand.
+const LITERAL_FIELDS: [&str; 4] = ["id", "name", "extends", "hooks"];
+
+/// Looks up a secret by name; `Ok(None)` when there is no such secret
+pub type SecretLookup<'a> = &'a dyn Fn(&str) -> Result<Option<String>>;
+
+/// The profile with every reference in its string fields replaced
+///
+/// Environment variables come from this process. Fails with
+/// `ProfileInvalid` naming the field and the variable or secret that could
+/// not be resolved. The result is not validated.
+pub fn resolve_references(profile: &Profile, secrets: SecretLookup) -> Result<Profile> {
+    resolve_with(profile, &|name| std::env::var(name).ok(), secrets)
+}
+
+fn resolve_with(profile: &Profile, env: &dyn Fn(&str) -> Option<String>, secrets: SecretLookup) -> Result<Profile> {
+    let invalid = |field: &str, problem: String| {
+        CoreError::ProfileInvalid(format!("profile '{}', {}: {}", profile.name, field, problem))
+    };
+
+    let mut value = serde_json::to_value(profile).map_err(|e| CoreError::Other(e.to_string()))?;
+    let Value::Object(fields) = &mut value else {
+        return Err(CoreError::Other("profile did not serialize to an object".to_string()));
+    };
+
+    let mut resolved = false;
+    for (key, field) in fields.iter_mut() {
+        if LITERAL_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        resolved |= resolve_value(field, key, env, secrets).map_err(|(path, problem)| invalid(&path, problem))?;
+    }
+    if !resolved {
+        return Ok(profile.clone());
+    }
+
+    serde_json::from_value(value).map_err(|e| invalid("resolved profile", e.to_string()))
+}
+
+/// Resolve the strings in `value`, returning whether any changed
+///
+/// Errors carry the path of the field, e.g. `tunnels[0].local_host`.
+fn resolve_value(
+    value: &mut Value,
+    path: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+    secrets: SecretLookup,
+) -> std::result::Result<bool, (String, String)> {
+    match value {
+        Value::String(text) => match interpolate(text, env, secrets) {
+            Ok(Some(new)) => {
+                *text = new;
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(problem) => Err((path.to_string(), problem)),
+        },
+        Value::Array(items) => items.iter_mut().enumerate().try_fold(false, |changed, (index, item)| {
+            Ok(resolve_value(item, &format!("{}[{}]", path, index), env, secrets)? || changed)
+        }),
+        Value::Object(fields) => fields.iter_mut().try_fold(false, |changed, (key, field)| {
+            Ok(resolve_value(field, &format!("{}.{}", path, key), env, secrets)? || changed)
+        }),
+        _ => Ok(false),
+    }
+}
+
+/// Resolve the references in one string, or `None` when it has none
+///
+/// A string that is exactly `secret://<name>` becomes the secret. Elsewhere
+/// `${VAR}` is replaced by the variable, `${VAR:-default}` falls back to
+/// `default` when the variable is unset or empty, and `$${` is a literal `${`.
+fn interpolate(
+    text: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+    secrets: SecretLookup,
+) -> std::result::Result<Option<String>, String> {
+    if let Some(name) = text.strip_prefix(SECRET_PREFIX) {
+        if name.is_empty() {
+            return Err("'secret://' needs a secret name".to_string());
+        }
+        return match secrets(name) {
+            Ok(Some(secret)) => Ok(Some(secret)),
+            Ok(None) => Err(format!("secret '{}' is not in the secret store", name)),
+            Err(e) => Err(format!("cannot read secret '{}': {}", name, e)),
+        };
+    }
+    if !text.contains("${") {
+        return Ok(None);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            out.push_str(&rest[..start - 1]);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            return Err(format!("'{}' has an unterminated '${{'", text));
+        };
+        let reference = &rest[start + 2..start + len];
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("'${{{}}}' is not a valid variable reference", reference));
+        }
+
+        match (env(name).filter(|value| default.is_none() || !value.is_empty()), default) {
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => return Err(format!("environment variable '{}' is not set", name)),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    Ok(Some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AuthMethod, HookCommand, TunnelSpec};
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "RELAY_HOST" => Some("relay.example.com".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn secrets(name: &str) -> Result<Option<String>> {
+        Ok((name == "relay-password").then(|| "hunter2".to_string()))
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let resolve = |text: &str| interpolate(text, &env, &secrets);
+
+        assert_eq!(resolve("plain"), Ok(None));
+        assert_eq!(resolve("${RELAY_HOST}:2222").unwrap().unwrap(), "relay.example.com:2222");
+        assert_eq!(resolve("${MISSING:-fallback} ${EMPTY:-x}").unwrap().unwrap(), "fallback x");
+        assert_eq!(resolve("${EMPTY}").unwrap().unwrap(), "");
+        assert_eq!(resolve("cost $${HOME}").unwrap().unwrap(), "cost ${HOME}");
+        assert_eq!(resolve("secret://relay-password").unwrap().unwrap(), "hunter2");
+
+        assert_eq!(resolve("${MISSING}").unwrap_err(), "environment variable 'MISSING' is not set");
+        assert!(resolve("${RELAY_HOST").unwrap_err().contains("unterminated"));
+        assert!(resolve("${1BAD}").unwrap_err().contains("not a valid variable reference"));
+        assert_eq!(resolve("secret://other").unwrap_err(), "secret 'other' is not in the secret store");
+    }
+
+    #[test]
+    fn test_resolve_profile() {
+        let mut profile = Profile::new("edge", "${RELAY_HOST}", "${MISSING:-tunnel}")
+            .with_tunnel(TunnelSpec::local(15432, "${DB_HOST:-db.internal}", 5432));
+        profile.auth = AuthMethod::Password;
+        profile.password = Some("secret://relay-password".to_string());
+
+        let resolved = resolve_with(&profile, &env, &secrets).unwrap();
+        assert_eq!(resolved.host, "relay.example.com");
+        assert_eq!(resolved.user, "tunnel");
+        assert_eq!(resolved.tunnels[0].remote_bind, "db.internal");
+        assert_eq!(resolved.password.as_deref(), Some("hunter2"));
+        assert_eq!(resolved.id, profile.id);
+
+        let script = vec!["sh".to_string(), "-c".to_string(), "notify ${HOOK_TARGET:-ops}".to_string()];
+        profile.hooks.on_connected = Some(HookCommand { command: script.clone(), timeout_secs: 30 });
+        let resolved = resolve_with(&profile, &env, &secrets).unwrap();
+        assert_eq!(resolved.hooks.on_connected.unwrap().command, script);
+
+        profile.tunnels[0].local_host = "${BIND_ADDRESS}".to_string();
+        let error = resolve_with(&profile, &env, &secrets).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Invalid profile: profile 'edge', tunnels[0].local_host: environment variable 'BIND_ADDRESS' is not set"
+        );
+    }
+}
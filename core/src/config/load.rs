@@ -1,8 +1,12 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
 
 use crate::error::{CoreError, Result};
-use crate::types::Profile;
+use crate::storage::SecretStore;
+use crate::types::{Diagnostic, Profile};
 
+use super::interpolate::SECRET_PREFIX;
 use super::model::AppConfig;
 use super::paths;
 
@@ -23,9 +27,15 @@ pub fn load_config_from(path: &Path) -> Result<AppConfig> {
         CoreError::ConfigParse(format!("Failed to read config file: {}", e))
     })?;
 
-    toml::from_str(&content).map_err(|e| {
+    let config: AppConfig = toml::from_str(&content).map_err(|e| {
         CoreError::ConfigParse(format!("Failed to parse config file: {}", e))
-    })
+    })?;
+
+    if let Some(problem) = config.ssh.reconnect.problem() {
+        return Err(CoreError::ConfigInvalid(format!("[ssh.reconnect]: {}", problem)));
+    }
+
+    Ok(config)
 }
 
 /// Save the application configuration to the default location
@@ -60,18 +70,137 @@ pub fn load_profiles() -> Result<Vec<Profile>> {
     load_profiles_from(&profiles_dir)
 }
 
+/// Whether a password is written out rather than referred to
+fn is_plaintext(password: &str) -> bool {
+    !password.starts_with(SECRET_PREFIX) && !password.contains("${")
+}
+
+/// A plaintext password moved into the secret store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovedPassword {
+    /// The profile or template file that held it
+    pub path: PathBuf,
+    /// The secret it was stored as
+    pub secret: String,
+}
+
+/// Whether a profile or template file has a password in plain text
+pub fn has_plaintext_passwords() -> bool {
+    has_plaintext_passwords_in(&paths::profiles_dir())
+}
+
+/// Whether a profile or template file in `dir` has a password in plain text
+pub fn has_plaintext_passwords_in(dir: &Path) -> bool {
+    password_files(dir).iter().any(|path| plaintext_password(path).is_some())
+}
+
+/// Move the plaintext passwords of profiles and templates into the secret store
+pub fn migrate_plaintext_passwords(store: &mut SecretStore) -> Result<Vec<MovedPassword>> {
+    migrate_plaintext_passwords_in(&paths::profiles_dir(), store)
+}
+
+/// Move the plaintext passwords of the profiles in `dir` and the templates
+/// in `dir/templates` into the secret store
+///
+/// Each becomes the secret `<name>-password`, named after the profile or
+/// the template file, and only the `password` of the file is rewritten to
+/// refer to it. Stops at the first password that cannot be moved.
+pub fn migrate_plaintext_passwords_in(dir: &Path, store: &mut SecretStore) -> Result<Vec<MovedPassword>> {
+    let mut moved = Vec::new();
+    for path in password_files(dir) {
+        let Some((table, password)) = plaintext_password(&path) else { continue };
+        let name = match table.get("name").and_then(toml::Value::as_str) {
+            Some(name) => name.to_string(),
+            None => path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+        };
+
+        let secret = free_secret_name(store, &name, &password)?;
+        store.set(&secret, &password)?;
+        rewrite_password(&path, &format!("{}{}", SECRET_PREFIX, secret))?;
+
+        tracing::info!("Moved the password in {:?} to secret '{}' in {}", path, secret, store.describe());
+        moved.push(MovedPassword { path, secret });
+    }
+    Ok(moved)
+}
+
+/// Point the `password` of a profile or template file at `reference`,
+/// keeping the rest of the file, comments included, as written
+fn rewrite_password(path: &Path, reference: &str) -> Result<()> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        CoreError::ConfigParse(format!("Failed to read profile file: {}", e))
+    })?;
+    let mut document: toml_edit::Document = content.parse().map_err(|e| {
+        CoreError::ConfigParse(format!("Failed to parse {}: {}", path.display(), e))
+    })?;
+
+    let Some(password) = document.get_mut("password").and_then(toml_edit::Item::as_value_mut) else {
+        return Err(CoreError::ConfigParse(format!("{} has no password", path.display())));
+    };
+    let decor = password.decor().clone();
+    *password = reference.into();
+    *password.decor_mut() = decor;
+
+    // Write beside the file and rename, so a failed write leaves it intact
+    let write_error = |e: std::io::Error| CoreError::ConfigWrite(format!("Failed to write profile file: {}", e));
+    let temp = path.with_extension("toml.tmp");
+    std::fs::write(&temp, document.to_string()).map_err(write_error)?;
+    std::fs::rename(&temp, path).map_err(write_error)
+}
+
+/// The profile files in `dir` followed by the templates in `dir/templates`
+fn password_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = profile_files(dir).unwrap_or_default();
+    files.extend(profile_files(&dir.join("templates")).unwrap_or_default());
+    files
+}
+
+/// The file's own settings and its password, when that is in plain text
+fn plaintext_password(path: &Path) -> Option<(toml::Table, String)> {
+    let table = load_raw_profile_from(path).ok()?;
+    let password = table.get("password").and_then(toml::Value::as_str).filter(|p| is_plaintext(p))?.to_string();
+    Some((table, password))
+}
+
+/// A secret name for a profile's password that does not hold another value
+fn free_secret_name(store: &SecretStore, profile: &str, password: &str) -> Result<String> {
+    let base = format!("{}-password", sanitize_filename(profile));
+    let mut name = base.clone();
+    let mut count = 1;
+    while store.get(&name)?.is_some_and(|value| value != password) {
+        count += 1;
+        name = format!("{}-{}", base, count);
+    }
+    Ok(name)
+}
+
 /// Load all profiles from a specific directory
 pub fn load_profiles_from(dir: &Path) -> Result<Vec<Profile>> {
+    let mut profiles = Vec::new();
+
+    for path in profile_files(dir)? {
+        match load_profile_from(&path) {
+            Ok(profile) => profiles.push(profile),
+            Err(e) => {
+                tracing::warn!("Failed to load profile from {:?}: {}", path, e);
+            }
+        }
+    }
+
+    Ok(profiles)
+}
+
+/// The profile files in a directory, in name order
+fn profile_files(dir: &Path) -> Result<Vec<PathBuf>> {
     if !dir.exists() {
         return Ok(Vec::new());
     }
 
-    let mut profiles = Vec::new();
-
     let entries = std::fs::read_dir(dir).map_err(|e| {
         CoreError::ConfigParse(format!("Failed to read profiles directory: {}", e))
     })?;
 
+    let mut files = Vec::new();
     for entry in entries {
         let entry = entry.map_err(|e| {
             CoreError::ConfigParse(format!("Failed to read directory entry: {}", e))
@@ -80,30 +209,247 @@ pub fn load_profiles_from(dir: &Path) -> Result<Vec<Profile>> {
         let path = entry.path();
         
         // Only process .toml files
-        if path.extension().map_or(false, |ext| ext == "toml") {
-            match load_profile_from(&path) {
-                Ok(profile) => profiles.push(profile),
-                Err(e) => {
-                    tracing::warn!("Failed to load profile from {:?}: {}", path, e);
-                }
-            }
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            files.push(path);
         }
     }
+    files.sort();
 
-    Ok(profiles)
+    Ok(files)
+}
+
+/// What `rssh profile lint` found in one profile file
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileLint {
+    pub path: PathBuf,
+    /// The profile's name, or the file's when it cannot be read
+    pub name: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ProfileLint {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+}
+
+/// Check every profile in the profiles directory
+pub fn lint_profiles() -> Result<Vec<ProfileLint>> {
+    lint_profiles_from(&paths::profiles_dir())
+}
+
+/// Check every profile in a directory, including those that fail to load
+///
+/// A file that cannot be read or parsed gets a single error. The others are
+/// validated and compared with each other for clashing remote ports.
+pub fn lint_profiles_from(dir: &Path) -> Result<Vec<ProfileLint>> {
+    let parsed: Vec<(PathBuf, Result<Profile>)> =
+        profile_files(dir)?.into_iter().map(|path| {
+            let profile = parse_profile_file(&path);
+            (path, profile)
+        }).collect();
+    let profiles: Vec<Profile> = parsed.iter().filter_map(|(_, profile)| profile.as_ref().ok().cloned()).collect();
+
+    Ok(parsed
+        .into_iter()
+        .map(|(path, profile)| match profile {
+            Ok(profile) => ProfileLint {
+                diagnostics: profile.validate_among(&profiles),
+                name: profile.name,
+                path,
+            },
+            Err(e) => ProfileLint {
+                name: path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+                diagnostics: vec![Diagnostic::error("", e.to_string())],
+                path,
+            },
+        })
+        .collect())
 }
 
+/// Keys a profile never takes from the one it extends
+const NOT_INHERITED: [&str; 3] = ["id", "name", "extends"];
+
 /// Load a single profile from a file
+///
+/// A profile with `extends` starts from the settings of that template, in
+/// `templates/` beside it, or else of the profile with that name, which may
+/// extend another in turn. Its own settings win; tables such as
+/// `extra_options` are merged key by key and arrays such as `tunnels` are
+/// replaced whole. Its tunnels, jump hosts, fallback hosts, reconnect policy
+/// and hooks must be valid; [`lint_profiles`] reports everything else.
 pub fn load_profile_from(path: &Path) -> Result<Profile> {
+    let profile = parse_profile_file(path)?;
+
+    for tunnel in &profile.tunnels {
+        tunnel.validate()?;
+    }
+    for jump in &profile.jump_hosts {
+        jump.validate()?;
+    }
+    for endpoint in &profile.fallback_hosts {
+        endpoint.validate()?;
+    }
+    if let Some(policy) = &profile.reconnect {
+        policy.validate()?;
+    }
+    profile.hooks.validate()?;
+
+    Ok(profile)
+}
+
+/// Read a profile file and what it extends, without validating the result
+fn parse_profile_file(path: &Path) -> Result<Profile> {
     let content = std::fs::read_to_string(path).map_err(|e| {
         CoreError::ConfigParse(format!("Failed to read profile file: {}", e))
     })?;
 
-    toml::from_str(&content).map_err(|e| {
+    let own: toml::Table = toml::from_str(&content).map_err(|e| {
         CoreError::ConfigParse(format!("Failed to parse profile file: {}", e))
+    })?;
+
+    let profile: Profile = match extends_of(&own)? {
+        None => toml::from_str(&content),
+        Some(base) => {
+            let name = own.get("name").and_then(toml::Value::as_str).unwrap_or_default().to_string();
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let mut settings = inherited_settings(&name, path, &base, dir)?;
+            merge_tables(&mut settings, own);
+            toml::Value::Table(settings).try_into()
+        }
+    }
+    .map_err(|e| CoreError::ConfigParse(format!("Failed to parse profile file: {}", e)))?;
+
+    Ok(profile)
+}
+
+/// Load a profile's own settings by name, without what it inherits
+pub fn load_raw_profile(name: &str) -> Result<toml::Table> {
+    let path = find_profile_file(&paths::profiles_dir(), name)
+        .ok_or_else(|| CoreError::ProfileNotFound(name.to_string()))?;
+    load_raw_profile_from(&path)
+}
+
+/// Load the settings written in a profile or template file, without what it inherits
+pub fn load_raw_profile_from(path: &Path) -> Result<toml::Table> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        CoreError::ConfigParse(format!("Failed to read profile file: {}", e))
+    })?;
+
+    toml::from_str(&content).map_err(|e| {
+        CoreError::ConfigParse(format!("Failed to parse {}: {}", path.display(), e))
     })
 }
 
+/// The settings the profile `name` at `path` inherits through `extends = base`
+fn inherited_settings(name: &str, path: &Path, base: &str, dir: &Path) -> Result<toml::Table> {
+    let canonical = |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let mut chain = vec![name.to_string()];
+    let mut visited = vec![canonical(path)];
+    let mut layers = Vec::new();
+    let mut next = Some(base.to_string());
+
+    while let Some(base) = next {
+        let base_path = find_base(dir, &base).ok_or_else(|| {
+            CoreError::ProfileInvalid(format!(
+                "'{}' extends '{}', which is neither a template nor a profile",
+                chain.last().map(String::as_str).unwrap_or_default(),
+                base
+            ))
+        })?;
+
+        chain.push(base);
+        let base_path = canonical(&base_path);
+        if visited.contains(&base_path) {
+            return Err(CoreError::ProfileInvalid(format!("Profile inheritance cycle: {}", chain.join(" -> "))));
+        }
+
+        let mut layer = load_raw_profile_from(&base_path)?;
+        next = extends_of(&layer)?;
+        for key in NOT_INHERITED {
+            layer.remove(key);
+        }
+        visited.push(base_path);
+        layers.push(layer);
+    }
+
+    let mut settings = toml::Table::new();
+    for layer in layers.into_iter().rev() {
+        merge_tables(&mut settings, layer);
+    }
+    Ok(settings)
+}
+
+fn extends_of(table: &toml::Table) -> Result<Option<String>> {
+    match table.get("extends") {
+        None => Ok(None),
+        Some(toml::Value::String(base)) => Ok(Some(base.clone())),
+        Some(_) => Err(CoreError::ProfileInvalid(
+            "'extends' must be the name of a template or profile".to_string(),
+        )),
+    }
+}
+
+/// A template of that name, or else the profile
+fn find_base(dir: &Path, name: &str) -> Option<PathBuf> {
+    let template = dir.join("templates").join(format!("{}.toml", sanitize_filename(name)));
+    if template.is_file() {
+        return Some(template);
+    }
+    find_profile_file(dir, name)
+}
+
+/// The file in `dir` holding the profile named `name`
+fn find_profile_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    // Files are normally named after the profile; others are found by reading them
+    let expected = dir.join(format!("{}.toml", sanitize_filename(name)));
+    let named = |path: &Path| {
+        load_raw_profile_from(path)
+            .is_ok_and(|table| table.get("name").and_then(toml::Value::as_str) == Some(name))
+    };
+    if expected.is_file() && named(&expected) {
+        return Some(expected);
+    }
+
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| path.extension().is_some_and(|ext| ext == "toml") && named(path))
+}
+
+/// What a profile's optional settings are when neither it nor its bases set them
+fn default_settings() -> toml::Table {
+    let mut defaults = toml::Table::try_from(Profile::new("", "", "")).unwrap_or_default();
+    for key in NOT_INHERITED.iter().chain(&["host", "user", "tunnels"]) {
+        defaults.remove(*key);
+    }
+    defaults
+}
+
+/// Lay `overlay` over `base`, merging nested tables
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => merge_tables(existing, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Drop the settings `table` has in common with `inherited`
+fn remove_inherited(table: &mut toml::Table, inherited: &toml::Table) {
+    table.retain(|key, value| match (value, inherited.get(key)) {
+        (toml::Value::Table(own), Some(toml::Value::Table(base))) => {
+            remove_inherited(own, base);
+            !own.is_empty()
+        }
+        (value, base) => base != Some(&*value),
+    });
+}
+
 /// Save a profile to the profiles directory
 pub fn save_profile(profile: &Profile) -> Result<()> {
     let profiles_dir = paths::profiles_dir();
@@ -112,6 +458,8 @@ pub fn save_profile(profile: &Profile) -> Result<()> {
 
 /// Save a profile to a specific directory
 pub fn save_profile_to(profile: &Profile, dir: &Path) -> Result<()> {
+    profile.ensure_valid()?;
+
     // Ensure directory exists
     std::fs::create_dir_all(dir).map_err(|e| {
         CoreError::ConfigWrite(format!("Failed to create profiles directory: {}", e))
@@ -121,7 +469,20 @@ pub fn save_profile_to(profile: &Profile, dir: &Path) -> Result<()> {
     let filename = sanitize_filename(&profile.name);
     let path = dir.join(format!("{}.toml", filename));
 
-    let content = toml::to_string_pretty(profile).map_err(|e| {
+    // A profile that extends another only keeps what it changes
+    let content = match &profile.extends {
+        None => toml::to_string_pretty(profile),
+        Some(base) => {
+            let mut inherited = default_settings();
+            merge_tables(&mut inherited, inherited_settings(&profile.name, &path, base, dir)?);
+            let mut table = toml::Table::try_from(profile).map_err(|e| {
+                CoreError::ConfigWrite(format!("Failed to serialize profile: {}", e))
+            })?;
+            remove_inherited(&mut table, &inherited);
+            toml::to_string_pretty(&table)
+        }
+    }
+    .map_err(|e| {
         CoreError::ConfigWrite(format!("Failed to serialize profile: {}", e))
     })?;
 
@@ -134,10 +495,19 @@ pub fn save_profile_to(profile: &Profile, dir: &Path) -> Result<()> {
 }
 
 /// Delete a profile file
+///
+/// Fails with `ProfileInUse` while other profiles extend it.
 pub fn delete_profile(profile: &Profile) -> Result<()> {
     let profiles_dir = paths::profiles_dir();
+    delete_profile_from(profile, &profiles_dir)
+}
+
+/// Delete a profile file from a specific directory
+pub fn delete_profile_from(profile: &Profile, dir: &Path) -> Result<()> {
+    ensure_not_extended_in(&profile.name, dir)?;
+
     let filename = sanitize_filename(&profile.name);
-    let path = profiles_dir.join(format!("{}.toml", filename));
+    let path = dir.join(format!("{}.toml", filename));
 
     if path.exists() {
         std::fs::remove_file(&path).map_err(|e| {
@@ -149,11 +519,41 @@ pub fn delete_profile(profile: &Profile) -> Result<()> {
     Ok(())
 }
 
+/// Fail with `ProfileInUse` if other profiles extend the profile `name`,
+/// naming them; it can then be neither deleted nor renamed
+pub fn ensure_not_extended(name: &str) -> Result<()> {
+    ensure_not_extended_in(name, &paths::profiles_dir())
+}
+
+fn ensure_not_extended_in(name: &str, dir: &Path) -> Result<()> {
+    // A template of the same name is what `extends` finds first
+    if dir.join("templates").join(format!("{}.toml", sanitize_filename(name))).is_file() {
+        return Ok(());
+    }
+
+    let mut dependents = Vec::new();
+    for path in profile_files(dir)? {
+        let Ok(table) = load_raw_profile_from(&path) else { continue };
+        let extends_it = extends_of(&table).ok().flatten().as_deref() == Some(name);
+        match table.get("name").and_then(toml::Value::as_str) {
+            Some(dependent) if extends_it && dependent != name => dependents.push(dependent.to_string()),
+            _ => {}
+        }
+    }
+
+    if dependents.is_empty() {
+        Ok(())
+    } else {
+        Err(CoreError::ProfileInUse(format!("'{}' is extended by {}", name, dependents.join(", "))))
+    }
+}
+
 /// Update an existing profile by its current name.
 ///
 /// - If `updated.name` differs from `existing_name`, this performs a rename:
 ///   it saves the new profile and deletes the old profile file.
 /// - If a different profile already exists with the new name, returns `ProfileAlreadyExists`.
+/// - A profile that others extend cannot be renamed (`ProfileInUse`).
 pub fn update_profile(existing_name: &str, updated: &Profile) -> Result<()> {
     let profiles = load_profiles()?;
 
@@ -165,6 +565,9 @@ pub fn update_profile(existing_name: &str, updated: &Profile) -> Result<()> {
     if updated.name != existing_name && profiles.iter().any(|p| p.name == updated.name) {
         return Err(CoreError::ProfileAlreadyExists(updated.name.clone()));
     }
+    if updated.name != existing_name {
+        ensure_not_extended(existing_name)?;
+    }
 
     // Save new/updated profile
     save_profile(updated)?;
@@ -178,7 +581,7 @@ pub fn update_profile(existing_name: &str, updated: &Profile) -> Result<()> {
 }
 
 /// Sanitize a string for use as a filename
-fn sanitize_filename(name: &str) -> String {
+pub(crate) fn sanitize_filename(name: &str) -> String {
     name.chars()
         .map(|c| {
             if c.is_alphanumeric() || c == '-' || c == '_' {
@@ -217,4 +620,212 @@ mod tests {
         assert_eq!(sanitize_filename("test@server"), "test_server");
         assert_eq!(sanitize_filename("profile/with/slashes"), "profile_with_slashes");
     }
+
+    #[test]
+    fn test_load_profile_rejects_invalid_socket() {
+        let dir = std::env::temp_dir().join(format!("rssh-load-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bad.toml");
+        std::fs::write(&path, r#"
+name = "bad"
+host = "example.com"
+user = "me"
+
+[[tunnels]]
+remote_port = 2375
+local_socket = "docker.sock"
+"#).unwrap();
+
+        let result = load_profile_from(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(CoreError::ProfileInvalid(_))));
+    }
+
+    #[test]
+    fn test_reconnect_policy() {
+        use crate::types::JitterStrategy;
+
+        let dir = std::env::temp_dir().join(format!("rssh-reconnect-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.toml");
+        let profile_path = dir.join("edge.toml");
+
+        std::fs::write(&config_path, "[ssh.reconnect]\njitter = \"full\"\nreset_after_stable_secs = 60\n").unwrap();
+        let config = load_config_from(&config_path).unwrap();
+        assert_eq!(config.ssh.reconnect.jitter, JitterStrategy::Full);
+        assert_eq!(config.ssh.reconnect.max_delay_secs, 300);
+
+        std::fs::write(&config_path, "[ssh.reconnect]\nmultiplier = 0.5\n").unwrap();
+        let config = load_config_from(&config_path);
+
+        std::fs::write(&profile_path, r#"
+name = "edge"
+host = "example.com"
+user = "me"
+tunnels = []
+
+[reconnect]
+initial_delay_secs = 10
+max_delay_secs = 5
+"#).unwrap();
+        let profile = load_profile_from(&profile_path);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(config, Err(CoreError::ConfigInvalid(_))));
+        assert!(matches!(profile, Err(CoreError::ProfileInvalid(_))));
+    }
+
+    #[test]
+    fn test_profile_extends() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        std::fs::create_dir_all(dir.join("templates")).unwrap();
+        std::fs::write(dir.join("templates/device.toml"), r#"
+host = "relay.example.com"
+user = "device"
+keepalive_interval = 60
+
+[[tunnels]]
+remote_port = 0
+local_port = 22
+
+[extra_options]
+Compression = "yes"
+"#).unwrap();
+        std::fs::write(dir.join("edge-base.toml"), r#"
+id = "7c4f7ad8-4f63-4a51-9d5b-1f0c2d2b0d11"
+name = "edge-base"
+extends = "device"
+port = 2222
+
+[extra_options]
+ConnectTimeout = "10"
+"#).unwrap();
+        std::fs::write(dir.join("edge-7.toml"), r#"
+name = "edge-7"
+extends = "edge-base"
+
+[[tunnels]]
+remote_port = 10007
+local_port = 22
+"#).unwrap();
+
+        let profile = load_profile_from(&dir.join("edge-7.toml")).unwrap();
+        assert_eq!(profile.name, "edge-7");
+        assert_eq!(profile.extends.as_deref(), Some("edge-base"));
+        assert_eq!((profile.host.as_str(), profile.port, profile.keepalive_interval), ("relay.example.com", 2222, 60));
+        assert_eq!(profile.tunnels[0].remote_port, 10007);
+        assert_eq!(profile.extra_options.len(), 2);
+        // The base's id stays with the base
+        assert_ne!(profile.id.to_string(), "7c4f7ad8-4f63-4a51-9d5b-1f0c2d2b0d11");
+
+        // Saving keeps only what the profile changes
+        let mut edited = profile.clone();
+        edited.keepalive_count = 5;
+        save_profile_to(&edited, &dir).unwrap();
+        let raw = load_raw_profile_from(&dir.join("edge-7.toml")).unwrap();
+        let mut keys: Vec<&str> = raw.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["extends", "id", "keepalive_count", "name", "tunnels"]);
+        assert_eq!(load_profile_from(&dir.join("edge-7.toml")).unwrap().id, profile.id);
+
+        std::fs::write(dir.join("loop-a.toml"), "name = \"loop-a\"\nextends = \"loop-b\"\n").unwrap();
+        std::fs::write(dir.join("loop-b.toml"), "name = \"loop-b\"\nextends = \"loop-a\"\n").unwrap();
+        std::fs::write(dir.join("orphan.toml"), "name = \"orphan\"\nextends = \"gone\"\n").unwrap();
+        let cycle = load_profile_from(&dir.join("loop-a.toml"));
+        let orphan = load_profile_from(&dir.join("orphan.toml"));
+        let loaded = load_profiles_from(&dir).unwrap();
+
+        assert!(matches!(cycle, Err(CoreError::ProfileInvalid(ref e)) if e.ends_with("loop-a -> loop-b -> loop-a")));
+        assert!(matches!(orphan, Err(CoreError::ProfileInvalid(_))));
+        // Broken chains are skipped; templates are not profiles
+        let mut names: Vec<_> = loaded.iter().map(|p| p.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["edge-7", "edge-base"]);
+
+        // A base cannot go while profiles extend it
+        let base = loaded.iter().find(|p| p.name == "edge-base").unwrap();
+        let in_use = delete_profile_from(base, &dir);
+        assert!(matches!(in_use, Err(CoreError::ProfileInUse(ref e)) if e == "'edge-base' is extended by edge-7"));
+        assert!(dir.join("edge-base.toml").exists());
+        let leaf = loaded.iter().find(|p| p.name == "edge-7").unwrap();
+        delete_profile_from(leaf, &dir).unwrap();
+        delete_profile_from(base, &dir).unwrap();
+        assert!(!dir.join("edge-base.toml").exists());
+    }
+
+    #[test]
+    fn test_migrate_plaintext_passwords() {
+        use crate::storage::Vault;
+
+        let dir = tempfile::tempdir().unwrap();
+        let profiles_dir = dir.path().join("profiles");
+        let vault_path = dir.path().join("secrets.vault");
+        let open_store = || Vault::open_with_cost(&vault_path, "passphrase", 64, 1).map(SecretStore::Vault);
+
+        let mut edge = Profile::new("edge", "relay.example.com", "tunnel");
+        edge.password = Some("hunter2".to_string());
+        let mut env = Profile::new("env", "relay.example.com", "tunnel");
+        env.password = Some("${RELAY_PASSWORD}".to_string());
+        save_profile_to(&edge, &profiles_dir).unwrap();
+        save_profile_to(&env, &profiles_dir).unwrap();
+        std::fs::create_dir_all(profiles_dir.join("templates")).unwrap();
+        let template = "# Shared by the sensor fleet\nuser = \"device\"\npassword = \"swordfish\" # rotated yearly\nport = 2222\n";
+        std::fs::write(profiles_dir.join("templates/device.toml"), template).unwrap();
+        // Another value already holds the name the password would get
+        open_store().unwrap().set("edge-password", "older").unwrap();
+
+        // Loading leaves the files alone
+        let before = std::fs::read_to_string(profiles_dir.join("edge.toml")).unwrap();
+        assert_eq!(load_profiles_from(&profiles_dir).unwrap().len(), 2);
+        assert_eq!(std::fs::read_to_string(profiles_dir.join("edge.toml")).unwrap(), before);
+        assert!(has_plaintext_passwords_in(&profiles_dir));
+
+        let mut store = open_store().unwrap();
+        let moved = migrate_plaintext_passwords_in(&profiles_dir, &mut store).unwrap();
+        let secrets: Vec<_> = moved.iter().map(|moved| moved.secret.as_str()).collect();
+        assert_eq!(secrets, ["edge-password-2", "device-password"]);
+        assert!(!has_plaintext_passwords_in(&profiles_dir));
+
+        let mut profiles = load_profiles_from(&profiles_dir).unwrap();
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(profiles[0].password.as_deref(), Some("secret://edge-password-2"));
+        assert_eq!(profiles[1].password.as_deref(), Some("${RELAY_PASSWORD}"));
+        let saved = std::fs::read_to_string(profiles_dir.join("edge.toml")).unwrap();
+        assert!(saved.contains("secret://edge-password-2") && !saved.contains("hunter2"));
+        assert_eq!(
+            std::fs::read_to_string(profiles_dir.join("templates/device.toml")).unwrap(),
+            template.replace("\"swordfish\"", "\"secret://device-password\"")
+        );
+
+        assert_eq!(store.get("edge-password-2").unwrap().as_deref(), Some("hunter2"));
+        assert_eq!(store.get("edge-password").unwrap().as_deref(), Some("older"));
+        assert_eq!(store.get("device-password").unwrap().as_deref(), Some("swordfish"));
+        assert!(migrate_plaintext_passwords_in(&profiles_dir, &mut store).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lint_profiles() {
+        use crate::types::TunnelSpec;
+
+        let dir = tempfile::tempdir().unwrap();
+        let web = Profile::new("web", "relay.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 3000));
+        let api = Profile::new("api", "relay.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 4000));
+        save_profile_to(&web, dir.path()).unwrap();
+        save_profile_to(&api, dir.path()).unwrap();
+        std::fs::write(dir.path().join("broken.toml"), "name = \"broken\"\nhost = \"https://relay\"\nuser = \"me\"\ntunnels = []\n").unwrap();
+
+        let lints = lint_profiles_from(dir.path()).unwrap();
+        let names: Vec<_> = lints.iter().map(|lint| lint.name.as_str()).collect();
+        assert_eq!(names, ["api", "broken", "web"]);
+        assert!(!lints[0].has_errors());
+        assert_eq!(lints[0].diagnostics.len(), 1);
+        assert!(lints[0].diagnostics[0].message.contains("profile 'web'"));
+        assert!(lints[1].has_errors());
+        assert!(lints[1].diagnostics[0].message.contains("is a URL"), "{:?}", lints[1].diagnostics);
+
+        // Loading does not drop the broken profile; saving refuses one like it
+        assert_eq!(load_profiles_from(dir.path()).unwrap().len(), 3);
+        assert!(save_profile_to(&Profile::new("bad", "relay example", "me"), dir.path()).is_err());
+    }
 }
@@ -1,13 +1,23 @@
+pub mod interpolate;
 pub mod load;
 pub mod model;
 pub mod paths;
+pub mod ssh_config;
 
+pub use interpolate::{resolve_references, SecretLookup, SECRET_PREFIX};
 pub use load::{
-    delete_profile, init_config, load_config, load_config_from, load_profile_from, load_profiles,
-    load_profiles_from, save_config, save_config_to, save_profile, save_profile_to, update_profile,
+    delete_profile, delete_profile_from, ensure_not_extended, has_plaintext_passwords, has_plaintext_passwords_in, init_config, lint_profiles,
+    lint_profiles_from, load_config, load_config_from, load_profile_from, load_profiles, load_profiles_from, load_raw_profile,
+    load_raw_profile_from, migrate_plaintext_passwords, migrate_plaintext_passwords_in, save_config, save_config_to, save_profile,
+    save_profile_to, update_profile, MovedPassword, ProfileLint,
+};
+pub use model::{
+    ApiToken, AppConfig, GeneralConfig, HistoryConfig, LoggingConfig, SecretBackend, SecretsConfig, SshConfig,
+    StrictHostKeyChecking, WebConfig, WebRole, WebUser, WebhookConfig, WebhookFormat,
 };
-pub use model::{AppConfig, GeneralConfig, LoggingConfig, SshConfig, StrictHostKeyChecking, WebConfig};
 pub use paths::{
-    cache_dir, config_dir, config_file, data_dir, ensure_directories, known_hosts_file, logs_dir,
-    profiles_dir, state_file,
+    cache_dir, config_dir, config_file, daemon_log_file, data_dir, ensure_directories, history_file, jump_config_file,
+    keyring_index_file, known_hosts_file, logs_dir, profile_log_file, profile_logs_dir, profiles_dir, runtime_dir,
+    self_signed_cert_file, self_signed_key_file, session_log_file, socket_file, state_file, templates_dir, tls_dir, vault_file,
 };
+pub use ssh_config::{export_host_block, import_profiles, HostBlock, ResolvedHost, SshConfigFile, SshConfigImport};
@@ -2,8 +2,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::types::ReconnectPolicy;
+
 /// Global application configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
     /// General settings
     #[serde(default)]
@@ -17,21 +19,19 @@ pub struct AppConfig {
     /// Web server settings (if running web interface)
     #[serde(default)]
     pub web: WebConfig,
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            general: GeneralConfig::default(),
-            ssh: SshConfig::default(),
-            logging: LoggingConfig::default(),
-            web: WebConfig::default(),
-        }
-    }
+    /// Session history database settings
+    #[serde(default)]
+    pub history: HistoryConfig,
+    /// Where secrets referenced by profiles are kept
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    /// Webhooks notified of session lifecycle events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
 }
 
 /// General application settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GeneralConfig {
     /// Whether to start minimized (GUI)
     #[serde(default)]
@@ -43,16 +43,6 @@ pub struct GeneralConfig {
     pub default_profile: Option<String>,
 }
 
-impl Default for GeneralConfig {
-    fn default() -> Self {
-        Self {
-            start_minimized: false,
-            auto_start_sessions: false,
-            default_profile: None,
-        }
-    }
-}
-
 /// SSH-related configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SshConfig {
@@ -73,6 +63,13 @@ pub struct SshConfig {
     /// Use app-managed known_hosts file
     #[serde(default = "default_true")]
     pub use_app_known_hosts: bool,
+    /// Seconds a connection may take to authenticate and confirm all of its
+    /// tunnels before it is abandoned as timed out (0 = wait forever)
+    #[serde(default = "default_readiness_timeout")]
+    pub readiness_timeout_secs: u64,
+    /// Reconnection delays for profiles that do not set their own
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
 }
 
 fn default_keepalive_interval() -> u32 {
@@ -87,6 +84,10 @@ fn default_true() -> bool {
     true
 }
 
+fn default_readiness_timeout() -> u64 {
+    30
+}
+
 impl Default for SshConfig {
     fn default() -> Self {
         Self {
@@ -96,6 +97,8 @@ impl Default for SshConfig {
             default_options: HashMap::new(),
             strict_host_key_checking: StrictHostKeyChecking::default(),
             use_app_known_hosts: true,
+            readiness_timeout_secs: default_readiness_timeout(),
+            reconnect: ReconnectPolicy::default(),
         }
     }
 }
@@ -138,6 +141,9 @@ pub struct LoggingConfig {
     /// Number of rotated log files to keep
     #[serde(default = "default_max_log_files")]
     pub max_files: u32,
+    /// Number of per-session log files to keep
+    #[serde(default = "default_max_session_logs")]
+    pub max_session_logs: u32,
 }
 
 fn default_log_level() -> String {
@@ -152,6 +158,10 @@ fn default_max_log_files() -> u32 {
     5
 }
 
+fn default_max_session_logs() -> u32 {
+    50
+}
+
 impl Default for LoggingConfig {
     fn default() -> Self {
         Self {
@@ -159,10 +169,122 @@ impl Default for LoggingConfig {
             file_logging: true,
             max_file_size_mb: default_max_log_size(),
             max_files: default_max_log_files(),
+            max_session_logs: default_max_session_logs(),
         }
     }
 }
 
+/// Session history database settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Record connects, disconnects and failures in the history database
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Days of history to keep (0 = forever)
+    #[serde(default = "default_history_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_history_retention_days() -> u32 {
+    90
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: default_history_retention_days(),
+        }
+    }
+}
+
+/// Which secret store holds the secrets profiles refer to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    /// An existing vault, else the OS keyring when one is reachable, else a new vault
+    #[default]
+    Auto,
+    /// The OS keyring (Secret Service, macOS Keychain or Windows Credential Manager)
+    Keyring,
+    /// A file encrypted with a key derived from a passphrase
+    Vault,
+}
+
+/// Secret store settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    #[serde(default)]
+    pub backend: SecretBackend,
+    /// Vault file (default: `secrets.vault` in the data directory)
+    pub vault_path: Option<PathBuf>,
+}
+
+/// Payload style of a webhook
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// Event fields as a flat JSON object
+    #[default]
+    Generic,
+    /// Slack incoming webhook (`{"text": ...}`)
+    Slack,
+    /// Microsoft Teams incoming webhook (`{"text": ...}`)
+    Teams,
+}
+
+/// A webhook and the events it is sent for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Name shown in logs and used by `rssh notify test --webhook`
+    pub name: String,
+    /// URL the payload is POSTed to
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// Profiles to notify about (empty = all)
+    #[serde(default)]
+    pub profiles: Vec<String>,
+    /// Event types to notify about, e.g. `session_failed`
+    #[serde(default = "default_webhook_events")]
+    pub events: Vec<String>,
+    /// Minimum seconds between notifications of one event type for one profile;
+    /// events in between are counted and reported with the next one
+    #[serde(default = "default_webhook_debounce_secs")]
+    pub debounce_secs: u64,
+    /// JSON payload with `{{placeholder}}` strings, replacing the format's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<serde_json::Value>,
+    /// Extra request headers, e.g. `Authorization`
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub headers: std::collections::BTreeMap<String, String>,
+    /// Further attempts after a failed delivery
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+    /// Seconds to wait for the server to answer
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_webhook_events() -> Vec<String> {
+    ["session_connected", "session_disconnected", "session_failed"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_webhook_debounce_secs() -> u64 {
+    60
+}
+
+fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
 /// Web server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebConfig {
@@ -175,9 +297,89 @@ pub struct WebConfig {
     /// Port number
     #[serde(default = "default_web_port")]
     pub port: u16,
-    /// Enable CORS for development
+    /// Answer cross-origin requests from `cors_allowed_origins`
     #[serde(default)]
     pub cors_enabled: bool,
+    /// Origins (e.g. `https://dashboard.example.com`) allowed to call the API
+    /// from a browser when `cors_enabled` is set
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+    /// Static tokens accepted as `Authorization: Bearer <token>`
+    #[serde(default)]
+    pub api_tokens: Vec<ApiToken>,
+    /// Accounts that can sign in to the web UI
+    #[serde(default)]
+    pub users: Vec<WebUser>,
+    /// Lifetime of a web UI login in seconds
+    #[serde(default = "default_login_ttl")]
+    pub login_ttl_secs: u64,
+    /// PEM certificate chain to serve HTTPS with
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key for `tls_cert_path`
+    pub tls_key_path: Option<PathBuf>,
+    /// Serve HTTPS with a self-signed certificate, generated on first run,
+    /// when no certificate is configured
+    #[serde(default)]
+    pub tls_self_signed: bool,
+    /// Port of a plain HTTP listener that redirects to HTTPS
+    pub http_redirect_port: Option<u16>,
+    /// Recent events kept for event stream clients resuming with `Last-Event-ID`
+    #[serde(default = "default_event_buffer_size")]
+    pub event_buffer_size: usize,
+}
+
+impl WebConfig {
+    /// Whether any credentials are configured; without them every request is
+    /// treated as coming from an operator
+    pub fn auth_enabled(&self) -> bool {
+        !self.api_tokens.is_empty() || !self.users.is_empty()
+    }
+
+    /// Whether the web server speaks HTTPS
+    pub fn tls_enabled(&self) -> bool {
+        self.tls_self_signed || self.tls_cert_path.is_some() || self.tls_key_path.is_some()
+    }
+}
+
+/// What a web API caller may do
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebRole {
+    /// View profiles and sessions
+    #[default]
+    ReadOnly,
+    /// Also create, change and delete profiles, and start and stop sessions
+    Operator,
+}
+
+impl std::fmt::Display for WebRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebRole::ReadOnly => write!(f, "read_only"),
+            WebRole::Operator => write!(f, "operator"),
+        }
+    }
+}
+
+/// A static API token; only its hash is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// Label shown in logs
+    pub name: String,
+    /// Hex-encoded SHA-256 of the token
+    pub sha256: String,
+    #[serde(default)]
+    pub role: WebRole,
+}
+
+/// A web UI account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebUser {
+    pub username: String,
+    /// Argon2 hash in PHC string format (`$argon2id$...`)
+    pub password_hash: String,
+    #[serde(default)]
+    pub role: WebRole,
 }
 
 fn default_bind_address() -> String {
@@ -188,6 +390,14 @@ fn default_web_port() -> u16 {
     3847
 }
 
+fn default_login_ttl() -> u64 {
+    12 * 60 * 60
+}
+
+fn default_event_buffer_size() -> usize {
+    1024
+}
+
 impl Default for WebConfig {
     fn default() -> Self {
         Self {
@@ -195,6 +405,15 @@ impl Default for WebConfig {
             bind_address: default_bind_address(),
             port: default_web_port(),
             cors_enabled: false,
+            cors_allowed_origins: Vec::new(),
+            api_tokens: Vec::new(),
+            users: Vec::new(),
+            login_ttl_secs: default_login_ttl(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_self_signed: false,
+            http_redirect_port: None,
+            event_buffer_size: default_event_buffer_size(),
         }
     }
 }
@@ -210,6 +429,35 @@ mod tests {
         assert_eq!(config.ssh.default_keepalive_interval, 20);
         assert_eq!(config.logging.level, "info");
         assert_eq!(config.web.port, 3847);
+        assert!(!config.web.auth_enabled());
+        assert!(!config.web.tls_enabled());
+    }
+
+    #[test]
+    fn test_web_auth_config() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [web]
+            cors_enabled = true
+            cors_allowed_origins = ["https://dashboard.example.com"]
+
+            [[web.api_tokens]]
+            name = "monitoring"
+            sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+
+            [[web.users]]
+            username = "admin"
+            password_hash = "$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA"
+            role = "operator"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.web.auth_enabled());
+        assert_eq!(config.web.api_tokens[0].role, WebRole::ReadOnly);
+        assert_eq!(config.web.users[0].role, WebRole::Operator);
+        assert!(WebRole::Operator > WebRole::ReadOnly);
+        assert_eq!(config.web.login_ttl_secs, 12 * 60 * 60);
     }
 
     #[test]
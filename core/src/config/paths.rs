@@ -56,6 +56,21 @@ pub fn logs_dir() -> PathBuf {
     data_dir().join("logs")
 }
 
+/// Get the per-profile logs directory path
+pub fn profile_logs_dir() -> PathBuf {
+    logs_dir().join("profiles")
+}
+
+/// Get the log file path for a session
+pub fn session_log_file(session_id: &uuid::Uuid) -> PathBuf {
+    logs_dir().join(format!("{}.log", session_id))
+}
+
+/// Get the log file path for a profile (all of its sessions)
+pub fn profile_log_file(profile_name: &str) -> PathBuf {
+    profile_logs_dir().join(format!("{}.log", super::load::sanitize_filename(profile_name)))
+}
+
 /// Get the main configuration file path
 pub fn config_file() -> PathBuf {
     config_dir().join("config.toml")
@@ -66,11 +81,72 @@ pub fn profiles_dir() -> PathBuf {
     config_dir().join("profiles")
 }
 
+/// Get the directory of profile templates, which profiles may extend
+pub fn templates_dir() -> PathBuf {
+    profiles_dir().join("templates")
+}
+
 /// Get the state file path (runtime state persistence)
 pub fn state_file() -> PathBuf {
     data_dir().join("state.json")
 }
 
+/// Get the background daemon's log file path
+pub fn daemon_log_file() -> PathBuf {
+    data_dir().join("daemon.log")
+}
+
+/// Get the session history database path
+pub fn history_file() -> PathBuf {
+    data_dir().join("history.db")
+}
+
+/// Get the default secret vault path
+pub fn vault_file() -> PathBuf {
+    data_dir().join("secrets.vault")
+}
+
+/// Get the path of the list of secret names kept in the OS keyring
+///
+/// Keyrings cannot list entries by service, so the names are recorded here.
+pub fn keyring_index_file() -> PathBuf {
+    data_dir().join("keyring-secrets")
+}
+
+/// Get the runtime directory path (for sockets and other ephemeral files)
+/// - Linux: $XDG_RUNTIME_DIR/reverse-ssh-interface/ when set
+/// - Otherwise: the data directory
+pub fn runtime_dir() -> PathBuf {
+    project_dirs()
+        .and_then(|dirs| dirs.runtime_dir().map(|p| p.to_path_buf()))
+        .unwrap_or_else(data_dir)
+}
+
+/// Get the daemon control socket path
+pub fn socket_file() -> PathBuf {
+    runtime_dir().join("rssh.sock")
+}
+
+/// Get the generated ssh_config path for a profile's jump hosts
+pub fn jump_config_file(profile_id: &uuid::Uuid) -> PathBuf {
+    runtime_dir().join("jump").join(format!("{}.conf", profile_id))
+}
+
+/// Get the directory holding the web server's generated TLS certificate
+pub fn tls_dir() -> PathBuf {
+    data_dir().join("tls")
+}
+
+/// Get the self-signed web server certificate path
+pub fn self_signed_cert_file() -> PathBuf {
+    tls_dir().join("self-signed.crt")
+}
+
+/// Get the self-signed web server private key path
+pub fn self_signed_key_file() -> PathBuf {
+    tls_dir().join("self-signed.key")
+}
+
 /// Get the known_hosts file path (app-managed)
 pub fn known_hosts_file() -> PathBuf {
     config_dir().join("known_hosts")
@@ -97,9 +173,17 @@ mod tests {
         let _ = data_dir();
         let _ = cache_dir();
         let _ = logs_dir();
+        let _ = profile_logs_dir();
+        let _ = session_log_file(&uuid::Uuid::nil());
+        let _ = profile_log_file("test");
         let _ = config_file();
         let _ = profiles_dir();
         let _ = state_file();
+        let _ = runtime_dir();
+        let _ = socket_file();
         let _ = known_hosts_file();
+        let _ = jump_config_file(&uuid::Uuid::nil());
+        let _ = self_signed_cert_file();
+        let _ = self_signed_key_file();
     }
 }
@@ -0,0 +1,801 @@
+//! Reading profiles from OpenSSH client configuration and writing them back.
+//!
+//! Supports the subset of `ssh_config(5)` that describes hosts: `Host`
+//! blocks with wildcard and negated patterns, options before the first
+//! block, and `Include`. `Match` blocks are skipped. As in ssh, the first
+//! value found for an option wins, except for the options that may repeat
+//! (forwards, identity files).
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{CoreError, Result};
+use crate::types::{AuthMethod, JumpHost, Profile, TunnelEndpoint, TunnelKind, TunnelSpec};
+
+/// Nesting limit for `Include`, as in ssh
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Options that may be given more than once, every value counting
+const MULTI_VALUED: [&str; 5] = ["identityfile", "localforward", "remoteforward", "dynamicforward", "certificatefile"];
+
+/// Options copied into a profile's `extra_options` as they are
+const PASSED_THROUGH: [&str; 17] = [
+    "AddressFamily",
+    "BindAddress",
+    "Ciphers",
+    "Compression",
+    "ConnectTimeout",
+    "ConnectionAttempts",
+    "GatewayPorts",
+    "HostKeyAlgorithms",
+    "HostKeyAlias",
+    "IPQoS",
+    "KexAlgorithms",
+    "MACs",
+    "PubkeyAcceptedAlgorithms",
+    "PubkeyAcceptedKeyTypes",
+    "StreamLocalBindMask",
+    "StreamLocalBindUnlink",
+    "TCPKeepAlive",
+];
+
+/// Options left out on purpose, with the reason given in the warning
+const DROPPED: [(&str, &str); 4] = [
+    ("controlmaster", "a shared master connection would outlive the session"),
+    ("controlpath", "a shared master connection would outlive the session"),
+    ("controlpersist", "a shared master connection would outlive the session"),
+    ("stricthostkeychecking", "set ssh.strict_host_key_checking in config.toml instead"),
+];
+
+/// Options turned into profile fields rather than copied
+const MAPPED: [&str; 13] = [
+    "hostname",
+    "user",
+    "port",
+    "identityfile",
+    "proxyjump",
+    "userknownhostsfile",
+    "serveraliveinterval",
+    "serveralivecountmax",
+    "localforward",
+    "remoteforward",
+    "dynamicforward",
+    "exitonforwardfailure",
+    "identitiesonly",
+];
+
+/// One `Host` block, or the options before the first one (pattern `*`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostBlock {
+    pub patterns: Vec<String>,
+    /// Options in file order, keys lowercased
+    pub options: Vec<(String, String)>,
+}
+
+impl HostBlock {
+    fn new(patterns: Vec<String>) -> Self {
+        Self { patterns, options: Vec::new() }
+    }
+
+    /// Whether the block applies to `alias`: some pattern matches and no
+    /// negated pattern does
+    pub fn matches(&self, alias: &str) -> bool {
+        let alias = alias.to_ascii_lowercase();
+        let mut matched = false;
+        for pattern in &self.patterns {
+            let pattern = pattern.to_ascii_lowercase();
+            match pattern.strip_prefix('!') {
+                Some(negated) if wildcard_match(negated, &alias) => return false,
+                Some(_) => {}
+                None => matched |= wildcard_match(&pattern, &alias),
+            }
+        }
+        matched
+    }
+}
+
+/// A parsed ssh_config file with its includes inlined
+#[derive(Debug, Clone, Default)]
+pub struct SshConfigFile {
+    pub blocks: Vec<HostBlock>,
+    /// Lines that were skipped, e.g. `Match` blocks
+    pub warnings: Vec<String>,
+}
+
+impl SshConfigFile {
+    /// The user's `~/.ssh/config`
+    pub fn default_path() -> Option<PathBuf> {
+        directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(".ssh").join("config"))
+    }
+
+    /// Read a config file, following `Include` relative to `~/.ssh`
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| CoreError::ConfigParse(format!("Failed to read {}: {}", path.display(), e)))?;
+        let include_dir = directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(".ssh"));
+        Self::parse_with_includes(&text, include_dir.as_deref())
+    }
+
+    /// Parse config text; `Include` lines are skipped with a warning
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::parse_with_includes(text, None)
+    }
+
+    /// Parse config text, resolving relative `Include` paths against `include_dir`
+    pub fn parse_with_includes(text: &str, include_dir: Option<&Path>) -> Result<Self> {
+        let mut config = Self { blocks: vec![HostBlock::new(vec!["*".to_string()])], warnings: Vec::new() };
+        let mut in_match = false;
+        config.read(text, include_dir, 0, &mut in_match)?;
+        config.blocks.retain(|block| !block.options.is_empty() || block.patterns != ["*"]);
+        Ok(config)
+    }
+
+    fn read(&mut self, text: &str, include_dir: Option<&Path>, depth: usize, in_match: &mut bool) -> Result<()> {
+        for (number, line) in text.lines().enumerate() {
+            let words = split_words(line)
+                .map_err(|e| CoreError::ConfigParse(format!("ssh_config line {}: {}", number + 1, e)))?;
+            let Some((keyword, args)) = words.split_first() else { continue };
+            let keyword = keyword.to_ascii_lowercase();
+
+            match keyword.as_str() {
+                "host" => {
+                    *in_match = false;
+                    self.blocks.push(HostBlock::new(args.to_vec()));
+                }
+                "match" => {
+                    *in_match = true;
+                    self.warnings.push(format!("Skipped 'Match {}' block", args.join(" ")));
+                }
+                _ if *in_match => {}
+                "include" => self.include(args, include_dir, depth, in_match)?,
+                _ => {
+                    if args.is_empty() {
+                        return Err(CoreError::ConfigParse(format!(
+                            "ssh_config line {}: '{}' has no value",
+                            number + 1,
+                            words[0]
+                        )));
+                    }
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.options.push((keyword, args.join(" ")));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn include(&mut self, args: &[String], include_dir: Option<&Path>, depth: usize, in_match: &mut bool) -> Result<()> {
+        let Some(include_dir) = include_dir else {
+            self.warnings.push(format!("Skipped 'Include {}'", args.join(" ")));
+            return Ok(());
+        };
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(CoreError::ConfigParse("ssh_config Include nested too deeply".to_string()));
+        }
+
+        // Host blocks in an included file end at the end of that file
+        let outer = self.blocks.last().map(|block| block.patterns.clone()).unwrap_or_default();
+        let blocks_before = self.blocks.len();
+
+        for pattern in args {
+            for path in expand_include(pattern, include_dir) {
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => self.read(&text, Some(include_dir), depth + 1, in_match)?,
+                    Err(e) => self.warnings.push(format!("Skipped include {}: {}", path.display(), e)),
+                }
+            }
+        }
+
+        if self.blocks.len() != blocks_before {
+            *in_match = false;
+            self.blocks.push(HostBlock::new(outer));
+        }
+        Ok(())
+    }
+
+    /// Host names given literally in `Host` lines, in file order
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for block in &self.blocks {
+            for pattern in &block.patterns {
+                let literal = !pattern.contains(['*', '?', '!']);
+                if literal && !aliases.iter().any(|alias| alias.eq_ignore_ascii_case(pattern)) {
+                    aliases.push(pattern.clone());
+                }
+            }
+        }
+        aliases
+    }
+
+    /// The options that apply to `alias`, first value first
+    pub fn resolve(&self, alias: &str) -> ResolvedHost {
+        let mut options: Vec<(String, Vec<String>)> = Vec::new();
+        for block in self.blocks.iter().filter(|block| block.matches(alias)) {
+            for (key, value) in &block.options {
+                match options.iter_mut().find(|(existing, _)| existing == key) {
+                    Some((_, values)) if MULTI_VALUED.contains(&key.as_str()) => values.push(value.clone()),
+                    Some(_) => {}
+                    None => options.push((key.clone(), vec![value.clone()])),
+                }
+            }
+        }
+        ResolvedHost { alias: alias.to_string(), options }
+    }
+}
+
+/// The effective options for one host alias
+#[derive(Debug, Clone)]
+pub struct ResolvedHost {
+    pub alias: String,
+    /// Lowercased option names with their values
+    pub options: Vec<(String, Vec<String>)>,
+}
+
+impl ResolvedHost {
+    /// The value of a single-valued option
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.all(key).first().map(String::as_str)
+    }
+
+    /// Every value of a repeatable option
+    pub fn all(&self, key: &str) -> &[String] {
+        self.options
+            .iter()
+            .find(|(existing, _)| existing == key)
+            .map(|(_, values)| values.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// `HostName` with `%h` expanded, or the alias
+    pub fn hostname(&self) -> String {
+        match self.get("hostname") {
+            Some(name) => name.replace("%h", &self.alias).replace("%%", "%"),
+            None => self.alias.clone(),
+        }
+    }
+
+    fn port(&self) -> Result<u16> {
+        match self.get("port") {
+            Some(port) => port
+                .parse()
+                .map_err(|_| CoreError::ConfigParse(format!("Host {}: invalid Port '{}'", self.alias, port))),
+            None => Ok(22),
+        }
+    }
+
+    pub fn has_forwards(&self) -> bool {
+        ["localforward", "remoteforward", "dynamicforward"]
+            .iter()
+            .any(|key| !self.all(key).is_empty())
+    }
+}
+
+/// Profiles made from a config, and what could not be carried over
+#[derive(Debug, Clone, Default)]
+pub struct SshConfigImport {
+    pub profiles: Vec<Profile>,
+    pub warnings: Vec<String>,
+}
+
+/// Make a profile of each host with port forwards
+///
+/// `hosts` limits the import to those aliases; hosts named there must
+/// exist and have forwards. `default_user` is used for hosts without `User`.
+pub fn import_profiles(config: &SshConfigFile, hosts: &[String], default_user: Option<&str>) -> Result<SshConfigImport> {
+    let mut import = SshConfigImport { profiles: Vec::new(), warnings: config.warnings.clone() };
+    let aliases = config.aliases();
+
+    for wanted in hosts {
+        if !aliases.iter().any(|alias| alias.eq_ignore_ascii_case(wanted)) {
+            return Err(CoreError::ConfigParse(format!("Host '{}' is not in the ssh config", wanted)));
+        }
+    }
+
+    for alias in aliases {
+        let requested = hosts.iter().any(|wanted| wanted.eq_ignore_ascii_case(&alias));
+        if !hosts.is_empty() && !requested {
+            continue;
+        }
+
+        let host = config.resolve(&alias);
+        if !host.has_forwards() {
+            if requested {
+                return Err(CoreError::ConfigParse(format!("Host '{}' has no port forwards", alias)));
+            }
+            continue;
+        }
+
+        let profile = profile_from_host(config, &host, default_user, &mut import.warnings)?;
+        profile.tunnels.iter().try_for_each(TunnelSpec::validate)?;
+        profile.jump_hosts.iter().try_for_each(JumpHost::validate)?;
+        import.profiles.push(profile);
+    }
+
+    Ok(import)
+}
+
+fn profile_from_host(
+    config: &SshConfigFile,
+    host: &ResolvedHost,
+    default_user: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> Result<Profile> {
+    let alias = &host.alias;
+    let user = match (host.get("user"), default_user) {
+        (Some(user), _) | (None, Some(user)) => user.to_string(),
+        (None, None) => return Err(CoreError::ConfigParse(format!("Host {}: no User set", alias))),
+    };
+
+    let mut profile = Profile::new(alias.clone(), host.hostname(), user);
+    profile.port = host.port()?;
+
+    let identities = host.all("identityfile");
+    if let Some(path) = identities.first() {
+        profile.auth = AuthMethod::KeyFile { path: expand_home(path) };
+        if identities.len() > 1 {
+            warnings.push(format!("Host {}: only the first IdentityFile is used", alias));
+        }
+    }
+    if let Some(path) = host.get("userknownhostsfile") {
+        let first = path.split_whitespace().next().unwrap_or(path);
+        profile.known_hosts_file = Some(expand_home(first));
+    }
+    if let Some(interval) = host.get("serveraliveinterval").and_then(|v| v.parse().ok()) {
+        profile.keepalive_interval = interval;
+    }
+    if let Some(count) = host.get("serveralivecountmax").and_then(|v| v.parse().ok()) {
+        profile.keepalive_count = count;
+    }
+
+    match host.get("proxyjump") {
+        None => {}
+        Some(jumps) if jumps.eq_ignore_ascii_case("none") => {}
+        Some(jumps) => {
+            for jump in jumps.split(',') {
+                profile.jump_hosts.push(jump_host(config, jump.trim(), alias)?);
+            }
+        }
+    }
+
+    for spec in host.all("remoteforward") {
+        profile.tunnels.push(parse_forward(TunnelKind::Remote, spec).map_err(|e| forward_error(alias, spec, e))?);
+    }
+    for spec in host.all("localforward") {
+        profile.tunnels.push(parse_forward(TunnelKind::Local, spec).map_err(|e| forward_error(alias, spec, e))?);
+    }
+    for spec in host.all("dynamicforward") {
+        profile.tunnels.push(parse_forward(TunnelKind::Dynamic, spec).map_err(|e| forward_error(alias, spec, e))?);
+    }
+
+    for (key, values) in &host.options {
+        if let Some(name) = PASSED_THROUGH.iter().find(|name| name.eq_ignore_ascii_case(key)) {
+            profile.extra_options.insert(name.to_string(), values[0].clone());
+        } else if let Some((_, reason)) = DROPPED.iter().find(|(name, _)| name == key) {
+            warnings.push(format!("Host {}: '{}' is not imported: {}", alias, key, reason));
+        } else if !MAPPED.contains(&key.as_str()) {
+            warnings.push(format!("Host {}: '{}' is not imported", alias, key));
+        }
+    }
+
+    Ok(profile)
+}
+
+fn forward_error(alias: &str, spec: &str, error: String) -> CoreError {
+    CoreError::ConfigParse(format!("Host {}: cannot import forward '{}': {}", alias, spec, error))
+}
+
+/// A `ProxyJump` hop, looked up in the config when it names a host there
+fn jump_host(config: &SshConfigFile, spec: &str, alias: &str) -> Result<JumpHost> {
+    let spec = spec.strip_prefix("ssh://").unwrap_or(spec);
+    let (user, rest) = match spec.rsplit_once('@') {
+        Some((user, rest)) => (Some(user.to_string()), rest),
+        None => (None, spec),
+    };
+    let (name, port) = match split_host_port(rest) {
+        Some((host, port)) => {
+            let port = port
+                .parse()
+                .map_err(|_| CoreError::ConfigParse(format!("Host {}: invalid ProxyJump port in '{}'", alias, spec)))?;
+            (host, Some(port))
+        }
+        None => (rest.trim_matches(['[', ']']).to_string(), None),
+    };
+
+    let mut jump = JumpHost::new(name.clone());
+    if config.aliases().iter().any(|known| known.eq_ignore_ascii_case(&name)) {
+        let hop = config.resolve(&name);
+        jump.host = hop.hostname();
+        jump.port = hop.port()?;
+        jump.user = hop.get("user").map(str::to_string);
+        if let Some(path) = hop.all("identityfile").first() {
+            jump.auth = AuthMethod::KeyFile { path: expand_home(path) };
+        }
+    }
+    if let Some(port) = port {
+        jump.port = port;
+    }
+    if user.is_some() {
+        jump.user = user;
+    }
+    Ok(jump)
+}
+
+/// Parse the arguments of a `RemoteForward`, `LocalForward` or `DynamicForward` line
+fn parse_forward(kind: TunnelKind, spec: &str) -> std::result::Result<TunnelSpec, String> {
+    let parts: Vec<&str> = spec.split_whitespace().collect();
+    match (kind, parts.as_slice()) {
+        (TunnelKind::Remote, [listen]) => {
+            let TunnelEndpoint::Tcp { host, port } = listen_endpoint(listen)? else {
+                return Err("a SOCKS forward needs a port, not a socket".to_string());
+            };
+            let mut tunnel = TunnelSpec::remote_dynamic(port);
+            tunnel.remote_bind = host;
+            Ok(tunnel)
+        }
+        (TunnelKind::Remote, [listen, target]) => Ok(TunnelSpec::new(0, 0)
+            .with_remote_endpoint(listen_endpoint(listen)?)
+            .with_local_endpoint(target_endpoint(target)?)),
+        (TunnelKind::Local, [listen, target]) => Ok(TunnelSpec::local(0, "localhost", 0)
+            .with_local_endpoint(listen_endpoint(listen)?)
+            .with_remote_endpoint(target_endpoint(target)?)),
+        (TunnelKind::Dynamic, [listen]) => {
+            let TunnelEndpoint::Tcp { host, port } = listen_endpoint(listen)? else {
+                return Err("a SOCKS forward needs a port, not a socket".to_string());
+            };
+            let mut tunnel = TunnelSpec::dynamic(port);
+            tunnel.local_host = host;
+            Ok(tunnel)
+        }
+        _ => Err("expected '[bind_address:]port host:hostport'".to_string()),
+    }
+}
+
+/// `[bind:]port` or a socket path; no bind address means localhost
+fn listen_endpoint(spec: &str) -> std::result::Result<TunnelEndpoint, String> {
+    if spec.starts_with('/') {
+        return Ok(TunnelEndpoint::Socket(spec.to_string()));
+    }
+    let (host, port) = split_host_port(spec).unwrap_or_else(|| ("localhost".to_string(), spec));
+    let port = port.parse().map_err(|_| format!("invalid port '{}'", port))?;
+    Ok(TunnelEndpoint::Tcp { host, port })
+}
+
+/// `host:port` or a socket path
+fn target_endpoint(spec: &str) -> std::result::Result<TunnelEndpoint, String> {
+    if spec.starts_with('/') {
+        return Ok(TunnelEndpoint::Socket(spec.to_string()));
+    }
+    let (host, port) = split_host_port(spec).ok_or_else(|| format!("expected host:port, got '{}'", spec))?;
+    let port = port.parse().map_err(|_| format!("invalid port '{}'", port))?;
+    Ok(TunnelEndpoint::Tcp { host, port })
+}
+
+/// Split `host:port`, `[v6]:port` or `host/port`; `None` without a separator
+fn split_host_port(spec: &str) -> Option<(String, &str)> {
+    if let Some(rest) = spec.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:")?;
+        return Some((host.to_string(), port));
+    }
+    spec.rsplit_once(':')
+        .or_else(|| spec.rsplit_once('/'))
+        .map(|(host, port)| (host.to_string(), port))
+}
+
+/// An ssh_config `Host` block equivalent to the profile
+///
+/// Settings ssh has no option for (fallback hosts, hooks, health checks,
+/// reconnection) are left out; those a reader might miss are noted in
+/// comments above the block.
+pub fn export_host_block(profile: &Profile) -> String {
+    let mut comments = vec![format!("# rssh profile '{}'", profile.name)];
+    let mut options: Vec<(String, String)> = Vec::new();
+    let mut option = |key: &str, value: String| options.push((key.to_string(), value));
+
+    option("HostName", profile.host.clone());
+    option("User", profile.user.clone());
+    if profile.port != 22 {
+        option("Port", profile.port.to_string());
+    }
+    match &profile.auth {
+        AuthMethod::KeyFile { path } => {
+            option("IdentityFile", quote(path));
+            option("IdentitiesOnly", "yes".to_string());
+        }
+        AuthMethod::Agent => {}
+        AuthMethod::Password => comments.push("# Authenticates with a password".to_string()),
+    }
+    if let Some(path) = &profile.identity_file {
+        option("IdentityFile", quote(path));
+    }
+    if let Some(path) = &profile.known_hosts_file {
+        option("UserKnownHostsFile", quote(path));
+    }
+    if !profile.jump_hosts.is_empty() {
+        let chain: Vec<String> = profile.jump_hosts.iter().map(JumpHost::destination).collect();
+        option("ProxyJump", chain.join(","));
+        for hop in profile.jump_hosts.iter().filter(|hop| hop.needs_config()) {
+            comments.push(format!("# Jump host {} needs its own Host block for its key or known_hosts", hop.host));
+        }
+    }
+    option("ServerAliveInterval", profile.keepalive_interval.to_string());
+    option("ServerAliveCountMax", profile.keepalive_count.to_string());
+
+    for tunnel in &profile.tunnels {
+        let remote = endpoint(&tunnel.remote_endpoint());
+        let local = endpoint(&tunnel.local_endpoint());
+        match tunnel.kind {
+            TunnelKind::Remote => option("RemoteForward", format!("{} {}", remote, local)),
+            TunnelKind::Local => option("LocalForward", format!("{} {}", local, remote)),
+            TunnelKind::Dynamic => option("DynamicForward", local),
+            TunnelKind::RemoteDynamic => option("RemoteForward", remote),
+        }
+    }
+    if !profile.tunnels.is_empty() {
+        option("ExitOnForwardFailure", "yes".to_string());
+    }
+
+    let mut extra: Vec<_> = profile.extra_options.iter().collect();
+    extra.sort();
+    for (key, value) in extra {
+        option(key, value.clone());
+    }
+
+    if !profile.fallback_hosts.is_empty() {
+        let hosts: Vec<String> = profile.fallback_hosts.iter().map(ToString::to_string).collect();
+        comments.push(format!("# Fallback hosts are not included: {}", hosts.join(", ")));
+    }
+
+    let mut block = comments.join("\n");
+    block.push_str(&format!("\nHost {}\n", quote(&profile.name)));
+    for (key, value) in options {
+        block.push_str(&format!("    {} {}\n", key, value));
+    }
+    block
+}
+
+/// A forward endpoint as written in ssh_config
+fn endpoint(endpoint: &TunnelEndpoint) -> String {
+    match endpoint {
+        TunnelEndpoint::Tcp { host, port } if host.contains(':') => format!("[{}]:{}", host, port),
+        TunnelEndpoint::Tcp { host, port } => format!("{}:{}", host, port),
+        TunnelEndpoint::Socket(path) => path.clone(),
+    }
+}
+
+/// Quote a name or path containing spaces
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Split a config line into words: `Key value`, `Key=value`, double quotes
+/// group words and `#` starts a comment
+fn split_words(line: &str) -> std::result::Result<Vec<String>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(Vec::new());
+    }
+
+    // The keyword may be followed by '=' instead of (or around) whitespace
+    let keyword_end = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(keyword_end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut words = vec![keyword.to_string()];
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            '#' if !quoted && !in_word => break,
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".to_string());
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+/// ssh's `*` and `?` wildcards
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|skip| matches(rest, &text[skip..])),
+            Some(('?', rest)) => !text.is_empty() && matches(rest, &text[1..]),
+            Some((c, rest)) => text.first() == Some(c) && matches(rest, &text[1..]),
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+/// Files an `Include` argument names, sorted; wildcards only in the file name
+fn expand_include(pattern: &str, include_dir: &Path) -> Vec<PathBuf> {
+    let path = PathBuf::from(expand_home(pattern));
+    let path = if path.is_absolute() { path } else { include_dir.join(path) };
+
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return if path.exists() { vec![path] } else { Vec::new() };
+    }
+
+    let Some(dir) = path.parent() else { return Vec::new() };
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.is_file()
+                        && path
+                            .file_name()
+                            .is_some_and(|file| wildcard_match(&name, &file.to_string_lossy()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Replace a leading `~/` with the home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), directories::BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Defaults for everything
+ServerAliveInterval 15
+
+Host edge-* !edge-lab
+    User tunnel
+    IdentityFile ~/.ssh/edge_ed25519
+
+Host bastion
+    HostName bastion.example.com
+    User ops
+    Port 2222
+
+Host edge-web edge-lab
+    HostName %h.example.com
+    ProxyJump bastion
+    RemoteForward 8080 localhost:3000
+    RemoteForward 0.0.0.0:9090 /run/app.sock
+    LocalForward=127.0.0.1:15432 db.internal:5432
+    DynamicForward 1080
+    ForwardAgent yes
+    ControlMaster auto
+
+Match host *.corp
+    User nobody
+
+Host plain
+    HostName plain.example.com
+"#;
+
+    #[test]
+    fn test_parse_and_resolve() {
+        let config = SshConfigFile::parse(CONFIG).unwrap();
+        assert_eq!(config.aliases(), vec!["bastion", "edge-web", "edge-lab", "plain"]);
+        assert!(config.warnings.iter().any(|w| w.contains("Match host *.corp")));
+
+        let web = config.resolve("edge-web");
+        assert_eq!(web.get("user"), Some("tunnel"));
+        assert_eq!(web.hostname(), "edge-web.example.com");
+        assert_eq!(web.get("serveraliveinterval"), Some("15"));
+        assert_eq!(web.all("remoteforward").len(), 2);
+
+        // Negated patterns exclude a host from a block
+        assert_eq!(config.resolve("edge-lab").get("user"), None);
+        // Options in a Match block apply to nothing
+        assert_eq!(config.resolve("plain").get("user"), None);
+    }
+
+    #[test]
+    fn test_import_profiles() {
+        let config = SshConfigFile::parse(CONFIG).unwrap();
+        let import = import_profiles(&config, &[], Some("me")).unwrap();
+
+        // Only hosts with forwards become profiles
+        let names: Vec<_> = import.profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["edge-web", "edge-lab"]);
+
+        let web = &import.profiles[0];
+        assert_eq!(web.host, "edge-web.example.com");
+        assert_eq!(web.user, "tunnel");
+        assert_eq!(web.keepalive_interval, 15);
+        assert!(matches!(&web.auth, AuthMethod::KeyFile { path } if path.ends_with(".ssh/edge_ed25519")));
+        assert_eq!(web.jump_hosts[0].host, "bastion.example.com");
+        assert_eq!(web.jump_hosts[0].port, 2222);
+        assert_eq!(web.jump_hosts[0].user.as_deref(), Some("ops"));
+        assert!(!web.extra_options.contains_key("ControlMaster"));
+        assert!(import.warnings.iter().any(|w| w.contains("'controlmaster' is not imported: a shared master")));
+
+        let tunnels: Vec<String> = web.tunnels.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            tunnels,
+            vec![
+                "-R localhost:8080:localhost:3000",
+                "-R 0.0.0.0:9090:/run/app.sock",
+                "-L 127.0.0.1:15432:db.internal:5432",
+                "-D localhost:1080",
+            ]
+        );
+        assert!(import.warnings.iter().any(|w| w.contains("'forwardagent' is not imported")));
+
+        // Hosts without User take the default
+        assert_eq!(import.profiles[1].user, "me");
+
+        assert!(import_profiles(&config, &["plain".to_string()], None).is_err());
+        assert!(import_profiles(&config, &["missing".to_string()], None).is_err());
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let config = SshConfigFile::parse(CONFIG).unwrap();
+        let profile = import_profiles(&config, &["edge-web".to_string()], None).unwrap().profiles.remove(0);
+
+        let block = export_host_block(&profile);
+        assert!(block.starts_with("# rssh profile 'edge-web'\nHost edge-web\n"), "{}", block);
+        assert!(block.contains("    RemoteForward localhost:8080 localhost:3000\n"));
+        assert!(block.contains("    ProxyJump ops@bastion.example.com:2222\n"));
+
+        let again = import_profiles(&SshConfigFile::parse(&block).unwrap(), &[], None).unwrap();
+        let reimported = &again.profiles[0];
+        assert_eq!(reimported.host, profile.host);
+        assert_eq!(reimported.tunnels, profile.tunnels);
+        assert_eq!(reimported.jump_hosts, profile.jump_hosts);
+        assert_eq!(reimported.extra_options, profile.extra_options);
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = std::env::temp_dir().join(format!("rssh-ssh-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        std::fs::write(dir.join("conf.d/10-edge"), "Host edge\n    RemoteForward 8080 localhost:80\n").unwrap();
+        std::fs::write(dir.join("conf.d/20-other"), "Host other\n    HostName other.example.com\n").unwrap();
+
+        let text = "Host edge\n    User tunnel\n    Include conf.d/*\n    Port 2200\n";
+        let config = SshConfigFile::parse_with_includes(text, Some(&dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let edge = config.resolve("edge");
+        assert_eq!(edge.get("user"), Some("tunnel"));
+        assert_eq!(edge.all("remoteforward"), ["8080 localhost:80"]);
+        // Lines after the Include belong to the block that held it again
+        assert_eq!(edge.get("port"), Some("2200"));
+        assert_eq!(config.resolve("other").get("port"), None);
+
+        // Text imports do not read other files
+        assert!(SshConfigFile::parse(text).unwrap().warnings[0].starts_with("Skipped 'Include"));
+    }
+}
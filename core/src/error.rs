@@ -23,6 +23,15 @@ pub enum CoreError {
     #[error("SSH process terminated by signal")]
     SshSignalTerminated,
 
+    #[error("SSH backend '{0}' is not available")]
+    BackendUnavailable(crate::types::BackendKind),
+
+    #[error("SSH protocol error: {0}")]
+    Ssh(#[from] russh::Error),
+
+    #[error("Host key verification failed: {0}")]
+    HostKeyRejected(String),
+
     // Config-related errors
     #[error("Configuration file not found: {0}")]
     ConfigNotFound(PathBuf),
@@ -46,6 +55,9 @@ pub enum CoreError {
     #[error("Invalid profile: {0}")]
     ProfileInvalid(String),
 
+    #[error("Profile is extended by others: {0}")]
+    ProfileInUse(String),
+
     // Session-related errors
     #[error("Session not found: {0}")]
     SessionNotFound(String),
@@ -59,16 +71,39 @@ pub enum CoreError {
     #[error("Maximum reconnection attempts reached")]
     MaxReconnectAttemptsReached,
 
+    #[error("{0} hook failed: {1}")]
+    HookFailed(crate::types::HookPoint, String),
+
     // Storage errors
     #[error("Failed to access storage: {0}")]
     StorageAccess(String),
 
+    #[error("Secret store error: {0}")]
+    SecretStore(String),
+
     #[error("Failed to serialize data: {0}")]
     Serialization(String),
 
     #[error("Failed to deserialize data: {0}")]
     Deserialization(String),
 
+    // IPC errors
+    #[error("Daemon is not running")]
+    DaemonNotRunning,
+
+    #[error("Daemon is already running at {0}")]
+    DaemonAlreadyRunning(PathBuf),
+
+    #[error("IPC protocol version mismatch: client speaks {client}, daemon speaks {daemon}")]
+    IpcVersionMismatch { client: u32, daemon: u32 },
+
+    #[error("IPC error: {0}")]
+    Ipc(String),
+
+    // Notification errors
+    #[error("Webhook delivery failed: {0}")]
+    Webhook(String),
+
     // IO errors
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use uuid::Uuid;
+
+use crate::error::{CoreError, Result};
+use crate::supervisor::StartSessionOptions;
+use crate::types::{Event, Profile, Session};
+
+use super::protocol::{decode, encode, Request, Response, PROTOCOL_VERSION};
+
+/// Client connection to a running daemon
+pub struct IpcClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    daemon_pid: u32,
+}
+
+impl IpcClient {
+    /// Connect to the daemon socket and perform the version handshake.
+    ///
+    /// Returns `DaemonNotRunning` when nothing is listening at `path`.
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self> {
+        let stream = UnixStream::connect(path.as_ref())
+            .await
+            .map_err(|_| CoreError::DaemonNotRunning)?;
+        let (reader, writer) = stream.into_split();
+
+        let mut client = Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+            daemon_pid: 0,
+        };
+
+        match client.request(&Request::Hello { version: PROTOCOL_VERSION }).await? {
+            Response::Hello { version, pid } if version == PROTOCOL_VERSION => {
+                client.daemon_pid = pid;
+                Ok(client)
+            }
+            Response::Hello { version, .. } => Err(CoreError::IpcVersionMismatch {
+                client: PROTOCOL_VERSION,
+                daemon: version,
+            }),
+            Response::Error { message } => Err(CoreError::Ipc(message)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Process ID reported by the daemon during the handshake
+    pub fn daemon_pid(&self) -> u32 {
+        self.daemon_pid
+    }
+
+    async fn send(&mut self, request: &Request) -> Result<()> {
+        self.writer.write_all(encode(request)?.as_bytes()).await?;
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<Response> {
+        match self.lines.next_line().await? {
+            Some(line) => decode(&line),
+            None => Err(CoreError::Ipc("Daemon closed the connection".to_string())),
+        }
+    }
+
+    async fn request(&mut self, request: &Request) -> Result<Response> {
+        self.send(request).await?;
+        self.recv().await
+    }
+
+    /// Start a session for a profile
+    pub async fn start(&mut self, profile: Profile, options: StartSessionOptions) -> Result<Uuid> {
+        let request = Request::Start { profile: Box::new(profile), options };
+        match self.request(&request).await? {
+            Response::Started { session_id } => Ok(session_id),
+            Response::Error { message } => Err(CoreError::Other(message)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Stop a session
+    pub async fn stop(&mut self, session_id: Uuid) -> Result<()> {
+        match self.request(&Request::Stop { session_id }).await? {
+            Response::Stopped { .. } => Ok(()),
+            Response::Error { message } => Err(CoreError::Other(message)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Stop all sessions
+    pub async fn stop_all(&mut self) -> Result<()> {
+        match self.request(&Request::StopAll).await? {
+            Response::AllStopped => Ok(()),
+            Response::Error { message } => Err(CoreError::Other(message)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Get status of all sessions
+    pub async fn status(&mut self) -> Result<Vec<Session>> {
+        match self.request(&Request::Status).await? {
+            Response::Status { sessions } => Ok(sessions),
+            Response::Error { message } => Err(CoreError::Other(message)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Stop all sessions and ask the daemon to exit
+    pub async fn shutdown(&mut self) -> Result<()> {
+        match self.request(&Request::Shutdown).await? {
+            Response::ShuttingDown => Ok(()),
+            Response::Error { message } => Err(CoreError::Other(message)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Turn this connection into an event stream
+    pub async fn subscribe(mut self) -> Result<EventStream> {
+        match self.request(&Request::Subscribe).await? {
+            Response::Subscribed => Ok(EventStream { client: self }),
+            Response::Error { message } => Err(CoreError::Other(message)),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+/// Stream of manager events received from the daemon
+pub struct EventStream {
+    client: IpcClient,
+}
+
+impl EventStream {
+    /// Wait for the next event.
+    ///
+    /// Returns `None` once the daemon shuts down. Dropped events are logged
+    /// and skipped.
+    pub async fn next(&mut self) -> Result<Option<Event>> {
+        loop {
+            match self.client.recv().await? {
+                Response::Event { event } => return Ok(Some(*event)),
+                Response::Lagged { missed } => {
+                    tracing::warn!("Missed {} events from daemon", missed);
+                }
+                Response::ShuttingDown => return Ok(None),
+                Response::Error { message } => return Err(CoreError::Ipc(message)),
+                other => return Err(unexpected(other)),
+            }
+        }
+    }
+}
+
+fn unexpected(response: Response) -> CoreError {
+    CoreError::Ipc(format!("Unexpected response from daemon: {:?}", response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::ipc::IpcServer;
+    use crate::supervisor::SessionManager;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_client_server_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("rssh.sock");
+
+        let (manager, handle) = SessionManager::new(AppConfig::default());
+        tokio::spawn(manager.run());
+
+        let server = IpcServer::bind(&path, handle).unwrap();
+        let server_task = tokio::spawn(server.run());
+
+        let mut client = IpcClient::connect(&path).await.unwrap();
+        assert_eq!(client.daemon_pid(), std::process::id());
+        assert!(client.status().await.unwrap().is_empty());
+
+        // The manager was never initialized, so starting must surface an error
+        let profile = Profile::new("test", "example.com", "user");
+        assert!(client.start(profile, StartSessionOptions::default()).await.is_err());
+
+        client.shutdown().await.unwrap();
+        server_task.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_connect_without_daemon() {
+        let dir = tempdir().unwrap();
+        let result = IpcClient::connect(dir.path().join("missing.sock")).await;
+        assert!(matches!(result, Err(CoreError::DaemonNotRunning)));
+    }
+}
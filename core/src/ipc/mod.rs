@@ -0,0 +1,12 @@
+//! Local control channel between the `rssh` daemon and its clients.
+//!
+//! The daemon owns a single `SessionManager` and exposes it over a Unix
+//! domain socket so that separate CLI invocations share the same sessions.
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+pub use client::{EventStream, IpcClient};
+pub use protocol::{Request, Response, PROTOCOL_VERSION};
+pub use server::IpcServer;
@@ -0,0 +1,113 @@
+//! Wire protocol spoken between the daemon and its clients.
+//!
+//! Every message is a single line of JSON. A connection starts with a
+//! `Hello` exchange so both sides can reject an incompatible peer before
+//! any command is processed.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{CoreError, Result};
+use crate::supervisor::StartSessionOptions;
+use crate::types::{Event, Profile, Session};
+
+/// Protocol version spoken by this build. Bump on any incompatible change.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Request sent from a client to the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Handshake; must be the first request on a connection
+    Hello { version: u32 },
+    /// Start a session for a profile
+    Start {
+        profile: Box<Profile>,
+        #[serde(default)]
+        options: StartSessionOptions,
+    },
+    /// Stop a session by ID
+    Stop { session_id: Uuid },
+    /// Stop all sessions
+    StopAll,
+    /// Get status of all sessions
+    Status,
+    /// Switch the connection into event streaming mode
+    Subscribe,
+    /// Stop all sessions and exit the daemon
+    Shutdown,
+}
+
+/// Response sent from the daemon to a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    /// Handshake accepted
+    Hello { version: u32, pid: u32 },
+    /// Session started successfully
+    Started { session_id: Uuid },
+    /// Session stopped
+    Stopped { session_id: Uuid },
+    /// All sessions stopped
+    AllStopped,
+    /// Status of all sessions
+    Status { sessions: Vec<Session> },
+    /// Subscription accepted; `Event` responses follow
+    Subscribed,
+    /// An event from the session manager (subscribed connections only)
+    Event { event: Box<Event> },
+    /// Events were dropped because the client could not keep up
+    Lagged { missed: u64 },
+    /// Daemon is shutting down
+    ShuttingDown,
+    /// Error occurred
+    Error { message: String },
+}
+
+/// Encode a message as a single newline-terminated JSON line
+pub fn encode<T: Serialize>(message: &T) -> Result<String> {
+    let mut line = serde_json::to_string(message)
+        .map_err(|e| CoreError::Serialization(format!("Failed to encode IPC message: {}", e)))?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Decode a message from a single JSON line
+pub fn decode<'a, T: Deserialize<'a>>(line: &'a str) -> Result<T> {
+    serde_json::from_str(line.trim_end())
+        .map_err(|e| CoreError::Deserialization(format!("Failed to decode IPC message: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_roundtrip() {
+        let profile = Profile::new("test", "example.com", "user");
+        let line = encode(&Request::Start {
+            profile: Box::new(profile.clone()),
+            options: StartSessionOptions::default(),
+        })
+        .unwrap();
+
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.matches('\n').count(), 1);
+
+        match decode::<Request>(&line).unwrap() {
+            Request::Start { profile: decoded, .. } => assert_eq!(decoded.id, profile.id),
+            other => panic!("Unexpected request: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_hello_wire_format() {
+        let line = encode(&Request::Hello { version: PROTOCOL_VERSION }).unwrap();
+        assert_eq!(line, format!("{{\"type\":\"hello\",\"version\":{}}}\n", PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode::<Request>("{\"type\":\"launch_missiles\"}").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{broadcast, Notify};
+
+use crate::error::{CoreError, Result};
+use crate::supervisor::SessionManagerHandle;
+
+use super::protocol::{decode, encode, Request, Response, PROTOCOL_VERSION};
+
+/// Serves `SessionManagerHandle` operations over a Unix domain socket
+pub struct IpcServer {
+    listener: UnixListener,
+    path: PathBuf,
+    handle: SessionManagerHandle,
+    shutdown: Arc<Notify>,
+}
+
+impl IpcServer {
+    /// Bind the control socket at `path`.
+    ///
+    /// A leftover socket file from a crashed daemon is removed; a socket that
+    /// still accepts connections means another daemon owns it.
+    pub fn bind(path: impl AsRef<Path>, handle: SessionManagerHandle) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Err(CoreError::DaemonAlreadyRunning(path));
+            }
+            std::fs::remove_file(&path)?;
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+
+        // Only the owning user may control the daemon
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        tracing::info!("Daemon listening on {:?}", path);
+
+        Ok(Self {
+            listener,
+            path,
+            handle,
+            shutdown: Arc::new(Notify::new()),
+        })
+    }
+
+    /// Path of the bound socket
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Notifier that stops the accept loop when triggered
+    pub fn shutdown_notifier(&self) -> Arc<Notify> {
+        self.shutdown.clone()
+    }
+
+    /// Accept connections until a client requests shutdown or the notifier fires
+    pub async fn run(self) -> Result<()> {
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => {
+                    match accepted {
+                        Ok((stream, _)) => {
+                            let handle = self.handle.clone();
+                            let shutdown = self.shutdown.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = handle_connection(stream, handle, shutdown).await {
+                                    tracing::debug!("IPC connection closed: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) => tracing::warn!("Failed to accept IPC connection: {}", e),
+                    }
+                }
+                _ = self.shutdown.notified() => break,
+            }
+        }
+
+        tracing::info!("Daemon stopped listening");
+        Ok(())
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn send(writer: &mut OwnedWriteHalf, response: &Response) -> Result<()> {
+    writer.write_all(encode(response)?.as_bytes()).await?;
+    Ok(())
+}
+
+async fn handle_connection(
+    stream: UnixStream,
+    handle: SessionManagerHandle,
+    shutdown: Arc<Notify>,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    // Handshake
+    let Some(line) = lines.next_line().await? else {
+        return Ok(());
+    };
+    match decode::<Request>(&line) {
+        Ok(Request::Hello { version }) if version == PROTOCOL_VERSION => {
+            send(&mut writer, &Response::Hello {
+                version: PROTOCOL_VERSION,
+                pid: std::process::id(),
+            })
+            .await?;
+        }
+        Ok(Request::Hello { version }) => {
+            let err = CoreError::IpcVersionMismatch { client: version, daemon: PROTOCOL_VERSION };
+            send(&mut writer, &Response::Error { message: err.to_string() }).await?;
+            return Err(err);
+        }
+        _ => {
+            send(&mut writer, &Response::Error {
+                message: "Expected hello as first request".to_string(),
+            })
+            .await?;
+            return Err(CoreError::Ipc("Missing handshake".to_string()));
+        }
+    }
+
+    while let Some(line) = lines.next_line().await? {
+        let request = match decode::<Request>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                send(&mut writer, &Response::Error { message: e.to_string() }).await?;
+                continue;
+            }
+        };
+
+        let response = match request {
+            Request::Hello { .. } => Response::Error {
+                message: "Handshake already completed".to_string(),
+            },
+            Request::Start { profile, options } => {
+                match handle.start_with_options(*profile, options).await {
+                    Ok(session_id) => Response::Started { session_id },
+                    Err(e) => Response::Error { message: e.to_string() },
+                }
+            }
+            Request::Stop { session_id } => match handle.stop(session_id).await {
+                Ok(()) => Response::Stopped { session_id },
+                Err(e) => Response::Error { message: e.to_string() },
+            },
+            Request::StopAll => match handle.stop_all().await {
+                Ok(()) => Response::AllStopped,
+                Err(e) => Response::Error { message: e.to_string() },
+            },
+            Request::Status => match handle.status().await {
+                Ok(sessions) => Response::Status { sessions },
+                Err(e) => Response::Error { message: e.to_string() },
+            },
+            Request::Subscribe => {
+                send(&mut writer, &Response::Subscribed).await?;
+                return stream_events(writer, handle).await;
+            }
+            Request::Shutdown => {
+                handle.shutdown().await?;
+                send(&mut writer, &Response::ShuttingDown).await?;
+                shutdown.notify_one();
+                return Ok(());
+            }
+        };
+
+        send(&mut writer, &response).await?;
+    }
+
+    Ok(())
+}
+
+/// Forward every manager event to the client until it disconnects
+async fn stream_events(mut writer: OwnedWriteHalf, handle: SessionManagerHandle) -> Result<()> {
+    let mut events = handle.subscribe();
+
+    loop {
+        let response = match events.recv().await {
+            Ok(event) => Response::Event { event: Box::new(event) },
+            Err(broadcast::error::RecvError::Lagged(missed)) => Response::Lagged { missed },
+            Err(broadcast::error::RecvError::Closed) => Response::ShuttingDown,
+        };
+        let closed = matches!(response, Response::ShuttingDown);
+
+        send(&mut writer, &response).await?;
+
+        if closed {
+            return Ok(());
+        }
+    }
+}
@@ -9,9 +9,13 @@
 //! The core is organized into several modules:
 //!
 //! - [`config`]: Configuration loading, saving, and management
+//! - `ipc`: Unix socket protocol used by the background daemon (Unix only)
+//! - [`logs`]: Per-session and per-profile log files with rotation
+//! - [`metrics`]: Prometheus metrics for sessions and tunnels
+//! - [`notify`]: Webhook notifications for session lifecycle events
 //! - [`ssh`]: SSH binary detection, argument building, and process spawning
 //! - [`supervisor`]: Session management with reconnection logic
-//! - [`storage`]: State persistence and optional keyring integration
+//! - [`storage`]: State persistence, session history and the secret store
 //! - [`types`]: Core data types (profiles, sessions, events)
 //! - [`error`]: Error types and result aliases
 //! - [`util`]: Utility functions (redaction, etc.)
@@ -51,6 +55,11 @@
 
 pub mod config;
 pub mod error;
+#[cfg(unix)]
+pub mod ipc;
+pub mod logs;
+pub mod metrics;
+pub mod notify;
 pub mod prelude;
 pub mod ssh;
 pub mod storage;
@@ -60,4 +69,4 @@ pub mod util;
 
 // Re-export commonly used items at the crate root
 pub use error::{CoreError, Result};
-pub use types::{Profile, Session, SessionStatus, TunnelSpec};
+pub use types::{BackendKind, FailoverStrategy, JumpHost, Profile, RelayEndpoint, Session, SessionStatus, TunnelKind, TunnelSpec};
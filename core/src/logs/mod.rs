@@ -0,0 +1,5 @@
+pub mod rotate;
+pub mod sink;
+
+pub use rotate::RotatingFile;
+pub use sink::{LogSink, GENERAL_LOG};
@@ -0,0 +1,131 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A log file that rotates once it grows past a size limit
+///
+/// Rotated files are kept next to it as `<name>.1` (newest) through
+/// `<name>.<max_files>` (oldest); anything older is deleted.
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: u32,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, max_files: u32) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes,
+            max_files,
+            file: None,
+            size: 0,
+        }
+    }
+
+    /// Path of the active (unrotated) file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a line, rotating first if it would exceed the size limit
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+
+        self.open()?;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let file = self.open()?;
+        writeln!(file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    /// Path of the `index`-th rotated file
+    pub fn rotated_path(path: &Path, index: u32) -> PathBuf {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn open(&mut self) -> io::Result<&mut File> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => {
+                if let Some(parent) = self.path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+                self.size = file.metadata()?.len();
+                file
+            }
+        };
+        Ok(self.file.insert(file))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        self.size = 0;
+
+        if self.max_files == 0 {
+            return remove_if_exists(&self.path);
+        }
+
+        remove_if_exists(&Self::rotated_path(&self.path, self.max_files))?;
+        for index in (1..self.max_files).rev() {
+            let from = Self::rotated_path(&self.path, index);
+            if from.exists() {
+                std::fs::rename(&from, Self::rotated_path(&self.path, index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, Self::rotated_path(&self.path, 1))
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rotation_keeps_max_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("session.log");
+        let mut file = RotatingFile::new(&path, 10, 2);
+
+        // Each line is 6 bytes, so every second write rotates
+        for line in ["aaaaa", "bbbbb", "ccccc", "ddddd"] {
+            file.write_line(line).unwrap();
+        }
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ddddd\n");
+        assert_eq!(std::fs::read_to_string(RotatingFile::rotated_path(&path, 1)).unwrap(), "ccccc\n");
+        assert_eq!(std::fs::read_to_string(RotatingFile::rotated_path(&path, 2)).unwrap(), "bbbbb\n");
+        assert!(!RotatingFile::rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn test_rotation_without_history() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("session.log");
+        let mut file = RotatingFile::new(&path, 10, 0);
+
+        file.write_line("aaaaa").unwrap();
+        file.write_line("bbbbb").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "bbbbb\n");
+        assert!(!RotatingFile::rotated_path(&path, 1).exists());
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use std::thread::JoinHandle;
+
+use tokio::sync::broadcast::error::RecvError;
+use tracing::Level;
+use uuid::Uuid;
+
+use crate::config::load::sanitize_filename;
+use crate::config::LoggingConfig;
+use crate::types::{Event, EventReceiver, SessionStatus};
+
+use super::rotate::RotatingFile;
+
+/// Name of the log for events that belong to no session
+pub const GENERAL_LOG: &str = "rssh.log";
+
+/// Writes session events to per-session and per-profile log files
+///
+/// Layout under the logs directory:
+/// - `<session-id>.log`: everything for one session
+/// - `profiles/<profile>.log`: the same lines tagged with the session ID,
+///   so history survives new session IDs
+/// - `rssh.log`: errors and events not tied to a session
+pub struct LogSink {
+    dir: PathBuf,
+    level: Level,
+    max_bytes: u64,
+    max_files: u32,
+    max_session_logs: u32,
+    files: HashMap<PathBuf, RotatingFile>,
+    /// Profile log of each session with an open session log
+    open_sessions: HashMap<Uuid, PathBuf>,
+}
+
+impl LogSink {
+    pub fn new(dir: impl Into<PathBuf>, config: &LoggingConfig) -> Self {
+        let level = Level::from_str(&config.level).unwrap_or_else(|_| {
+            tracing::warn!("Unknown log level '{}', using info", config.level);
+            Level::INFO
+        });
+
+        Self {
+            dir: dir.into(),
+            level,
+            max_bytes: u64::from(config.max_file_size_mb) * 1024 * 1024,
+            max_files: config.max_files,
+            max_session_logs: config.max_session_logs,
+            files: HashMap::new(),
+            open_sessions: HashMap::new(),
+        }
+    }
+
+    /// Path of a session's log file under `dir`
+    ///
+    /// Mirrors [`crate::config::session_log_file`] for an arbitrary directory.
+    pub fn session_log_path(dir: &Path, session_id: Uuid) -> PathBuf {
+        dir.join(format!("{}.log", session_id))
+    }
+
+    /// Path of a profile's log file
+    pub fn profile_log_path(dir: &Path, profile_name: &str) -> PathBuf {
+        dir.join("profiles").join(format!("{}.log", sanitize_filename(profile_name)))
+    }
+
+    /// Consume events until the bus closes
+    ///
+    /// Writes and rotation are blocking file IO, so the sink runs on its own
+    /// thread rather than on the async runtime.
+    pub fn spawn(mut self, mut events: EventReceiver) -> JoinHandle<()> {
+        std::thread::Builder::new()
+            .name("log-sink".to_string())
+            .spawn(move || loop {
+                match events.blocking_recv() {
+                    Ok(event) => self.handle(&event),
+                    Err(RecvError::Lagged(missed)) => {
+                        self.write_general(Level::WARN, &format!("Log sink lagged, {} events not logged", missed));
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            })
+            .expect("failed to spawn log sink thread")
+    }
+
+    /// Write one event to the files it belongs to
+    pub fn handle(&mut self, event: &Event) {
+        let Some((level, message)) = describe(event) else {
+            return;
+        };
+        if level > self.level {
+            return;
+        }
+
+        let timestamp = event.timestamp().format("%Y-%m-%d %H:%M:%S");
+
+        match session_of(event) {
+            Some((session_id, profile_name)) => {
+                let session_path = Self::session_log_path(&self.dir, session_id);
+                if !self.files.contains_key(&session_path) {
+                    self.prune_session_logs();
+                }
+                self.write(session_path.clone(), &format!("[{}] [{}] {}", timestamp, level, message));
+
+                let short_id = &session_id.to_string()[..8];
+                let profile_path = Self::profile_log_path(&self.dir, profile_name);
+                self.write(
+                    profile_path.clone(),
+                    &format!("[{}] [{}] [{}] {}", timestamp, level, short_id, message),
+                );
+                self.open_sessions.insert(session_id, profile_path);
+
+                // Nothing more will be written for a session that has ended;
+                // its profile log stays open only while another session uses it
+                if ends_session(event) {
+                    self.files.remove(&session_path);
+                    if let Some(profile_path) = self.open_sessions.remove(&session_id) {
+                        if !self.open_sessions.values().any(|path| *path == profile_path) {
+                            self.files.remove(&profile_path);
+                        }
+                    }
+                }
+            }
+            None => {
+                let line = format!("[{}] [{}] {}", timestamp, level, message);
+                self.write(self.dir.join(GENERAL_LOG), &line);
+            }
+        }
+    }
+
+    fn write_general(&mut self, level: Level, message: &str) {
+        let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S");
+        let line = format!("[{}] [{}] {}", timestamp, level, message);
+        self.write(self.dir.join(GENERAL_LOG), &line);
+    }
+
+    fn write(&mut self, path: PathBuf, line: &str) {
+        let (max_bytes, max_files) = (self.max_bytes, self.max_files);
+        let file = self
+            .files
+            .entry(path.clone())
+            .or_insert_with(|| RotatingFile::new(path, max_bytes, max_files));
+
+        if let Err(e) = file.write_line(line) {
+            tracing::warn!("Failed to write log file {:?}: {}", file.path(), e);
+        }
+    }
+
+    /// Keep only the newest `max_session_logs - 1` session logs, making room for a new one
+    fn prune_session_logs(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut session_logs: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+            .filter(|p| {
+                p.file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|s| Uuid::parse_str(s).is_ok())
+            })
+            .collect();
+
+        let keep = self.max_session_logs.saturating_sub(1) as usize;
+        if session_logs.len() <= keep {
+            return;
+        }
+
+        // Newest first
+        session_logs.sort_by_key(|p| std::cmp::Reverse(p.metadata().and_then(|m| m.modified()).ok()));
+
+        for path in session_logs.into_iter().skip(keep) {
+            let _ = std::fs::remove_file(&path);
+            for index in 1..=self.max_files {
+                let _ = std::fs::remove_file(RotatingFile::rotated_path(&path, index));
+            }
+        }
+    }
+}
+
+/// Level and message for an event, or `None` if it is not logged
+fn describe(event: &Event) -> Option<(Level, String)> {
+    let described = match event {
+        Event::SessionStatusChanged { old_status, new_status, .. } => {
+            (Level::INFO, format!("Status changed: {} -> {}", old_status, new_status))
+        }
+        Event::SessionConnected { .. } => (Level::INFO, "Connected".to_string()),
+        Event::SessionDisconnected { reason, .. } => (
+            Level::WARN,
+            format!("Disconnected: {}", reason.as_deref().unwrap_or("unknown reason")),
+        ),
+        Event::SessionReconnecting { attempt, max_attempts, .. } => {
+            let max = if *max_attempts == 0 { "unlimited".to_string() } else { max_attempts.to_string() };
+            (Level::WARN, format!("Reconnecting (attempt {}/{})", attempt, max))
+        }
+        Event::SessionSpawnFailed { error, .. } => (Level::ERROR, format!("Could not start connection: {}", error)),
+        Event::SessionFailed { error, .. } => (Level::ERROR, format!("Failed: {}", error)),
+        Event::TunnelPortAllocated { tunnel_index, allocated_port, .. } => (
+            Level::INFO,
+            format!("Tunnel {}: server allocated port {}", tunnel_index + 1, allocated_port),
+        ),
+        Event::TunnelHealthChanged { tunnel_index, healthy: true, .. } => {
+            (Level::INFO, format!("Tunnel {}: health check passing again", tunnel_index + 1))
+        }
+        Event::TunnelHealthChanged { tunnel_index, error, .. } => (
+            Level::WARN,
+            format!("Tunnel {}: health check failed: {}", tunnel_index + 1, error.as_deref().unwrap_or("unknown error")),
+        ),
+        Event::SessionEndpointChanged { previous, endpoint, reason, .. } => match previous {
+            Some(previous) => (Level::WARN, format!("Switching server {} -> {} ({})", previous, endpoint, reason)),
+            None => (Level::INFO, format!("Connecting to {}", endpoint)),
+        },
+        Event::SessionOutput { output, .. } => {
+            // ssh's own verbose output is debug noise unless asked for
+            let level = if output.trim_start().starts_with("debug") { Level::DEBUG } else { Level::INFO };
+            (level, output.clone())
+        }
+        Event::SshBinaryChanged { path, version, .. } => (
+            Level::INFO,
+            format!("Using SSH binary {} ({})", path, version.as_deref().unwrap_or("unknown version")),
+        ),
+        Event::Error { message, context, .. } => match context {
+            Some(context) => (Level::ERROR, format!("{} ({})", message, context)),
+            None => (Level::ERROR, message.clone()),
+        },
+        Event::ProfileCreated { .. } | Event::ProfileUpdated { .. } | Event::ProfileDeleted { .. } => {
+            return None;
+        }
+    };
+    Some(described)
+}
+
+/// Session ID and profile name of a session event
+fn session_of(event: &Event) -> Option<(Uuid, &str)> {
+    match event {
+        Event::SessionStatusChanged { session_id, profile_name, .. }
+        | Event::SessionConnected { session_id, profile_name, .. }
+        | Event::SessionDisconnected { session_id, profile_name, .. }
+        | Event::SessionReconnecting { session_id, profile_name, .. }
+        | Event::SessionSpawnFailed { session_id, profile_name, .. }
+        | Event::SessionFailed { session_id, profile_name, .. }
+        | Event::TunnelPortAllocated { session_id, profile_name, .. }
+        | Event::TunnelHealthChanged { session_id, profile_name, .. }
+        | Event::SessionEndpointChanged { session_id, profile_name, .. }
+        | Event::SessionOutput { session_id, profile_name, .. } => Some((*session_id, profile_name.as_str())),
+        _ => None,
+    }
+}
+
+fn ends_session(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::SessionStatusChanged { new_status: SessionStatus::Stopped | SessionStatus::Failed, .. }
+            | Event::SessionFailed { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn config(level: &str) -> LoggingConfig {
+        LoggingConfig {
+            level: level.to_string(),
+            ..LoggingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_writes_session_and_profile_logs() {
+        let dir = tempdir().unwrap();
+        let mut sink = LogSink::new(dir.path(), &config("info"));
+        let session_id = Uuid::new_v4();
+
+        sink.handle(&Event::session_connected(session_id, "web server"));
+        sink.handle(&Event::session_output(session_id, "web server", "debug1: noisy", true));
+        sink.handle(&Event::session_output(session_id, "web server", "Warning: something", true));
+        sink.handle(&Event::error("boom", None));
+
+        let session_log = std::fs::read_to_string(LogSink::session_log_path(dir.path(), session_id)).unwrap();
+        assert!(session_log.contains("[INFO] Connected"));
+        assert!(session_log.contains("[INFO] Warning: something"));
+        assert!(!session_log.contains("noisy"));
+
+        let profile_log = std::fs::read_to_string(LogSink::profile_log_path(dir.path(), "web server")).unwrap();
+        assert!(profile_log.contains(&format!("[{}] Connected", &session_id.to_string()[..8])));
+
+        let general_log = std::fs::read_to_string(dir.path().join(GENERAL_LOG)).unwrap();
+        assert!(general_log.contains("[ERROR] boom"));
+    }
+
+    #[test]
+    fn test_closes_files_of_ended_sessions() {
+        let dir = tempdir().unwrap();
+        let mut sink = LogSink::new(dir.path(), &config("info"));
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+        sink.handle(&Event::session_connected(first, "web"));
+        sink.handle(&Event::session_connected(second, "web"));
+        sink.handle(&Event::session_failed(first, "web", "gone"));
+        // The profile log is still shared with the second session
+        assert_eq!(sink.files.len(), 2);
+
+        sink.handle(&Event::session_failed(second, "web", "gone"));
+        assert!(sink.files.is_empty());
+    }
+
+    #[test]
+    fn test_prunes_old_session_logs() {
+        let dir = tempdir().unwrap();
+        let mut sink = LogSink::new(dir.path(), &LoggingConfig {
+            max_session_logs: 2,
+            ..config("info")
+        });
+
+        let sessions: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for id in &sessions {
+            sink.handle(&Event::session_connected(*id, "test"));
+            // Distinct modification times
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        assert!(!LogSink::session_log_path(dir.path(), sessions[0]).exists());
+        assert!(LogSink::session_log_path(dir.path(), sessions[1]).exists());
+        assert!(LogSink::session_log_path(dir.path(), sessions[2]).exists());
+    }
+}
@@ -0,0 +1,137 @@
+//! Minimal HTTP listener that serves `GET /metrics` and nothing else.
+//!
+//! Used by the daemon, which has no web server of its own. Each connection
+//! gets one response and is then closed.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::error::Result;
+use crate::supervisor::SessionManagerHandle;
+
+use super::registry::{Metrics, CONTENT_TYPE};
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest request head that is read
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Answer scrapes on `listener` until the task is dropped
+pub async fn serve(listener: TcpListener, metrics: Arc<Metrics>, handle: SessionManagerHandle) -> Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let metrics = metrics.clone();
+        let handle = handle.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &metrics, &handle).await {
+                tracing::debug!("Metrics request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(stream: TcpStream, metrics: &Metrics, handle: &SessionManagerHandle) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader.take(MAX_REQUEST_BYTES as u64));
+
+    let request_line = match tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut reader)).await {
+        Ok(result) => result?,
+        Err(_) => return Ok(()),
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET" | "HEAD", "/metrics") => {
+            if let Ok(sessions) = handle.status().await {
+                metrics.observe_sessions(&sessions);
+            }
+            ("200 OK", metrics.render())
+        }
+        (_, "/metrics") => ("405 Method Not Allowed", "Method not allowed\n".to_string()),
+        _ => ("404 Not Found", "Not found; metrics are served at /metrics\n".to_string()),
+    };
+
+    let content_type = if status.starts_with("200") { CONTENT_TYPE } else { "text/plain; charset=utf-8" };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+
+    writer.write_all(head.as_bytes()).await?;
+    if method != "HEAD" {
+        writer.write_all(body.as_bytes()).await?;
+    }
+    writer.shutdown().await
+}
+
+/// Read the request head and return its first line
+async fn read_head<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> std::io::Result<String> {
+    let mut request_line = String::new();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if request_line.is_empty() {
+            request_line = line.trim_end().to_string();
+        }
+    }
+
+    Ok(request_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::storage::StateManager;
+    use crate::supervisor::SessionManager;
+    use crate::types::Event;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    async fn get(addr: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_exporter_serves_metrics() {
+        let dir = tempdir().unwrap();
+        let (manager, handle) =
+            SessionManager::with_state_manager(AppConfig::default(), StateManager::with_path(dir.path().join("state.json")));
+        tokio::spawn(manager.run());
+
+        let metrics = Arc::new(Metrics::new());
+        metrics.record(&Event::session_spawn_failed(Uuid::new_v4(), "web", "ssh not found"));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, metrics, handle));
+
+        let response = get(addr, "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(CONTENT_TYPE));
+        assert!(response.contains("rssh_session_spawn_failures_total{profile=\"web\"} 1\n"));
+
+        let response = get(addr, "GET / HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let response = get(addr, "POST /metrics HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
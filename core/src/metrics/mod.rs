@@ -0,0 +1,11 @@
+//! Prometheus metrics for sessions and tunnels.
+//!
+//! [`Metrics`] follows the event bus and renders per-profile gauges and
+//! counters in the Prometheus text exposition format. The web server serves
+//! them at `/metrics`; the daemon can serve them from a standalone
+//! [`exporter`] listener.
+
+pub mod exporter;
+pub mod registry;
+
+pub use registry::{Metrics, CONTENT_TYPE};
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::types::{Event, EventReceiver, Session, SessionStatus, TunnelTraffic};
+
+/// `Content-Type` of the rendered metrics
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const STATUSES: [SessionStatus; 7] = [
+    SessionStatus::Starting,
+    SessionStatus::Connected,
+    SessionStatus::Degraded,
+    SessionStatus::Reconnecting,
+    SessionStatus::Stopped,
+    SessionStatus::Failed,
+    SessionStatus::TimedOut,
+];
+
+/// Reads one of the per-tunnel traffic counters
+type TrafficField = fn(&TunnelTraffic) -> u64;
+
+/// What is known about the latest session of one profile
+#[derive(Debug, Default)]
+struct ProfileMetrics {
+    status: Option<SessionStatus>,
+    connected_since: Option<DateTime<Utc>>,
+    reconnects: u64,
+    spawn_failures: u64,
+    /// Last time a connection failed to start, dropped or timed out
+    last_failure: Option<DateTime<Utc>>,
+    backoff_delay: Duration,
+    /// Server-allocated ports by tunnel index
+    allocated_ports: BTreeMap<usize, u16>,
+    /// Latest health check result by tunnel index
+    tunnel_health: BTreeMap<usize, bool>,
+    /// Traffic on the current connection by tunnel index, for backends that count it
+    tunnel_traffic: BTreeMap<usize, TunnelTraffic>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    profiles: BTreeMap<String, ProfileMetrics>,
+    lagged_events: u64,
+}
+
+/// Session and tunnel metrics built from core events
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Inner>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create metrics fed from `events` by a background task
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn spawn(mut events: EventReceiver) -> Arc<Self> {
+        let metrics = Arc::new(Self::new());
+
+        let feeder = metrics.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => feeder.record(&event),
+                    Err(RecvError::Lagged(missed)) => feeder.record_lag(missed),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        metrics
+    }
+
+    /// Update the metrics for an event
+    pub fn record(&self, event: &Event) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Event::ProfileDeleted { profile_name, .. } = event {
+            inner.profiles.remove(profile_name);
+            return;
+        }
+        let Some(profile_name) = event.session_id().and(event.profile_name()) else {
+            return;
+        };
+        let profile = inner.profiles.entry(profile_name.to_string()).or_default();
+
+        match event {
+            Event::SessionStatusChanged { new_status, timestamp, .. } => {
+                profile.status = Some(*new_status);
+                match new_status {
+                    SessionStatus::Connected | SessionStatus::Degraded => {}
+                    SessionStatus::TimedOut => {
+                        profile.connected_since = None;
+                        profile.last_failure = Some(*timestamp);
+                    }
+                    SessionStatus::Stopped => {
+                        profile.connected_since = None;
+                        profile.backoff_delay = Duration::ZERO;
+                        profile.allocated_ports.clear();
+                        profile.tunnel_health.clear();
+                        profile.tunnel_traffic.clear();
+                    }
+                    _ => profile.connected_since = None,
+                }
+            }
+            Event::SessionConnected { timestamp, .. } => {
+                profile.status = Some(SessionStatus::Connected);
+                profile.connected_since = Some(*timestamp);
+                profile.backoff_delay = Duration::ZERO;
+            }
+            Event::SessionDisconnected { timestamp, .. } => {
+                profile.connected_since = None;
+                profile.last_failure = Some(*timestamp);
+                profile.allocated_ports.clear();
+                profile.tunnel_health.clear();
+                profile.tunnel_traffic.clear();
+            }
+            Event::SessionReconnecting { delay_ms, .. } => {
+                profile.status = Some(SessionStatus::Reconnecting);
+                profile.connected_since = None;
+                profile.reconnects += 1;
+                profile.backoff_delay = Duration::from_millis(*delay_ms);
+            }
+            Event::SessionSpawnFailed { timestamp, .. } => {
+                profile.spawn_failures += 1;
+                profile.last_failure = Some(*timestamp);
+            }
+            Event::SessionFailed { timestamp, .. } => {
+                profile.status = Some(SessionStatus::Failed);
+                profile.connected_since = None;
+                profile.last_failure = Some(*timestamp);
+                profile.backoff_delay = Duration::ZERO;
+            }
+            Event::TunnelPortAllocated { tunnel_index, allocated_port, .. } => {
+                profile.allocated_ports.insert(*tunnel_index, *allocated_port);
+            }
+            Event::TunnelHealthChanged { tunnel_index, healthy, .. } => {
+                profile.tunnel_health.insert(*tunnel_index, *healthy);
+            }
+            _ => {}
+        }
+    }
+
+    /// Count events a subscriber missed because it fell behind the bus
+    pub fn record_lag(&self, missed: u64) {
+        self.inner.lock().unwrap().lagged_events += missed;
+    }
+
+    /// Take status, connection time, tunnel ports and traffic from the manager's sessions
+    ///
+    /// Corrects anything missed while lagging or before the metrics were
+    /// subscribed. Counters only ever come from events.
+    pub fn observe_sessions(&self, sessions: &[Session]) {
+        let mut inner = self.inner.lock().unwrap();
+
+        for session in sessions {
+            let profile = inner.profiles.entry(session.profile_name.clone()).or_default();
+            profile.status = Some(session.status);
+            profile.connected_since = match session.is_connected() {
+                true => session.connected_at,
+                false => None,
+            };
+            profile.allocated_ports = session
+                .tunnels
+                .iter()
+                .enumerate()
+                .filter_map(|(index, tunnel)| Some((index, tunnel.allocated_port?)))
+                .collect();
+            profile.tunnel_health = session
+                .tunnels
+                .iter()
+                .enumerate()
+                .filter_map(|(index, tunnel)| Some((index, tunnel.health.as_ref()?.healthy)))
+                .collect();
+            profile.tunnel_traffic = session
+                .tunnels
+                .iter()
+                .enumerate()
+                .filter_map(|(index, tunnel)| Some((index, tunnel.traffic?)))
+                .collect();
+        }
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let profiles = &inner.profiles;
+        let mut out = String::new();
+
+        header(&mut out, "rssh_session_status", "gauge", "Status of the profile's latest session, 1 for the current one");
+        for (name, profile) in profiles {
+            for status in STATUSES {
+                let value = u8::from(profile.status == Some(status));
+                let _ = writeln!(out, "rssh_session_status{{profile=\"{}\",status=\"{}\"}} {}", escape(name), status, value);
+            }
+        }
+
+        header(&mut out, "rssh_session_connected_since_seconds", "gauge", "Unix time the session connected, for connected sessions");
+        for (name, profile) in profiles {
+            if let Some(since) = profile.connected_since {
+                sample(&mut out, "rssh_session_connected_since_seconds", name, unix_seconds(since));
+            }
+        }
+
+        header(&mut out, "rssh_session_reconnects_total", "counter", "Reconnection attempts");
+        for (name, profile) in profiles {
+            sample(&mut out, "rssh_session_reconnects_total", name, profile.reconnects);
+        }
+
+        header(&mut out, "rssh_session_spawn_failures_total", "counter", "Connection attempts whose SSH process could not be started");
+        for (name, profile) in profiles {
+            sample(&mut out, "rssh_session_spawn_failures_total", name, profile.spawn_failures);
+        }
+
+        header(&mut out, "rssh_session_last_failure_timestamp_seconds", "gauge", "Unix time a connection last failed to start, dropped or timed out");
+        for (name, profile) in profiles {
+            if let Some(failure) = profile.last_failure {
+                sample(&mut out, "rssh_session_last_failure_timestamp_seconds", name, unix_seconds(failure));
+            }
+        }
+
+        header(&mut out, "rssh_session_backoff_delay_seconds", "gauge", "Delay before the current reconnection attempt, 0 when not backing off");
+        for (name, profile) in profiles {
+            sample(&mut out, "rssh_session_backoff_delay_seconds", name, profile.backoff_delay.as_secs_f64());
+        }
+
+        header(&mut out, "rssh_tunnel_allocated_port", "gauge", "Port the server allocated for a remote_port = 0 tunnel");
+        for (name, profile) in profiles {
+            for (index, port) in &profile.allocated_ports {
+                let _ = writeln!(
+                    out,
+                    "rssh_tunnel_allocated_port{{profile=\"{}\",tunnel=\"{}\"}} {}",
+                    escape(name),
+                    index + 1,
+                    port
+                );
+            }
+        }
+
+        header(&mut out, "rssh_tunnel_healthy", "gauge", "1 if the tunnel's latest health check passed, 0 if it failed");
+        for (name, profile) in profiles {
+            for (index, healthy) in &profile.tunnel_health {
+                let _ = writeln!(
+                    out,
+                    "rssh_tunnel_healthy{{profile=\"{}\",tunnel=\"{}\"}} {}",
+                    escape(name),
+                    index + 1,
+                    u8::from(*healthy)
+                );
+            }
+        }
+
+        let traffic: [(&str, &str, TrafficField); 3] = [
+            ("rssh_tunnel_sent_bytes_total", "Bytes the tunnel sent to the server on the current connection", |t| t.bytes_sent),
+            ("rssh_tunnel_received_bytes_total", "Bytes the tunnel received from the server on the current connection", |t| t.bytes_received),
+            ("rssh_tunnel_channels_total", "Connections the tunnel carried on the current connection", |t| t.channels),
+        ];
+        for (metric, help, value) in traffic {
+            header(&mut out, metric, "counter", help);
+            for (name, profile) in profiles {
+                for (index, traffic) in &profile.tunnel_traffic {
+                    let _ = writeln!(
+                        out,
+                        "{}{{profile=\"{}\",tunnel=\"{}\"}} {}",
+                        metric,
+                        escape(name),
+                        index + 1,
+                        value(traffic)
+                    );
+                }
+            }
+        }
+
+        header(&mut out, "rssh_event_bus_lagged_events_total", "counter", "Events the metrics missed because they fell behind the event bus");
+        let _ = writeln!(out, "rssh_event_bus_lagged_events_total {}", inner.lagged_events);
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, profile: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "{}{{profile=\"{}\"}} {}", name, escape(profile), value);
+}
+
+fn unix_seconds(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 1000.0
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Profile, TunnelSpec, TunnelState};
+    use uuid::Uuid;
+
+    fn line<'a>(rendered: &'a str, prefix: &str) -> Option<&'a str> {
+        rendered.lines().find(|l| l.starts_with(prefix))
+    }
+
+    #[test]
+    fn test_metrics_follow_session_events() {
+        let metrics = Metrics::new();
+        let id = Uuid::new_v4();
+
+        metrics.record(&Event::session_spawn_failed(id, "web", "ssh not found"));
+        metrics.record(&Event::session_reconnecting(id, "web", 1, 0, Duration::from_millis(2500)));
+        metrics.record(&Event::session_status_changed(id, "web", SessionStatus::Reconnecting, SessionStatus::Connected));
+        metrics.record(&Event::session_connected(id, "web"));
+        metrics.record(&Event::tunnel_port_allocated(id, "web", 0, 41000));
+        metrics.record(&Event::tunnel_health_changed(id, "web", 0, false, Some("connection refused".into())));
+        metrics.record(&Event::session_status_changed(id, "web", SessionStatus::Connected, SessionStatus::Degraded));
+        metrics.record_lag(3);
+
+        let rendered = metrics.render();
+        assert_eq!(line(&rendered, "rssh_session_status{profile=\"web\",status=\"degraded\"}"), Some("rssh_session_status{profile=\"web\",status=\"degraded\"} 1"));
+        assert_eq!(line(&rendered, "rssh_tunnel_healthy{"), Some("rssh_tunnel_healthy{profile=\"web\",tunnel=\"1\"} 0"));
+        assert_eq!(line(&rendered, "rssh_session_status{profile=\"web\",status=\"reconnecting\"}"), Some("rssh_session_status{profile=\"web\",status=\"reconnecting\"} 0"));
+        assert_eq!(line(&rendered, "rssh_session_reconnects_total{"), Some("rssh_session_reconnects_total{profile=\"web\"} 1"));
+        assert_eq!(line(&rendered, "rssh_session_spawn_failures_total{"), Some("rssh_session_spawn_failures_total{profile=\"web\"} 1"));
+        assert_eq!(line(&rendered, "rssh_session_backoff_delay_seconds{"), Some("rssh_session_backoff_delay_seconds{profile=\"web\"} 0"));
+        assert_eq!(line(&rendered, "rssh_tunnel_allocated_port{"), Some("rssh_tunnel_allocated_port{profile=\"web\",tunnel=\"1\"} 41000"));
+        assert_eq!(line(&rendered, "rssh_event_bus_lagged_events_total "), Some("rssh_event_bus_lagged_events_total 3"));
+        assert!(line(&rendered, "rssh_session_connected_since_seconds{").is_some());
+        assert!(line(&rendered, "rssh_session_last_failure_timestamp_seconds{").is_some());
+
+        metrics.record(&Event::session_disconnected(id, "web", Some("broken pipe".into())));
+        metrics.record(&Event::session_reconnecting(id, "web", 2, 0, Duration::from_secs(4)));
+
+        let rendered = metrics.render();
+        assert_eq!(line(&rendered, "rssh_session_backoff_delay_seconds{"), Some("rssh_session_backoff_delay_seconds{profile=\"web\"} 4"));
+        assert!(line(&rendered, "rssh_session_connected_since_seconds{").is_none());
+        assert!(line(&rendered, "rssh_tunnel_allocated_port{").is_none());
+        assert!(line(&rendered, "rssh_tunnel_healthy{").is_none());
+
+        metrics.record(&Event::profile_deleted(Uuid::new_v4(), "web"));
+        assert!(!metrics.render().contains("profile=\"web\""));
+    }
+
+    #[test]
+    fn test_observe_sessions_and_escaping() {
+        let metrics = Metrics::new();
+        let mut session = Session::new(&Profile::new("a \"quoted\" name", "relay.example.com", "me"));
+        session.status = SessionStatus::Connected;
+        session.connected_at = Some(Utc::now());
+        session.tunnels.push(TunnelState::new(TunnelSpec::new(8080, 3000)));
+        session.tunnels[0].traffic = Some(TunnelTraffic { bytes_sent: 120, bytes_received: 4096, channels: 2 });
+
+        metrics.observe_sessions(&[session]);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("rssh_session_status{profile=\"a \\\"quoted\\\" name\",status=\"connected\"} 1"));
+        assert!(rendered.contains("rssh_session_connected_since_seconds{profile=\"a \\\"quoted\\\" name\"}"));
+        assert!(rendered.contains("rssh_session_reconnects_total{profile=\"a \\\"quoted\\\" name\"} 0"));
+        assert!(rendered.contains("rssh_tunnel_received_bytes_total{profile=\"a \\\"quoted\\\" name\",tunnel=\"1\"} 4096"));
+        assert!(rendered.contains("rssh_tunnel_channels_total{profile=\"a \\\"quoted\\\" name\",tunnel=\"1\"} 2"));
+    }
+}
@@ -0,0 +1,12 @@
+//! Webhook notifications for session lifecycle events.
+//!
+//! Each `[[webhooks]]` entry in the configuration is a rule: which profiles
+//! and event types it covers, how long to hold back repeats, and the JSON
+//! payload to POST. The [`Notifier`] follows the event bus and delivers
+//! matching notifications, retrying failures with backoff.
+
+pub mod notifier;
+pub mod template;
+
+pub use notifier::{Notifier, Webhook};
+pub use template::Notification;
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+use crate::config::WebhookConfig;
+use crate::error::{CoreError, Result};
+use crate::supervisor::Backoff;
+use crate::types::{Event, EventReceiver};
+
+use super::template::{self, Notification};
+
+/// A configured webhook with its template resolved
+#[derive(Debug)]
+pub struct Webhook {
+    pub config: WebhookConfig,
+    template: Value,
+}
+
+impl Webhook {
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let url = reqwest::Url::parse(&config.url)
+            .map_err(|e| CoreError::ConfigInvalid(format!("Webhook '{}' has an invalid URL: {}", config.name, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(CoreError::ConfigInvalid(format!("Webhook '{}' must use http or https", config.name)));
+        }
+
+        let template = config.template.clone().unwrap_or_else(|| template::default_template(config.format));
+        template::validate(&template)
+            .map_err(|e| CoreError::ConfigInvalid(format!("Webhook '{}': {}", config.name, e)))?;
+
+        Ok(Self { config, template })
+    }
+
+    /// Whether the webhook wants this notification, before debouncing
+    fn wants(&self, notification: &Notification) -> bool {
+        (self.config.events.is_empty() || self.config.events.contains(&notification.event))
+            && (self.config.profiles.is_empty() || self.config.profiles.contains(&notification.profile))
+    }
+
+    /// The JSON body for a notification
+    pub fn payload(&self, notification: &Notification) -> Value {
+        template::render(&self.template, notification)
+    }
+}
+
+/// Debounce state of one webhook, profile and event type
+#[derive(Debug)]
+struct Throttle {
+    last_sent: Instant,
+    suppressed: u32,
+}
+
+/// Sends webhooks for the events their rules match
+pub struct Notifier {
+    webhooks: Vec<Arc<Webhook>>,
+    client: reqwest::Client,
+    throttles: HashMap<(usize, String, String), Throttle>,
+}
+
+impl Notifier {
+    /// Validate the webhooks and prepare to send them
+    pub fn new(webhooks: &[WebhookConfig]) -> Result<Self> {
+        let webhooks = webhooks
+            .iter()
+            .cloned()
+            .map(|config| Webhook::new(config).map(Arc::new))
+            .collect::<Result<Vec<_>>>()?;
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("rssh/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| CoreError::Other(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            webhooks,
+            client,
+            throttles: HashMap::new(),
+        })
+    }
+
+    pub fn webhooks(&self) -> &[Arc<Webhook>] {
+        &self.webhooks
+    }
+
+    /// Notifications to send for an event at `now`, after filters and debouncing
+    pub fn due(&mut self, event: &Event, now: Instant) -> Vec<(Arc<Webhook>, Notification)> {
+        let Some(notification) = Notification::from_event(event) else {
+            return Vec::new();
+        };
+
+        let mut due = Vec::new();
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            if !webhook.wants(&notification) {
+                continue;
+            }
+
+            let window = Duration::from_secs(webhook.config.debounce_secs);
+            let key = (index, notification.profile.clone(), notification.event.clone());
+            match self.throttles.get_mut(&key) {
+                Some(throttle) if now.duration_since(throttle.last_sent) < window => {
+                    throttle.suppressed += 1;
+                }
+                Some(throttle) => {
+                    let mut notification = notification.clone();
+                    notification.suppressed = std::mem::take(&mut throttle.suppressed);
+                    throttle.last_sent = now;
+                    due.push((webhook.clone(), notification));
+                }
+                None => {
+                    self.throttles.insert(key, Throttle { last_sent: now, suppressed: 0 });
+                    due.push((webhook.clone(), notification.clone()));
+                }
+            }
+        }
+        due
+    }
+
+    /// Send notifications for events until the bus closes
+    ///
+    /// Each delivery runs in its own task so a slow or retrying webhook does
+    /// not hold up the others.
+    pub fn spawn(mut self, mut events: EventReceiver) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        for (webhook, notification) in self.due(&event, Instant::now()) {
+                            let client = self.client.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = deliver(&client, &webhook, &notification).await {
+                                    tracing::warn!("{}", e);
+                                }
+                            });
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("Notifier fell behind; {} events were not checked", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Send a notification to one webhook now, bypassing filters and debouncing
+    pub async fn send(&self, webhook: &Webhook, notification: &Notification) -> Result<()> {
+        deliver(&self.client, webhook, notification).await
+    }
+}
+
+/// POST a notification, retrying failed attempts with backoff
+async fn deliver(client: &reqwest::Client, webhook: &Webhook, notification: &Notification) -> Result<()> {
+    let payload = webhook.payload(notification);
+    let mut backoff = Backoff::new()
+        .with_initial_delay(Duration::from_secs(1))
+        .with_max_delay(Duration::from_secs(30))
+        .with_max_attempts(webhook.config.max_retries);
+
+    loop {
+        let error = match post(client, webhook, &payload).await {
+            Ok(()) => {
+                tracing::debug!("Sent {} notification to webhook '{}'", notification.event, webhook.config.name);
+                return Ok(());
+            }
+            Err(Attempt::Permanent(error)) => error,
+            Err(Attempt::Retry(error)) => match backoff.next_delay() {
+                Some(delay) => {
+                    tracing::debug!("Webhook '{}' failed ({}); retrying in {:?}", webhook.config.name, error, delay);
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                None => error,
+            },
+        };
+
+        return Err(CoreError::Webhook(format!("'{}': {}", webhook.config.name, error)));
+    }
+}
+
+/// Why one delivery attempt failed
+enum Attempt {
+    /// Worth trying again (network error, 5xx, 429)
+    Retry(String),
+    /// The server rejected the request itself
+    Permanent(String),
+}
+
+async fn post(client: &reqwest::Client, webhook: &Webhook, payload: &Value) -> std::result::Result<(), Attempt> {
+    let mut request = client
+        .post(&webhook.config.url)
+        .timeout(Duration::from_secs(webhook.config.timeout_secs.max(1)))
+        .json(payload);
+    for (name, value) in &webhook.config.headers {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await.map_err(|e| Attempt::Retry(e.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        Err(Attempt::Retry(format!("server answered {}", status)))
+    } else {
+        Err(Attempt::Permanent(format!("server answered {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhookFormat;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    fn webhook(url: &str) -> WebhookConfig {
+        WebhookConfig {
+            name: "test".to_string(),
+            url: url.to_string(),
+            format: WebhookFormat::Generic,
+            profiles: vec!["web".to_string()],
+            events: vec!["session_failed".to_string(), "session_connected".to_string()],
+            debounce_secs: 60,
+            template: None,
+            headers: [("X-Token".to_string(), "secret".to_string())].into(),
+            max_retries: 2,
+            timeout_secs: 5,
+        }
+    }
+
+    /// Answer each request with the next status and hand back the request
+    async fn stand_in(statuses: Vec<u16>) -> (String, tokio::sync::mpsc::UnboundedReceiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            for status in statuses {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (reader, mut writer) = stream.into_split();
+                let mut reader = BufReader::new(reader);
+
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line.trim_end().is_empty() {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).await.unwrap();
+                let _ = tx.send((head, serde_json::from_slice(&body).unwrap()));
+
+                let response = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                writer.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, rx)
+    }
+
+    #[test]
+    fn test_filters_and_debounce() {
+        let mut notifier = Notifier::new(&[webhook("http://127.0.0.1:9/hook")]).unwrap();
+        let id = Uuid::new_v4();
+        let start = Instant::now();
+
+        assert_eq!(notifier.due(&Event::session_failed(id, "web", "boom"), start).len(), 1);
+        // Other profiles and unlisted event types are ignored
+        assert!(notifier.due(&Event::session_failed(id, "db", "boom"), start).is_empty());
+        assert!(notifier.due(&Event::session_disconnected(id, "web", None), start).is_empty());
+        // Flapping within the window is counted, not sent
+        assert!(notifier.due(&Event::session_failed(id, "web", "boom"), start + Duration::from_secs(10)).is_empty());
+        assert!(notifier.due(&Event::session_failed(id, "web", "boom"), start + Duration::from_secs(20)).is_empty());
+        // A different event type has its own window
+        assert_eq!(notifier.due(&Event::session_connected(id, "web"), start + Duration::from_secs(20)).len(), 1);
+
+        let due = notifier.due(&Event::session_failed(id, "web", "boom"), start + Duration::from_secs(61));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].1.suppressed, 2);
+
+        assert!(Notifier::new(&[webhook("ftp://example.com")]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_server_errors() {
+        let (url, mut requests) = stand_in(vec![503, 200]).await;
+        let notifier = Notifier::new(&[webhook(&url)]).unwrap();
+        let notification = Notification::from_event(&Event::session_failed(Uuid::new_v4(), "web", "boom")).unwrap();
+
+        notifier.send(&notifier.webhooks()[0], &notification).await.unwrap();
+
+        for _ in 0..2 {
+            let (head, body) = requests.recv().await.unwrap();
+            assert!(head.starts_with("POST /hook "));
+            assert!(head.to_ascii_lowercase().contains("x-token: secret"));
+            assert_eq!(body["event"], "session_failed");
+            assert_eq!(body["profile"], "web");
+            assert_eq!(body["reason"], "boom");
+            assert_eq!(body["suppressed"], 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delivery_gives_up_on_client_errors() {
+        let (url, _requests) = stand_in(vec![404]).await;
+        let notifier = Notifier::new(&[webhook(&url)]).unwrap();
+
+        let result = notifier.send(&notifier.webhooks()[0], &Notification::test("web")).await;
+        assert!(matches!(result, Err(CoreError::Webhook(_))));
+    }
+}
@@ -0,0 +1,253 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::WebhookFormat;
+use crate::error::{CoreError, Result};
+use crate::types::Event;
+
+/// Placeholders a payload template may use
+pub const PLACEHOLDERS: [&str; 7] = ["event", "profile", "session_id", "message", "reason", "timestamp", "suppressed"];
+
+/// What a webhook is told about one event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    /// Event type, e.g. `session_failed`
+    pub event: String,
+    /// Profile name, empty for events not about a profile
+    pub profile: String,
+    pub session_id: Option<Uuid>,
+    /// One-line human-readable summary
+    pub message: String,
+    /// Disconnect reason or error, when the event has one
+    pub reason: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Matching events left out by debouncing since the last notification
+    pub suppressed: u32,
+}
+
+impl Notification {
+    /// The notification for an event, or `None` for events never sent (ssh output)
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let profile = event.profile_name().unwrap_or_default().to_string();
+        let (message, reason) = match event {
+            Event::SessionConnected { .. } => (format!("Tunnel '{}' connected", profile), None),
+            Event::SessionDisconnected { reason, .. } => (
+                format!("Tunnel '{}' disconnected: {}", profile, reason.as_deref().unwrap_or("unknown reason")),
+                reason.clone(),
+            ),
+            Event::SessionReconnecting { attempt, .. } => {
+                (format!("Tunnel '{}' reconnecting (attempt {})", profile, attempt), None)
+            }
+            Event::SessionSpawnFailed { error, .. } => {
+                (format!("Tunnel '{}' could not start ssh: {}", profile, error), Some(error.clone()))
+            }
+            Event::SessionFailed { error, .. } => {
+                (format!("Tunnel '{}' failed: {}", profile, error), Some(error.clone()))
+            }
+            Event::SessionStatusChanged { new_status, .. } => (format!("Tunnel '{}' is {}", profile, new_status), None),
+            Event::TunnelPortAllocated { tunnel_index, allocated_port, .. } => (
+                format!("Tunnel '{}': server allocated port {} for tunnel {}", profile, allocated_port, tunnel_index + 1),
+                None,
+            ),
+            Event::TunnelHealthChanged { tunnel_index, healthy: true, .. } => {
+                (format!("Tunnel '{}': health check for tunnel {} passing again", profile, tunnel_index + 1), None)
+            }
+            Event::TunnelHealthChanged { tunnel_index, error, .. } => (
+                format!(
+                    "Tunnel '{}': health check for tunnel {} failed: {}",
+                    profile,
+                    tunnel_index + 1,
+                    error.as_deref().unwrap_or("unknown error")
+                ),
+                error.clone(),
+            ),
+            Event::SessionEndpointChanged { endpoint, reason, .. } => {
+                (format!("Tunnel '{}' connecting to {} ({})", profile, endpoint, reason), None)
+            }
+            Event::ProfileCreated { .. } => (format!("Profile '{}' created", profile), None),
+            Event::ProfileUpdated { .. } => (format!("Profile '{}' updated", profile), None),
+            Event::ProfileDeleted { .. } => (format!("Profile '{}' deleted", profile), None),
+            Event::Error { message, context, .. } => (
+                match context {
+                    Some(context) => format!("{} ({})", message, context),
+                    None => message.clone(),
+                },
+                Some(message.clone()),
+            ),
+            Event::SessionOutput { .. } | Event::SshBinaryChanged { .. } => return None,
+        };
+
+        Some(Self {
+            event: event.kind().to_string(),
+            profile,
+            session_id: event.session_id(),
+            message,
+            reason,
+            timestamp: event.timestamp(),
+            suppressed: 0,
+        })
+    }
+
+    /// A notification for `rssh notify test`
+    pub fn test(profile: &str) -> Self {
+        Self {
+            event: "test".to_string(),
+            profile: profile.to_string(),
+            session_id: None,
+            message: format!("Test notification from rssh for '{}'", profile),
+            reason: None,
+            timestamp: Utc::now(),
+            suppressed: 0,
+        }
+    }
+
+    fn value_of(&self, placeholder: &str) -> Option<Value> {
+        let value = match placeholder {
+            "event" => json!(self.event),
+            "profile" => json!(self.profile),
+            "session_id" => json!(self.session_id.map(|id| id.to_string()).unwrap_or_default()),
+            "message" => json!(self.message),
+            "reason" => json!(self.reason.clone().unwrap_or_default()),
+            "timestamp" => json!(self.timestamp.to_rfc3339()),
+            "suppressed" => json!(self.suppressed),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// The payload template used when a webhook sets none
+pub fn default_template(format: WebhookFormat) -> Value {
+    match format {
+        WebhookFormat::Generic => json!({
+            "event": "{{event}}",
+            "profile": "{{profile}}",
+            "session_id": "{{session_id}}",
+            "message": "{{message}}",
+            "reason": "{{reason}}",
+            "timestamp": "{{timestamp}}",
+            "suppressed": "{{suppressed}}",
+        }),
+        WebhookFormat::Slack | WebhookFormat::Teams => json!({ "text": "{{message}}" }),
+    }
+}
+
+/// Check that a template only uses known placeholders
+pub fn validate(template: &Value) -> Result<()> {
+    let mut unknown = None;
+    walk_strings(template, &mut |s| {
+        for name in placeholders(s) {
+            if !PLACEHOLDERS.contains(&name) && unknown.is_none() {
+                unknown = Some(name.to_string());
+            }
+        }
+    });
+
+    match unknown {
+        Some(name) => Err(CoreError::ConfigInvalid(format!(
+            "Unknown webhook template placeholder '{{{{{}}}}}'; use one of: {}",
+            name,
+            PLACEHOLDERS.join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Fill in a template's placeholders
+///
+/// A string that is exactly one placeholder takes the value's JSON type, so
+/// `"{{suppressed}}"` becomes a number; elsewhere values are spliced in as text.
+pub fn render(template: &Value, notification: &Notification) -> Value {
+    match template {
+        Value::String(s) => render_string(s, notification),
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, notification)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, notification)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn render_string(s: &str, notification: &Notification) -> Value {
+    if let Some(name) = s.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}")) {
+        if let Some(value) = notification.value_of(name.trim()) {
+            return value;
+        }
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else { break };
+        let name = rest[start + 2..start + len].trim();
+        out.push_str(&rest[..start]);
+        match notification.value_of(name) {
+            Some(Value::String(text)) => out.push_str(&text),
+            Some(value) => out.push_str(&value.to_string()),
+            None => out.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    Value::String(out)
+}
+
+/// Placeholder names in a string
+fn placeholders(s: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else { break };
+        names.push(rest[start + 2..start + len].trim());
+        rest = &rest[start + len + 2..];
+    }
+    names
+}
+
+fn walk_strings(value: &Value, f: &mut impl FnMut(&str)) {
+    match value {
+        Value::String(s) => f(s),
+        Value::Array(items) => items.iter().for_each(|item| walk_strings(item, f)),
+        Value::Object(fields) => fields.values().for_each(|value| walk_strings(value, f)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let mut notification =
+            Notification::from_event(&Event::session_failed(Uuid::new_v4(), "web", "Maximum reconnection attempts reached"))
+                .unwrap();
+        notification.suppressed = 2;
+
+        let template = json!({
+            "text": "[{{ profile }}] {{message}} ({{suppressed}} more)",
+            "count": "{{suppressed}}",
+            "tags": ["{{event}}", 1, true],
+            "literal": "{{not closed",
+        });
+        validate(&template).unwrap();
+
+        assert_eq!(
+            render(&template, &notification),
+            json!({
+                "text": "[web] Tunnel 'web' failed: Maximum reconnection attempts reached (2 more)",
+                "count": 2,
+                "tags": ["session_failed", 1, true],
+                "literal": "{{not closed",
+            })
+        );
+
+        assert!(validate(&json!({ "text": "{{hostname}}" })).is_err());
+        assert!(Notification::from_event(&Event::session_output(Uuid::new_v4(), "web", "debug1", true)).is_none());
+    }
+}
@@ -2,6 +2,6 @@
 
 pub use crate::error::{CoreError, Result};
 pub use crate::types::{
-    AuthMethod, Event, EventReceiver, EventSender, Profile, Session, SessionHandle, SessionStatus,
-    TunnelSpec, event_channel, new_session_handle,
+    AuthMethod, BackendKind, EndpointChangeReason, Event, EventReceiver, EventSender, FailoverStrategy, JumpHost, Profile,
+    RelayEndpoint, Session, SessionHandle, SessionStatus, TunnelEndpoint, TunnelKind, TunnelSpec, TunnelState, event_channel, new_session_handle,
 };
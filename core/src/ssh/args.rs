@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
-use crate::config::StrictHostKeyChecking;
-use crate::types::{AuthMethod, Profile, TunnelSpec};
+use std::path::Path;
+
+use crate::config::{paths, StrictHostKeyChecking};
+use crate::types::{AuthMethod, JumpHost, Profile, TunnelSpec};
+
+use super::jump::{jump_alias, needs_jump_config};
 
 /// SSH argument builder
 /// 
@@ -22,9 +26,9 @@ impl SshArgs {
     pub fn from_profile(profile: &Profile) -> Self {
         let mut builder = Self::new();
 
-        // Add reverse tunnel specifications (-R)
+        // Add tunnel specifications (-R, -L, -D)
         for tunnel in &profile.tunnels {
-            builder = builder.reverse_tunnel(tunnel);
+            builder = builder.tunnel(tunnel);
         }
 
         // Add keepalive options
@@ -35,6 +39,21 @@ impl SshArgs {
         // Add safety options
         builder = builder.option("ExitOnForwardFailure", "yes");
 
+        // Replace stale socket files left by a previous connection; otherwise
+        // the bind fails and ExitOnForwardFailure ends the session. This only
+        // covers sockets ssh binds on this machine (-L): for -R the server binds
+        // and follows StreamLocalBindUnlink in its own sshd_config. An explicit
+        // extra option wins (ssh uses the first value it sees, so skip ours).
+        let unlink_overridden = profile
+            .extra_options
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case("StreamLocalBindUnlink"));
+        if profile.tunnels.iter().any(TunnelSpec::listens_on_socket) && !unlink_overridden {
+            builder = builder.option("StreamLocalBindUnlink", "yes");
+        }
+
+        builder = builder.jump_chain(profile);
+
         // Add authentication-related options
         match &profile.auth {
             AuthMethod::Agent => {
@@ -105,6 +124,13 @@ impl SshArgs {
         self.option("StrictHostKeyChecking", mode.to_ssh_option())
     }
 
+    /// Add a tunnel using the flag that matches its kind
+    pub fn tunnel(mut self, tunnel: &TunnelSpec) -> Self {
+        self.args.push(tunnel.kind.ssh_flag().to_string());
+        self.args.push(tunnel.to_ssh_arg());
+        self
+    }
+
     /// Add a reverse tunnel (-R)
     pub fn reverse_tunnel(mut self, tunnel: &TunnelSpec) -> Self {
         self.args.push("-R".to_string());
@@ -112,6 +138,39 @@ impl SshArgs {
         self
     }
 
+    /// Route the connection through the profile's jump hosts.
+    ///
+    /// Hops that only need host, port and user become a `-J` list; otherwise
+    /// the generated config written by `write_jump_config` is used (`-F`)
+    /// and the connection jumps through its last hop alias.
+    pub fn jump_chain(self, profile: &Profile) -> Self {
+        if profile.jump_hosts.is_empty() {
+            return self;
+        }
+
+        if needs_jump_config(profile) {
+            self.config_file(&paths::jump_config_file(&profile.id))
+                .option("ProxyJump", &jump_alias(profile.jump_hosts.len() - 1))
+        } else {
+            self.jump_hosts(&profile.jump_hosts)
+        }
+    }
+
+    /// Add jump hosts (-J), first hop first
+    pub fn jump_hosts(mut self, hops: &[JumpHost]) -> Self {
+        let list: Vec<String> = hops.iter().map(JumpHost::destination).collect();
+        self.args.push("-J".to_string());
+        self.args.push(list.join(","));
+        self
+    }
+
+    /// Use a specific ssh_config file (-F)
+    pub fn config_file(mut self, path: &Path) -> Self {
+        self.args.push("-F".to_string());
+        self.args.push(path.display().to_string());
+        self
+    }
+
     /// Add identity file (-i)
     pub fn identity_file(mut self, path: &str) -> Self {
         self.args.push("-i".to_string());
@@ -168,9 +227,18 @@ impl SshArgs {
     }
 
     /// Build arguments specifically for tunnel-only mode
-    /// Adds -N (no command) and -T (no TTY)
+    /// Adds -N (no command), -T (no TTY) and -v (connection and forward
+    /// progress is read from ssh's debug output)
     pub fn build_tunnel_mode(self) -> Vec<String> {
-        let mut args = vec!["-N".to_string(), "-T".to_string()];
+        let mut args = vec!["-N".to_string(), "-T".to_string(), "-v".to_string()];
+        args.extend(self.args);
+        args
+    }
+
+    /// Build arguments that connect ssh's stdin/stdout to `target`
+    /// (`host:port`) as seen from the server (-W), e.g. to probe it
+    pub fn build_probe_mode(self, target: &str) -> Vec<String> {
+        let mut args = vec!["-W".to_string(), target.to_string()];
         args.extend(self.args);
         args
     }
@@ -217,7 +285,7 @@ pub fn validate_args(args: &[String]) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::TunnelSpec;
+    use crate::types::{TunnelEndpoint, TunnelSpec};
 
     #[test]
     fn test_ssh_args_basic() {
@@ -243,7 +311,7 @@ mod tests {
             .build();
 
         assert!(args.contains(&"-R".to_string()));
-        assert!(args.contains(&"localhost:8080:localhost:3000".to_string()));
+        assert!(args.contains(&"0.0.0.0:8080:0.0.0.0:3000".to_string()));
     }
 
     #[test]
@@ -260,6 +328,45 @@ mod tests {
         assert!(args.contains(&"testuser@example.com".to_string()));
     }
 
+    #[test]
+    fn test_ssh_args_from_profile_mixed_kinds() {
+        let profile = Profile::new("test", "example.com", "testuser")
+            .with_tunnel(TunnelSpec::new(8080, 3000))
+            .with_tunnel(TunnelSpec::local(5432, "db.internal", 5432))
+            .with_tunnel(TunnelSpec::dynamic(1080));
+
+        let args = SshArgs::from_profile(&profile).build();
+
+        let pairs: Vec<(&str, &str)> = args
+            .windows(2)
+            .filter(|w| matches!(w[0].as_str(), "-R" | "-L" | "-D"))
+            .map(|w| (w[0].as_str(), w[1].as_str()))
+            .collect();
+        assert_eq!(pairs, vec![
+            ("-R", "0.0.0.0:8080:0.0.0.0:3000"),
+            ("-L", "0.0.0.0:5432:db.internal:5432"),
+            ("-D", "0.0.0.0:1080"),
+        ]);
+    }
+
+    #[test]
+    fn test_ssh_args_socket_tunnel_unlinks_stale_socket() {
+        let socket_tunnel = TunnelSpec::new(0, 0)
+            .with_remote_endpoint(TunnelEndpoint::Socket("/run/remote.sock".into()))
+            .with_local_endpoint(TunnelEndpoint::Socket("/var/run/docker.sock".into()));
+        let profile = Profile::new("test", "example.com", "testuser").with_tunnel(socket_tunnel);
+
+        let args = SshArgs::from_profile(&profile).build();
+        assert!(args.contains(&"/run/remote.sock:/var/run/docker.sock".to_string()));
+        assert!(args.contains(&"StreamLocalBindUnlink=yes".to_string()));
+
+        // TCP-only profiles don't get the option
+        let profile = Profile::new("test", "example.com", "testuser")
+            .with_tunnel(TunnelSpec::new(8080, 3000));
+        let args = SshArgs::from_profile(&profile).build();
+        assert!(!args.iter().any(|a| a.starts_with("StreamLocalBindUnlink")));
+    }
+
     #[test]
     fn test_validate_args_safe() {
         let args = vec![
@@ -280,4 +387,37 @@ mod tests {
         ];
         assert!(validate_args(&args).is_err());
     }
+
+    #[test]
+    fn test_jump_hosts_as_j_list() {
+        let mut profile = Profile::new("test", "relay.internal", "user");
+        profile.jump_hosts = vec![
+            JumpHost { user: Some("ops".to_string()), ..JumpHost::new("bastion.example.com") },
+            JumpHost { port: 2222, ..JumpHost::new("fd00::5") },
+        ];
+
+        let args = SshArgs::from_profile(&profile).build();
+        let j = args.iter().position(|a| a == "-J").unwrap();
+        assert_eq!(args[j + 1], "ops@bastion.example.com,[fd00::5]:2222");
+        assert!(!args.contains(&"-F".to_string()));
+        assert_eq!(args.last().unwrap(), "user@relay.internal");
+    }
+
+    #[test]
+    fn test_jump_hosts_with_keys_use_generated_config() {
+        let mut profile = Profile::new("test", "relay.internal", "user");
+        profile.jump_hosts = vec![
+            JumpHost::new("bastion.example.com"),
+            JumpHost {
+                auth: AuthMethod::KeyFile { path: "/keys/inner".to_string() },
+                ..JumpHost::new("inner.example.com")
+            },
+        ];
+
+        let args = SshArgs::from_profile(&profile).build();
+        let f = args.iter().position(|a| a == "-F").unwrap();
+        assert_eq!(args[f + 1], paths::jump_config_file(&profile.id).display().to_string());
+        assert!(args.contains(&"ProxyJump=rssh-jump-1".to_string()));
+        assert!(!args.contains(&"-J".to_string()));
+    }
 }
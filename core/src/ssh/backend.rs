@@ -0,0 +1,183 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use tokio::process::Child;
+use tokio::sync::mpsc;
+
+use crate::config::StrictHostKeyChecking;
+use crate::error::{CoreError, Result};
+use crate::types::{BackendKind, Profile};
+
+use super::detect::SshInfo;
+use super::readiness::watch_readiness;
+use super::spawn::{probe_remote, spawn_ssh, SshOutput};
+
+/// Boxed future returned by backend trait methods
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Per-connection settings that are not part of the profile
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    /// Password for `AuthMethod::Password`
+    pub password: Option<String>,
+    /// Path to `sshpass` (OpenSSH backend only)
+    pub sshpass_path: Option<String>,
+    /// How unknown and changed host keys are treated (embedded backend only)
+    pub host_key_checking: StrictHostKeyChecking,
+    /// known_hosts file for hosts that do not name their own; `None` means
+    /// `~/.ssh/known_hosts` (embedded backend only)
+    pub known_hosts_file: Option<PathBuf>,
+}
+
+/// A way of establishing a profile's SSH connection and tunnels
+///
+/// The supervisor picks the backend named by `Profile::backend` and drives
+/// the returned [`TunnelConnection`] the same way for every backend.
+pub trait TunnelBackend: Send + Sync {
+    /// Which profile setting selects this backend
+    fn kind(&self) -> BackendKind;
+
+    /// Start connecting; the connection reports progress through its output channel
+    fn connect<'a>(
+        &'a self,
+        profile: &'a Profile,
+        options: &'a ConnectOptions,
+    ) -> BoxFuture<'a, Result<TunnelConnection>>;
+
+    /// Open a TCP connection from the server to `host:port` and close it again
+    ///
+    /// Used by `remote_tcp` health checks; backends that cannot make such a
+    /// connection fail every check.
+    fn probe_remote<'a>(
+        &'a self,
+        _profile: &'a Profile,
+        _options: &'a ConnectOptions,
+        _host: &'a str,
+        _port: u16,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            Err(CoreError::Other(format!("The {} backend cannot run remote health checks", self.kind())))
+        })
+    }
+}
+
+/// Stops a connection and reports whether it has ended
+pub trait ConnectionControl: Send + Sync {
+    /// Exit status if the connection has ended: `Some(code)` with `None`
+    /// for "killed", or `None` while it is still running
+    fn try_wait(&mut self) -> Result<Option<Option<i32>>>;
+
+    /// Tear the connection down
+    fn kill(&mut self) -> BoxFuture<'_, Result<()>>;
+}
+
+impl ConnectionControl for Child {
+    fn try_wait(&mut self) -> Result<Option<Option<i32>>> {
+        Ok(Child::try_wait(self)?.map(|status| status.code()))
+    }
+
+    fn kill(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { Ok(Child::kill(self).await?) })
+    }
+}
+
+/// A running connection, independent of the backend that made it
+pub struct TunnelConnection {
+    /// Process ID, for backends that run a separate process
+    pub pid: Option<u32>,
+    /// Output and progress signals
+    pub output_rx: mpsc::Receiver<SshOutput>,
+    control: Box<dyn ConnectionControl>,
+}
+
+impl TunnelConnection {
+    pub fn new(
+        pid: Option<u32>,
+        output_rx: mpsc::Receiver<SshOutput>,
+        control: Box<dyn ConnectionControl>,
+    ) -> Self {
+        Self { pid, output_rx, control }
+    }
+
+    /// Check whether the connection has ended
+    pub fn try_wait(&mut self) -> Result<Option<Option<i32>>> {
+        self.control.try_wait()
+    }
+
+    /// Tear the connection down
+    pub async fn kill(&mut self) -> Result<()> {
+        self.control.kill().await
+    }
+
+    /// Pass the output through another stage, e.g. to add signals
+    pub fn map_output(
+        mut self,
+        f: impl FnOnce(mpsc::Receiver<SshOutput>) -> mpsc::Receiver<SshOutput>,
+    ) -> Self {
+        let (_, empty) = mpsc::channel(1);
+        let output_rx = std::mem::replace(&mut self.output_rx, empty);
+        self.output_rx = f(output_rx);
+        self
+    }
+}
+
+/// Backend that runs the system OpenSSH client
+///
+/// ssh runs with `-v` and its debug output is parsed for readiness signals.
+pub struct OpenSshBackend {
+    ssh_info: SshInfo,
+}
+
+impl OpenSshBackend {
+    pub fn new(ssh_info: SshInfo) -> Self {
+        Self { ssh_info }
+    }
+
+    pub fn ssh_info(&self) -> &SshInfo {
+        &self.ssh_info
+    }
+}
+
+impl TunnelBackend for OpenSshBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::OpenSsh
+    }
+
+    fn connect<'a>(
+        &'a self,
+        profile: &'a Profile,
+        options: &'a ConnectOptions,
+    ) -> BoxFuture<'a, Result<TunnelConnection>> {
+        Box::pin(async move {
+            let process = spawn_ssh(
+                &self.ssh_info,
+                profile,
+                options.password.as_deref().or(profile.password.as_deref()),
+                options.sshpass_path.as_deref(),
+            )
+            .await?;
+            let ssh_path = self.ssh_info.path.clone();
+            Ok(process
+                .into_connection()
+                .map_output(|raw| watch_readiness(raw, profile, ssh_path)))
+        })
+    }
+
+    fn probe_remote<'a>(
+        &'a self,
+        profile: &'a Profile,
+        options: &'a ConnectOptions,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(probe_remote(
+            &self.ssh_info,
+            profile,
+            options.password.as_deref().or(profile.password.as_deref()),
+            options.sshpass_path.as_deref(),
+            host,
+            port,
+        ))
+    }
+}
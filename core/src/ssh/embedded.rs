@@ -0,0 +1,1049 @@
+//! In-process SSH client backend built on russh.
+//!
+//! There is no output to interpret: the backend reports authentication,
+//! every forward request's reply and running per-tunnel traffic totals
+//! directly, and needs no ssh binary on the machine.
+
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use russh::client::{self, DisconnectReason, Handle, Msg};
+use russh::keys::{self, PrivateKeyWithHashAlg, PublicKey};
+use russh::{Channel, Disconnect};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::timeout;
+
+use crate::config::StrictHostKeyChecking;
+use crate::error::{CoreError, Result};
+use crate::types::{AuthMethod, BackendKind, Profile, TunnelEndpoint, TunnelKind, TunnelSpec, TunnelTraffic};
+
+use super::backend::{BoxFuture, ConnectOptions, ConnectionControl, TunnelBackend, TunnelConnection};
+use super::socks::SocksRequest;
+use super::spawn::SshOutput;
+
+/// Upper bound for opening the TCP connection to the first hop
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often changed traffic totals are reported
+const TRAFFIC_INTERVAL: Duration = Duration::from_secs(1);
+/// Exit code for a lost or failed connection, as ssh uses
+const EXIT_FAILURE: i32 = 255;
+
+/// Backend that speaks SSH in-process
+///
+/// Host keys are checked against the hop's `known_hosts_file`, else the
+/// connection's (the app-managed file when `ssh.use_app_known_hosts` is
+/// set), else `~/.ssh/known_hosts`, following `ssh.strict_host_key_checking`
+/// the way OpenSSH follows `StrictHostKeyChecking`. `extra_options` are
+/// ssh_config options and do not apply.
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedBackend;
+
+impl EmbeddedBackend {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TunnelBackend for EmbeddedBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Embedded
+    }
+
+    fn connect<'a>(
+        &'a self,
+        profile: &'a Profile,
+        options: &'a ConnectOptions,
+    ) -> BoxFuture<'a, Result<TunnelConnection>> {
+        Box::pin(async move {
+            for hop in &profile.jump_hosts {
+                hop.validate()?;
+            }
+
+            let (tx, rx) = mpsc::channel(100);
+            let exit = Arc::new(Mutex::new(None));
+            let task = tokio::spawn(run_connection(profile.clone(), options.clone(), tx, exit.clone()));
+            tracing::info!("Started embedded SSH connection to {}", profile.destination());
+
+            Ok(TunnelConnection::new(None, rx, Box::new(EmbeddedControl { task, exit })))
+        })
+    }
+
+    fn probe_remote<'a>(
+        &'a self,
+        profile: &'a Profile,
+        options: &'a ConnectOptions,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let connection = open_session(profile, options, None).await?;
+            let opened = connection
+                .handle
+                .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+                .await;
+            let _ = connection.handle.disconnect(Disconnect::ByApplication, "", "en").await;
+
+            let channel = opened.map_err(|e| CoreError::Other(format!("server cannot connect to {}:{}: {}", host, port, e)))?;
+            let _ = channel.close().await;
+            Ok(())
+        })
+    }
+}
+
+/// Stops the connection task and reports how it ended
+struct EmbeddedControl {
+    task: JoinHandle<()>,
+    exit: Arc<Mutex<Option<Option<i32>>>>,
+}
+
+impl ConnectionControl for EmbeddedControl {
+    fn try_wait(&mut self) -> Result<Option<Option<i32>>> {
+        Ok(*self.exit.lock().unwrap())
+    }
+
+    fn kill(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            // Dropping the task drops the session, its listeners and channels
+            self.task.abort();
+            let _ = (&mut self.task).await;
+            self.exit.lock().unwrap().get_or_insert(None);
+            Ok(())
+        })
+    }
+}
+
+impl Drop for EmbeddedControl {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Run a connection until it ends, then report why and exit like ssh would
+async fn run_connection(
+    profile: Profile,
+    options: ConnectOptions,
+    tx: mpsc::Sender<SshOutput>,
+    exit: Arc<Mutex<Option<Option<i32>>>>,
+) {
+    let reason = match drive(&profile, &options, &tx).await {
+        Ok(reason) => reason,
+        Err(e) => e.to_string(),
+    };
+    tracing::info!("Embedded SSH connection to {} ended: {}", profile.destination(), reason);
+
+    *exit.lock().unwrap() = Some(Some(EXIT_FAILURE));
+    let _ = tx.send(SshOutput::Stderr(reason)).await;
+    let _ = tx.send(SshOutput::Exit(Some(EXIT_FAILURE))).await;
+}
+
+/// Connect, set up every tunnel and serve them until the connection is lost
+///
+/// Returns why the connection ended; errors are failures to set it up.
+async fn drive(profile: &Profile, options: &ConnectOptions, tx: &mpsc::Sender<SshOutput>) -> Result<String> {
+    let (incoming_tx, mut incoming_rx) = mpsc::unbounded_channel();
+    let mut connection = open_session(profile, options, Some(incoming_tx)).await?;
+    let _ = tx.send(SshOutput::Connected).await;
+
+    let counters: Vec<Arc<TrafficCounter>> = profile.tunnels.iter().map(|_| Arc::default()).collect();
+
+    // The server listens for remote tunnels and opens a channel per connection
+    let mut routes = Vec::new();
+    for (index, tunnel) in profile.tunnels.iter().enumerate() {
+        if !tunnel.kind.listens_remotely() {
+            continue;
+        }
+        match request_remote_forward(&mut connection.handle, tunnel).await {
+            Ok((listener, allocated_port)) => {
+                routes.push((listener, index));
+                let _ = tx.send(SshOutput::ForwardReady { index, allocated_port }).await;
+            }
+            Err(message) => {
+                let _ = tx.send(SshOutput::ForwardFailed { index, message: message.clone() }).await;
+                return Err(CoreError::Other(message));
+            }
+        }
+    }
+
+    // Local and dynamic tunnels listen here and open a channel per connection
+    let handle = Arc::new(connection.handle);
+    let mut tasks = JoinSet::new();
+    for (index, tunnel) in profile.tunnels.iter().enumerate() {
+        if tunnel.kind.listens_remotely() {
+            continue;
+        }
+        match LocalListener::bind(&tunnel.local_endpoint()).await {
+            Ok(listener) => {
+                tasks.spawn(serve_local(listener, handle.clone(), tunnel.clone(), counters[index].clone()));
+                let _ = tx.send(SshOutput::ForwardReady { index, allocated_port: None }).await;
+            }
+            Err(e) => {
+                let message = format!("cannot listen on {}: {}", tunnel.local_endpoint(), e);
+                let _ = tx.send(SshOutput::ForwardFailed { index, message: message.clone() }).await;
+                return Err(CoreError::Other(message));
+            }
+        }
+    }
+
+    let mut reported = vec![TunnelTraffic::default(); counters.len()];
+    let mut ticker = tokio::time::interval(TRAFFIC_INTERVAL);
+    let reason = loop {
+        tokio::select! {
+            reason = &mut connection.closed => {
+                break reason.unwrap_or_else(|_| "connection closed".to_string());
+            }
+            Some(incoming) = incoming_rx.recv() => {
+                match routes.iter().find(|(listener, _)| *listener == incoming.listener) {
+                    Some(&(_, index)) => {
+                        let tunnel = profile.tunnels[index].clone();
+                        tasks.spawn(serve_forwarded(incoming.channel, tunnel, counters[index].clone()));
+                    }
+                    None => {
+                        tracing::debug!("Closing forwarded channel for unrequested listener {:?}", incoming.listener);
+                        let _ = incoming.channel.close().await;
+                    }
+                }
+            }
+            _ = ticker.tick() => report_traffic(tx, &counters, &mut reported).await,
+            Some(_) = tasks.join_next() => {}
+        }
+    };
+
+    report_traffic(tx, &counters, &mut reported).await;
+    drop(connection.hops);
+    Ok(reason)
+}
+
+/// Send the totals that changed since the last report
+async fn report_traffic(tx: &mpsc::Sender<SshOutput>, counters: &[Arc<TrafficCounter>], reported: &mut [TunnelTraffic]) {
+    for (index, counter) in counters.iter().enumerate() {
+        let traffic = counter.snapshot();
+        if traffic != reported[index] {
+            reported[index] = traffic;
+            let _ = tx.send(SshOutput::Traffic { index, traffic }).await;
+        }
+    }
+}
+
+/// An authenticated session with the profile's server
+struct Connection {
+    handle: Handle<ClientHandler>,
+    /// Sessions with the jump hosts, which carry `handle`'s transport
+    hops: Vec<Handle<ClientHandler>>,
+    /// Why the server session ended, once it has
+    closed: oneshot::Receiver<String>,
+}
+
+/// One SSH server on the way: a jump host or the profile's server
+struct Hop<'a> {
+    host: &'a str,
+    port: u16,
+    user: String,
+    auth: &'a AuthMethod,
+    identity_file: Option<&'a str>,
+    known_hosts_file: Option<&'a str>,
+}
+
+/// Connect through the jump hosts to the server and authenticate every hop
+///
+/// Forwarded channels the server opens are passed to `incoming`.
+async fn open_session(
+    profile: &Profile,
+    options: &ConnectOptions,
+    mut incoming: Option<mpsc::UnboundedSender<Incoming>>,
+) -> Result<Connection> {
+    let config = Arc::new(client::Config {
+        keepalive_interval: match profile.keepalive_interval {
+            0 => None,
+            secs => Some(Duration::from_secs(secs as u64)),
+        },
+        keepalive_max: profile.keepalive_count as usize,
+        ..Default::default()
+    });
+
+    let mut route: Vec<Hop> = profile
+        .jump_hosts
+        .iter()
+        .map(|hop| Hop {
+            host: &hop.host,
+            port: hop.port,
+            user: hop.user.clone().unwrap_or_else(local_user),
+            auth: &hop.auth,
+            identity_file: None,
+            known_hosts_file: hop.known_hosts_file.as_deref(),
+        })
+        .collect();
+    route.push(Hop {
+        host: &profile.host,
+        port: profile.port,
+        user: profile.user.clone(),
+        auth: &profile.auth,
+        identity_file: profile.identity_file.as_deref(),
+        known_hosts_file: profile.known_hosts_file.as_deref(),
+    });
+
+    let password = options
+        .password
+        .clone()
+        .or_else(|| profile.password.clone())
+        .or_else(|| std::env::var("SSHPASS").ok());
+
+    let last = route.len() - 1;
+    let mut hops: Vec<Handle<ClientHandler>> = Vec::new();
+    let mut closed = None;
+    for (index, hop) in route.iter().enumerate() {
+        let (closed_tx, closed_rx) = oneshot::channel();
+        let handler = ClientHandler {
+            host: hop.host.to_string(),
+            port: hop.port,
+            known_hosts: known_hosts_path(hop.known_hosts_file, options.known_hosts_file.as_ref())?,
+            host_key_checking: options.host_key_checking,
+            incoming: if index == last { incoming.take() } else { None },
+            closed: Some(closed_tx),
+        };
+
+        let mut handle = match hops.last() {
+            None => {
+                let stream = timeout(CONNECT_TIMEOUT, TcpStream::connect((hop.host, hop.port)))
+                    .await
+                    .map_err(|_| CoreError::Other(format!("connection to {}:{} timed out", hop.host, hop.port)))?
+                    .map_err(|e| CoreError::Other(format!("cannot connect to {}:{}: {}", hop.host, hop.port, e)))?;
+                client::connect_stream(config.clone(), stream, handler).await?
+            }
+            Some(previous) => {
+                let channel = previous
+                    .channel_open_direct_tcpip(hop.host, hop.port as u32, "127.0.0.1", 0)
+                    .await
+                    .map_err(|e| CoreError::Other(format!("cannot reach {}:{} through the jump host: {}", hop.host, hop.port, e)))?;
+                client::connect_stream(config.clone(), channel.into_stream(), handler).await?
+            }
+        };
+        authenticate(&mut handle, hop, password.as_deref()).await?;
+
+        hops.push(handle);
+        closed = Some(closed_rx);
+    }
+
+    let handle = hops.pop().expect("route includes the server");
+    Ok(Connection {
+        handle,
+        hops,
+        closed: closed.expect("route includes the server"),
+    })
+}
+
+/// Log in to one hop with its authentication method, then its identity file
+async fn authenticate(handle: &mut Handle<ClientHandler>, hop: &Hop<'_>, password: Option<&str>) -> Result<()> {
+    let mut authenticated = match hop.auth {
+        AuthMethod::Agent => authenticate_agent(handle, &hop.user).await?,
+        AuthMethod::KeyFile { path } => authenticate_key_file(handle, &hop.user, path).await?,
+        AuthMethod::Password => {
+            let password = password.ok_or_else(|| {
+                CoreError::Other(
+                    "Password auth requires a password. Provide it via the start-session request (recommended) or set SSHPASS in the parent process environment."
+                        .to_string(),
+                )
+            })?;
+            handle.authenticate_password(&hop.user, password).await?.success()
+        }
+    };
+
+    if !authenticated {
+        if let Some(path) = hop.identity_file {
+            authenticated = authenticate_key_file(handle, &hop.user, path).await?;
+        }
+    }
+
+    if authenticated {
+        Ok(())
+    } else {
+        Err(CoreError::Other(format!("{}@{}: Permission denied", hop.user, hop.host)))
+    }
+}
+
+async fn authenticate_key_file(handle: &mut Handle<ClientHandler>, user: &str, path: &str) -> Result<bool> {
+    let key = keys::load_secret_key(expand_home(path), None)
+        .map_err(|e| CoreError::Other(format!("cannot load key file '{}': {}", path, e)))?;
+    let hash_alg = handle.best_supported_rsa_hash().await?.flatten();
+    let key = PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg);
+    Ok(handle.authenticate_publickey(user, key).await?.success())
+}
+
+#[cfg(unix)]
+async fn authenticate_agent(handle: &mut Handle<ClientHandler>, user: &str) -> Result<bool> {
+    let mut agent = keys::agent::client::AgentClient::connect_env()
+        .await
+        .map_err(|e| CoreError::Other(format!("cannot reach the SSH agent: {}", e)))?;
+    let identities = agent
+        .request_identities()
+        .await
+        .map_err(|e| CoreError::Other(format!("cannot list SSH agent keys: {}", e)))?;
+    let hash_alg = handle.best_supported_rsa_hash().await?.flatten();
+
+    for key in identities {
+        let result = handle
+            .authenticate_publickey_with(user, key, hash_alg, &mut agent)
+            .await
+            .map_err(|e| CoreError::Other(format!("SSH agent signing failed: {}", e)))?;
+        if result.success() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(not(unix))]
+async fn authenticate_agent(_handle: &mut Handle<ClientHandler>, _user: &str) -> Result<bool> {
+    Err(CoreError::Other("the embedded backend cannot use an SSH agent on this platform".to_string()))
+}
+
+/// The name ssh logs in with when a jump host sets no user
+fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "root".to_string())
+}
+
+fn known_hosts_path(file: Option<&str>, default: Option<&PathBuf>) -> Result<PathBuf> {
+    match (file, default) {
+        (Some(file), _) => Ok(expand_home(file)),
+        (None, Some(default)) => Ok(default.clone()),
+        (None, None) => directories::BaseDirs::new()
+            .map(|dirs| dirs.home_dir().join(".ssh").join("known_hosts"))
+            .ok_or_else(|| CoreError::Other("cannot find the home directory for known_hosts".to_string())),
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), directories::BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Where the server accepted connections for a remote tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RemoteListener {
+    Tcp(u16),
+    Socket(String),
+}
+
+/// A connection the server accepted on a remote tunnel's listener
+struct Incoming {
+    listener: RemoteListener,
+    channel: Channel<Msg>,
+}
+
+/// Ask the server to listen for a remote or remote dynamic tunnel
+///
+/// Returns the listener and, for port 0, the port the server picked.
+async fn request_remote_forward(
+    handle: &mut Handle<ClientHandler>,
+    tunnel: &TunnelSpec,
+) -> std::result::Result<(RemoteListener, Option<u16>), String> {
+    match tunnel.remote_endpoint() {
+        TunnelEndpoint::Tcp { host, port } => {
+            let allocated = handle
+                .tcpip_forward(host, port as u32)
+                .await
+                .map_err(|e| format!("remote port forwarding failed for listen port {}: {}", port, e))?;
+            if port == 0 {
+                let allocated = allocated as u16;
+                Ok((RemoteListener::Tcp(allocated), Some(allocated)))
+            } else {
+                Ok((RemoteListener::Tcp(port), None))
+            }
+        }
+        TunnelEndpoint::Socket(path) => {
+            handle
+                .streamlocal_forward(path.clone())
+                .await
+                .map_err(|e| format!("remote port forwarding failed for listen path {}: {}", path, e))?;
+            Ok((RemoteListener::Socket(path), None))
+        }
+    }
+}
+
+/// Connect a channel the server opened for a remote tunnel to its local side
+async fn serve_forwarded(channel: Channel<Msg>, tunnel: TunnelSpec, counter: Arc<TrafficCounter>) {
+    counter.channels.fetch_add(1, Ordering::Relaxed);
+    let mut remote = Counted::new(channel.into_stream(), counter);
+
+    let local = match tunnel.kind {
+        TunnelKind::RemoteDynamic => connect_socks_target(&mut remote).await,
+        _ => connect_local(&tunnel.local_endpoint()).await,
+    };
+
+    match local {
+        Ok(mut local) => {
+            let _ = tokio::io::copy_bidirectional(&mut local, &mut remote).await;
+        }
+        Err(e) => tracing::debug!("Dropping forwarded connection for '{}': {}", tunnel, e),
+    }
+}
+
+/// Connect to the target a SOCKS client on the server asks for
+async fn connect_socks_target<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> io::Result<Box<dyn Transport>> {
+    let request = SocksRequest::read(stream).await?;
+    let local = connect_local(&TunnelEndpoint::Tcp { host: request.host.clone(), port: request.port }).await;
+    request.reply(stream, local.is_ok()).await?;
+    local
+}
+
+/// Accept connections for a local or dynamic tunnel until the task is dropped
+async fn serve_local(
+    listener: LocalListener,
+    handle: Arc<Handle<ClientHandler>>,
+    tunnel: TunnelSpec,
+    counter: Arc<TrafficCounter>,
+) {
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, originator)) => {
+                    connections.spawn(serve_local_connection(
+                        stream,
+                        originator,
+                        handle.clone(),
+                        tunnel.clone(),
+                        counter.clone(),
+                    ));
+                }
+                Err(e) => {
+                    tracing::warn!("Accepting a connection for '{}' failed: {}", tunnel, e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            },
+            Some(_) = connections.join_next() => {}
+        }
+    }
+}
+
+/// Carry one local connection over a new channel to the tunnel's remote side
+async fn serve_local_connection(
+    mut local: Box<dyn Transport>,
+    (originator_host, originator_port): (String, u16),
+    handle: Arc<Handle<ClientHandler>>,
+    tunnel: TunnelSpec,
+    counter: Arc<TrafficCounter>,
+) {
+    let opened = match tunnel.kind {
+        TunnelKind::Dynamic => match SocksRequest::read(&mut local).await {
+            Ok(request) => {
+                let opened = handle
+                    .channel_open_direct_tcpip(request.host.clone(), request.port as u32, originator_host, originator_port as u32)
+                    .await
+                    .map_err(|e| e.to_string());
+                match request.reply(&mut local, opened.is_ok()).await {
+                    Ok(()) => opened,
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(e.to_string()),
+        },
+        _ => match tunnel.remote_endpoint() {
+            TunnelEndpoint::Tcp { host, port } => handle
+                .channel_open_direct_tcpip(host, port as u32, originator_host, originator_port as u32)
+                .await
+                .map_err(|e| e.to_string()),
+            TunnelEndpoint::Socket(path) => handle.channel_open_direct_streamlocal(path).await.map_err(|e| e.to_string()),
+        },
+    };
+
+    match opened {
+        Ok(channel) => {
+            counter.channels.fetch_add(1, Ordering::Relaxed);
+            let mut remote = Counted::new(channel.into_stream(), counter);
+            let _ = tokio::io::copy_bidirectional(&mut local, &mut remote).await;
+        }
+        Err(e) => tracing::debug!("Dropping local connection for '{}': {}", tunnel, e),
+    }
+}
+
+/// A stream to or from this machine
+trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+async fn connect_local(endpoint: &TunnelEndpoint) -> io::Result<Box<dyn Transport>> {
+    match endpoint {
+        TunnelEndpoint::Tcp { host, port } => {
+            let stream = TcpStream::connect((bind_host(host), *port)).await?;
+            Ok(Box::new(stream))
+        }
+        #[cfg(unix)]
+        TunnelEndpoint::Socket(path) => Ok(Box::new(tokio::net::UnixStream::connect(path).await?)),
+        #[cfg(not(unix))]
+        TunnelEndpoint::Socket(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "Unix sockets are not supported")),
+    }
+}
+
+/// ssh's spelling of "every address"
+fn bind_host(host: &str) -> &str {
+    match host {
+        "" | "*" => "0.0.0.0",
+        host => host,
+    }
+}
+
+/// The listening side of a local or dynamic tunnel
+enum LocalListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Socket(tokio::net::UnixListener, PathBuf),
+}
+
+impl LocalListener {
+    async fn bind(endpoint: &TunnelEndpoint) -> io::Result<Self> {
+        match endpoint {
+            TunnelEndpoint::Tcp { host, port } => Ok(Self::Tcp(TcpListener::bind((bind_host(host), *port)).await?)),
+            #[cfg(unix)]
+            TunnelEndpoint::Socket(path) => Ok(Self::Socket(tokio::net::UnixListener::bind(path)?, PathBuf::from(path))),
+            #[cfg(not(unix))]
+            TunnelEndpoint::Socket(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "Unix sockets are not supported")),
+        }
+    }
+
+    /// The next connection and the address it came from
+    async fn accept(&self) -> io::Result<(Box<dyn Transport>, (String, u16))> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, peer) = listener.accept().await?;
+                Ok((Box::new(stream), (peer.ip().to_string(), peer.port())))
+            }
+            #[cfg(unix)]
+            Self::Socket(listener, _) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Box::new(stream), ("127.0.0.1".to_string(), 0)))
+            }
+        }
+    }
+}
+
+impl Drop for LocalListener {
+    fn drop(&mut self) {
+        // The next connection binds the same path
+        #[cfg(unix)]
+        if let Self::Socket(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Running totals for one tunnel
+#[derive(Debug, Default)]
+struct TrafficCounter {
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    channels: AtomicU64,
+}
+
+impl TrafficCounter {
+    fn snapshot(&self) -> TunnelTraffic {
+        TunnelTraffic {
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            channels: self.channels.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A channel stream that adds what passes through it to a tunnel's totals
+struct Counted<S> {
+    inner: S,
+    counter: Arc<TrafficCounter>,
+}
+
+impl<S> Counted<S> {
+    fn new(inner: S, counter: Arc<TrafficCounter>) -> Self {
+        Self { inner, counter }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Counted<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = (buf.filled().len() - before) as u64;
+        self.counter.bytes_received.fetch_add(read, Ordering::Relaxed);
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Counted<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            self.counter.bytes_sent.fetch_add(written as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// russh callbacks for one hop: host key checks, forwarded channels and
+/// the end of the session
+struct ClientHandler {
+    host: String,
+    port: u16,
+    known_hosts: PathBuf,
+    host_key_checking: StrictHostKeyChecking,
+    incoming: Option<mpsc::UnboundedSender<Incoming>>,
+    closed: Option<oneshot::Sender<String>>,
+}
+
+impl ClientHandler {
+    fn forward(&self, listener: RemoteListener, channel: Channel<Msg>) {
+        if let Some(incoming) = &self.incoming {
+            let _ = incoming.send(Incoming { listener, channel });
+        }
+    }
+}
+
+impl client::Handler for ClientHandler {
+    type Error = CoreError;
+
+    async fn check_server_key(&mut self, server_public_key: &PublicKey) -> Result<bool> {
+        if self.host_key_checking == StrictHostKeyChecking::No {
+            tracing::warn!("Not checking the host key for {} (strict_host_key_checking = no)", self.host);
+            return Ok(true);
+        }
+
+        match keys::check_known_hosts_path(&self.host, self.port, server_public_key, &self.known_hosts) {
+            Ok(true) => Ok(true),
+            Ok(false) if self.host_key_checking == StrictHostKeyChecking::Yes => Err(CoreError::HostKeyRejected(format!(
+                "no {} host key for {} is known in {} and strict host key checking is on",
+                server_public_key.algorithm(),
+                self.host,
+                self.known_hosts.display()
+            ))),
+            Ok(false) => {
+                if let Some(dir) = self.known_hosts.parent() {
+                    let _ = std::fs::create_dir_all(dir);
+                }
+                keys::known_hosts::learn_known_hosts_path(&self.host, self.port, server_public_key, &self.known_hosts)
+                    .map_err(|e| CoreError::HostKeyRejected(format!("cannot record the key for {}: {}", self.host, e)))?;
+                tracing::info!("Added {} host key for {} to {}", server_public_key.algorithm(), self.host, self.known_hosts.display());
+                Ok(true)
+            }
+            Err(keys::Error::KeyChanged { line }) => Err(CoreError::HostKeyRejected(format!(
+                "the host key for {} has changed (offending line {} of {})",
+                self.host,
+                line,
+                self.known_hosts.display()
+            ))),
+            Err(e) => Err(CoreError::HostKeyRejected(format!("cannot check the key for {}: {}", self.host, e))),
+        }
+    }
+
+    async fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        _connected_address: &str,
+        connected_port: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut client::Session,
+    ) -> Result<()> {
+        self.forward(RemoteListener::Tcp(connected_port as u16), channel);
+        Ok(())
+    }
+
+    async fn server_channel_open_forwarded_streamlocal(
+        &mut self,
+        channel: Channel<Msg>,
+        socket_path: &str,
+        _session: &mut client::Session,
+    ) -> Result<()> {
+        self.forward(RemoteListener::Socket(socket_path.to_string()), channel);
+        Ok(())
+    }
+
+    async fn disconnected(&mut self, reason: DisconnectReason<CoreError>) -> Result<()> {
+        let (message, result) = match reason {
+            DisconnectReason::ReceivedDisconnect(info) if info.message.is_empty() => {
+                ("server closed the connection".to_string(), Ok(()))
+            }
+            DisconnectReason::ReceivedDisconnect(info) => {
+                (format!("server closed the connection: {}", info.message), Ok(()))
+            }
+            DisconnectReason::Error(e) => (format!("connection lost: {}", e), Err(e)),
+        };
+        if let Some(closed) = self.closed.take() {
+            let _ = closed.send(message);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh::server::{self, Auth};
+    use tempfile::tempdir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const PASSWORD: &str = "hunter2";
+
+    /// In-process SSH server: password auth, direct-tcpip and tcpip-forward
+    /// on loopback
+    struct TestServerHandler;
+
+    impl server::Handler for TestServerHandler {
+        type Error = russh::Error;
+
+        async fn auth_password(&mut self, _user: &str, password: &str) -> std::result::Result<Auth, Self::Error> {
+            Ok(if password == PASSWORD { Auth::Accept } else { Auth::reject() })
+        }
+
+        async fn channel_open_direct_tcpip(
+            &mut self,
+            channel: Channel<server::Msg>,
+            host_to_connect: &str,
+            port_to_connect: u32,
+            _originator_address: &str,
+            _originator_port: u32,
+            _session: &mut server::Session,
+        ) -> std::result::Result<bool, Self::Error> {
+            match TcpStream::connect((host_to_connect, port_to_connect as u16)).await {
+                Ok(mut target) => {
+                    tokio::spawn(async move {
+                        let _ = tokio::io::copy_bidirectional(&mut target, &mut channel.into_stream()).await;
+                    });
+                    Ok(true)
+                }
+                Err(_) => Ok(false),
+            }
+        }
+
+        async fn tcpip_forward(
+            &mut self,
+            address: &str,
+            port: &mut u32,
+            session: &mut server::Session,
+        ) -> std::result::Result<bool, Self::Error> {
+            let listener = TcpListener::bind(("127.0.0.1", *port as u16)).await?;
+            *port = listener.local_addr()?.port() as u32;
+
+            let handle = session.handle();
+            let (address, port) = (address.to_string(), *port);
+            tokio::spawn(async move {
+                while let Ok((mut stream, peer)) = listener.accept().await {
+                    let opened = handle
+                        .channel_open_forwarded_tcpip(address.clone(), port, peer.ip().to_string(), peer.port() as u32)
+                        .await;
+                    if let Ok(channel) = opened {
+                        tokio::spawn(async move {
+                            let _ = tokio::io::copy_bidirectional(&mut stream, &mut channel.into_stream()).await;
+                        });
+                    }
+                }
+            });
+            Ok(true)
+        }
+    }
+
+    fn random_key() -> keys::PrivateKey {
+        keys::PrivateKey::random(&mut keys::ssh_key::rand_core::OsRng, keys::Algorithm::Ed25519).unwrap()
+    }
+
+    /// Start the test server and return its port
+    async fn start_server() -> u16 {
+        let config = Arc::new(server::Config {
+            keys: vec![random_key()],
+            auth_rejection_time: Duration::ZERO,
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let config = config.clone();
+                tokio::spawn(async move {
+                    if let Ok(session) = server::run_stream(config, stream, TestServerHandler).await {
+                        let _ = session.await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    /// Echo every connection back, returning the port
+    async fn start_echo() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = stream.split();
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+            }
+        });
+        port
+    }
+
+    async fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port()
+    }
+
+    fn test_profile(ssh_port: u16, known_hosts: &std::path::Path) -> Profile {
+        let mut profile = Profile::new("embedded", "127.0.0.1", "tunnel");
+        profile.port = ssh_port;
+        profile.auth = AuthMethod::Password;
+        profile.backend = BackendKind::Embedded;
+        profile.known_hosts_file = Some(known_hosts.display().to_string());
+        profile
+    }
+
+    fn with_password(password: &str) -> ConnectOptions {
+        ConnectOptions { password: Some(password.to_string()), ..Default::default() }
+    }
+
+    async fn next_output(connection: &mut TunnelConnection) -> SshOutput {
+        timeout(Duration::from_secs(10), connection.output_rx.recv()).await.unwrap().unwrap()
+    }
+
+    async fn echo_through(port: u16, message: &[u8]) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream.write_all(message).await.unwrap();
+        let mut reply = vec![0u8; message.len()];
+        timeout(Duration::from_secs(10), stream.read_exact(&mut reply)).await.unwrap().unwrap();
+        assert_eq!(reply, message);
+    }
+
+    #[tokio::test]
+    async fn test_forwards_traffic_and_counts_it() {
+        let dir = tempdir().unwrap();
+        let known_hosts = dir.path().join("known_hosts");
+        let (ssh_port, echo_port, local_port) = (start_server().await, start_echo().await, free_port().await);
+
+        let mut remote = TunnelSpec::new(0, echo_port);
+        remote.local_host = "127.0.0.1".to_string();
+        let profile = test_profile(ssh_port, &known_hosts)
+            .with_tunnel(remote)
+            .with_tunnel(TunnelSpec::local(local_port, "127.0.0.1", echo_port));
+
+        let mut connection = EmbeddedBackend::new().connect(&profile, &with_password(PASSWORD)).await.unwrap();
+        assert!(matches!(next_output(&mut connection).await, SshOutput::Connected));
+        let allocated = match next_output(&mut connection).await {
+            SshOutput::ForwardReady { index: 0, allocated_port: Some(port) } => port,
+            other => panic!("unexpected output {:?}", other),
+        };
+        assert!(matches!(next_output(&mut connection).await, SshOutput::ForwardReady { index: 1, allocated_port: None }));
+
+        // The new host key was recorded
+        assert!(std::fs::read_to_string(&known_hosts).unwrap().contains("ssh-ed25519"));
+
+        echo_through(allocated, b"through the server").await;
+        echo_through(local_port, b"from here").await;
+
+        let mut totals = [TunnelTraffic::default(); 2];
+        while totals.iter().any(|traffic| traffic.channels == 0 || traffic.bytes_received == 0) {
+            if let SshOutput::Traffic { index, traffic } = next_output(&mut connection).await {
+                totals[index] = traffic;
+            }
+        }
+        assert_eq!(totals[0], TunnelTraffic { bytes_sent: 18, bytes_received: 18, channels: 1 });
+        assert_eq!(totals[1], TunnelTraffic { bytes_sent: 9, bytes_received: 9, channels: 1 });
+
+        assert_eq!(connection.try_wait().unwrap(), None);
+        connection.kill().await.unwrap();
+        assert_eq!(connection.try_wait().unwrap(), Some(None));
+        assert!(TcpStream::connect(("127.0.0.1", local_port)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_password_ends_connection() {
+        let dir = tempdir().unwrap();
+        let profile = test_profile(start_server().await, &dir.path().join("known_hosts"));
+
+        let mut connection = EmbeddedBackend::new().connect(&profile, &with_password("wrong")).await.unwrap();
+        match next_output(&mut connection).await {
+            SshOutput::Stderr(line) => assert!(line.contains("Permission denied"), "{}", line),
+            other => panic!("unexpected output {:?}", other),
+        }
+        assert!(matches!(next_output(&mut connection).await, SshOutput::Exit(Some(255))));
+        assert_eq!(connection.try_wait().unwrap(), Some(Some(255)));
+    }
+
+    #[tokio::test]
+    async fn test_changed_host_key_is_refused() {
+        let dir = tempdir().unwrap();
+        let known_hosts = dir.path().join("known_hosts");
+        let ssh_port = start_server().await;
+        keys::known_hosts::learn_known_hosts_path("127.0.0.1", ssh_port, random_key().public_key(), &known_hosts).unwrap();
+
+        let profile = test_profile(ssh_port, &known_hosts);
+        let mut connection = EmbeddedBackend::new().connect(&profile, &with_password(PASSWORD)).await.unwrap();
+        match next_output(&mut connection).await {
+            SshOutput::Stderr(line) => assert!(line.contains("host key for 127.0.0.1 has changed"), "{}", line),
+            other => panic!("unexpected output {:?}", other),
+        }
+        assert!(matches!(next_output(&mut connection).await, SshOutput::Exit(Some(255))));
+    }
+
+    #[tokio::test]
+    async fn test_host_key_policy() {
+        let dir = tempdir().unwrap();
+        let known_hosts = dir.path().join("known_hosts");
+        let ssh_port = start_server().await;
+        let profile = test_profile(ssh_port, &known_hosts);
+
+        // Strict checking refuses a host that is not known yet
+        let strict = ConnectOptions { host_key_checking: StrictHostKeyChecking::Yes, ..with_password(PASSWORD) };
+        let mut connection = EmbeddedBackend::new().connect(&profile, &strict).await.unwrap();
+        match next_output(&mut connection).await {
+            SshOutput::Stderr(line) => assert!(line.contains("strict host key checking is on"), "{}", line),
+            other => panic!("unexpected output {:?}", other),
+        }
+        assert!(!known_hosts.exists());
+
+        // Without checking, even a changed key is accepted and nothing is recorded
+        keys::known_hosts::learn_known_hosts_path("127.0.0.1", ssh_port, random_key().public_key(), &known_hosts).unwrap();
+        let recorded = std::fs::read_to_string(&known_hosts).unwrap();
+        let off = ConnectOptions { host_key_checking: StrictHostKeyChecking::No, ..with_password(PASSWORD) };
+        let mut connection = EmbeddedBackend::new().connect(&profile, &off).await.unwrap();
+        assert!(matches!(next_output(&mut connection).await, SshOutput::Connected));
+        assert_eq!(std::fs::read_to_string(&known_hosts).unwrap(), recorded);
+        connection.kill().await.unwrap();
+
+        // The connection's known_hosts file is used when the profile names none
+        let mut profile = profile;
+        profile.known_hosts_file = None;
+        let app_known_hosts = dir.path().join("app").join("known_hosts");
+        let options = ConnectOptions { known_hosts_file: Some(app_known_hosts.clone()), ..with_password(PASSWORD) };
+        let mut connection = EmbeddedBackend::new().connect(&profile, &options).await.unwrap();
+        assert!(matches!(next_output(&mut connection).await, SshOutput::Connected));
+        assert!(std::fs::read_to_string(&app_known_hosts).unwrap().contains("ssh-ed25519"));
+        connection.kill().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_probe_remote() {
+        let dir = tempdir().unwrap();
+        let profile = test_profile(start_server().await, &dir.path().join("known_hosts"));
+        let (backend, options) = (EmbeddedBackend::new(), with_password(PASSWORD));
+
+        let echo_port = start_echo().await;
+        backend.probe_remote(&profile, &options, "127.0.0.1", echo_port).await.unwrap();
+        let closed = free_port().await;
+        assert!(backend.probe_remote(&profile, &options, "127.0.0.1", closed).await.is_err());
+    }
+}
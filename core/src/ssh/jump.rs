@@ -0,0 +1,153 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::config::paths;
+use crate::error::{CoreError, Result};
+use crate::types::{AuthMethod, JumpHost, Profile};
+
+/// Prefix of the hop aliases in the generated config
+const JUMP_ALIAS_PREFIX: &str = "rssh-jump-";
+
+/// Host alias of a jump hop in the generated config
+pub fn jump_alias(index: usize) -> String {
+    format!("{}{}", JUMP_ALIAS_PREFIX, index)
+}
+
+/// Whether a profile's jump hosts need a generated config rather than `-J`
+pub fn needs_jump_config(profile: &Profile) -> bool {
+    profile.jump_hosts.iter().any(JumpHost::needs_config)
+}
+
+/// Render an ssh_config with one `Host` block per hop, each reaching the
+/// next through `ProxyJump`.
+///
+/// `-F` replaces the user's and the system configuration, so `includes`
+/// come first for every host but the hops, giving the server the settings
+/// ssh would read without `-F`. The hops read them last, as defaults for
+/// what their own blocks leave out.
+pub fn render_jump_config(hops: &[JumpHost], includes: &[PathBuf]) -> String {
+    let mut config = String::from("# Generated by reverse-ssh-interface for jump hosts; changes are overwritten\n");
+    let include_all = |config: &mut String, patterns: &str| {
+        if includes.is_empty() {
+            return;
+        }
+        let _ = writeln!(config, "\nHost {}", patterns);
+        for include in includes {
+            let _ = writeln!(config, "    Include \"{}\"", include.display());
+        }
+    };
+
+    include_all(&mut config, &format!("* !{}*", JUMP_ALIAS_PREFIX));
+
+    for (index, hop) in hops.iter().enumerate() {
+        let _ = writeln!(config, "\nHost {}", jump_alias(index));
+        let _ = writeln!(config, "    HostName {}", hop.host);
+        let _ = writeln!(config, "    Port {}", hop.port);
+        if let Some(user) = &hop.user {
+            let _ = writeln!(config, "    User {}", user);
+        }
+        if let AuthMethod::KeyFile { path } = &hop.auth {
+            let _ = writeln!(config, "    IdentityFile \"{}\"", path);
+            let _ = writeln!(config, "    IdentitiesOnly yes");
+        }
+        if let Some(known_hosts) = &hop.known_hosts_file {
+            let _ = writeln!(config, "    UserKnownHostsFile \"{}\"", known_hosts);
+        }
+        // Hops cannot prompt: there is no terminal
+        let _ = writeln!(config, "    BatchMode yes");
+        if index > 0 {
+            let _ = writeln!(config, "    ProxyJump {}", jump_alias(index - 1));
+        }
+    }
+
+    include_all(&mut config, &format!("{}*", JUMP_ALIAS_PREFIX));
+
+    config
+}
+
+/// Write the generated jump config for a profile when it needs one
+///
+/// Returns the path passed to ssh with `-F`.
+pub fn write_jump_config(profile: &Profile) -> Result<Option<PathBuf>> {
+    if !needs_jump_config(profile) {
+        return Ok(None);
+    }
+
+    for hop in &profile.jump_hosts {
+        hop.validate()?;
+    }
+
+    let path = paths::jump_config_file(&profile.id);
+    let config = render_jump_config(&profile.jump_hosts, &default_config_files());
+    write_private(&path, &config)
+        .map_err(|e| CoreError::SshSpawnFailed(format!("Failed to write jump host config {:?}: {}", path, e)))?;
+
+    Ok(Some(path))
+}
+
+/// The configuration ssh reads when not given `-F`
+fn default_config_files() -> Vec<PathBuf> {
+    let user = directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(".ssh").join("config"));
+    let system = PathBuf::from("/etc/ssh/ssh_config");
+
+    user.into_iter().chain([system]).filter(|p| p.is_file()).collect()
+}
+
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        // ssh refuses config files others can write to
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(contents.as_bytes())
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_jump_config() {
+        let first = JumpHost {
+            user: Some("ops".to_string()),
+            known_hosts_file: Some("/etc/rssh/bastion_known_hosts".to_string()),
+            ..JumpHost::new("bastion.example.com")
+        };
+        let second = JumpHost {
+            port: 2222,
+            auth: AuthMethod::KeyFile { path: "/home/me/.ssh/inner key".to_string() },
+            ..JumpHost::new("10.0.0.5")
+        };
+
+        let config = render_jump_config(&[first, second], &[PathBuf::from("/home/me/.ssh/config")]);
+
+        assert!(config.contains("Host rssh-jump-0\n    HostName bastion.example.com\n    Port 22\n    User ops\n"));
+        assert!(config.contains("    UserKnownHostsFile \"/etc/rssh/bastion_known_hosts\"\n"));
+        assert!(config.contains("Host rssh-jump-1\n    HostName 10.0.0.5\n    Port 2222\n"));
+        assert!(config.contains("    IdentityFile \"/home/me/.ssh/inner key\"\n"));
+        assert!(config.contains("    ProxyJump rssh-jump-0\n"));
+        // The server reads the user's config before anything else; the hops after their own settings
+        let generated = "# Generated by reverse-ssh-interface for jump hosts; changes are overwritten\n";
+        assert!(config.starts_with(&format!("{}\nHost * !rssh-jump-*\n    Include \"/home/me/.ssh/config\"\n\nHost rssh-jump-0\n", generated)));
+        assert!(config.ends_with("\nHost rssh-jump-*\n    Include \"/home/me/.ssh/config\"\n"));
+
+        assert!(!render_jump_config(&[JumpHost::new("bastion")], &[]).contains("Include"));
+    }
+}
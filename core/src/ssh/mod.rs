@@ -1,9 +1,18 @@
 pub mod args;
+pub mod backend;
 pub mod detect;
+pub mod embedded;
+pub mod jump;
 pub mod known_hosts;
+pub mod readiness;
+pub mod socks;
 pub mod spawn;
 
 pub use args::{SshArgs, validate_args};
+pub use backend::{BoxFuture, ConnectOptions, ConnectionControl, OpenSshBackend, TunnelBackend, TunnelConnection};
 pub use detect::{SshInfo, detect_ssh, verify_ssh};
+pub use embedded::EmbeddedBackend;
+pub use jump::{render_jump_config, write_jump_config};
 pub use known_hosts::{KnownHostEntry, KnownHostsManager};
-pub use spawn::{SshOutput, SshProcess, spawn_ssh, spawn_ssh_with_args, test_connection};
+pub use readiness::{ReadinessParser, watch_readiness};
+pub use spawn::{SshOutput, SshProcess, probe_remote, spawn_ssh, spawn_ssh_with_args, test_connection};
@@ -0,0 +1,444 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, timeout, Instant};
+
+use crate::types::{Profile, TunnelEndpoint, TunnelSpec, TunnelState};
+
+use super::spawn::SshOutput;
+
+/// How long after the session starts to wait for forward replies before probing
+const PROBE_GRACE: Duration = Duration::from_secs(2);
+/// How often to repeat probes for tunnels that are still unconfirmed
+const PROBE_INTERVAL: Duration = Duration::from_secs(2);
+/// Upper bound for a single probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Turns ssh's `-v` output into structured readiness signals
+///
+/// ssh answers forward requests in the order they were made, so the n-th
+/// remote forward reply belongs to the n-th remote tunnel and the n-th
+/// local listener setup to the n-th local or dynamic tunnel.
+#[derive(Debug)]
+pub struct ReadinessParser {
+    tunnels: Vec<TunnelSpec>,
+    /// Remote and remote dynamic tunnels still waiting for a server reply
+    remote_queue: VecDeque<usize>,
+    /// Local and dynamic tunnels whose listeners have not been set up yet
+    local_queue: VecDeque<usize>,
+    /// Remote tunnel accepted with port 0, waiting for its allocated port
+    awaiting_port: Option<usize>,
+    /// Local tunnel whose listener is being set up
+    current_local: Option<usize>,
+    /// Whether each tunnel is still unconfirmed
+    pending: Vec<bool>,
+    authenticated: bool,
+    session_started: bool,
+    /// Last non-debug line, used as the reason for a failed local listener
+    last_error: Option<String>,
+}
+
+impl ReadinessParser {
+    pub fn new(tunnels: &[TunnelSpec]) -> Self {
+        let (remote, local): (Vec<usize>, Vec<usize>) =
+            (0..tunnels.len()).partition(|&i| tunnels[i].kind.listens_remotely());
+
+        Self {
+            tunnels: tunnels.to_vec(),
+            remote_queue: remote.into(),
+            local_queue: local.into(),
+            awaiting_port: None,
+            current_local: None,
+            pending: vec![true; tunnels.len()],
+            authenticated: false,
+            session_started: false,
+            last_error: None,
+        }
+    }
+
+    /// Whether ssh has sent all its forward requests and entered the session
+    pub fn session_started(&self) -> bool {
+        self.session_started
+    }
+
+    /// Indexes of tunnels that are neither confirmed nor failed
+    pub fn pending(&self) -> impl Iterator<Item = usize> + '_ {
+        self.pending.iter().enumerate().filter(|(_, p)| **p).map(|(i, _)| i)
+    }
+
+    /// The profile's tunnels, by index
+    pub fn tunnel(&self, index: usize) -> &TunnelSpec {
+        &self.tunnels[index]
+    }
+
+    /// Confirm a tunnel by other means (e.g. a probe)
+    pub fn confirm(&mut self, index: usize) -> Option<SshOutput> {
+        self.ready(index, None)
+    }
+
+    /// Signals implied by one line of ssh output
+    pub fn parse_line(&mut self, line: &str) -> Vec<SshOutput> {
+        let mut signals = Vec::new();
+        let message = line.strip_prefix("debug1: ").unwrap_or(line);
+
+        if !self.authenticated
+            && (message.starts_with("Authenticated to ") || message.starts_with("Authentication succeeded"))
+        {
+            self.authenticated = true;
+            signals.push(SshOutput::Connected);
+        } else if message.starts_with("Entering interactive session") {
+            self.session_started = true;
+            // The session cannot start unauthenticated, whatever was logged
+            if !self.authenticated {
+                self.authenticated = true;
+                signals.push(SshOutput::Connected);
+            }
+        } else if let Some(rest) = message.strip_prefix("remote forward ") {
+            if let Some(index) = self.remote_queue.pop_front() {
+                if rest.starts_with("success") {
+                    if TunnelState::new(self.tunnels[index].clone()).wants_allocated_port() {
+                        // The port follows on the next line
+                        self.awaiting_port = Some(index);
+                    } else {
+                        signals.extend(self.ready(index, None));
+                    }
+                } else if rest.starts_with("failure") {
+                    signals.extend(self.failed(
+                        index,
+                        "server rejected the forward (check the server's GatewayPorts setting and whether the port is in use)"
+                            .to_string(),
+                    ));
+                }
+            }
+        } else if let Some((port, _)) = parse_allocated_port(message) {
+            let index = self.awaiting_port.take().or_else(|| {
+                // No reply line seen (e.g. reduced verbosity): first port-0 tunnel still waiting
+                self.pending()
+                    .find(|&i| TunnelState::new(self.tunnels[i].clone()).wants_allocated_port())
+            });
+            if let Some(index) = index {
+                self.remote_queue.retain(|&i| i != index);
+                signals.extend(self.ready(index, Some(port)));
+            }
+        } else if message.starts_with("Local connections to ") {
+            self.current_local = self.local_queue.pop_front();
+            self.last_error = None;
+        } else if message.starts_with("Local forwarding listening on ") {
+            if let Some(index) = self.current_local {
+                signals.extend(self.ready(index, None));
+            }
+        } else if message.starts_with("Could not request local forwarding") {
+            if let Some(index) = self.current_local.take() {
+                let reason = self
+                    .last_error
+                    .take()
+                    .unwrap_or_else(|| "could not listen on the local address".to_string());
+                signals.extend(self.failed(index, reason));
+            }
+        } else if !line.starts_with("debug") {
+            self.last_error = Some(line.trim().to_string());
+        }
+
+        signals
+    }
+
+    fn ready(&mut self, index: usize, allocated_port: Option<u16>) -> Option<SshOutput> {
+        let pending = self.pending.get_mut(index)?;
+        if !*pending {
+            return None;
+        }
+        *pending = false;
+        Some(SshOutput::ForwardReady { index, allocated_port })
+    }
+
+    fn failed(&mut self, index: usize, message: String) -> Option<SshOutput> {
+        let pending = self.pending.get_mut(index)?;
+        *pending = false;
+        Some(SshOutput::ForwardFailed { index, message })
+    }
+}
+
+/// Add readiness signals to the output of an ssh process started with `-v`
+///
+/// ssh's `debug1:` lines are only read here, not passed on, so they do not
+/// reach session output or the event bus.
+///
+/// Tunnels that ssh's output leaves unconfirmed once the session has started
+/// are probed: local listeners by connecting to them, remote forwards through
+/// `ssh -O check` when the profile configures a `ControlPath`.
+pub fn watch_readiness(
+    mut raw: mpsc::Receiver<SshOutput>,
+    profile: &Profile,
+    ssh_path: PathBuf,
+) -> mpsc::Receiver<SshOutput> {
+    let (tx, rx) = mpsc::channel(100);
+    let profile = profile.clone();
+
+    tokio::spawn(async move {
+        let mut parser = ReadinessParser::new(&profile.tunnels);
+        let mut next_probe: Option<Instant> = None;
+
+        loop {
+            let probing = next_probe.is_some() && parser.pending().next().is_some();
+
+            let signals = tokio::select! {
+                output = raw.recv() => {
+                    let Some(output) = output else { break };
+                    let signals = match &output {
+                        SshOutput::Stderr(line) => parser.parse_line(line),
+                        _ => Vec::new(),
+                    };
+                    // The -v chatter is only for the parser; passed on it would flood session output
+                    let verbose = matches!(&output, SshOutput::Stderr(line) if line.starts_with("debug1: "));
+                    if !verbose && tx.send(output).await.is_err() {
+                        break;
+                    }
+                    if next_probe.is_none() && parser.session_started() {
+                        next_probe = Some(Instant::now() + PROBE_GRACE);
+                    }
+                    signals
+                }
+                _ = sleep_until(next_probe.unwrap_or_else(Instant::now)), if probing => {
+                    next_probe = Some(Instant::now() + PROBE_INTERVAL);
+                    probe_pending(&mut parser, &profile, &ssh_path).await
+                }
+            };
+
+            for signal in signals {
+                if tx.send(signal).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+/// Confirm whichever unconfirmed tunnels can be probed
+async fn probe_pending(parser: &mut ReadinessParser, profile: &Profile, ssh_path: &Path) -> Vec<SshOutput> {
+    let mut signals = Vec::new();
+    let mut master_alive = None;
+
+    for index in parser.pending().collect::<Vec<_>>() {
+        let spec = parser.tunnel(index);
+        let ready = if spec.kind.listens_remotely() {
+            if master_alive.is_none() {
+                master_alive = Some(check_control_master(profile, ssh_path).await);
+            }
+            master_alive == Some(true)
+        } else {
+            probe_listener(&spec.local_endpoint()).await
+        };
+
+        if ready {
+            tracing::debug!("Tunnel {} of '{}' confirmed by probe", index, profile.name);
+            signals.extend(parser.confirm(index));
+        }
+    }
+
+    signals
+}
+
+/// Ask the connection's ControlMaster whether it is alive
+///
+/// With `ExitOnForwardFailure` a master that is still running after the
+/// session started has had its forwards accepted.
+async fn check_control_master(profile: &Profile, ssh_path: &Path) -> bool {
+    let Some(control_path) = profile
+        .extra_options
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("ControlPath"))
+        .map(|(_, value)| value)
+    else {
+        return false;
+    };
+
+    let check = Command::new(ssh_path)
+        .arg("-O")
+        .arg("check")
+        .arg("-o")
+        .arg(format!("ControlPath={}", control_path))
+        .arg("-p")
+        .arg(profile.port.to_string())
+        .arg(profile.destination())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status();
+
+    matches!(timeout(PROBE_TIMEOUT, check).await, Ok(Ok(status)) if status.success())
+}
+
+/// Check that something accepts connections on a local listening endpoint
+async fn probe_listener(endpoint: &TunnelEndpoint) -> bool {
+    match endpoint {
+        TunnelEndpoint::Tcp { port: 0, .. } => false,
+        TunnelEndpoint::Tcp { host, port } => {
+            // Wildcard binds are reachable on loopback
+            let host = match host.as_str() {
+                "" | "*" | "0.0.0.0" => "127.0.0.1",
+                "::" => "::1",
+                host => host,
+            };
+            let connect = tokio::net::TcpStream::connect((host, *port));
+            matches!(timeout(PROBE_TIMEOUT, connect).await, Ok(Ok(_)))
+        }
+        #[cfg(unix)]
+        TunnelEndpoint::Socket(path) => {
+            let connect = tokio::net::UnixStream::connect(path);
+            matches!(timeout(PROBE_TIMEOUT, connect).await, Ok(Ok(_)))
+        }
+        #[cfg(not(unix))]
+        TunnelEndpoint::Socket(_) => false,
+    }
+}
+
+/// Parse ssh's `Allocated port N for remote forward to host:port` line.
+///
+/// Returns the port and, when present, the forward target as ssh printed it.
+pub fn parse_allocated_port(line: &str) -> Option<(u16, Option<&str>)> {
+    let rest = &line[line.find("Allocated port ")? + "Allocated port ".len()..];
+    let (port, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    let port = port.parse().ok()?;
+    let target = rest
+        .strip_prefix("for remote forward to ")
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+    Some((port, target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals(parser: &mut ReadinessParser, lines: &[&str]) -> Vec<String> {
+        lines
+            .iter()
+            .flat_map(|line| parser.parse_line(line))
+            .map(|s| format!("{:?}", s))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_allocated_port() {
+        assert_eq!(
+            parse_allocated_port("Allocated port 41234 for remote forward to localhost:3000"),
+            Some((41234, Some("localhost:3000")))
+        );
+        assert_eq!(
+            parse_allocated_port("debug1: Allocated port 5000 for remote forward to /var/run/docker.sock:0"),
+            Some((5000, Some("/var/run/docker.sock:0")))
+        );
+        assert_eq!(parse_allocated_port("Allocated port 41234"), Some((41234, None)));
+        assert_eq!(parse_allocated_port("Authenticated to example.com"), None);
+        assert_eq!(parse_allocated_port("Allocated port abc for remote forward"), None);
+    }
+
+    #[test]
+    fn test_remote_forwards_confirmed_in_order() {
+        let mut parser = ReadinessParser::new(&[
+            TunnelSpec::new(8080, 3000),
+            TunnelSpec::local(5432, "db.internal", 5432),
+            TunnelSpec::new(0, 4000),
+        ]);
+
+        let out = signals(&mut parser, &[
+            "debug1: Connecting to example.com [203.0.113.5] port 22.",
+            "debug1: Authenticated to example.com ([203.0.113.5]:22) using \"publickey\".",
+            "debug1: Local connections to LOCALHOST:5432 forwarded to remote address db.internal:5432",
+            "debug1: Local forwarding listening on 127.0.0.1 port 5432.",
+            "debug1: Local forwarding listening on ::1 port 5432.",
+            "debug1: Remote connections from LOCALHOST:8080 forwarded to local address localhost:3000",
+            "debug1: Remote connections from LOCALHOST:0 forwarded to local address localhost:4000",
+            "debug1: Entering interactive session.",
+            "debug1: remote forward success for: listen 8080, connect localhost:3000",
+            "debug1: remote forward success for: listen 0, connect localhost:4000",
+            "Allocated port 41234 for remote forward to localhost:4000",
+        ]);
+
+        assert_eq!(out, vec![
+            "Connected",
+            "ForwardReady { index: 1, allocated_port: None }",
+            "ForwardReady { index: 0, allocated_port: None }",
+            "ForwardReady { index: 2, allocated_port: Some(41234) }",
+        ]);
+        assert!(parser.session_started());
+        assert_eq!(parser.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_forward_failures() {
+        let mut parser = ReadinessParser::new(&[
+            TunnelSpec::dynamic(1080),
+            TunnelSpec::new(80, 3000),
+        ]);
+
+        let out = signals(&mut parser, &[
+            "debug1: Authenticated to example.com ([203.0.113.5]:22) using \"publickey\".",
+            "debug1: Local connections to LOCALHOST:1080 forwarded to remote address socks:0",
+            "bind [127.0.0.1]:1080: Address already in use",
+            "channel_setup_fwd_listener_tcpip: cannot listen to port: 1080",
+            "Could not request local forwarding.",
+            "debug1: remote forward failure for: listen 80, connect localhost:3000",
+        ]);
+
+        assert_eq!(out.len(), 3);
+        assert!(out[1].starts_with("ForwardFailed { index: 0, message: \"channel_setup_fwd_listener_tcpip"));
+        assert!(out[2].starts_with("ForwardFailed { index: 1, message: \"server rejected"));
+        assert_eq!(parser.pending().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_watch_readiness_drops_debug_lines() {
+        let (raw_tx, raw_rx) = mpsc::channel(8);
+        let profile = Profile::new("edge", "example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 3000));
+        let mut rx = watch_readiness(raw_rx, &profile, PathBuf::from("ssh"));
+
+        for line in [
+            "debug1: Authenticated to example.com ([203.0.113.5]:22) using \"publickey\".",
+            "Warning: Permanently added 'example.com' (ED25519) to the list of known hosts.",
+            "debug1: remote forward success for: listen 8080, connect localhost:3000",
+        ] {
+            raw_tx.send(SshOutput::Stderr(line.to_string())).await.unwrap();
+        }
+        drop(raw_tx);
+
+        let mut out = Vec::new();
+        while let Some(output) = rx.recv().await {
+            out.push(format!("{:?}", output));
+        }
+        assert_eq!(out, vec![
+            "Connected",
+            "Stderr(\"Warning: Permanently added 'example.com' (ED25519) to the list of known hosts.\")",
+            "ForwardReady { index: 0, allocated_port: None }",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_probe_listener() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        assert!(probe_listener(&TunnelEndpoint::Tcp { host: "*".to_string(), port }).await);
+        drop(listener);
+        assert!(!probe_listener(&TunnelEndpoint::Tcp { host: "127.0.0.1".to_string(), port }).await);
+    }
+
+    #[test]
+    fn test_session_start_implies_authentication() {
+        let mut parser = ReadinessParser::new(&[TunnelSpec::new(8080, 3000)]);
+
+        let out = signals(&mut parser, &["debug1: Entering interactive session."]);
+        assert_eq!(out, vec!["Connected"]);
+        assert_eq!(parser.pending().collect::<Vec<_>>(), vec![0]);
+
+        assert!(matches!(parser.confirm(0), Some(SshOutput::ForwardReady { index: 0, allocated_port: None })));
+        assert!(parser.confirm(0).is_none());
+    }
+}
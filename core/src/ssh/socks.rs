@@ -0,0 +1,178 @@
+//! The server side of the SOCKS handshake, for dynamic forwards made by the
+//! embedded backend.
+//!
+//! Like ssh's own `-D` listener this speaks SOCKS4, SOCKS4a and SOCKS5
+//! without authentication, and only supports CONNECT.
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const SOCKS4: u8 = 4;
+const SOCKS5: u8 = 5;
+const CONNECT: u8 = 1;
+
+/// A CONNECT request read from a SOCKS client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocksRequest {
+    version: u8,
+    pub host: String,
+    pub port: u16,
+}
+
+impl SocksRequest {
+    /// Read the client's greeting and CONNECT request
+    pub async fn read<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> io::Result<Self> {
+        match stream.read_u8().await? {
+            SOCKS4 => Self::read_v4(stream).await,
+            SOCKS5 => Self::read_v5(stream).await,
+            version => Err(invalid(format!("unsupported SOCKS version {}", version))),
+        }
+    }
+
+    async fn read_v4<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Self> {
+        let command = stream.read_u8().await?;
+        let port = stream.read_u16().await?;
+        let mut ip = [0u8; 4];
+        stream.read_exact(&mut ip).await?;
+        let _user = read_nul_terminated(stream).await?;
+        if command != CONNECT {
+            return Err(invalid(format!("unsupported SOCKS4 command {}", command)));
+        }
+
+        // SOCKS4a: 0.0.0.x with x != 0 means a host name follows
+        let host = match ip {
+            [0, 0, 0, x] if x != 0 => read_nul_terminated(stream).await?,
+            _ => Ipv4Addr::from(ip).to_string(),
+        };
+        Ok(Self { version: SOCKS4, host, port })
+    }
+
+    async fn read_v5<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> io::Result<Self> {
+        let count = stream.read_u8().await?;
+        let mut methods = vec![0u8; count as usize];
+        stream.read_exact(&mut methods).await?;
+        if !methods.contains(&0) {
+            stream.write_all(&[SOCKS5, 0xff]).await?;
+            return Err(invalid("SOCKS5 client does not offer unauthenticated access"));
+        }
+        stream.write_all(&[SOCKS5, 0]).await?;
+
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await?;
+        let [version, command, _, address_type] = header;
+        if version != SOCKS5 {
+            return Err(invalid(format!("unexpected SOCKS version {} in request", version)));
+        }
+
+        let host = match address_type {
+            1 => {
+                let mut ip = [0u8; 4];
+                stream.read_exact(&mut ip).await?;
+                Ipv4Addr::from(ip).to_string()
+            }
+            3 => {
+                let len = stream.read_u8().await?;
+                let mut name = vec![0u8; len as usize];
+                stream.read_exact(&mut name).await?;
+                String::from_utf8(name).map_err(|_| invalid("SOCKS5 host name is not UTF-8"))?
+            }
+            4 => {
+                let mut ip = [0u8; 16];
+                stream.read_exact(&mut ip).await?;
+                Ipv6Addr::from(ip).to_string()
+            }
+            other => return Err(invalid(format!("unsupported SOCKS5 address type {}", other))),
+        };
+        let port = stream.read_u16().await?;
+
+        if command != CONNECT {
+            // 7: command not supported
+            stream.write_all(&[SOCKS5, 7, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+            return Err(invalid(format!("unsupported SOCKS5 command {}", command)));
+        }
+        Ok(Self { version: SOCKS5, host, port })
+    }
+
+    /// Tell the client whether the connection was made
+    pub async fn reply<S: AsyncWrite + Unpin>(&self, stream: &mut S, success: bool) -> io::Result<()> {
+        let reply: &[u8] = match (self.version, success) {
+            (SOCKS4, true) => &[0, 0x5a, 0, 0, 0, 0, 0, 0],
+            (SOCKS4, false) => &[0, 0x5b, 0, 0, 0, 0, 0, 0],
+            (_, true) => &[SOCKS5, 0, 0, 1, 0, 0, 0, 0, 0, 0],
+            // 5: connection refused
+            (_, false) => &[SOCKS5, 5, 0, 1, 0, 0, 0, 0, 0, 0],
+        };
+        stream.write_all(reply).await?;
+        stream.flush().await
+    }
+}
+
+async fn read_nul_terminated<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        match stream.read_u8().await? {
+            0 => break,
+            _ if bytes.len() >= 255 => return Err(invalid("SOCKS4 field is too long")),
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid("SOCKS4 field is not UTF-8"))
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_socks5_domain_request() {
+        let (mut client, mut server) = tokio::io::duplex(256);
+        let reader = tokio::spawn(async move {
+            let request = SocksRequest::read(&mut server).await.unwrap();
+            request.reply(&mut server, true).await.unwrap();
+            request
+        });
+
+        client.write_all(&[5, 1, 0]).await.unwrap();
+        let mut choice = [0u8; 2];
+        client.read_exact(&mut choice).await.unwrap();
+        assert_eq!(choice, [5, 0]);
+
+        client.write_all(&[5, 1, 0, 3, 11]).await.unwrap();
+        client.write_all(b"example.com").await.unwrap();
+        client.write_all(&443u16.to_be_bytes()).await.unwrap();
+        let mut reply = [0u8; 10];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply[1], 0);
+
+        let request = reader.await.unwrap();
+        assert_eq!((request.host.as_str(), request.port), ("example.com", 443));
+    }
+
+    #[tokio::test]
+    async fn test_socks4a_request() {
+        let (mut client, mut server) = tokio::io::duplex(256);
+        client.write_all(&[4, 1, 0, 80, 0, 0, 0, 1]).await.unwrap();
+        client.write_all(b"user\0internal.lan\0").await.unwrap();
+
+        let request = SocksRequest::read(&mut server).await.unwrap();
+        assert_eq!((request.host.as_str(), request.port), ("internal.lan", 80));
+
+        request.reply(&mut server, false).await.unwrap();
+        let mut reply = [0u8; 8];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply[1], 0x5b);
+    }
+
+    #[tokio::test]
+    async fn test_socks5_rejects_bind() {
+        let (mut client, mut server) = tokio::io::duplex(256);
+        client.write_all(&[5, 1, 0, 5, 2, 0, 1, 127, 0, 0, 1, 0, 80]).await.unwrap();
+        assert!(SocksRequest::read(&mut server).await.is_err());
+    }
+}
@@ -6,17 +6,31 @@ use tokio::process::{Child, Command};
 use tokio::sync::mpsc;
 
 use crate::error::{CoreError, Result};
-use crate::types::{AuthMethod, Profile};
+use crate::types::{AuthMethod, Profile, TunnelTraffic};
 
 use super::args::{validate_args, SshArgs};
+use super::backend::TunnelConnection;
+use super::jump::write_jump_config;
 use super::detect::SshInfo;
 
-/// Output from the SSH process
+/// Output from an SSH connection
+///
+/// The OpenSSH backend only produces text lines that the monitor has to
+/// interpret; backends with direct protocol access report the structured
+/// signals instead.
 #[derive(Debug, Clone)]
 pub enum SshOutput {
     Stdout(String),
     Stderr(String),
     Exit(Option<i32>),
+    /// Authenticated and ready to forward
+    Connected,
+    /// The server accepted the forward for `profile.tunnels[index]`
+    ForwardReady { index: usize, allocated_port: Option<u16> },
+    /// The server rejected the forward for `profile.tunnels[index]`
+    ForwardFailed { index: usize, message: String },
+    /// Running totals for `profile.tunnels[index]` on this connection
+    Traffic { index: usize, traffic: TunnelTraffic },
 }
 
 /// Handle to a spawned SSH process
@@ -30,6 +44,11 @@ pub struct SshProcess {
 }
 
 impl SshProcess {
+    /// Split into the generic connection handle the supervisor works with
+    pub fn into_connection(self) -> TunnelConnection {
+        TunnelConnection::new(Some(self.pid), self.output_rx, Box::new(self.child))
+    }
+
     /// Wait for the process to exit
     pub async fn wait(&mut self) -> Result<Option<i32>> {
         let status = self.child.wait().await?;
@@ -61,6 +80,11 @@ pub async fn spawn_ssh(
     password: Option<&str>,
     sshpass_path: Option<&str>,
 ) -> Result<SshProcess> {
+    for hop in &profile.jump_hosts {
+        hop.validate()?;
+    }
+    write_jump_config(profile)?;
+
     let args = SshArgs::from_profile(profile).build_tunnel_mode();
     match profile.auth {
         AuthMethod::Password => spawn_ssh_with_password(ssh_info, args, password, sshpass_path).await,
@@ -103,7 +127,7 @@ async fn spawn_ssh_with_password(
     sshpass_path: Option<&str>,
 ) -> Result<SshProcess> {
     // Validate SSH args before spawning
-    validate_args(&args).map_err(|e| CoreError::SshSpawnFailed(e))?;
+    validate_args(&args).map_err(CoreError::SshSpawnFailed)?;
 
     // sshpass reads the password from SSHPASS when using -e.
     // Prefer an explicitly-provided password (e.g. from frontend), otherwise fall back
@@ -115,10 +139,6 @@ async fn spawn_ssh_with_password(
         ));
     }
 
-    // Add verbose flag for password auth to help debug auth failures
-    let mut verbose_args = args.clone();
-    verbose_args.insert(0, "-v".to_string());
-
     // Try to find sshpass first (Linux/Unix standard)
     let sshpass_found = if let Some(p) = sshpass_path {
         let p = PathBuf::from(p);
@@ -137,11 +157,11 @@ async fn spawn_ssh_with_password(
             "Spawning SSH with password via sshpass. sshpass={:?} ssh={:?} args={:?}",
             sshpass,
             ssh_info.path,
-            verbose_args
+            args
         );
 
         let mut cmd = Command::new(sshpass);
-        cmd.arg("-e").arg(&ssh_info.path).args(&verbose_args);
+        cmd.arg("-e").arg(&ssh_info.path).args(&args);
 
         if let Some(pw) = password {
             tracing::debug!("Setting SSHPASS env var (password length: {})", pw.len());
@@ -233,7 +253,7 @@ async fn spawn_ssh_process(mut cmd: Command) -> Result<SshProcess> {
 /// Spawn an SSH process with custom arguments
 pub async fn spawn_ssh_with_args(ssh_info: &SshInfo, args: Vec<String>) -> Result<SshProcess> {
     // Validate arguments before spawning
-    validate_args(&args).map_err(|e| CoreError::SshSpawnFailed(e))?;
+    validate_args(&args).map_err(CoreError::SshSpawnFailed)?;
 
     tracing::debug!("Spawning SSH with args: {:?}", args);
 
@@ -292,15 +312,56 @@ pub async fn spawn_ssh_with_args(ssh_info: &SshInfo, args: Vec<String>) -> Resul
     })
 }
 
+/// Connect from the server to `host:port` over a second ssh connection (`ssh -W`)
+///
+/// Uses the profile's server, jump hosts and authentication but none of its
+/// tunnels. Succeeds when ssh exits cleanly: the server reached the target
+/// and the target closed the connection after our end of input.
+pub async fn probe_remote(
+    ssh_info: &SshInfo,
+    profile: &Profile,
+    password: Option<&str>,
+    sshpass_path: Option<&str>,
+    host: &str,
+    port: u16,
+) -> Result<()> {
+    write_jump_config(profile)?;
+
+    let mut probe = profile.clone();
+    probe.tunnels.clear();
+    let target = if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) };
+    let args = SshArgs::from_profile(&probe).build_probe_mode(&target);
+
+    let mut process = match profile.auth {
+        AuthMethod::Password => spawn_ssh_with_password(ssh_info, args, password, sshpass_path).await?,
+        _ => spawn_ssh_with_args(ssh_info, args).await?,
+    };
+
+    let mut last_error = String::new();
+    while let Some(output) = process.output_rx.recv().await {
+        if let SshOutput::Stderr(line) = output {
+            last_error = line;
+        }
+    }
+
+    match process.wait().await? {
+        Some(0) => Ok(()),
+        code => Err(CoreError::SshExitError { code: code.unwrap_or(-1), message: last_error }),
+    }
+}
+
 /// Test SSH connection without establishing tunnels
 /// Returns Ok(()) if connection succeeds, Err otherwise
 pub async fn test_connection(ssh_info: &SshInfo, profile: &Profile) -> Result<()> {
+    write_jump_config(profile)?;
+
     let args = SshArgs::new()
         .option("ServerAliveInterval", &profile.keepalive_interval.to_string())
         .option("ServerAliveCountMax", "1")
         .option("ConnectTimeout", "10")
         .option("BatchMode", "yes")
         .no_tty()
+        .jump_chain(profile)
         .port(profile.port)
         .destination(&profile.destination())
         .build();
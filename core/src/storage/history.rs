@@ -0,0 +1,494 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::error::{CoreError, Result};
+use crate::types::{EndpointChangeReason, Event, EventReceiver, SessionStatus};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS session_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp_ms INTEGER NOT NULL,
+    session_id TEXT NOT NULL,
+    profile_name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    reason TEXT
+);
+CREATE INDEX IF NOT EXISTS session_history_profile_time ON session_history (profile_name, timestamp_ms);
+";
+
+/// A session lifecycle change kept in the history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    /// The session was started and is making its first connection
+    Started,
+    Connected,
+    /// An established or starting connection ended
+    Disconnected,
+    /// The SSH process could not be started
+    SpawnFailed,
+    /// The connection did not become ready in time
+    TimedOut,
+    /// The session gave up
+    Failed,
+    /// The session was stopped on purpose
+    Stopped,
+}
+
+impl HistoryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            HistoryKind::Started => "started",
+            HistoryKind::Connected => "connected",
+            HistoryKind::Disconnected => "disconnected",
+            HistoryKind::SpawnFailed => "spawn_failed",
+            HistoryKind::TimedOut => "timed_out",
+            HistoryKind::Failed => "failed",
+            HistoryKind::Stopped => "stopped",
+        }
+    }
+}
+
+impl std::fmt::Display for HistoryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HistoryKind {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "started" => Ok(HistoryKind::Started),
+            "connected" => Ok(HistoryKind::Connected),
+            "disconnected" => Ok(HistoryKind::Disconnected),
+            "spawn_failed" => Ok(HistoryKind::SpawnFailed),
+            "timed_out" => Ok(HistoryKind::TimedOut),
+            "failed" => Ok(HistoryKind::Failed),
+            "stopped" => Ok(HistoryKind::Stopped),
+            _ => Err(CoreError::Deserialization(format!("Unknown history entry kind: {}", s))),
+        }
+    }
+}
+
+/// One recorded lifecycle change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Uuid,
+    pub profile_name: String,
+    pub kind: HistoryKind,
+    pub reason: Option<String>,
+}
+
+impl HistoryEntry {
+    /// The history entry for an event, if the event is recorded
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let (kind, reason) = match event {
+            Event::SessionEndpointChanged { reason: EndpointChangeReason::Initial, endpoint, .. } => {
+                (HistoryKind::Started, Some(format!("Connecting to {}", endpoint)))
+            }
+            Event::SessionConnected { .. } => (HistoryKind::Connected, None),
+            Event::SessionDisconnected { reason, .. } => (HistoryKind::Disconnected, reason.clone()),
+            Event::SessionSpawnFailed { error, .. } => (HistoryKind::SpawnFailed, Some(error.clone())),
+            Event::SessionFailed { error, .. } => (HistoryKind::Failed, Some(error.clone())),
+            Event::SessionStatusChanged { new_status: SessionStatus::TimedOut, .. } => (HistoryKind::TimedOut, None),
+            Event::SessionStatusChanged { new_status: SessionStatus::Stopped, .. } => (HistoryKind::Stopped, None),
+            _ => return None,
+        };
+
+        Some(Self {
+            timestamp: event.timestamp(),
+            session_id: event.session_id()?,
+            profile_name: event.profile_name()?.to_string(),
+            kind,
+            reason,
+        })
+    }
+}
+
+/// Uptime and outages of a profile over a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryReport {
+    pub profile_name: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Seconds a session for the profile was running, connected or not
+    pub active_secs: u64,
+    /// Seconds a session was connected
+    pub connected_secs: u64,
+    /// Connected share of the active time, `None` when nothing ran
+    pub uptime_percent: Option<f64>,
+    /// Connections that dropped or timed out
+    pub outages: u32,
+    /// Mean time from a dropped connection to the next successful one
+    pub mean_time_to_reconnect_secs: Option<f64>,
+    /// Entries within the window, oldest first
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl HistoryReport {
+    /// Compute the report from `entries` (oldest first) inside `from..to`
+    ///
+    /// `before` is the last entry before the window, which tells whether a
+    /// session was already running or connected when the window opened.
+    pub fn compute(
+        profile_name: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        before: Option<&HistoryEntry>,
+        entries: Vec<HistoryEntry>,
+    ) -> Self {
+        let mut active_since = before.filter(|e| is_running_after(e.kind)).map(|_| from);
+        let mut connected_since = before.filter(|e| e.kind == HistoryKind::Connected).map(|_| from);
+        let mut outage_since: Option<DateTime<Utc>> = None;
+
+        let mut active = chrono::Duration::zero();
+        let mut connected = chrono::Duration::zero();
+        let mut outages = 0;
+        let mut reconnect_times = Vec::new();
+
+        for entry in &entries {
+            let at = entry.timestamp;
+
+            if is_running_after(entry.kind) {
+                active_since.get_or_insert(at);
+            }
+
+            match entry.kind {
+                HistoryKind::Connected => {
+                    connected_since.get_or_insert(at);
+                    if let Some(since) = outage_since.take() {
+                        reconnect_times.push(at - since);
+                    }
+                }
+                HistoryKind::Disconnected | HistoryKind::TimedOut => {
+                    if let Some(since) = connected_since.take() {
+                        connected += at - since;
+                        outages += 1;
+                        outage_since = Some(at);
+                    }
+                }
+                HistoryKind::Failed | HistoryKind::Stopped => {
+                    if let Some(since) = connected_since.take() {
+                        connected += at - since;
+                        // Only a failure counts; stopping is deliberate
+                        if entry.kind == HistoryKind::Failed {
+                            outages += 1;
+                        }
+                    }
+                    outage_since = None;
+                }
+                HistoryKind::Started | HistoryKind::SpawnFailed => {}
+            }
+
+            if !is_running_after(entry.kind) {
+                if let Some(since) = active_since.take() {
+                    active += at - since;
+                }
+            }
+        }
+
+        if let Some(since) = connected_since {
+            connected += to - since;
+        }
+        if let Some(since) = active_since {
+            active += to - since;
+        }
+
+        let active_secs = active.num_seconds().max(0) as u64;
+        let connected_secs = connected.num_seconds().max(0) as u64;
+        let uptime_percent = (active.num_milliseconds() > 0)
+            .then(|| connected.num_milliseconds() as f64 / active.num_milliseconds() as f64 * 100.0);
+        let mean_time_to_reconnect_secs = (!reconnect_times.is_empty()).then(|| {
+            let total: i64 = reconnect_times.iter().map(|d| d.num_milliseconds()).sum();
+            total as f64 / reconnect_times.len() as f64 / 1000.0
+        });
+
+        Self {
+            profile_name: profile_name.to_string(),
+            from,
+            to,
+            active_secs,
+            connected_secs,
+            uptime_percent,
+            outages,
+            mean_time_to_reconnect_secs,
+            entries,
+        }
+    }
+}
+
+/// Whether a session is still running after an entry of this kind
+fn is_running_after(kind: HistoryKind) -> bool {
+    !matches!(kind, HistoryKind::Failed | HistoryKind::Stopped)
+}
+
+/// SQLite database of session lifecycle changes
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+impl HistoryStore {
+    /// Open or create the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(path).map_err(db_error)?;
+        // The daemon, web server and GUI may all record into the same file
+        conn.busy_timeout(Duration::from_secs(5)).map_err(db_error)?;
+        conn.pragma_update(None, "journal_mode", "WAL").map_err(db_error)?;
+        Self::with_connection(conn)
+    }
+
+    /// A database that lives only as long as the store
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory().map_err(db_error)?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        Ok(Self { conn })
+    }
+
+    /// Record an entry
+    pub fn insert(&self, entry: &HistoryEntry) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT INTO session_history (timestamp_ms, session_id, profile_name, kind, reason)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    entry.timestamp.timestamp_millis(),
+                    entry.session_id.to_string(),
+                    entry.profile_name,
+                    entry.kind.as_str(),
+                    entry.reason,
+                ],
+            )
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Record an event if it is a lifecycle change worth keeping
+    pub fn record(&self, event: &Event) -> Result<()> {
+        match HistoryEntry::from_event(event) {
+            Some(entry) => self.insert(&entry),
+            None => Ok(()),
+        }
+    }
+
+    /// A profile's entries with `from <= timestamp < to`, oldest first
+    pub fn entries(&self, profile_name: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT timestamp_ms, session_id, profile_name, kind, reason FROM session_history
+                 WHERE profile_name = ?1 AND timestamp_ms >= ?2 AND timestamp_ms < ?3
+                 ORDER BY timestamp_ms, id",
+            )
+            .map_err(db_error)?;
+
+        let rows = stmt
+            .query_map(params![profile_name, from.timestamp_millis(), to.timestamp_millis()], read_row)
+            .map_err(db_error)?;
+        rows.map(|row| into_entry(row.map_err(db_error)?)).collect()
+    }
+
+    /// The newest entry for a profile before `before`
+    pub fn last_before(&self, profile_name: &str, before: DateTime<Utc>) -> Result<Option<HistoryEntry>> {
+        self.conn
+            .query_row(
+                "SELECT timestamp_ms, session_id, profile_name, kind, reason FROM session_history
+                 WHERE profile_name = ?1 AND timestamp_ms < ?2
+                 ORDER BY timestamp_ms DESC, id DESC LIMIT 1",
+                params![profile_name, before.timestamp_millis()],
+                read_row,
+            )
+            .optional()
+            .map_err(db_error)?
+            .map(into_entry)
+            .transpose()
+    }
+
+    /// Uptime report for a profile over `from..to`
+    pub fn report(&self, profile_name: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<HistoryReport> {
+        let before = self.last_before(profile_name, from)?;
+        let entries = self.entries(profile_name, from, to)?;
+        Ok(HistoryReport::compute(profile_name, from, to, before.as_ref(), entries))
+    }
+
+    /// Delete entries older than `before`; returns how many were removed
+    pub fn prune(&self, before: DateTime<Utc>) -> Result<usize> {
+        self.conn
+            .execute("DELETE FROM session_history WHERE timestamp_ms < ?1", params![before.timestamp_millis()])
+            .map_err(db_error)
+    }
+
+    /// Record events until the bus closes
+    ///
+    /// SQLite calls block (up to the busy timeout while another process
+    /// writes), so recording runs on its own thread rather than on the async
+    /// runtime.
+    pub fn spawn(self, mut events: EventReceiver) -> JoinHandle<()> {
+        std::thread::Builder::new()
+            .name("session-history".to_string())
+            .spawn(move || loop {
+                match events.blocking_recv() {
+                    Ok(event) => {
+                        if let Err(e) = self.record(&event) {
+                            tracing::warn!("Failed to record session history: {}", e);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("Session history fell behind; {} events were not recorded", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            })
+            .expect("failed to spawn session history thread")
+    }
+}
+
+type Row = (i64, String, String, String, Option<String>);
+
+fn read_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Row> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+}
+
+fn into_entry((timestamp_ms, session_id, profile_name, kind, reason): Row) -> Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        timestamp: Utc
+            .timestamp_millis_opt(timestamp_ms)
+            .single()
+            .ok_or_else(|| CoreError::Deserialization(format!("Invalid history timestamp: {}", timestamp_ms)))?,
+        session_id: Uuid::parse_str(&session_id)
+            .map_err(|e| CoreError::Deserialization(format!("Invalid history session ID: {}", e)))?,
+        profile_name,
+        kind: kind.parse()?,
+        reason,
+    })
+}
+
+fn db_error(e: rusqlite::Error) -> CoreError {
+    CoreError::StorageAccess(format!("History database: {}", e))
+}
+
+/// Parse a window length such as `30m`, `24h`, `7d` or `2w`
+pub fn parse_window(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let invalid = || CoreError::Other(format!("Invalid window '{}'; use e.g. 30m, 24h, 7d or 2w", s));
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+
+    match number.checked_mul(unit_secs) {
+        Some(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(minute: i64, kind: HistoryKind, session_id: Uuid) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Utc.timestamp_opt(1_700_000_000 + minute * 60, 0).unwrap(),
+            session_id,
+            profile_name: "web".to_string(),
+            kind,
+            reason: None,
+        }
+    }
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + minute * 60, 0).unwrap()
+    }
+
+    #[test]
+    fn test_report_uptime_outages_and_reconnect_time() {
+        let id = Uuid::new_v4();
+        let store = HistoryStore::open_in_memory().unwrap();
+        for e in [
+            entry(0, HistoryKind::Started, id),
+            entry(0, HistoryKind::Connected, id),
+            entry(30, HistoryKind::Disconnected, id),
+            entry(32, HistoryKind::SpawnFailed, id),
+            entry(34, HistoryKind::Connected, id),
+            entry(60, HistoryKind::TimedOut, id),
+            entry(66, HistoryKind::Connected, id),
+            entry(90, HistoryKind::Stopped, id),
+        ] {
+            store.insert(&e).unwrap();
+        }
+
+        // Stopped for the last 30 minutes of the window, which is not downtime
+        let report = store.report("web", at(0), at(120)).unwrap();
+        assert_eq!(report.active_secs, 90 * 60);
+        assert_eq!(report.connected_secs, (30 + 26 + 24) * 60);
+        assert_eq!(report.outages, 2);
+        assert_eq!(report.mean_time_to_reconnect_secs, Some(5.0 * 60.0));
+        let uptime = report.uptime_percent.unwrap();
+        assert!((uptime - 80.0 / 90.0 * 100.0).abs() < 1e-9);
+        assert_eq!(report.entries.len(), 8);
+
+        // A window opening mid-connection counts from its start
+        let report = store.report("web", at(10), at(30)).unwrap();
+        assert_eq!(report.connected_secs, 20 * 60);
+        assert_eq!(report.uptime_percent, Some(100.0));
+        assert_eq!(report.outages, 0);
+        assert!(report.entries.is_empty());
+
+        assert_eq!(store.report("other", at(0), at(120)).unwrap().uptime_percent, None);
+
+        assert_eq!(store.prune(at(60)).unwrap(), 5);
+        assert_eq!(store.entries("web", at(0), at(120)).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_records_lifecycle_events() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let id = Uuid::new_v4();
+
+        store.record(&Event::session_connected(id, "web")).unwrap();
+        store.record(&Event::session_output(id, "web", "debug1: hello", true)).unwrap();
+        store.record(&Event::session_failed(id, "web", "Maximum reconnection attempts reached")).unwrap();
+
+        let entries = store.entries("web", at(0), Utc::now() + chrono::Duration::seconds(1)).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind, HistoryKind::Connected);
+        assert_eq!(entries[1].kind, HistoryKind::Failed);
+        assert_eq!(entries[1].reason.as_deref(), Some("Maximum reconnection attempts reached"));
+        assert_eq!(entries[1].session_id, id);
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("30m").unwrap(), Duration::from_secs(30 * 60));
+        assert_eq!(parse_window("7d").unwrap(), Duration::from_secs(7 * 24 * 3600));
+        assert!(parse_window("0h").is_err());
+        assert!(parse_window("7").is_err());
+        assert!(parse_window("d").is_err());
+    }
+}
@@ -1,140 +1,202 @@
-//! Keyring integration for secure credential storage
+//! Secrets in the OS keyring.
 //!
-//! This module provides optional integration with the system keyring/keychain.
-//! Note: We do NOT store private SSH keys - we only store metadata references.
-//! Authentication should be handled by ssh-agent or key files.
+//! Linux and the BSDs use the Secret Service API (GNOME Keyring, KWallet),
+//! macOS the Keychain and Windows the Credential Manager. Every secret is an
+//! entry of [`SERVICE_NAME`] whose user is the secret's name.
+
+use std::path::{Path, PathBuf};
 
 use crate::error::{CoreError, Result};
 
 /// Service name for keyring entries
-#[allow(dead_code)]
-const SERVICE_NAME: &str = "reverse-ssh-interface";
-
-/// Entry types we might store in the keyring
-#[derive(Debug, Clone, Copy)]
-pub enum KeyringEntry {
-    /// Reference to an SSH key path (not the key itself)
-    SshKeyPath,
-    /// Server fingerprint for verification
-    ServerFingerprint,
+pub const SERVICE_NAME: &str = "reverse-ssh-interface";
+
+/// Entry read to find out whether the keyring can be reached
+const PROBE_ENTRY: &str = "rssh-keyring-probe";
+
+/// Where keyring entries are read and written, by secret name
+trait Credentials: Send + Sync {
+    fn get(&self, name: &str) -> keyring::Result<String>;
+    fn set(&self, name: &str, value: &str) -> keyring::Result<()>;
+    fn delete(&self, name: &str) -> keyring::Result<()>;
 }
 
-impl KeyringEntry {
-    fn to_key(&self, profile_name: &str) -> String {
-        match self {
-            KeyringEntry::SshKeyPath => format!("ssh-key-path:{}", profile_name),
-            KeyringEntry::ServerFingerprint => format!("server-fingerprint:{}", profile_name),
-        }
+/// The entries of [`SERVICE_NAME`] in the OS keyring
+struct OsKeyring;
+
+impl Credentials for OsKeyring {
+    fn get(&self, name: &str) -> keyring::Result<String> {
+        keyring_call(|| entry(name)?.get_password())
+    }
+
+    fn set(&self, name: &str, value: &str) -> keyring::Result<()> {
+        keyring_call(|| entry(name)?.set_password(value))
+    }
+
+    fn delete(&self, name: &str) -> keyring::Result<()> {
+        keyring_call(|| entry(name)?.delete_credential())
     }
 }
 
-/// Keyring manager for secure storage
-/// 
-/// Note: This is a placeholder implementation. In a production system,
-/// you would use the `keyring` crate to interact with the system keychain.
-/// We keep this simple to avoid additional dependencies and complexity.
-pub struct KeyringManager {
-    /// Whether keyring is available on this system
-    available: bool,
+/// Secrets kept in the OS keyring
+pub struct KeyringStore {
+    credentials: Box<dyn Credentials>,
+    /// Names of the secrets stored by rssh, one per line
+    index: PathBuf,
 }
 
-impl KeyringManager {
-    /// Create a new keyring manager
-    pub fn new() -> Self {
-        // In a real implementation, check if keyring is available
-        Self {
-            available: false, // Disabled by default for simplicity
-        }
+impl KeyringStore {
+    /// Connect to the keyring, failing when there is none or it is locked
+    pub fn connect(index: &Path) -> Result<Self> {
+        Self::connect_with(Box::new(OsKeyring), index)
     }
 
-    /// Check if keyring is available
-    pub fn is_available(&self) -> bool {
-        self.available
+    fn connect_with(credentials: Box<dyn Credentials>, index: &Path) -> Result<Self> {
+        match credentials.get(PROBE_ENTRY) {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(Self { credentials, index: index.to_path_buf() }),
+            Err(e) => Err(CoreError::SecretStore(format!("the OS keyring is not available: {}", e))),
+        }
     }
 
-    /// Store a value in the keyring
-    pub fn set(&self, entry: KeyringEntry, profile_name: &str, _value: &str) -> Result<()> {
-        if !self.available {
-            return Err(CoreError::StorageAccess("Keyring not available".to_string()));
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        match self.credentials.get(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error(name, e)),
         }
-
-        let _key = entry.to_key(profile_name);
-        
-        // Placeholder - in real implementation:
-        // let entry = keyring::Entry::new(SERVICE_NAME, &key)?;
-        // entry.set_password(value)?;
-        
-        tracing::debug!("Would store keyring entry for profile '{}'", profile_name);
-        Ok(())
     }
 
-    /// Get a value from the keyring
-    pub fn get(&self, entry: KeyringEntry, profile_name: &str) -> Result<Option<String>> {
-        if !self.available {
-            return Ok(None);
-        }
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        self.credentials.set(name, value).map_err(|e| keyring_error(name, e))?;
 
-        let _key = entry.to_key(profile_name);
-        
-        // Placeholder - in real implementation:
-        // let entry = keyring::Entry::new(SERVICE_NAME, &key)?;
-        // match entry.get_password() {
-        //     Ok(value) => Ok(Some(value)),
-        //     Err(keyring::Error::NoEntry) => Ok(None),
-        //     Err(e) => Err(CoreError::StorageAccess(e.to_string())),
-        // }
-        
-        Ok(None)
+        let mut names = self.names()?;
+        if !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+            self.write_index(&names)?;
+        }
+        Ok(())
     }
 
-    /// Delete a value from the keyring
-    pub fn delete(&self, entry: KeyringEntry, profile_name: &str) -> Result<()> {
-        if !self.available {
-            return Ok(());
+    /// Remove a secret, returning whether it existed
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        let existed = match self.credentials.delete(name) {
+            Ok(()) => true,
+            Err(keyring::Error::NoEntry) => false,
+            Err(e) => return Err(keyring_error(name, e)),
+        };
+
+        let mut names = self.names()?;
+        let before = names.len();
+        names.retain(|existing| existing != name);
+        if names.len() != before {
+            self.write_index(&names)?;
         }
+        Ok(existed)
+    }
 
-        let _key = entry.to_key(profile_name);
-        
-        // Placeholder - in real implementation:
-        // let entry = keyring::Entry::new(SERVICE_NAME, &key)?;
-        // entry.delete_password().ok();
-        
-        Ok(())
+    /// Names of the secrets rssh stored, in order
+    pub fn names(&self) -> Result<Vec<String>> {
+        let content = match std::fs::read_to_string(&self.index) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(CoreError::StorageAccess(format!("{}: {}", self.index.display(), e))),
+        };
+        let mut names: Vec<String> = content.lines().filter(|line| !line.is_empty()).map(str::to_string).collect();
+        names.sort();
+        Ok(names)
     }
 
-    /// Delete all entries for a profile
-    pub fn delete_profile(&self, profile_name: &str) -> Result<()> {
-        self.delete(KeyringEntry::SshKeyPath, profile_name)?;
-        self.delete(KeyringEntry::ServerFingerprint, profile_name)?;
-        Ok(())
+    fn write_index(&self, names: &[String]) -> Result<()> {
+        let write = || {
+            if let Some(parent) = self.index.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&self.index, names.iter().map(|name| format!("{}\n", name)).collect::<String>())
+        };
+        write().map_err(|e| CoreError::StorageAccess(format!("{}: {}", self.index.display(), e)))
     }
 }
 
-impl Default for KeyringManager {
-    fn default() -> Self {
-        Self::new()
-    }
+fn entry(name: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE_NAME, name)
+}
+
+/// Run a keyring call on a thread of its own
+///
+/// The Secret Service client drives its D-Bus connection with a runtime of
+/// its own, which cannot be started from a thread already running one.
+fn keyring_call<T: Send>(call: impl FnOnce() -> keyring::Result<T> + Send) -> keyring::Result<T> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(call)
+            .join()
+            .unwrap_or_else(|_| Err(keyring::Error::PlatformFailure("keyring call panicked".into())))
+    })
+}
+
+fn keyring_error(name: &str, error: keyring::Error) -> CoreError {
+    CoreError::SecretStore(format!("keyring entry '{}': {}", name, error))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
     use super::*;
 
-    #[test]
-    fn test_keyring_entry_key() {
-        let key = KeyringEntry::SshKeyPath.to_key("my-server");
-        assert_eq!(key, "ssh-key-path:my-server");
+    /// A keyring held in memory, or one that is locked
+    #[derive(Default)]
+    struct MemoryKeyring {
+        entries: Mutex<HashMap<String, String>>,
+        locked: bool,
+    }
 
-        let key = KeyringEntry::ServerFingerprint.to_key("my-server");
-        assert_eq!(key, "server-fingerprint:my-server");
+    impl Credentials for MemoryKeyring {
+        fn get(&self, name: &str) -> keyring::Result<String> {
+            if self.locked {
+                return Err(keyring::Error::NoStorageAccess("locked".into()));
+            }
+            self.entries.lock().unwrap().get(name).cloned().ok_or(keyring::Error::NoEntry)
+        }
+
+        fn set(&self, name: &str, value: &str) -> keyring::Result<()> {
+            self.entries.lock().unwrap().insert(name.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, name: &str) -> keyring::Result<()> {
+            self.entries.lock().unwrap().remove(name).map(drop).ok_or(keyring::Error::NoEntry)
+        }
+    }
+
+    #[test]
+    fn test_keyring_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = dir.path().join("keyring-secrets");
+        let store = KeyringStore::connect_with(Box::<MemoryKeyring>::default(), &index).unwrap();
+
+        assert!(store.get("relay-password").unwrap().is_none());
+        assert!(store.names().unwrap().is_empty());
+
+        store.set("relay-password", "hunter2").unwrap();
+        store.set("api-token", "abc").unwrap();
+        store.set("relay-password", "swordfish").unwrap();
+        assert_eq!(store.get("relay-password").unwrap().as_deref(), Some("swordfish"));
+        assert_eq!(store.names().unwrap(), ["api-token", "relay-password"]);
+        assert_eq!(std::fs::read_to_string(&index).unwrap(), "relay-password\napi-token\n");
+
+        assert!(store.remove("relay-password").unwrap());
+        assert!(!store.remove("relay-password").unwrap());
+        assert!(store.get("relay-password").unwrap().is_none());
+        assert_eq!(store.names().unwrap(), ["api-token"]);
     }
 
     #[test]
-    fn test_keyring_manager_unavailable() {
-        let manager = KeyringManager::new();
-        assert!(!manager.is_available());
-        
-        // Operations should gracefully handle unavailability
-        assert!(manager.get(KeyringEntry::SshKeyPath, "test").unwrap().is_none());
+    fn test_keyring_store_unavailable() {
+        let dir = tempfile::tempdir().unwrap();
+        let locked = MemoryKeyring { locked: true, ..Default::default() };
+        let error = KeyringStore::connect_with(Box::new(locked), &dir.path().join("keyring-secrets")).err().unwrap();
+        assert!(error.to_string().contains("the OS keyring is not available"), "{}", error);
     }
 }
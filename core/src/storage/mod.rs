@@ -1,5 +1,11 @@
+pub mod history;
 pub mod keyring;
+pub mod secrets;
 pub mod state;
+pub mod vault;
 
-pub use keyring::{KeyringEntry, KeyringManager};
+pub use history::{HistoryEntry, HistoryKind, HistoryReport, HistoryStore};
+pub use keyring::KeyringStore;
+pub use secrets::{validate_secret_name, PassphrasePrompt, SecretStore};
 pub use state::{AppState, PersistedSession, StateManager};
+pub use vault::{Vault, PASSPHRASE_ENV};
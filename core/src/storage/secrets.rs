@@ -0,0 +1,115 @@
+//! The secret store profiles refer to with `secret://<name>`.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{paths, SecretBackend, SecretsConfig};
+use crate::error::{CoreError, Result};
+
+use super::keyring::KeyringStore;
+use super::vault::{Vault, PASSPHRASE_ENV};
+
+/// Asks for the vault passphrase, given the vault path and whether the vault
+/// is about to be created
+pub type PassphrasePrompt<'a> = &'a dyn Fn(&Path, bool) -> Result<String>;
+
+/// Secrets in the OS keyring or in a passphrase-protected vault
+pub enum SecretStore {
+    Keyring(KeyringStore),
+    Vault(Vault),
+}
+
+impl SecretStore {
+    /// Open the configured store, taking a vault passphrase only from
+    /// `RSSH_VAULT_PASSPHRASE`
+    pub fn open(config: &SecretsConfig) -> Result<Self> {
+        Self::open_with(config, &|path, _| {
+            Err(CoreError::SecretStore(format!(
+                "vault {} is locked; set {} to its passphrase",
+                path.display(),
+                PASSPHRASE_ENV
+            )))
+        })
+    }
+
+    /// Open the configured store, calling `prompt` for the vault passphrase
+    /// when `RSSH_VAULT_PASSPHRASE` is not set
+    pub fn open_with(config: &SecretsConfig, prompt: PassphrasePrompt) -> Result<Self> {
+        let vault_path = config.vault_path.clone().unwrap_or_else(paths::vault_file);
+        let index = paths::keyring_index_file();
+
+        match config.backend {
+            SecretBackend::Keyring => Ok(Self::Keyring(KeyringStore::connect(&index)?)),
+            SecretBackend::Vault => Self::open_vault(vault_path, prompt),
+            SecretBackend::Auto if vault_path.exists() => Self::open_vault(vault_path, prompt),
+            SecretBackend::Auto => match KeyringStore::connect(&index) {
+                Ok(store) => Ok(Self::Keyring(store)),
+                Err(e) => {
+                    tracing::debug!("Using a vault for secrets: {}", e);
+                    Self::open_vault(vault_path, prompt)
+                }
+            },
+        }
+    }
+
+    fn open_vault(path: PathBuf, prompt: PassphrasePrompt) -> Result<Self> {
+        let passphrase = match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => passphrase,
+            _ => prompt(&path, !path.exists())?,
+        };
+        Ok(Self::Vault(Vault::open(&path, &passphrase)?))
+    }
+
+    /// Where the secrets are kept, for messages
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Keyring(_) => "the OS keyring".to_string(),
+            Self::Vault(vault) => format!("vault {}", vault.path().display()),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        match self {
+            Self::Keyring(store) => store.get(name),
+            Self::Vault(vault) => Ok(vault.get(name).map(str::to_string)),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        validate_secret_name(name)?;
+        match self {
+            Self::Keyring(store) => store.set(name, value),
+            Self::Vault(vault) => vault.set(name, value),
+        }
+    }
+
+    /// Remove a secret, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> Result<bool> {
+        match self {
+            Self::Keyring(store) => store.remove(name),
+            Self::Vault(vault) => vault.remove(name),
+        }
+    }
+
+    /// Names of the stored secrets, in order
+    pub fn names(&self) -> Result<Vec<String>> {
+        match self {
+            Self::Keyring(store) => store.names(),
+            Self::Vault(vault) => Ok(vault.names()),
+        }
+    }
+}
+
+/// Check a secret name: letters, digits, `-`, `_` and `.`, at most 128 characters
+pub fn validate_secret_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.chars().count() <= 128
+        && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::SecretStore(format!(
+            "'{}' is not a valid secret name; use letters, digits, '-', '_' and '.'",
+            name
+        )))
+    }
+}
@@ -5,7 +5,7 @@ use uuid::Uuid;
 
 use crate::config::paths;
 use crate::error::{CoreError, Result};
-use crate::types::{Session, SessionStatus};
+use crate::types::Session;
 
 /// Persisted application state
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -30,7 +30,7 @@ impl From<&Session> for PersistedSession {
         Self {
             profile_id: session.profile_id,
             profile_name: session.profile_name.clone(),
-            was_connected: session.status == SessionStatus::Connected,
+            was_connected: session.is_connected(),
         }
     }
 }
@@ -39,6 +39,8 @@ impl From<&Session> for PersistedSession {
 pub struct StateManager {
     path: std::path::PathBuf,
     state: AppState,
+    /// Held while this process owns the state file
+    lock: Option<std::fs::File>,
 }
 
 impl StateManager {
@@ -47,6 +49,7 @@ impl StateManager {
         Self {
             path: paths::state_file(),
             state: AppState::default(),
+            lock: None,
         }
     }
 
@@ -55,6 +58,7 @@ impl StateManager {
         Self {
             path: path.as_ref().to_path_buf(),
             state: AppState::default(),
+            lock: None,
         }
     }
 
@@ -90,6 +94,37 @@ impl StateManager {
         Ok(())
     }
 
+    /// Take the state file for this process through a lock file beside it
+    ///
+    /// Returns `false` when another process holds it. The lock is released
+    /// when the state manager is dropped or the process exits.
+    pub fn try_lock(&mut self) -> Result<bool> {
+        if self.lock.is_some() {
+            return Ok(true);
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| CoreError::StorageAccess(format!("Failed to create directory: {}", e)))?;
+        }
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("lock"))
+            .map_err(|e| CoreError::StorageAccess(format!("Failed to open state lock: {}", e)))?;
+        match file.try_lock() {
+            Ok(()) => {
+                self.lock = Some(file);
+                Ok(true)
+            }
+            Err(std::fs::TryLockError::WouldBlock) => Ok(false),
+            Err(std::fs::TryLockError::Error(e)) => {
+                Err(CoreError::StorageAccess(format!("Failed to lock state file: {}", e)))
+            }
+        }
+    }
+
     /// Get the current state
     pub fn state(&self) -> &AppState {
         &self.state
@@ -161,4 +196,19 @@ mod tests {
         );
         assert_eq!(manager.state.sessions.len(), manager2.state.sessions.len());
     }
+
+    #[test]
+    fn test_state_lock() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        let mut first = StateManager::with_path(&path);
+        let mut second = StateManager::with_path(&path);
+        assert!(first.try_lock().unwrap());
+        assert!(first.try_lock().unwrap());
+        assert!(!second.try_lock().unwrap());
+
+        drop(first);
+        assert!(second.try_lock().unwrap());
+    }
 }
@@ -0,0 +1,285 @@
+//! Passphrase-protected secret vault.
+//!
+//! The vault is one JSON file: the argon2id parameters and salt the key is
+//! derived with, and the secrets as a map sealed with XChaCha20-Poly1305.
+//! The key derivation settings are authenticated with the secrets, so they
+//! cannot be swapped out without the vault failing to open.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, Result};
+
+/// Environment variable a vault passphrase is taken from
+pub const PASSPHRASE_ENV: &str = "RSSH_VAULT_PASSPHRASE";
+
+const FORMAT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// How the vault key is derived from the passphrase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    /// Hex-encoded
+    salt: String,
+}
+
+impl KdfParams {
+    /// Fresh parameters with a random salt
+    fn generate(memory_kib: u32, iterations: u32) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            algorithm: "argon2id".to_string(),
+            memory_kib,
+            iterations,
+            parallelism: 1,
+            salt: to_hex(&salt),
+        }
+    }
+
+    fn derive_cipher(&self, passphrase: &str) -> Result<XChaCha20Poly1305> {
+        if self.algorithm != "argon2id" {
+            return Err(CoreError::SecretStore(format!("unsupported key derivation '{}'", self.algorithm)));
+        }
+        let salt = from_hex(&self.salt).ok_or_else(|| CoreError::SecretStore("vault salt is not valid hex".to_string()))?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| CoreError::SecretStore(format!("invalid key derivation parameters: {}", e)))?;
+
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| CoreError::SecretStore(format!("cannot derive the vault key: {}", e)))?;
+        let cipher = XChaCha20Poly1305::new(&key.into());
+        key.fill(0);
+        Ok(cipher)
+    }
+}
+
+/// The vault file as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    /// Hex-encoded
+    nonce: String,
+    /// Hex-encoded secrets map, sealed
+    ciphertext: String,
+}
+
+/// An unlocked vault
+pub struct Vault {
+    path: PathBuf,
+    kdf: KdfParams,
+    cipher: XChaCha20Poly1305,
+    secrets: BTreeMap<String, String>,
+}
+
+impl Vault {
+    /// Unlock the vault at `path`, or start a new one there if the file does
+    /// not exist yet (it is written on the first change)
+    pub fn open(path: &Path, passphrase: &str) -> Result<Self> {
+        // The argon2 defaults (19 MiB, two passes)
+        Self::open_with_cost(path, passphrase, Params::DEFAULT_M_COST, Params::DEFAULT_T_COST)
+    }
+
+    pub(crate) fn open_with_cost(path: &Path, passphrase: &str, memory_kib: u32, iterations: u32) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(CoreError::SecretStore("the vault passphrase must not be empty".to_string()));
+        }
+
+        if !path.exists() {
+            let kdf = KdfParams::generate(memory_kib, iterations);
+            let cipher = kdf.derive_cipher(passphrase)?;
+            return Ok(Self { path: path.to_path_buf(), kdf, cipher, secrets: BTreeMap::new() });
+        }
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| CoreError::SecretStore(format!("cannot read vault {}: {}", path.display(), e)))?;
+        let file: VaultFile = serde_json::from_str(&content)
+            .map_err(|e| CoreError::SecretStore(format!("vault {} is damaged: {}", path.display(), e)))?;
+        if file.version != FORMAT_VERSION {
+            return Err(CoreError::SecretStore(format!(
+                "vault {} has format version {}, this build reads version {}",
+                path.display(),
+                file.version,
+                FORMAT_VERSION
+            )));
+        }
+
+        let cipher = file.kdf.derive_cipher(passphrase)?;
+        let damaged = || CoreError::SecretStore(format!("vault {} is damaged", path.display()));
+        let nonce = from_hex(&file.nonce).filter(|nonce| nonce.len() == 24).ok_or_else(damaged)?;
+        let ciphertext = from_hex(&file.ciphertext).ok_or_else(damaged)?;
+        let aad = associated_data(&file.kdf)?;
+
+        let plaintext = cipher
+            .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &aad })
+            .map_err(|_| {
+                CoreError::SecretStore(format!(
+                    "cannot unlock vault {}: wrong passphrase, or the file was modified",
+                    path.display()
+                ))
+            })?;
+        let secrets = serde_json::from_slice(&plaintext).map_err(|_| damaged())?;
+
+        Ok(Self { path: path.to_path_buf(), kdf: file.kdf, cipher, secrets })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(String::as_str)
+    }
+
+    /// Store a secret and write the vault
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        self.secrets.insert(name.to_string(), value.to_string());
+        self.save()
+    }
+
+    /// Remove a secret and write the vault, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> Result<bool> {
+        if self.secrets.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Secret names in order
+    pub fn names(&self) -> Vec<String> {
+        self.secrets.keys().cloned().collect()
+    }
+
+    /// Seal the secrets under a fresh nonce and replace the file
+    fn save(&self) -> Result<()> {
+        let write_error = |e: std::io::Error| {
+            CoreError::SecretStore(format!("cannot write vault {}: {}", self.path.display(), e))
+        };
+
+        let plaintext = serde_json::to_vec(&self.secrets).map_err(|e| CoreError::Serialization(e.to_string()))?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let aad = associated_data(&self.kdf)?;
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: &plaintext, aad: &aad })
+            .map_err(|_| CoreError::SecretStore("cannot encrypt the vault".to_string()))?;
+
+        let file = VaultFile {
+            version: FORMAT_VERSION,
+            kdf: self.kdf.clone(),
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        };
+        let content = serde_json::to_string_pretty(&file).map_err(|e| CoreError::Serialization(e.to_string()))?;
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_error)?;
+        }
+        // Write beside the vault and rename, so a failed write leaves the old one intact
+        let temp = self.path.with_extension("vault.tmp");
+        write_private(&temp, content.as_bytes()).map_err(write_error)?;
+        std::fs::rename(&temp, &self.path).map_err(write_error)
+    }
+}
+
+/// Data authenticated along with the secrets
+fn associated_data(kdf: &KdfParams) -> Result<Vec<u8>> {
+    let mut aad = format!("rssh-vault-v{}:", FORMAT_VERSION).into_bytes();
+    aad.extend(serde_json::to_vec(kdf).map_err(|e| CoreError::Serialization(e.to_string()))?);
+    Ok(aad)
+}
+
+/// Write a file only its owner can read
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap key derivation; the cost parameters do not change the format
+    fn open(path: &Path, passphrase: &str) -> Result<Vault> {
+        Vault::open_with_cost(path, passphrase, 64, 1)
+    }
+
+    #[test]
+    fn test_vault_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("secrets.vault");
+
+        let mut vault = open(&path, "correct horse").unwrap();
+        assert!(vault.names().is_empty());
+        assert!(!path.exists());
+        vault.set("relay-password", "hunter2").unwrap();
+        vault.set("db-token", "s3cr3t").unwrap();
+        assert!(vault.remove("db-token").unwrap());
+        assert!(!vault.remove("db-token").unwrap());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("hunter2") && !content.contains("relay-password"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let vault = open(&path, "correct horse").unwrap();
+        assert_eq!(vault.get("relay-password"), Some("hunter2"));
+        assert_eq!(vault.names(), vec!["relay-password".to_string()]);
+    }
+
+    #[test]
+    fn test_vault_rejects_wrong_passphrase_and_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.vault");
+        open(&path, "correct horse").unwrap().set("relay-password", "hunter2").unwrap();
+
+        let error = open(&path, "battery staple").err().unwrap().to_string();
+        assert!(error.contains("wrong passphrase"), "{}", error);
+        assert!(open(&path, "").is_err());
+
+        // Lowering the key derivation cost is caught too
+        let content = std::fs::read_to_string(&path).unwrap();
+        let mut file: VaultFile = serde_json::from_str(&content).unwrap();
+        file.kdf.iterations = 2;
+        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        assert!(open(&path, "correct horse").is_err());
+    }
+}
@@ -1,4 +1,6 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use crate::types::{JitterStrategy, ReconnectPolicy};
 
 /// Exponential backoff calculator for reconnection attempts
 #[derive(Debug, Clone)]
@@ -13,6 +15,14 @@ pub struct Backoff {
     attempt: u32,
     /// Maximum number of attempts (0 = unlimited)
     max_attempts: u32,
+    jitter: JitterStrategy,
+    /// Previous delay, for decorrelated jitter
+    previous: Duration,
+    /// Stop once this much time has passed since the first delay
+    give_up_after: Option<Duration>,
+    /// When the first delay since the last reset was handed out
+    started: Option<Instant>,
+    rng: SplitMix64,
 }
 
 impl Backoff {
@@ -24,9 +34,27 @@ impl Backoff {
             multiplier: 2.0,
             attempt: 0,
             max_attempts: 0,
+            jitter: JitterStrategy::None,
+            previous: Duration::ZERO,
+            give_up_after: None,
+            started: None,
+            rng: SplitMix64::from_entropy(),
         }
     }
 
+    /// Create a backoff calculator following a reconnect policy
+    pub fn from_policy(policy: &ReconnectPolicy) -> Self {
+        Self::new()
+            .with_initial_delay(Duration::from_secs(policy.initial_delay_secs))
+            .with_max_delay(Duration::from_secs(policy.max_delay_secs))
+            .with_multiplier(policy.multiplier)
+            .with_jitter(policy.jitter)
+            .with_give_up_after(match policy.give_up_after_secs {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            })
+    }
+
     /// Set the initial delay
     pub fn with_initial_delay(mut self, delay: Duration) -> Self {
         self.initial_delay = delay;
@@ -51,33 +79,66 @@ impl Backoff {
         self
     }
 
+    /// Set how delays are randomized
+    pub fn with_jitter(mut self, jitter: JitterStrategy) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Give up once this long has passed since the first delay after a reset
+    pub fn with_give_up_after(mut self, period: Option<Duration>) -> Self {
+        self.give_up_after = period;
+        self
+    }
+
+    /// Seed the jitter, making the delays reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = SplitMix64(seed);
+        self
+    }
+
     /// Get the next delay and increment attempt counter
-    /// Returns None if max attempts reached
+    /// Returns None if max attempts reached or the give-up period has passed
     pub fn next_delay(&mut self) -> Option<Duration> {
-        if self.max_attempts > 0 && self.attempt >= self.max_attempts {
+        self.next_delay_at(Instant::now())
+    }
+
+    /// `next_delay` as of `now`
+    pub fn next_delay_at(&mut self, now: Instant) -> Option<Duration> {
+        if self.is_exhausted_at(now) {
             return None;
         }
+        self.started.get_or_insert(now);
 
-        let delay = self.calculate_delay();
+        let delay = match self.jitter {
+            JitterStrategy::None => self.calculate_delay(),
+            JitterStrategy::Full => self.calculate_delay().mul_f64(self.rng.next_f64()),
+            JitterStrategy::Decorrelated => {
+                let low = self.initial_delay.min(self.max_delay);
+                let high = self.previous.max(low).mul_f64(self.multiplier).min(self.max_delay);
+                low + (high - low).mul_f64(self.rng.next_f64())
+            }
+        };
+        self.previous = delay;
         self.attempt += 1;
         Some(delay)
     }
 
-    /// Calculate delay for current attempt without incrementing
+    /// Un-jittered delay for the current attempt, without incrementing
     pub fn calculate_delay(&self) -> Duration {
-        let delay_secs = self.initial_delay.as_secs_f64() * self.multiplier.powi(self.attempt as i32);
-        let delay = Duration::from_secs_f64(delay_secs);
-        
-        if delay > self.max_delay {
-            self.max_delay
-        } else {
-            delay
-        }
+        let exponent = i32::try_from(self.attempt).unwrap_or(i32::MAX);
+        let delay_secs = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+
+        // Clamp before converting: the product overflows a Duration (or is
+        // infinite) long before the attempt counter does
+        Duration::try_from_secs_f64(delay_secs.min(self.max_delay.as_secs_f64())).unwrap_or(self.max_delay)
     }
 
-    /// Reset the backoff counter
+    /// Reset the backoff counter and the give-up period
     pub fn reset(&mut self) {
         self.attempt = 0;
+        self.previous = Duration::ZERO;
+        self.started = None;
     }
 
     /// Get the current attempt number
@@ -85,9 +146,48 @@ impl Backoff {
         self.attempt
     }
 
-    /// Check if max attempts reached
+    /// Check if max attempts reached or the give-up period has passed
     pub fn is_exhausted(&self) -> bool {
-        self.max_attempts > 0 && self.attempt >= self.max_attempts
+        self.is_exhausted_at(Instant::now())
+    }
+
+    fn is_exhausted_at(&self, now: Instant) -> bool {
+        let out_of_time = match (self.started, self.give_up_after) {
+            (Some(started), Some(period)) => now.duration_since(started) >= period,
+            _ => false,
+        };
+        out_of_time || (self.max_attempts > 0 && self.attempt >= self.max_attempts)
+    }
+}
+
+/// Small non-cryptographic generator for jitter
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Seed from the clock and a stack address, so processes started
+    /// together still differ
+    fn from_entropy() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        let local = 0u8;
+        let address = &local as *const u8 as u64;
+        Self(nanos ^ address.rotate_left(32) ^ u64::from(std::process::id()))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
     }
 }
 
@@ -155,4 +255,86 @@ mod tests {
         assert!(!backoff.is_exhausted());
         assert_eq!(backoff.attempt(), 0);
     }
+
+    #[test]
+    fn test_backoff_huge_attempt_count() {
+        let mut backoff = Backoff::new().with_max_delay(Duration::from_secs(300));
+        for attempt in [64, 1100, u32::MAX - 1] {
+            backoff.attempt = attempt;
+            assert_eq!(backoff.calculate_delay(), Duration::from_secs(300));
+        }
+
+        let mut backoff = backoff.with_jitter(JitterStrategy::Full);
+        backoff.attempt = 1100;
+        assert!(backoff.next_delay().unwrap() <= Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_full_jitter() {
+        let delays = |seed| {
+            let mut backoff = Backoff::new()
+                .with_initial_delay(Duration::from_secs(10))
+                .with_max_delay(Duration::from_secs(60))
+                .with_jitter(JitterStrategy::Full)
+                .with_seed(seed);
+            (0..6).map(|_| backoff.next_delay().unwrap()).collect::<Vec<_>>()
+        };
+
+        let first = delays(7);
+        assert_eq!(first, delays(7));
+        assert_ne!(first, delays(8));
+        for (attempt, delay) in first.iter().enumerate() {
+            let ceiling = Duration::from_secs(10 * 2u64.pow(attempt as u32)).min(Duration::from_secs(60));
+            assert!(*delay <= ceiling, "attempt {}: {:?} > {:?}", attempt, delay, ceiling);
+        }
+        // Not all pinned to the exponential curve
+        assert!(first.iter().any(|delay| delay.subsec_nanos() != 0));
+    }
+
+    #[test]
+    fn test_decorrelated_jitter() {
+        let backoff = |seed| {
+            Backoff::new()
+                .with_initial_delay(Duration::from_secs(1))
+                .with_max_delay(Duration::from_secs(30))
+                .with_multiplier(3.0)
+                .with_jitter(JitterStrategy::Decorrelated)
+                .with_seed(seed)
+        };
+
+        let mut first = backoff(42);
+        let mut replay = backoff(42);
+        let mut previous = Duration::from_secs(1);
+        for _ in 0..20 {
+            let delay = first.next_delay().unwrap();
+            assert_eq!(replay.next_delay(), Some(delay));
+            // Between the initial delay and three times the previous one, capped
+            assert!(delay >= Duration::from_secs(1));
+            assert!(delay <= (previous * 3).min(Duration::from_secs(30)));
+            previous = delay;
+        }
+
+        // After a reset the delays start near the initial delay again
+        first.reset();
+        assert!(first.next_delay().unwrap() <= Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_give_up_after() {
+        let policy = ReconnectPolicy {
+            jitter: JitterStrategy::None,
+            give_up_after_secs: 60,
+            ..ReconnectPolicy::default()
+        };
+        let mut backoff = Backoff::from_policy(&policy).with_seed(1);
+        let start = Instant::now();
+
+        assert_eq!(backoff.next_delay_at(start), Some(Duration::from_secs(1)));
+        assert_eq!(backoff.next_delay_at(start + Duration::from_secs(59)), Some(Duration::from_secs(2)));
+        assert_eq!(backoff.next_delay_at(start + Duration::from_secs(60)), None);
+
+        // A reset starts the period over
+        backoff.reset();
+        assert_eq!(backoff.next_delay_at(start + Duration::from_secs(61)), Some(Duration::from_secs(1)));
+    }
 }
@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout, Instant};
+
+use crate::types::{FailoverStrategy, Profile, RelayEndpoint};
+
+/// How long a reachability probe waits for the TCP handshake
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Chooses which of a profile's servers the next connection goes to
+///
+/// Endpoints are tried in rounds: a round visits every endpoint once, in an
+/// order that depends on the strategy. Failing over moves to the next
+/// endpoint of the round; a new round starts when the round runs out or an
+/// established connection drops.
+#[derive(Debug, Clone)]
+pub struct EndpointSelector {
+    /// The profile's `host` first, then its fallbacks
+    endpoints: Vec<RelayEndpoint>,
+    strategy: FailoverStrategy,
+    /// Endpoint indices in the order this round tries them
+    order: Vec<usize>,
+    /// Position in `order` of the current endpoint
+    position: usize,
+}
+
+impl EndpointSelector {
+    pub fn new(profile: &Profile) -> Self {
+        let endpoints = profile.endpoints();
+        Self {
+            order: (0..endpoints.len()).collect(),
+            endpoints,
+            strategy: profile.failover_strategy,
+            position: 0,
+        }
+    }
+
+    /// The endpoint to connect to
+    pub fn current(&self) -> &RelayEndpoint {
+        &self.endpoints[self.order[self.position]]
+    }
+
+    /// The profile's own `host`
+    pub fn primary(&self) -> &RelayEndpoint {
+        &self.endpoints[0]
+    }
+
+    /// Whether the current endpoint is the profile's own `host`
+    pub fn is_primary(&self) -> bool {
+        self.order[self.position] == 0
+    }
+
+    /// Whether there is anywhere to fail over to
+    pub fn has_fallbacks(&self) -> bool {
+        self.endpoints.len() > 1
+    }
+
+    /// Whether the next round is ordered by latency and needs measurements
+    pub fn wants_latencies(&self) -> bool {
+        self.strategy == FailoverStrategy::LowestLatency && self.has_fallbacks()
+    }
+
+    /// All endpoints, primary first (the indexing of `start_round` latencies)
+    pub fn endpoints(&self) -> &[RelayEndpoint] {
+        &self.endpoints
+    }
+
+    /// Move to the next endpoint after the current one failed
+    ///
+    /// Returns `false` when every endpoint of the round has been tried; the
+    /// caller then waits before starting a new round.
+    pub fn fail_over(&mut self) -> bool {
+        if self.position + 1 < self.order.len() {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Begin a new round
+    ///
+    /// `latencies` holds the measured connect time of each endpoint (`None`
+    /// when unreachable) and is only used by the lowest-latency strategy.
+    pub fn start_round(&mut self, latencies: &[Option<Duration>]) {
+        let count = self.endpoints.len();
+        let last = self.order[self.position];
+
+        self.order = match self.strategy {
+            FailoverStrategy::Ordered => (0..count).collect(),
+            FailoverStrategy::RoundRobin => {
+                // Continue after the endpoint used last
+                let first = (last + 1) % count;
+                (0..count).map(|offset| (first + offset) % count).collect()
+            }
+            FailoverStrategy::LowestLatency => {
+                let mut order: Vec<usize> = (0..count).collect();
+                // Unmeasured endpoints keep their configured order, after the measured ones
+                order.sort_by_key(|&index| latencies.get(index).copied().flatten().unwrap_or(Duration::MAX));
+                order
+            }
+        };
+        self.position = 0;
+    }
+
+    /// Go back to the profile's own `host`, keeping the rest of the round's order
+    pub fn select_primary(&mut self) {
+        self.order.retain(|&index| index != 0);
+        self.order.insert(0, 0);
+        self.position = 0;
+    }
+}
+
+/// Time a TCP handshake with the endpoint; `None` when it cannot be reached
+pub async fn probe(endpoint: &RelayEndpoint) -> Option<Duration> {
+    let started = Instant::now();
+    match timeout(PROBE_TIMEOUT, TcpStream::connect((endpoint.host.as_str(), endpoint.port))).await {
+        Ok(Ok(_)) => Some(started.elapsed()),
+        _ => None,
+    }
+}
+
+/// Probe every endpoint concurrently, in endpoint order
+pub async fn measure_latencies(endpoints: &[RelayEndpoint]) -> Vec<Option<Duration>> {
+    let probes: Vec<_> = endpoints
+        .iter()
+        .cloned()
+        .map(|endpoint| tokio::spawn(async move { probe(&endpoint).await }))
+        .collect();
+
+    let mut latencies = Vec::with_capacity(probes.len());
+    for probe in probes {
+        latencies.push(probe.await.ok().flatten());
+    }
+    latencies
+}
+
+/// Resolve once the endpoint has accepted connections continuously for `period`
+pub async fn wait_until_healthy(endpoint: &RelayEndpoint, period: Duration) {
+    let interval = (period / 4).clamp(Duration::from_millis(100), Duration::from_secs(30));
+    let mut healthy_since: Option<Instant> = None;
+
+    loop {
+        if probe(endpoint).await.is_some() {
+            let since = *healthy_since.get_or_insert_with(Instant::now);
+            if since.elapsed() >= period {
+                return;
+            }
+        } else {
+            healthy_since = None;
+        }
+        sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(strategy: FailoverStrategy) -> Profile {
+        let mut profile = Profile::new("test", "relay1", "user");
+        profile.fallback_hosts = vec![RelayEndpoint::new("relay2", 22), RelayEndpoint::new("relay3", 22)];
+        profile.failover_strategy = strategy;
+        profile
+    }
+
+    fn hosts_of_round(selector: &mut EndpointSelector) -> Vec<String> {
+        let mut hosts = vec![selector.current().host.clone()];
+        while selector.fail_over() {
+            hosts.push(selector.current().host.clone());
+        }
+        hosts
+    }
+
+    #[test]
+    fn test_ordered_always_starts_at_primary() {
+        let mut selector = EndpointSelector::new(&profile(FailoverStrategy::Ordered));
+        assert!(selector.is_primary());
+        assert_eq!(hosts_of_round(&mut selector), ["relay1", "relay2", "relay3"]);
+
+        selector.start_round(&[]);
+        assert!(selector.fail_over());
+        selector.start_round(&[]);
+        assert_eq!(selector.current().host, "relay1");
+    }
+
+    #[test]
+    fn test_round_robin_continues_after_last_endpoint() {
+        let mut selector = EndpointSelector::new(&profile(FailoverStrategy::RoundRobin));
+        assert_eq!(selector.current().host, "relay1");
+
+        selector.start_round(&[]);
+        assert_eq!(hosts_of_round(&mut selector), ["relay2", "relay3", "relay1"]);
+
+        // The next round continues after the endpoint tried last
+        selector.start_round(&[]);
+        assert_eq!(selector.current().host, "relay2");
+    }
+
+    #[test]
+    fn test_lowest_latency_orders_by_measurement() {
+        let mut selector = EndpointSelector::new(&profile(FailoverStrategy::LowestLatency));
+        assert!(selector.wants_latencies());
+
+        selector.start_round(&[None, Some(Duration::from_millis(80)), Some(Duration::from_millis(20))]);
+        assert_eq!(hosts_of_round(&mut selector), ["relay3", "relay2", "relay1"]);
+
+        selector.start_round(&[Some(Duration::from_millis(20)), Some(Duration::from_millis(80)), Some(Duration::from_millis(20))]);
+        selector.fail_over();
+        selector.select_primary();
+        assert!(selector.is_primary());
+        assert_eq!(hosts_of_round(&mut selector), ["relay1", "relay3", "relay2"]);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_healthy() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = RelayEndpoint::new("127.0.0.1", listener.local_addr().unwrap().port());
+
+        assert!(probe(&endpoint).await.is_some());
+        timeout(Duration::from_secs(5), wait_until_healthy(&endpoint, Duration::from_millis(200)))
+            .await
+            .unwrap();
+
+        drop(listener);
+        assert!(probe(&endpoint).await.is_none());
+    }
+}
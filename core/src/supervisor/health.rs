@@ -0,0 +1,248 @@
+//! Periodic per-tunnel health checks.
+//!
+//! A running ssh process only shows that the connection is up. These checks
+//! show that the tunnel carries traffic: something answers on its local
+//! side, the server can reach its remote side, or an HTTP service behind it
+//! responds as expected.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::{timeout, MissedTickBehavior};
+
+use crate::error::{CoreError, Result};
+use crate::ssh::{ConnectOptions, TunnelBackend};
+use crate::types::{HealthCheck, HealthCheckKind, Profile, SessionHandle, TunnelEndpoint, TunnelKind, TunnelState};
+
+/// Outcome of one probe: the tunnel's index and why it failed, if it did
+pub type HealthReport = (usize, std::result::Result<(), String>);
+
+/// Runs the health checks of one session's tunnels
+pub struct HealthProber {
+    backend: Arc<dyn TunnelBackend>,
+    /// The profile as connected, for `remote_tcp` probes
+    profile: Profile,
+    options: ConnectOptions,
+    client: reqwest::Client,
+}
+
+impl HealthProber {
+    pub fn new(backend: Arc<dyn TunnelBackend>, profile: Profile, options: ConnectOptions) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("rssh/", env!("CARGO_PKG_VERSION")))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| CoreError::Other(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self { backend, profile, options, client })
+    }
+
+    /// Probe a tunnel once
+    pub async fn probe(&self, tunnel: &TunnelState, check: &HealthCheck) -> std::result::Result<(), String> {
+        let limit = Duration::from_secs(check.timeout_secs.max(1));
+        let probe = async {
+            match check.kind {
+                HealthCheckKind::LocalTcp => connect_local(&tunnel.spec.local_endpoint()).await,
+                HealthCheckKind::RemoteTcp => self.connect_remote(tunnel).await,
+                HealthCheckKind::Http => self.get(tunnel, check).await,
+            }
+        };
+
+        match timeout(limit, probe).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("no answer within {}s", limit.as_secs())),
+        }
+    }
+
+    /// Probe each checked tunnel of the session every `interval_secs`
+    ///
+    /// The first probes run right away. Dropping the returned set stops them.
+    pub fn start(self: Arc<Self>, session: SessionHandle) -> (JoinSet<()>, mpsc::Receiver<HealthReport>) {
+        let (tx, rx) = mpsc::channel(16);
+        let mut probes = JoinSet::new();
+
+        let checked: Vec<(usize, HealthCheck)> = session
+            .try_read()
+            .map(|session| {
+                session
+                    .tunnels
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, tunnel)| tunnel.spec.health_check.clone().map(|check| (index, check)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for (index, check) in checked {
+            let prober = self.clone();
+            let session = session.clone();
+            let tx = tx.clone();
+            probes.spawn(async move {
+                let mut ticks = tokio::time::interval(Duration::from_secs(check.interval_secs.max(1)));
+                ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    ticks.tick().await;
+                    // Read the tunnel each time; its allocated port may change
+                    let Some(tunnel) = session.read().await.tunnels.get(index).cloned() else {
+                        return;
+                    };
+                    let result = prober.probe(&tunnel, &check).await;
+                    if tx.send((index, result)).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        (probes, rx)
+    }
+
+    async fn connect_remote(&self, tunnel: &TunnelState) -> std::result::Result<(), String> {
+        let spec = tunnel.effective_spec();
+        let host = match (spec.kind, spec.remote_bind.as_str()) {
+            // A forward the server listens on for everyone is reachable on its loopback
+            (TunnelKind::Remote | TunnelKind::RemoteDynamic, "" | "*" | "0.0.0.0" | "::") => "localhost",
+            (_, host) => host,
+        };
+        if spec.remote_port == 0 {
+            return Err("the server has not reported the tunnel's port".to_string());
+        }
+
+        self.backend
+            .probe_remote(&self.profile, &self.options, host, spec.remote_port)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get(&self, tunnel: &TunnelState, check: &HealthCheck) -> std::result::Result<(), String> {
+        let host = match tunnel.spec.local_host.as_str() {
+            "" | "*" | "0.0.0.0" => "127.0.0.1".to_string(),
+            "::" => "[::1]".to_string(),
+            host if host.contains(':') => format!("[{}]", host),
+            host => host.to_string(),
+        };
+        let url = format!("http://{}:{}{}", host, tunnel.spec.local_port, check.path);
+
+        let response = self.client.get(&url).send().await.map_err(|e| e.to_string())?;
+        let status = response.status().as_u16();
+        if status == check.expected_status {
+            Ok(())
+        } else {
+            Err(format!("GET {} answered {}, expected {}", check.path, status, check.expected_status))
+        }
+    }
+}
+
+/// Open and close a connection to a tunnel's local side
+async fn connect_local(endpoint: &TunnelEndpoint) -> std::result::Result<(), String> {
+    match endpoint {
+        TunnelEndpoint::Tcp { host, port } => {
+            // Wildcard binds are reachable on loopback
+            let host = match host.as_str() {
+                "" | "*" | "0.0.0.0" => "127.0.0.1",
+                "::" => "::1",
+                host => host,
+            };
+            tokio::net::TcpStream::connect((host, *port))
+                .await
+                .map(drop)
+                .map_err(|e| format!("cannot connect to {}: {}", endpoint, e))
+        }
+        #[cfg(unix)]
+        TunnelEndpoint::Socket(path) => tokio::net::UnixStream::connect(path)
+            .await
+            .map(drop)
+            .map_err(|e| format!("cannot connect to {}: {}", path, e)),
+        #[cfg(not(unix))]
+        TunnelEndpoint::Socket(path) => Err(format!("cannot connect to Unix socket {} on this platform", path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh::{BoxFuture, TunnelConnection};
+    use crate::types::{BackendKind, TunnelSpec};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A backend whose server can reach exactly one port
+    struct ReachableBackend(u16);
+
+    impl TunnelBackend for ReachableBackend {
+        fn kind(&self) -> BackendKind {
+            BackendKind::Embedded
+        }
+
+        fn connect<'a>(&'a self, _: &'a Profile, _: &'a ConnectOptions) -> BoxFuture<'a, Result<TunnelConnection>> {
+            Box::pin(async { Err(CoreError::Other("not used".to_string())) })
+        }
+
+        fn probe_remote<'a>(
+            &'a self,
+            _: &'a Profile,
+            _: &'a ConnectOptions,
+            host: &'a str,
+            port: u16,
+        ) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move {
+                if port == self.0 {
+                    Ok(())
+                } else {
+                    Err(CoreError::Other(format!("channel to {}:{} refused", host, port)))
+                }
+            })
+        }
+    }
+
+    fn prober(reachable: u16) -> HealthProber {
+        let profile = Profile::new("web", "relay.example.com", "tunnel");
+        HealthProber::new(Arc::new(ReachableBackend(reachable)), profile, ConnectOptions::default()).unwrap()
+    }
+
+    /// Answer each HTTP request with `status`
+    async fn http_stand_in(status: u16) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf).await;
+                let response = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_probes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let prober = prober(9000);
+
+        let local = HealthCheck::new(HealthCheckKind::LocalTcp);
+        let tunnel = TunnelState::new;
+        assert!(prober.probe(&tunnel(TunnelSpec::new(9000, open)), &local).await.is_ok());
+        let error = prober.probe(&tunnel(TunnelSpec::new(9000, closed)), &local).await.unwrap_err();
+        assert!(error.contains("cannot connect"), "{}", error);
+
+        // Remote checks go through the backend, using the allocated port
+        let remote = HealthCheck::new(HealthCheckKind::RemoteTcp);
+        let mut allocated = tunnel(TunnelSpec::new(0, open));
+        assert!(prober.probe(&allocated, &remote).await.is_err());
+        allocated.allocated_port = Some(9000);
+        assert!(prober.probe(&allocated, &remote).await.is_ok());
+        assert!(prober.probe(&tunnel(TunnelSpec::new(9001, open)), &remote).await.is_err());
+
+        let http = HealthCheck { path: "/healthz".to_string(), ..HealthCheck::new(HealthCheckKind::Http) };
+        let healthy = http_stand_in(200).await;
+        let failing = http_stand_in(503).await;
+        assert!(prober.probe(&tunnel(TunnelSpec::new(9000, healthy)), &http).await.is_ok());
+        let error = prober.probe(&tunnel(TunnelSpec::new(9000, failing)), &http).await.unwrap_err();
+        assert_eq!(error, "GET /healthz answered 503, expected 200");
+    }
+}
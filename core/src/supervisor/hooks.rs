@@ -0,0 +1,277 @@
+//! Runs a profile's hook commands for one session.
+//!
+//! Hooks are argument arrays executed without a shell. Details of the
+//! session and the triggering event are passed as `RSSH_*` environment
+//! variables, and everything the command prints goes to the session log.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::error::{CoreError, Result};
+use crate::types::{Event, EventReceiver, EventSender, HookPoint, ProfileHooks, Session, SessionHandle, SessionStatus};
+
+/// Runs hooks for one session
+#[derive(Clone)]
+pub struct HookRunner {
+    hooks: ProfileHooks,
+    session: SessionHandle,
+    event_tx: EventSender,
+}
+
+impl HookRunner {
+    pub fn new(hooks: ProfileHooks, session: SessionHandle, event_tx: EventSender) -> Self {
+        Self { hooks, session, event_tx }
+    }
+
+    /// Run the hook for `point`, if one is configured, and wait for it
+    ///
+    /// Fails if the command cannot be started, exits unsuccessfully or
+    /// outlives its timeout (it is killed then).
+    pub async fn run(&self, point: HookPoint, reason: Option<&str>) -> Result<()> {
+        let Some(hook) = self.hooks.get(point) else {
+            return Ok(());
+        };
+        let Some((program, args)) = hook.command.split_first() else {
+            return Err(CoreError::HookFailed(point, "command is empty".to_string()));
+        };
+
+        let env = hook_env(point, &*self.session.read().await, reason);
+        tracing::debug!("Running {} hook: {:?}", point, hook.command);
+
+        let mut child = Command::new(program)
+            .args(args)
+            .envs(env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| self.fail(point, format!("could not run '{}': {}", program, e)))?;
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let finished = async {
+            tokio::join!(self.forward(point, stdout, false), self.forward(point, stderr, true));
+            child.wait().await
+        };
+
+        let status = match tokio::time::timeout(Duration::from_secs(hook.timeout_secs), finished).await {
+            Ok(status) => status.map_err(|e| self.fail(point, e.to_string()))?,
+            Err(_) => {
+                let _ = child.kill().await;
+                return Err(self.fail(point, format!("timed out after {}s", hook.timeout_secs)));
+            }
+        };
+
+        if !status.success() {
+            let message = match status.code() {
+                Some(code) => format!("exited with code {}", code),
+                None => "terminated by signal".to_string(),
+            };
+            return Err(self.fail(point, message));
+        }
+        Ok(())
+    }
+
+    /// Run hooks as the session's events arrive until `done` fires
+    ///
+    /// Hooks run one at a time in event order. Events already sent when
+    /// `done` fires are still handled, so the session task signals it last.
+    pub fn spawn(self, mut events: EventReceiver, mut done: oneshot::Receiver<()>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let session_id = self.session.read().await.id;
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = &mut done => break,
+                };
+                match event {
+                    Ok(event) => self.handle(session_id, &event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("Hook runner fell behind; {} events were not checked", missed);
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+
+            loop {
+                match events.try_recv() {
+                    Ok(event) => self.handle(session_id, &event).await,
+                    Err(TryRecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+        })
+    }
+
+    async fn handle(&self, session_id: uuid::Uuid, event: &Event) {
+        if event.session_id() != Some(session_id) {
+            return;
+        }
+
+        let (point, reason) = match event {
+            Event::SessionConnected { .. } => (HookPoint::OnConnected, None),
+            Event::SessionDisconnected { reason, .. } => (HookPoint::OnDisconnected, reason.clone()),
+            Event::SessionStatusChanged { new_status: SessionStatus::TimedOut, .. } => {
+                (HookPoint::OnDisconnected, self.session.read().await.last_error.clone())
+            }
+            Event::SessionStatusChanged {
+                old_status: SessionStatus::Connected | SessionStatus::Degraded,
+                new_status: SessionStatus::Stopped,
+                ..
+            } => (HookPoint::OnDisconnected, Some("Stopped".to_string())),
+            Event::SessionFailed { error, .. } => (HookPoint::OnFailed, Some(error.clone())),
+            _ => return,
+        };
+
+        // Failures are already in the session log
+        let _ = self.run(point, reason.as_deref()).await;
+    }
+
+    /// Copy a hook's output into the session log, line by line
+    async fn forward(&self, point: HookPoint, stream: Option<impl AsyncRead + Unpin>, is_stderr: bool) {
+        let Some(stream) = stream else { return };
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            self.log(point, &line, is_stderr).await;
+        }
+    }
+
+    async fn log(&self, point: HookPoint, line: &str, is_stderr: bool) {
+        let session = self.session.read().await;
+        let _ = self.event_tx.send(Event::session_output(
+            session.id,
+            &session.profile_name,
+            format!("[{} hook] {}", point, line),
+            is_stderr,
+        ));
+    }
+
+    /// Log a hook failure to the session and build its error
+    fn fail(&self, point: HookPoint, message: String) -> CoreError {
+        let error = CoreError::HookFailed(point, message);
+        tracing::warn!("{}", error);
+        if let Ok(session) = self.session.try_read() {
+            let _ = self.event_tx.send(Event::session_output(
+                session.id,
+                &session.profile_name,
+                format!("WARNING: {}", error),
+                true,
+            ));
+        }
+        error
+    }
+}
+
+/// Environment variables describing the session to a hook
+pub fn hook_env(point: HookPoint, session: &Session, reason: Option<&str>) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("RSSH_HOOK".to_string(), point.to_string()),
+        ("RSSH_PROFILE".to_string(), session.profile_name.clone()),
+        ("RSSH_SESSION_ID".to_string(), session.id.to_string()),
+        ("RSSH_STATUS".to_string(), session.status.to_string()),
+        ("RSSH_RECONNECT_COUNT".to_string(), session.reconnect_count.to_string()),
+        ("RSSH_REASON".to_string(), reason.unwrap_or_default().to_string()),
+    ];
+
+    if let Some(endpoint) = &session.active_endpoint {
+        env.push(("RSSH_HOST".to_string(), endpoint.host.clone()));
+        env.push(("RSSH_PORT".to_string(), endpoint.port.to_string()));
+    }
+
+    let mut allocated = Vec::new();
+    for (index, tunnel) in session.tunnels.iter().enumerate() {
+        env.push((format!("RSSH_TUNNEL_{}", index + 1), tunnel.effective_spec().to_string()));
+        if let Some(port) = tunnel.allocated_port {
+            env.push((format!("RSSH_TUNNEL_{}_ALLOCATED_PORT", index + 1), port.to_string()));
+            allocated.push(port.to_string());
+        }
+    }
+    env.push(("RSSH_ALLOCATED_PORTS".to_string(), allocated.join(" ")));
+
+    env
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::types::{event_channel, new_session_handle, HookCommand, Profile, RelayEndpoint, TunnelSpec};
+
+    fn profile() -> Profile {
+        Profile::new("web", "relay.example.com", "tunnel").with_tunnel(TunnelSpec::new(0, 3000))
+    }
+
+    /// Session output lines sent so far
+    fn output(events: &mut EventReceiver) -> Vec<(String, bool)> {
+        let mut lines = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let Event::SessionOutput { output, is_stderr, .. } = event {
+                lines.push((output, is_stderr));
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn test_hook_env() {
+        let session = new_session_handle(&profile());
+        let mut session = session.try_write().unwrap().clone();
+        session.active_endpoint = Some(RelayEndpoint::new("relay.example.com", 2222));
+        session.tunnels[0].allocated_port = Some(41000);
+
+        let env: std::collections::HashMap<_, _> =
+            hook_env(HookPoint::OnConnected, &session, None).into_iter().collect();
+        assert_eq!(env["RSSH_HOOK"], "on_connected");
+        assert_eq!(env["RSSH_PROFILE"], "web");
+        assert_eq!(env["RSSH_PORT"], "2222");
+        assert_eq!(env["RSSH_TUNNEL_1_ALLOCATED_PORT"], "41000");
+        assert_eq!(env["RSSH_ALLOCATED_PORTS"], "41000");
+        assert_eq!(env["RSSH_REASON"], "");
+    }
+
+    #[tokio::test]
+    async fn test_hook_output_goes_to_session_log() {
+        let (event_tx, mut events) = event_channel(64);
+        let hooks = ProfileHooks {
+            on_disconnected: Some(HookCommand::new([
+                "sh",
+                "-c",
+                "echo \"$RSSH_HOOK for $RSSH_PROFILE: $RSSH_REASON\"; echo oops >&2; exit 3",
+            ])),
+            ..Default::default()
+        };
+        let runner = HookRunner::new(hooks, new_session_handle(&profile()), event_tx);
+
+        let result = runner.run(HookPoint::OnDisconnected, Some("Connection reset")).await;
+        assert!(matches!(result, Err(CoreError::HookFailed(HookPoint::OnDisconnected, _))));
+
+        let lines = output(&mut events);
+        assert!(lines.contains(&("[on_disconnected hook] on_disconnected for web: Connection reset".to_string(), false)));
+        assert!(lines.contains(&("[on_disconnected hook] oops".to_string(), true)));
+        assert!(lines.iter().any(|(line, _)| line.contains("exited with code 3")));
+
+        // Points without a hook do nothing
+        assert!(runner.run(HookPoint::OnConnected, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_hook_timeout_kills_command() {
+        let (event_tx, _events) = event_channel(64);
+        let hooks = ProfileHooks {
+            before_start: Some(HookCommand { command: vec!["sleep".into(), "10".into()], timeout_secs: 1 }),
+            ..Default::default()
+        };
+        let runner = HookRunner::new(hooks, new_session_handle(&profile()), event_tx);
+
+        let started = std::time::Instant::now();
+        let error = runner.run(HookPoint::BeforeStart, None).await.unwrap_err();
+        assert!(error.to_string().contains("timed out after 1s"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -1,27 +1,35 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::sync::{mpsc, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex, RwLock};
 use tokio::time::sleep;
 use uuid::Uuid;
 
-use crate::config::AppConfig;
+use crate::config::{load_profiles, paths, resolve_references, AppConfig};
 use crate::error::{CoreError, Result};
-use crate::ssh::{SshInfo, detect_ssh, spawn_ssh};
+use crate::logs::LogSink;
+use crate::notify::Notifier;
+use crate::ssh::{ConnectOptions, EmbeddedBackend, OpenSshBackend, SshInfo, TunnelBackend, detect_ssh};
+use crate::storage::{HistoryStore, SecretStore, StateManager};
 use crate::types::{
-    Event, EventReceiver, EventSender, Profile, Session, SessionHandle, SessionStatus,
-    event_channel, new_session_handle,
+    AuthMethod, BackendKind, EndpointChangeReason, Event, EventReceiver, EventSender, HookPoint, Profile, RelayEndpoint,
+    Session, SessionHandle, SessionStatus, event_channel, new_session_handle,
 };
 
 use super::backoff::Backoff;
+use super::failover::{self, EndpointSelector};
+use super::health::HealthProber;
+use super::hooks::HookRunner;
 use super::monitor::{MonitorResult, SessionMonitor};
 
 /// Command sent to the session manager
 #[derive(Debug)]
 pub enum ManagerCommand {
     /// Start a session for a profile
-    Start(Profile, StartSessionOptions),
+    Start(Box<Profile>, StartSessionOptions),
     /// Stop a session by ID
     Stop(Uuid),
     /// Stop all sessions
@@ -33,7 +41,7 @@ pub enum ManagerCommand {
 }
 
 /// Options that apply to a started session but are not persisted in the profile.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StartSessionOptions {
     /// Password for `AuthMethod::Password`.
     ///
@@ -77,6 +85,8 @@ pub struct SessionManager {
     config: AppConfig,
     /// Detected SSH binary info
     ssh_info: Option<SshInfo>,
+    /// Connection backends by the profile setting that selects them
+    backends: HashMap<BackendKind, Arc<dyn TunnelBackend>>,
     /// Active sessions
     sessions: Arc<RwLock<HashMap<Uuid, ActiveSession>>>,
     /// Event broadcaster
@@ -86,21 +96,39 @@ pub struct SessionManager {
     /// Command sender (kept for potential future use)
     #[allow(dead_code)]
     cmd_tx: mpsc::Sender<(ManagerCommand, mpsc::Sender<ManagerResponse>)>,
+    /// Persisted record of running sessions
+    state: Arc<Mutex<StateManager>>,
+    /// Whether this manager writes session logs, history and webhooks
+    record_sessions: bool,
+    /// Whether this manager restores and records the persisted sessions
+    persist_state: bool,
+    /// Set on shutdown so stopping everything doesn't erase what to restore
+    shutting_down: Arc<AtomicBool>,
 }
 
 impl SessionManager {
     /// Create a new session manager
     pub fn new(config: AppConfig) -> (Self, SessionManagerHandle) {
+        Self::with_state_manager(config, StateManager::new())
+    }
+
+    /// Create a session manager that persists sessions through a specific state manager
+    pub fn with_state_manager(config: AppConfig, state: StateManager) -> (Self, SessionManagerHandle) {
         let (event_tx, _) = event_channel(100);
         let (cmd_tx, cmd_rx) = mpsc::channel(32);
 
         let manager = Self {
             config,
             ssh_info: None,
+            backends: HashMap::new(),
             sessions: Arc::new(RwLock::new(HashMap::new())),
             event_tx: event_tx.clone(),
             cmd_rx,
             cmd_tx: cmd_tx.clone(),
+            state: Arc::new(Mutex::new(state)),
+            record_sessions: true,
+            persist_state: true,
+            shutting_down: Arc::new(AtomicBool::new(false)),
         };
 
         let handle = SessionManagerHandle {
@@ -111,11 +139,43 @@ impl SessionManager {
         (manager, handle)
     }
 
-    /// Initialize the manager (detect SSH, etc.)
+    /// Choose whether this manager writes session log files and records
+    /// lifecycle changes to the history database and the configured webhooks
+    /// (default: enabled).
+    ///
+    /// Short-lived managers (e.g. a one-off CLI command) should disable this.
+    pub fn set_record_sessions(&mut self, enabled: bool) {
+        self.record_sessions = enabled;
+    }
+
+    /// Choose whether this manager restores sessions on `init()` and keeps
+    /// the state file in sync with them (default: enabled).
+    ///
+    /// Only the first process to take the state file's lock does either, so
+    /// the daemon, web server and GUI do not all start remembered sessions.
+    /// Short-lived managers should disable this.
+    pub fn set_persist_state(&mut self, enabled: bool) {
+        self.persist_state = enabled;
+    }
+
+    /// Register a connection backend, replacing any backend of the same kind.
+    ///
+    /// `init()` registers the embedded backend, and the OpenSSH backend for
+    /// the detected binary.
+    pub fn register_backend(&mut self, backend: Arc<dyn TunnelBackend>) {
+        self.backends.insert(backend.kind(), backend);
+    }
+
+    /// Initialize the manager (detect SSH, restore persisted sessions, etc.)
     pub async fn init(&mut self) -> Result<()> {
-        // Detect SSH binary
+        self.register_backend(Arc::new(EmbeddedBackend::new()));
+
+        // Detect SSH binary; without one only embedded profiles can connect
         let ssh_path = self.config.ssh.binary_path.as_ref();
-        self.ssh_info = Some(detect_ssh(ssh_path).await?);
+        match detect_ssh(ssh_path).await {
+            Ok(info) => self.ssh_info = Some(info),
+            Err(e) => tracing::warn!("OpenSSH backend unavailable: {}", e),
+        }
         
         if let Some(ref info) = self.ssh_info {
             tracing::info!("Detected SSH: {:?} ({})", info.path, info.version.as_deref().unwrap_or("unknown version"));
@@ -124,22 +184,135 @@ impl SessionManager {
                 version: info.version.clone(),
                 timestamp: chrono::Utc::now(),
             });
+            self.register_backend(Arc::new(OpenSshBackend::new(info.clone())));
+        }
+
+        // Log before restoring so restored sessions are captured too
+        if self.record_sessions && self.config.logging.file_logging {
+            LogSink::new(paths::logs_dir(), &self.config.logging).spawn(self.event_tx.subscribe());
+        }
+
+        if self.record_sessions && self.config.history.enabled {
+            self.start_history();
+        }
+
+        if self.record_sessions && !self.config.webhooks.is_empty() {
+            match Notifier::new(&self.config.webhooks) {
+                Ok(notifier) => {
+                    notifier.spawn(self.event_tx.subscribe());
+                }
+                Err(e) => tracing::warn!("Webhook notifications disabled: {}", e),
+            }
+        }
+
+        if self.persist_state {
+            let locked = self.state.lock().await.try_lock();
+            match locked {
+                Ok(true) => self.restore_sessions().await,
+                Ok(false) => tracing::info!("Another process restores and records persisted sessions"),
+                Err(e) => tracing::warn!("Session restore disabled: {}", e),
+            }
         }
 
         Ok(())
     }
 
+    /// Record session lifecycle changes in the history database, dropping
+    /// entries past the retention period first
+    fn start_history(&self) {
+        let store = match HistoryStore::open(paths::history_file()) {
+            Ok(store) => store,
+            Err(e) => {
+                tracing::warn!("Session history disabled: {}", e);
+                return;
+            }
+        };
+
+        let retention_days = self.config.history.retention_days;
+        if retention_days > 0 {
+            let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(retention_days));
+            if let Err(e) = store.prune(cutoff) {
+                tracing::warn!("Failed to prune session history: {}", e);
+            }
+        }
+
+        store.spawn(self.event_tx.subscribe());
+    }
+
+    /// Start the sessions recorded as connected (plus the default profile) when
+    /// auto-start is enabled, then keep the state file in sync with the sessions.
+    async fn restore_sessions(&self) {
+        let remembered = {
+            let mut state = self.state.lock().await;
+            if let Err(e) = state.load() {
+                tracing::warn!("Failed to load session state: {}", e);
+            }
+            state.get_auto_start_profiles()
+        };
+
+        spawn_state_recorder(
+            self.event_tx.subscribe(),
+            self.sessions.clone(),
+            self.state.clone(),
+            self.shutting_down.clone(),
+        );
+
+        if !self.config.general.auto_start_sessions {
+            return;
+        }
+
+        let profiles = match load_profiles() {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                tracing::warn!("Failed to load profiles for auto-start: {}", e);
+                return;
+            }
+        };
+
+        let mut to_start: Vec<&Profile> = remembered
+            .iter()
+            .filter_map(|id| profiles.iter().find(|p| p.id == *id))
+            .collect();
+
+        if let Some(ref default) = self.config.general.default_profile {
+            match profiles.iter().find(|p| p.name == *default || p.id.to_string() == *default) {
+                Some(profile) if !to_start.iter().any(|p| p.id == profile.id) => to_start.push(profile),
+                Some(_) => {}
+                None => tracing::warn!("Default profile '{}' not found", default),
+            }
+        }
+
+        for profile in to_start {
+            // Passwords are never persisted, so these cannot start unattended
+            if matches!(profile.auth, AuthMethod::Password) && profile.password.is_none() {
+                tracing::warn!("Not auto-starting '{}': password authentication needs a password", profile.name);
+                continue;
+            }
+
+            match self.handle_start(profile.clone(), StartSessionOptions::default()).await {
+                ManagerResponse::Started(id) => {
+                    tracing::info!("Auto-started session {} for profile '{}'", id, profile.name);
+                }
+                ManagerResponse::Error(e) => {
+                    tracing::warn!("Failed to auto-start profile '{}': {}", profile.name, e);
+                }
+                _ => {}
+            }
+        }
+    }
+
     /// Run the manager event loop
     pub async fn run(mut self) -> Result<()> {
         tracing::info!("Session manager started");
 
         while let Some((cmd, response_tx)) = self.cmd_rx.recv().await {
             let response = match cmd {
-                ManagerCommand::Start(profile, options) => self.handle_start(profile, options).await,
+                ManagerCommand::Start(profile, options) => self.handle_start(*profile, options).await,
                 ManagerCommand::Stop(id) => self.handle_stop(id).await,
                 ManagerCommand::StopAll => self.handle_stop_all().await,
                 ManagerCommand::GetStatus => self.handle_get_status().await,
                 ManagerCommand::Shutdown => {
+                    self.shutting_down.store(true, Ordering::SeqCst);
                     let _ = self.handle_stop_all().await;
                     let _ = response_tx.send(ManagerResponse::ShuttingDown).await;
                     break;
@@ -154,9 +327,38 @@ impl SessionManager {
     }
 
     async fn handle_start(&self, profile: Profile, options: StartSessionOptions) -> ManagerResponse {
-        let ssh_info = match &self.ssh_info {
-            Some(info) => info,
-            None => return ManagerResponse::Error("SSH not detected".to_string()),
+        let mut profile = profile;
+        if options.password.is_some() {
+            // A password sent with the request wins over the profile's
+            profile.password = None;
+        }
+
+        // Resolved for this run only; the saved profile keeps its references.
+        // Opening the secret store may derive a key or wait on D-Bus.
+        let secrets = self.config.secrets.clone();
+        let resolved = tokio::task::spawn_blocking(move || {
+            let store = std::cell::OnceCell::new();
+            resolve_references(&profile, &|name| {
+                match store.get_or_init(|| SecretStore::open(&secrets)) {
+                    Ok(store) => store.get(name),
+                    Err(e) => Err(CoreError::Other(e.to_string())),
+                }
+            })
+        })
+        .await;
+        let profile = match resolved {
+            Ok(Ok(profile)) => profile,
+            Ok(Err(e)) => return ManagerResponse::Error(e.to_string()),
+            Err(e) => return ManagerResponse::Error(e.to_string()),
+        };
+        // Loading only checks what it must to read the file; the rest is checked here
+        if let Err(e) = profile.ensure_valid() {
+            return ManagerResponse::Error(e.to_string());
+        }
+
+        let backend = match self.backends.get(&profile.backend) {
+            Some(backend) => backend.clone(),
+            None => return ManagerResponse::Error(CoreError::BackendUnavailable(profile.backend).to_string()),
         };
 
         // Check if session already exists for this profile
@@ -189,7 +391,7 @@ impl SessionManager {
         let task_handle = session_handle.clone();
         let task_profile = profile.clone();
         let task_options = options.clone();
-        let task_ssh_info = ssh_info.clone();
+
         let task_event_tx = self.event_tx.clone();
         let task_sessions = self.sessions.clone();
         let task_config = self.config.clone();
@@ -199,7 +401,7 @@ impl SessionManager {
                 task_handle,
                 task_profile,
                 task_options,
-                task_ssh_info,
+                backend,
                 task_event_tx,
                 task_sessions,
                 task_config,
@@ -255,7 +457,15 @@ impl SessionManager {
             let _ = active.stop_tx.send(()).await;
             
             let mut session = active.handle.write().await;
+            let old_status = session.status;
             session.status = SessionStatus::Stopped;
+
+            let _ = self.event_tx.send(Event::session_status_changed(
+                session.id,
+                &session.profile_name,
+                old_status,
+                SessionStatus::Stopped,
+            ));
         }
 
         ManagerResponse::AllStopped
@@ -304,7 +514,7 @@ impl SessionManagerHandle {
 
     /// Start a session with non-persisted options (e.g. password auth).
     pub async fn start_with_options(&self, profile: Profile, options: StartSessionOptions) -> Result<Uuid> {
-        match self.send_command(ManagerCommand::Start(profile, options)).await? {
+        match self.send_command(ManagerCommand::Start(Box::new(profile), options)).await? {
             ManagerResponse::Started(id) => Ok(id),
             ManagerResponse::Error(e) => Err(CoreError::Other(e)),
             _ => Err(CoreError::Other("Unexpected response".to_string())),
@@ -348,17 +558,72 @@ impl SessionManagerHandle {
     pub fn subscribe(&self) -> EventReceiver {
         self.event_tx.subscribe()
     }
+
+    /// Publish an event that happened outside the manager, such as a profile
+    /// change, to every subscriber
+    pub fn emit(&self, event: Event) {
+        let _ = self.event_tx.send(event);
+    }
+}
+
+/// Keep the state file in sync with the running sessions
+///
+/// Rewrites the record after every session lifecycle event until the manager
+/// starts shutting down, so the sessions running at shutdown are restored.
+fn spawn_state_recorder(
+    mut events: EventReceiver,
+    sessions: Arc<RwLock<HashMap<Uuid, ActiveSession>>>,
+    state: Arc<Mutex<StateManager>>,
+    shutting_down: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(
+                    Event::SessionStatusChanged { .. }
+                    | Event::SessionConnected { .. }
+                    | Event::SessionDisconnected { .. }
+                    | Event::SessionReconnecting { .. }
+                    | Event::SessionFailed { .. },
+                ) => {}
+                // Missed events may have been lifecycle changes, so record anyway
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+
+            if shutting_down.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let snapshot: Vec<Session> = {
+                let sessions = sessions.read().await;
+                let mut snapshot = Vec::with_capacity(sessions.len());
+                for active in sessions.values() {
+                    snapshot.push(active.handle.read().await.clone());
+                }
+                snapshot
+            };
+
+            let mut state = state.lock().await;
+            state.update_sessions(&snapshot);
+            if let Err(e) = state.save() {
+                tracing::warn!("Failed to save session state: {}", e);
+            }
+        }
+    });
 }
 
 /// Background task that manages a single session with reconnection logic
+#[allow(clippy::too_many_arguments)]
 async fn run_session_task(
     session_handle: SessionHandle,
     profile: Profile,
     options: StartSessionOptions,
-    ssh_info: SshInfo,
+    backend: Arc<dyn TunnelBackend>,
     event_tx: EventSender,
     sessions: Arc<RwLock<HashMap<Uuid, ActiveSession>>>,
-    _config: AppConfig,
+    config: AppConfig,
     mut stop_rx: mpsc::Receiver<()>,
 ) {
     let session_id = {
@@ -366,27 +631,84 @@ async fn run_session_task(
         session.id
     };
 
-    let mut backoff = Backoff::new()
-        .with_initial_delay(Duration::from_secs(1))
-        .with_max_delay(Duration::from_secs(300))
-        .with_max_attempts(profile.max_reconnect_attempts);
+    let policy = profile.reconnect.clone().unwrap_or_else(|| config.ssh.reconnect.clone());
+    let mut backoff = Backoff::from_policy(&policy).with_max_attempts(profile.max_reconnect_attempts);
+    let stable_after = match policy.reset_after_stable_secs {
+        0 => None,
+        secs => Some(chrono::Duration::seconds(secs as i64)),
+    };
+
+    let readiness_timeout = match config.ssh.readiness_timeout_secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+
+    let connect_options = ConnectOptions {
+        password: options.password,
+        sshpass_path: options.sshpass_path,
+        host_key_checking: config.ssh.strict_host_key_checking,
+        known_hosts_file: config.ssh.use_app_known_hosts.then(paths::known_hosts_file),
+    };
+
+    // Probes go straight to the servers, which jump hosts may not allow
+    let can_probe = profile.jump_hosts.is_empty();
+    let fail_back_after = match profile.fail_back_after_secs {
+        secs if secs > 0 && can_probe => Some(Duration::from_secs(secs)),
+        _ => None,
+    };
+
+    // Hooks follow the session's events; subscribe before anything is sent
+    let hooks = HookRunner::new(profile.hooks.clone(), session_handle.clone(), event_tx.clone());
+    let (hooks_done, hooks_done_rx) = oneshot::channel();
+    if !profile.hooks.is_empty() {
+        hooks.clone().spawn(event_tx.subscribe(), hooks_done_rx);
+    }
+
+    let before_start = tokio::select! {
+        result = hooks.run(HookPoint::BeforeStart, None) => result,
+        _ = stop_rx.recv() => {
+            sessions.write().await.remove(&session_id);
+            let _ = hooks_done.send(());
+            return;
+        }
+    };
+    if let Err(e) = before_start {
+        let mut session = session_handle.write().await;
+        session.status = SessionStatus::Failed;
+        session.last_error = Some(e.to_string());
+        let _ = event_tx.send(Event::session_failed(session.id, &session.profile_name, e.to_string()));
+        drop(session);
+
+        sessions.write().await.remove(&session_id);
+        let _ = hooks_done.send(());
+        return;
+    }
+
+    let mut selector = EndpointSelector::new(&profile);
+    if selector.wants_latencies() && can_probe {
+        start_round(&mut selector, can_probe).await;
+    }
+    let mut change_reason = EndpointChangeReason::Initial;
 
     loop {
-        // Spawn SSH process
-        let process = match spawn_ssh(
-            &ssh_info,
-            &profile,
-            options.password.as_deref().or(profile.password.as_deref()),
-            options.sshpass_path.as_deref(),
-        )
-        .await
-        {
+        let endpoint = selector.current().clone();
+        record_endpoint(&session_handle, &event_tx, &endpoint, change_reason).await;
+        change_reason = EndpointChangeReason::Failover;
+        let target = profile.with_endpoint(&endpoint);
+
+        // Connect through the profile's backend
+        let process = match backend.connect(&target, &connect_options).await {
             Ok(p) => p,
             Err(e) => {
-                tracing::error!("Failed to spawn SSH for '{}': {}", profile.name, e);
+                tracing::error!("Failed to start {} connection for '{}': {}", backend.kind(), profile.name, e);
                 
                 let mut session = session_handle.write().await;
                 session.last_error = Some(e.to_string());
+                let _ = event_tx.send(Event::session_spawn_failed(
+                    session.id,
+                    &session.profile_name,
+                    e.to_string(),
+                ));
                 
                 // Check if we should retry
                 if !profile.auto_reconnect || backoff.is_exhausted() {
@@ -403,16 +725,25 @@ async fn run_session_task(
                 if let Some(delay) = backoff.next_delay() {
                     session.status = SessionStatus::Reconnecting;
                     session.reconnect_count += 1;
+
+                    // Try the next server straight away; wait once all have failed
+                    let failing_over = selector.fail_over();
                     
                     let _ = event_tx.send(Event::session_reconnecting(
                         session.id,
                         &session.profile_name,
                         session.reconnect_count,
                         profile.max_reconnect_attempts,
+                        if failing_over { Duration::ZERO } else { delay },
                     ));
                     
                     drop(session);
-                    
+
+                    if failing_over {
+                        continue;
+                    }
+                    start_round(&mut selector, can_probe).await;
+
                     tokio::select! {
                         _ = sleep(delay) => continue,
                         _ = stop_rx.recv() => break,
@@ -425,17 +756,34 @@ async fn run_session_task(
         // Update session with PID
         {
             let mut session = session_handle.write().await;
-            session.pid = Some(process.pid);
+            session.pid = process.pid;
             session.status = SessionStatus::Starting;
+            session.clear_allocated_ports();
+            session.clear_health();
+            session.clear_traffic();
         }
 
+        let health_checks = if profile.tunnels.iter().any(|tunnel| tunnel.health_check.is_some()) {
+            match HealthProber::new(backend.clone(), target.clone(), connect_options.clone()) {
+                Ok(prober) => Some(Arc::new(prober)),
+                Err(e) => {
+                    tracing::warn!("Health checks for '{}' are disabled: {}", profile.name, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         // Create and run monitor
         let mut monitor = SessionMonitor::new(
             session_handle.clone(),
             process,
             event_tx.clone(),
             backoff.clone(),
-        );
+        )
+        .with_readiness_timeout(readiness_timeout)
+        .with_health_checks(health_checks);
 
         // Run monitor with stop signal handling
         let result = tokio::select! {
@@ -444,8 +792,39 @@ async fn run_session_task(
                 let _ = monitor.stop().await;
                 break;
             }
+            _ = wait_for_fail_back(&selector, fail_back_after) => {
+                let _ = monitor.stop().await;
+
+                let primary = selector.primary().clone();
+                tracing::info!("Session '{}' failing back to {}", profile.name, primary);
+                let session = session_handle.read().await;
+                let _ = event_tx.send(Event::session_disconnected(
+                    session.id,
+                    &session.profile_name,
+                    Some(format!("Failing back to {}", primary)),
+                ));
+                drop(session);
+
+                selector.select_primary();
+                change_reason = EndpointChangeReason::FailBack;
+                continue;
+            }
         };
 
+        // Whether this connection came up before it ended
+        let (was_connected, connected_at) = {
+            let session = session_handle.read().await;
+            (session.is_connected(), session.connected_at)
+        };
+
+        // A connection that held long enough earns a fresh backoff
+        if let (true, Some(connected_at), Some(stable_after)) = (was_connected, connected_at, stable_after) {
+            if chrono::Utc::now() - connected_at >= stable_after {
+                tracing::debug!("Session '{}' was stable; resetting its backoff", profile.name);
+                backoff.reset();
+            }
+        }
+
         // Handle result
         match result {
             MonitorResult::ExitedNormally => {
@@ -454,7 +833,13 @@ async fn run_session_task(
                 
                 if !profile.auto_reconnect {
                     let mut session = session_handle.write().await;
-                    session.status = SessionStatus::Stopped;
+                    let old_status = std::mem::replace(&mut session.status, SessionStatus::Stopped);
+                    let _ = event_tx.send(Event::session_status_changed(
+                        session.id,
+                        &session.profile_name,
+                        old_status,
+                        SessionStatus::Stopped,
+                    ));
                     break;
                 }
             }
@@ -474,11 +859,62 @@ async fn run_session_task(
                 let _ = event_tx.send(Event::session_disconnected(
                     session.id,
                     &session.profile_name,
-                    Some(error_msg),
+                    Some(error_msg.clone()),
                 ));
 
                 if !profile.auto_reconnect || backoff.is_exhausted() {
                     session.status = SessionStatus::Failed;
+                    let _ = event_tx.send(Event::session_failed(
+                        session.id,
+                        &session.profile_name,
+                        error_msg,
+                    ));
+                    break;
+                }
+            }
+            MonitorResult::Unhealthy(reason) => {
+                tracing::warn!("Session '{}' is unhealthy: {}", profile.name, reason);
+
+                let mut session = session_handle.write().await;
+                session.last_error = Some(reason.clone());
+
+                let _ = event_tx.send(Event::session_disconnected(
+                    session.id,
+                    &session.profile_name,
+                    Some(reason.clone()),
+                ));
+
+                if !profile.auto_reconnect || backoff.is_exhausted() {
+                    session.status = SessionStatus::Failed;
+                    let _ = event_tx.send(Event::session_failed(
+                        session.id,
+                        &session.profile_name,
+                        reason,
+                    ));
+                    break;
+                }
+            }
+            MonitorResult::NotReady(reason) => {
+                tracing::warn!("Session '{}' timed out: {}", profile.name, reason);
+
+                let mut session = session_handle.write().await;
+                let old_status = session.status;
+                session.status = SessionStatus::TimedOut;
+                session.last_error = Some(reason.clone());
+
+                let _ = event_tx.send(Event::session_status_changed(
+                    session.id,
+                    &session.profile_name,
+                    old_status,
+                    SessionStatus::TimedOut,
+                ));
+
+                if !profile.auto_reconnect || backoff.is_exhausted() {
+                    let _ = event_tx.send(Event::session_failed(
+                        session.id,
+                        &session.profile_name,
+                        reason,
+                    ));
                     break;
                 }
             }
@@ -496,29 +932,44 @@ async fn run_session_task(
             let mut session = session_handle.write().await;
             session.status = SessionStatus::Reconnecting;
             session.reconnect_count += 1;
+
+            // A server that never came up is skipped right away; anything
+            // else starts over as the strategy prescribes
+            let failing_over = !was_connected && selector.fail_over();
             
             let _ = event_tx.send(Event::session_reconnecting(
                 session.id,
                 &session.profile_name,
                 session.reconnect_count,
                 profile.max_reconnect_attempts,
+                if failing_over { Duration::ZERO } else { delay },
             ));
             
             drop(session);
-            
+
+            if failing_over {
+                continue;
+            }
+            start_round(&mut selector, can_probe).await;
+
             tokio::select! {
                 _ = sleep(delay) => {},
                 _ = stop_rx.recv() => break,
             }
         } else {
-            // Max attempts reached
+            // Max attempts reached, or reconnecting for too long
             let mut session = session_handle.write().await;
             session.status = SessionStatus::Failed;
-            
+
+            let reason = if profile.max_reconnect_attempts > 0 && backoff.attempt() >= profile.max_reconnect_attempts {
+                "Maximum reconnection attempts reached".to_string()
+            } else {
+                format!("Gave up reconnecting after {}s", policy.give_up_after_secs)
+            };
             let _ = event_tx.send(Event::session_failed(
                 session.id,
                 &session.profile_name,
-                "Maximum reconnection attempts reached",
+                reason,
             ));
             break;
         }
@@ -527,4 +978,369 @@ async fn run_session_task(
     // Remove from active sessions
     let mut sessions_guard = sessions.write().await;
     sessions_guard.remove(&session_id);
+    drop(sessions_guard);
+
+    // A stop's status change is sent before the entry is removed, so the
+    // hooks have every event by now
+    let _ = hooks_done.send(());
+}
+
+/// Begin a new pass over the profile's servers, measuring them when the strategy needs it
+async fn start_round(selector: &mut EndpointSelector, can_probe: bool) {
+    let latencies = if can_probe && selector.wants_latencies() {
+        failover::measure_latencies(selector.endpoints()).await
+    } else {
+        Vec::new()
+    };
+    selector.start_round(&latencies);
+}
+
+/// Resolve when the session should leave its fallback server for the primary
+async fn wait_for_fail_back(selector: &EndpointSelector, period: Option<Duration>) {
+    match period {
+        Some(period) if !selector.is_primary() => failover::wait_until_healthy(selector.primary(), period).await,
+        _ => std::future::pending().await,
+    }
+}
+
+/// Record the server a session connects to, announcing a change
+async fn record_endpoint(
+    session_handle: &SessionHandle,
+    event_tx: &EventSender,
+    endpoint: &RelayEndpoint,
+    reason: EndpointChangeReason,
+) {
+    let mut session = session_handle.write().await;
+    if session.active_endpoint.as_ref() == Some(endpoint) {
+        return;
+    }
+
+    let previous = session.active_endpoint.replace(endpoint.clone());
+    let reason = if previous.is_none() { EndpointChangeReason::Initial } else { reason };
+    tracing::info!("Session '{}' connecting to {} ({})", session.profile_name, endpoint, reason);
+
+    let _ = event_tx.send(Event::session_endpoint_changed(
+        session.id,
+        &session.profile_name,
+        previous,
+        endpoint.clone(),
+        reason,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh::{BoxFuture, ConnectionControl, SshOutput, TunnelConnection};
+    use crate::storage::StateManager;
+    use crate::types::TunnelSpec;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_state_recorder_tracks_sessions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        let (event_tx, _) = event_channel(16);
+        let sessions = Arc::new(RwLock::new(HashMap::new()));
+        let state = Arc::new(Mutex::new(StateManager::with_path(&path)));
+        let shutting_down = Arc::new(AtomicBool::new(false));
+
+        spawn_state_recorder(event_tx.subscribe(), sessions.clone(), state, shutting_down.clone());
+
+        let profile = Profile::new("test", "example.com", "user");
+        let handle = new_session_handle(&profile);
+        let session_id = {
+            let mut session = handle.write().await;
+            session.status = SessionStatus::Connected;
+            session.id
+        };
+        let (stop_tx, _stop_rx) = mpsc::channel(1);
+        sessions.write().await.insert(session_id, ActiveSession {
+            handle,
+            profile: profile.clone(),
+            stop_tx,
+        });
+
+        event_tx.send(Event::session_connected(session_id, "test")).unwrap();
+
+        // Wait for the recorder to write the file
+        let mut recorded = StateManager::with_path(&path);
+        for _ in 0..50 {
+            sleep(Duration::from_millis(10)).await;
+            if recorded.load().is_ok() && !recorded.state().sessions.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(recorded.get_auto_start_profiles(), vec![profile.id]);
+
+        // Stopping everything during shutdown must leave the record intact
+        shutting_down.store(true, Ordering::SeqCst);
+        sessions.write().await.clear();
+        event_tx
+            .send(Event::session_status_changed(session_id, "test", SessionStatus::Connected, SessionStatus::Stopped))
+            .unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        let mut recorded = StateManager::with_path(&path);
+        recorded.load().unwrap();
+        assert_eq!(recorded.get_auto_start_profiles(), vec![profile.id]);
+    }
+
+    /// Backend that reports a scripted sequence of signals
+    struct ScriptedBackend {
+        script: Vec<SshOutput>,
+    }
+
+    /// Holds the sender so the connection stays open, as a live one would
+    struct ScriptedControl {
+        _tx: mpsc::Sender<SshOutput>,
+    }
+
+    impl ConnectionControl for ScriptedControl {
+        fn try_wait(&mut self) -> Result<Option<Option<i32>>> {
+            Ok(None)
+        }
+
+        fn kill(&mut self) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    impl TunnelBackend for ScriptedBackend {
+        fn kind(&self) -> BackendKind {
+            BackendKind::Embedded
+        }
+
+        fn connect<'a>(
+            &'a self,
+            _profile: &'a Profile,
+            _options: &'a ConnectOptions,
+        ) -> BoxFuture<'a, Result<TunnelConnection>> {
+            Box::pin(async move {
+                let (tx, rx) = mpsc::channel(16);
+                for output in &self.script {
+                    tx.send(output.clone()).await.unwrap();
+                }
+                Ok(TunnelConnection::new(None, rx, Box::new(ScriptedControl { _tx: tx })))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sessions_use_profile_backend() {
+        let dir = tempdir().unwrap();
+        let (mut manager, handle) =
+            SessionManager::with_state_manager(AppConfig::default(), StateManager::with_path(dir.path().join("state.json")));
+        manager.register_backend(Arc::new(ScriptedBackend {
+            script: vec![
+                SshOutput::Connected,
+                SshOutput::ForwardReady { index: 0, allocated_port: Some(41234) },
+            ],
+        }));
+        tokio::spawn(manager.run());
+
+        // Nothing provides the OpenSSH backend without init()
+        let openssh = Profile::new("openssh", "example.com", "user").with_tunnel(TunnelSpec::new(8080, 3000));
+        assert!(handle.start(openssh).await.is_err());
+
+        let mut events = handle.subscribe();
+        let mut profile = Profile::new("embedded", "example.com", "user").with_tunnel(TunnelSpec::new(0, 3000));
+        profile.backend = BackendKind::Embedded;
+        let session_id = handle.start(profile).await.unwrap();
+
+        let mut connected = false;
+        let mut allocated = None;
+        while !(connected && allocated.is_some()) {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap() {
+                Event::SessionConnected { session_id: id, .. } if id == session_id => connected = true,
+                Event::TunnelPortAllocated { session_id: id, allocated_port, .. } if id == session_id => {
+                    allocated = Some(allocated_port)
+                }
+                _ => {}
+            }
+        }
+
+        let sessions = handle.status().await.unwrap();
+        assert_eq!(sessions[0].status, SessionStatus::Connected);
+        assert_eq!(sessions[0].tunnels[0].allocated_port, Some(41234));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_runs_hooks() {
+        use crate::types::HookCommand;
+
+        let dir = tempdir().unwrap();
+        let (mut manager, handle) =
+            SessionManager::with_state_manager(AppConfig::default(), StateManager::with_path(dir.path().join("state.json")));
+        manager.register_backend(Arc::new(ScriptedBackend {
+            script: vec![
+                SshOutput::Connected,
+                SshOutput::ForwardReady { index: 0, allocated_port: Some(41234) },
+            ],
+        }));
+        tokio::spawn(manager.run());
+        let mut events = handle.subscribe();
+
+        // A failing before_start hook keeps the session from connecting
+        let mut blocked = Profile::new("blocked", "example.com", "user").with_tunnel(TunnelSpec::new(0, 3000));
+        blocked.backend = BackendKind::Embedded;
+        blocked.hooks.before_start = Some(HookCommand::new(["false"]));
+        let blocked_id = handle.start(blocked).await.unwrap();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap() {
+                Event::SessionConnected { session_id, .. } => assert_ne!(session_id, blocked_id),
+                Event::SessionFailed { session_id, error, .. } if session_id == blocked_id => {
+                    assert!(error.contains("before_start hook failed"));
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let ports = dir.path().join("ports");
+        let mut profile = Profile::new("web", "example.com", "user").with_tunnel(TunnelSpec::new(0, 3000));
+        profile.backend = BackendKind::Embedded;
+        profile.hooks.on_connected = Some(HookCommand::new([
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("echo \"$RSSH_ALLOCATED_PORTS\" > '{}'", ports.display()),
+        ]));
+        handle.start(profile).await.unwrap();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while std::fs::read_to_string(&ports).unwrap_or_default() != "41234\n" {
+            assert!(tokio::time::Instant::now() < deadline, "on_connected hook did not run");
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_runs_on_failed_hook_without_reconnect() {
+        use crate::types::HookCommand;
+
+        let dir = tempdir().unwrap();
+        let (mut manager, handle) =
+            SessionManager::with_state_manager(AppConfig::default(), StateManager::with_path(dir.path().join("state.json")));
+        manager.register_backend(Arc::new(ScriptedBackend {
+            script: vec![
+                SshOutput::Connected,
+                SshOutput::ForwardReady { index: 0, allocated_port: None },
+                SshOutput::Exit(Some(255)),
+            ],
+        }));
+        tokio::spawn(manager.run());
+
+        let calls = dir.path().join("calls");
+        let record = |name: &str| {
+            HookCommand::new([
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("echo {} >> '{}'", name, calls.display()),
+            ])
+        };
+        let mut profile = Profile::new("web", "example.com", "user").with_tunnel(TunnelSpec::new(8080, 3000));
+        profile.backend = BackendKind::Embedded;
+        profile.auto_reconnect = false;
+        profile.hooks.on_disconnected = Some(record("disconnected"));
+        profile.hooks.on_failed = Some(record("failed"));
+        handle.start(profile).await.unwrap();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while std::fs::read_to_string(&calls).unwrap_or_default() != "disconnected\nfailed\n" {
+            assert!(tokio::time::Instant::now() < deadline, "on_failed hook did not run");
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_start_rejects_invalid_profile() {
+        let dir = tempdir().unwrap();
+        let (mut manager, handle) =
+            SessionManager::with_state_manager(AppConfig::default(), StateManager::with_path(dir.path().join("state.json")));
+        manager.register_backend(Arc::new(ScriptedBackend { script: vec![SshOutput::Connected] }));
+        tokio::spawn(manager.run());
+
+        // No references to resolve, but the host is a URL
+        let mut profile = Profile::new("web", "https://relay.example.com", "user").with_tunnel(TunnelSpec::new(8080, 3000));
+        profile.backend = BackendKind::Embedded;
+        let error = handle.start(profile).await.unwrap_err().to_string();
+        assert!(error.contains("Invalid profile") && error.contains("host"), "{}", error);
+        assert!(handle.status().await.unwrap().is_empty());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    /// Backend that cannot reach one host and connects everywhere else
+    struct UnreachableHostBackend {
+        unreachable: String,
+    }
+
+    impl TunnelBackend for UnreachableHostBackend {
+        fn kind(&self) -> BackendKind {
+            BackendKind::Embedded
+        }
+
+        fn connect<'a>(
+            &'a self,
+            profile: &'a Profile,
+            _options: &'a ConnectOptions,
+        ) -> BoxFuture<'a, Result<TunnelConnection>> {
+            Box::pin(async move {
+                if profile.host == self.unreachable {
+                    return Err(CoreError::SshSpawnFailed(format!("{} unreachable", profile.host)));
+                }
+                let (tx, rx) = mpsc::channel(16);
+                tx.send(SshOutput::Connected).await.unwrap();
+                tx.send(SshOutput::ForwardReady { index: 0, allocated_port: None }).await.unwrap();
+                Ok(TunnelConnection::new(None, rx, Box::new(ScriptedControl { _tx: tx })))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_fails_over_to_fallback_host() {
+        let dir = tempdir().unwrap();
+        let (mut manager, handle) =
+            SessionManager::with_state_manager(AppConfig::default(), StateManager::with_path(dir.path().join("state.json")));
+        manager.register_backend(Arc::new(UnreachableHostBackend { unreachable: "relay1.example.com".to_string() }));
+        tokio::spawn(manager.run());
+
+        let mut events = handle.subscribe();
+        let mut profile = Profile::new("relays", "relay1.example.com", "user").with_tunnel(TunnelSpec::new(8080, 3000));
+        profile.backend = BackendKind::Embedded;
+        profile.fallback_hosts.push(RelayEndpoint::new("relay2.example.com", 2222));
+        let session_id = handle.start(profile).await.unwrap();
+
+        let mut changes = Vec::new();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap() {
+                Event::SessionEndpointChanged { session_id: id, endpoint, reason, .. } if id == session_id => {
+                    changes.push((endpoint.to_string(), reason));
+                }
+                Event::SessionConnected { session_id: id, .. } if id == session_id => break,
+                _ => {}
+            }
+        }
+        assert_eq!(
+            changes,
+            vec![
+                ("relay1.example.com:22".to_string(), EndpointChangeReason::Initial),
+                ("relay2.example.com:2222".to_string(), EndpointChangeReason::Failover),
+            ]
+        );
+
+        let sessions = handle.status().await.unwrap();
+        assert_eq!(sessions[0].active_endpoint, Some(RelayEndpoint::new("relay2.example.com", 2222)));
+
+        handle.shutdown().await.unwrap();
+    }
 }
@@ -1,8 +1,14 @@
 pub mod backoff;
+pub mod failover;
+pub mod health;
+pub mod hooks;
 pub mod manager;
 pub mod monitor;
 
 pub use backoff::Backoff;
+pub use failover::EndpointSelector;
+pub use health::HealthProber;
+pub use hooks::HookRunner;
 pub use manager::{
     ManagerCommand, ManagerResponse, SessionManager, SessionManagerHandle, StartSessionOptions,
 };
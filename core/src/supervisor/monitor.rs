@@ -1,12 +1,16 @@
+use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::time::sleep;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::{sleep, sleep_until, Instant};
 
 use crate::error::Result;
-use crate::ssh::{SshOutput, SshProcess};
-use crate::types::{Event, EventSender, SessionHandle, SessionStatus};
+use crate::ssh::{SshOutput, TunnelConnection};
+use crate::types::{Event, EventSender, SessionHandle, SessionStatus, TunnelHealth, TunnelState};
 
 use super::backoff::Backoff;
+use super::health::{HealthProber, HealthReport};
 
 /// Monitor result indicating what happened
 #[derive(Debug)]
@@ -19,21 +23,40 @@ pub enum MonitorResult {
     Killed,
     /// Monitor was stopped externally
     Stopped,
+    /// The connection was abandoned because it did not become ready in time
+    NotReady(String),
+    /// The connection was dropped after a tunnel failed too many health checks
+    Unhealthy(String),
 }
 
-/// Monitor an SSH process and update session state
+/// What has been confirmed about the connection so far
+struct Readiness {
+    authenticated: bool,
+    /// Tunnels not yet confirmed by the backend
+    pending: Vec<bool>,
+}
+
+impl Readiness {
+    fn is_ready(&self) -> bool {
+        self.authenticated && !self.pending.contains(&true)
+    }
+}
+
+/// Monitor an SSH connection and update session state
 pub struct SessionMonitor {
     session: SessionHandle,
-    process: SshProcess,
+    process: TunnelConnection,
     event_tx: EventSender,
     #[allow(dead_code)]
     backoff: Backoff,
+    readiness_timeout: Option<Duration>,
+    health: Option<Arc<HealthProber>>,
 }
 
 impl SessionMonitor {
     pub fn new(
         session: SessionHandle,
-        process: SshProcess,
+        process: TunnelConnection,
         event_tx: EventSender,
         backoff: Backoff,
     ) -> Self {
@@ -42,15 +65,47 @@ impl SessionMonitor {
             process,
             event_tx,
             backoff,
+            readiness_timeout: None,
+            health: None,
         }
     }
 
+    /// Give up on connections that are not ready after `timeout` (default: never)
+    pub fn with_readiness_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.readiness_timeout = timeout;
+        self
+    }
+
+    /// Run the tunnels' health checks with `prober` once the session is connected
+    pub fn with_health_checks(mut self, prober: Option<Arc<HealthProber>>) -> Self {
+        self.health = prober;
+        self
+    }
+
     /// Run the monitor loop
     /// Returns when the process exits or is stopped
     pub async fn run(&mut self) -> MonitorResult {
         let mut last_output = String::new();
+        let mut readiness = Readiness {
+            authenticated: false,
+            pending: vec![true; self.session.read().await.tunnels.len()],
+        };
+        let deadline = self.readiness_timeout.map(|timeout| Instant::now() + timeout);
+        // Probes run while the monitor holds the set
+        let mut health_checks: Option<JoinSet<()>> = None;
+        let mut reports: Option<mpsc::Receiver<HealthReport>> = None;
         
         loop {
+            let waiting = deadline.is_some() && !readiness.is_ready();
+
+            if health_checks.is_none() && readiness.is_ready() {
+                if let Some(prober) = &self.health {
+                    let (probes, rx) = prober.clone().start(self.session.clone());
+                    health_checks = Some(probes);
+                    reports = Some(rx);
+                }
+            }
+
             tokio::select! {
                 // Check for process output
                 output = self.process.output_rx.recv() => {
@@ -61,13 +116,33 @@ impl SessionMonitor {
                         }
                         Some(SshOutput::Stderr(line)) => {
                             self.handle_output(&line, true).await;
-                            
-                            // Check for connection established indicators
-                            if self.is_connection_established(&line) {
-                                self.mark_connected().await;
+
+                            // Debug chatter is not a useful error message
+                            if !line.starts_with("debug") {
+                                last_output = line;
+                            }
+                        }
+                        Some(SshOutput::Connected) => {
+                            readiness.authenticated = true;
+                            self.update_readiness(&readiness).await;
+                        }
+                        Some(SshOutput::ForwardReady { index, allocated_port }) => {
+                            if let Some(port) = allocated_port {
+                                self.record_forward_port(index, port).await;
+                            }
+                            if let Some(pending) = readiness.pending.get_mut(index) {
+                                *pending = false;
+                            }
+                            self.update_readiness(&readiness).await;
+                        }
+                        Some(SshOutput::ForwardFailed { index, message }) => {
+                            self.report_forward_failure(index, &message).await;
+                            last_output = message;
+                        }
+                        Some(SshOutput::Traffic { index, traffic }) => {
+                            if let Some(tunnel) = self.session.write().await.tunnels.get_mut(index) {
+                                tunnel.traffic = Some(traffic);
                             }
-                            
-                            last_output = line;
                         }
                         Some(SshOutput::Exit(code)) => {
                             return self.handle_exit(code, &last_output).await;
@@ -79,7 +154,25 @@ impl SessionMonitor {
                     }
                 }
                 
-                // Periodic health check
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if waiting => {
+                    let reason = self.not_ready_reason(&readiness).await;
+                    let _ = self.process.kill().await;
+                    return MonitorResult::NotReady(reason);
+                }
+
+                report = next_report(&mut reports) => {
+                    match report {
+                        Some((index, result)) => {
+                            if let Some(reason) = self.record_health(index, result).await {
+                                let _ = self.process.kill().await;
+                                return MonitorResult::Unhealthy(reason);
+                            }
+                        }
+                        None => reports = None,
+                    }
+                }
+
+                // Periodic process check
                 _ = sleep(Duration::from_secs(30)) => {
                     let check_result = self.check_process_status(&last_output).await;
                     if let Some(result) = IntoOption::into(check_result) {
@@ -94,7 +187,6 @@ impl SessionMonitor {
         // Log SSH debug/error output to help diagnose password auth failures
         if is_stderr {
             tracing::debug!("SSH stderr: {}", line);
-            self.check_for_forwarding_errors(line).await;
         }
 
         let session = self.session.read().await;
@@ -106,31 +198,61 @@ impl SessionMonitor {
         ));
     }
 
-    fn is_connection_established(&self, line: &str) -> bool {
-        // Common indicators that SSH tunnel is established
-        let indicators = [
-            "Authenticated to",
-            "pledge: ",
-            "debug1: Entering interactive session",
-            "debug1: Remote connections from",
-            "debug1: remote forward success",
-            "Allocated port",
-        ];
-        
-        indicators.iter().any(|i| line.contains(i))
-    }
-
-    async fn check_for_forwarding_errors(&self, line: &str) {
-        if line.contains("remote port forwarding failed") {
-            let session = self.session.read().await;
-            tracing::warn!("Session {}: Remote forwarding failed. Check server GatewayPorts setting or if port is in use.", session.profile_name);
-            let _ = self.event_tx.send(Event::session_output(
-                session.id,
-                &session.profile_name,
-                "WARNING: Remote forwarding failed. Check server 'GatewayPorts' setting or port availability.",
-                true,
-            ));
+    /// Record a port the backend reports for a specific tunnel
+    async fn record_forward_port(&self, index: usize, port: u16) {
+        let mut session = self.session.write().await;
+        let Some(tunnel) = session.tunnels.get_mut(index) else {
+            tracing::warn!("Session {}: port {} reported for unknown tunnel {}", session.profile_name, port, index);
+            return;
+        };
+        if !tunnel.wants_allocated_port() {
+            return;
         }
+
+        tunnel.allocated_port = Some(port);
+        tracing::info!("Session {}: server allocated port {} for tunnel {}", session.profile_name, port, index);
+        let _ = self.event_tx.send(Event::tunnel_port_allocated(
+            session.id,
+            &session.profile_name,
+            index,
+            port,
+        ));
+    }
+
+    async fn report_forward_failure(&self, index: usize, message: &str) {
+        let session = self.session.read().await;
+        tracing::warn!("Session {}: forwarding failed for tunnel {}: {}", session.profile_name, index, message);
+        let _ = self.event_tx.send(Event::session_output(
+            session.id,
+            &session.profile_name,
+            format!("WARNING: Forwarding failed for tunnel {}: {}", index + 1, message),
+            true,
+        ));
+    }
+
+    /// Mark the session connected once it is authenticated and every tunnel is confirmed
+    async fn update_readiness(&self, readiness: &Readiness) {
+        if readiness.is_ready() && !self.session.read().await.is_connected() {
+            self.mark_connected().await;
+        }
+    }
+
+    async fn not_ready_reason(&self, readiness: &Readiness) -> String {
+        let timeout = self.readiness_timeout.unwrap_or_default().as_secs();
+        if !readiness.authenticated {
+            return format!("Not authenticated after {}s", timeout);
+        }
+
+        let session = self.session.read().await;
+        let unconfirmed: Vec<String> = readiness
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, pending)| **pending)
+            .filter_map(|(index, _)| session.tunnels.get(index))
+            .map(|tunnel| tunnel.spec.to_string())
+            .collect();
+        format!("Tunnels not confirmed after {}s: {}", timeout, unconfirmed.join(", "))
     }
 
     async fn mark_connected(&self) {
@@ -154,6 +276,69 @@ impl SessionMonitor {
         tracing::info!("Session {} connected", session.profile_name);
     }
 
+    /// Record a health probe, updating the tunnel and the session status
+    ///
+    /// Returns why the connection should be dropped once the tunnel has
+    /// failed `reconnect_after` probes in a row.
+    async fn record_health(&self, index: usize, result: std::result::Result<(), String>) -> Option<String> {
+        let mut session = self.session.write().await;
+        let (session_id, profile_name) = (session.id, session.profile_name.clone());
+        let tunnel = session.tunnels.get_mut(index)?;
+        let reconnect_after = tunnel.spec.health_check.as_ref()?.reconnect_after;
+
+        let was_healthy = !tunnel.is_unhealthy();
+        let failures = match result {
+            Ok(()) => 0,
+            Err(_) => tunnel.health.as_ref().map_or(0, |health| health.consecutive_failures) + 1,
+        };
+        tunnel.health = Some(TunnelHealth {
+            healthy: result.is_ok(),
+            consecutive_failures: failures,
+            last_checked: chrono::Utc::now(),
+            last_error: result.clone().err(),
+        });
+
+        if was_healthy != result.is_ok() {
+            match &result {
+                Ok(()) => tracing::info!("Session {}: tunnel {} is healthy again", profile_name, index),
+                Err(e) => tracing::warn!("Session {}: health check failed for tunnel {}: {}", profile_name, index, e),
+            }
+            let _ = self.event_tx.send(Event::tunnel_health_changed(
+                session_id,
+                &profile_name,
+                index,
+                result.is_ok(),
+                result.clone().err(),
+            ));
+        }
+
+        let degraded = session.tunnels.iter().any(TunnelState::is_unhealthy);
+        let new_status = match session.status {
+            SessionStatus::Connected if degraded => SessionStatus::Degraded,
+            SessionStatus::Degraded if !degraded => SessionStatus::Connected,
+            status => status,
+        };
+        if new_status != session.status {
+            let old_status = std::mem::replace(&mut session.status, new_status);
+            let _ = self.event_tx.send(Event::session_status_changed(
+                session_id,
+                &profile_name,
+                old_status,
+                new_status,
+            ));
+        }
+
+        match result {
+            Err(e) if reconnect_after > 0 && failures >= reconnect_after => Some(format!(
+                "Tunnel {} failed {} health checks in a row: {}",
+                index + 1,
+                failures,
+                e
+            )),
+            _ => None,
+        }
+    }
+
     async fn handle_exit(&self, code: Option<i32>, last_output: &str) -> MonitorResult {
         match code {
             Some(0) => MonitorResult::ExitedNormally,
@@ -174,12 +359,20 @@ impl SessionMonitor {
         }
     }
 
-    /// Stop the monitored process
+    /// Stop the monitored connection
     pub async fn stop(&mut self) -> Result<()> {
         self.process.kill().await
     }
 }
 
+/// The next health report, or never when checks are not running
+async fn next_report(reports: &mut Option<mpsc::Receiver<HealthReport>>) -> Option<HealthReport> {
+    match reports {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Helper trait for converting check results
 trait IntoOption<T> {
     fn into(self) -> Option<T>;
@@ -206,4 +399,113 @@ mod tests {
         let result = MonitorResult::ExitedWithError(1, "error".to_string());
         assert!(matches!(result, MonitorResult::ExitedWithError(1, _)));
     }
+
+    struct IdleControl;
+
+    impl crate::ssh::ConnectionControl for IdleControl {
+        fn try_wait(&mut self) -> Result<Option<Option<i32>>> {
+            Ok(None)
+        }
+
+        fn kill(&mut self) -> crate::ssh::BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_tunnel_times_out() {
+        use crate::types::{event_channel, new_session_handle, Profile, TunnelSpec};
+
+        let profile = Profile::new("test", "example.com", "user")
+            .with_tunnel(TunnelSpec::new(8080, 3000))
+            .with_tunnel(TunnelSpec::new(8081, 3001));
+        let session = new_session_handle(&profile);
+        let (event_tx, _) = event_channel(16);
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        tx.send(SshOutput::Connected).await.unwrap();
+        tx.send(SshOutput::ForwardReady { index: 0, allocated_port: None }).await.unwrap();
+
+        let connection = TunnelConnection::new(None, rx, Box::new(IdleControl));
+        let mut monitor = SessionMonitor::new(session.clone(), connection, event_tx, Backoff::new())
+            .with_readiness_timeout(Some(Duration::from_millis(100)));
+
+        match monitor.run().await {
+            MonitorResult::NotReady(reason) => {
+                assert!(reason.contains("-R 0.0.0.0:8081:0.0.0.0:3001"), "{}", reason);
+                assert!(!reason.contains("8080"), "{}", reason);
+            }
+            other => panic!("expected NotReady, got {:?}", other),
+        }
+        assert_eq!(session.read().await.status, SessionStatus::Starting);
+        drop(tx);
+    }
+
+    struct NoBackend;
+
+    impl crate::ssh::TunnelBackend for NoBackend {
+        fn kind(&self) -> crate::types::BackendKind {
+            crate::types::BackendKind::Embedded
+        }
+
+        fn connect<'a>(
+            &'a self,
+            _: &'a crate::types::Profile,
+            _: &'a crate::ssh::ConnectOptions,
+        ) -> crate::ssh::BoxFuture<'a, Result<TunnelConnection>> {
+            Box::pin(async { Err(crate::error::CoreError::Other("not used".to_string())) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_health_checks_degrade_then_drop_connection() {
+        use crate::ssh::ConnectOptions;
+        use crate::types::{event_channel, new_session_handle, HealthCheck, HealthCheckKind, Profile, TunnelSpec};
+
+        // Nothing listens on the tunnel's local side
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let check = HealthCheck {
+            interval_secs: 1,
+            reconnect_after: 2,
+            ..HealthCheck::new(HealthCheckKind::LocalTcp)
+        };
+        let profile = Profile::new("test", "example.com", "user")
+            .with_tunnel(TunnelSpec::new(8080, closed).with_health_check(check));
+        let session = new_session_handle(&profile);
+        let (event_tx, mut events) = event_channel(64);
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        tx.send(SshOutput::Connected).await.unwrap();
+        tx.send(SshOutput::ForwardReady { index: 0, allocated_port: None }).await.unwrap();
+
+        let prober = HealthProber::new(Arc::new(NoBackend), profile, ConnectOptions::default()).unwrap();
+        let connection = TunnelConnection::new(None, rx, Box::new(IdleControl));
+        let mut monitor = SessionMonitor::new(session.clone(), connection, event_tx, Backoff::new())
+            .with_health_checks(Some(Arc::new(prober)));
+
+        match monitor.run().await {
+            MonitorResult::Unhealthy(reason) => {
+                assert!(reason.starts_with("Tunnel 1 failed 2 health checks in a row"), "{}", reason);
+            }
+            other => panic!("expected Unhealthy, got {:?}", other),
+        }
+
+        let session = session.read().await;
+        assert_eq!(session.status, SessionStatus::Degraded);
+        assert_eq!(session.tunnels[0].health.as_ref().unwrap().consecutive_failures, 2);
+
+        let mut health_changes = 0;
+        let mut degraded = false;
+        while let Ok(event) = events.try_recv() {
+            match event {
+                Event::TunnelHealthChanged { healthy: false, .. } => health_changes += 1,
+                Event::SessionStatusChanged { new_status: SessionStatus::Degraded, .. } => degraded = true,
+                _ => {}
+            }
+        }
+        // Only the change from healthy to failing is announced
+        assert_eq!(health_changes, 1);
+        assert!(degraded);
+        drop(tx);
+    }
 }
@@ -1,9 +1,34 @@
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::profile::RelayEndpoint;
 use super::session::SessionStatus;
 
+/// Why a session moved to another server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointChangeReason {
+    /// First server the session connects to
+    Initial,
+    /// The previous server failed or the strategy rotated away from it
+    Failover,
+    /// The primary server has been healthy again for the fail-back period
+    FailBack,
+}
+
+impl std::fmt::Display for EndpointChangeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndpointChangeReason::Initial => write!(f, "initial"),
+            EndpointChangeReason::Failover => write!(f, "failover"),
+            EndpointChangeReason::FailBack => write!(f, "fail-back"),
+        }
+    }
+}
+
 /// Event types for UI/CLI notifications
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -35,6 +60,16 @@ pub enum Event {
         profile_name: String,
         attempt: u32,
         max_attempts: u32,
+        /// Backoff delay before the attempt, 0 when failing over straight away
+        #[serde(default)]
+        delay_ms: u64,
+        timestamp: DateTime<Utc>,
+    },
+    /// The SSH process for a connection attempt could not be started
+    SessionSpawnFailed {
+        session_id: Uuid,
+        profile_name: String,
+        error: String,
         timestamp: DateTime<Utc>,
     },
     /// Session failed permanently
@@ -44,6 +79,35 @@ pub enum Event {
         error: String,
         timestamp: DateTime<Utc>,
     },
+    /// Server allocated the listening port for a `remote_port = 0` tunnel
+    TunnelPortAllocated {
+        session_id: Uuid,
+        profile_name: String,
+        /// Index of the tunnel in the profile
+        tunnel_index: usize,
+        allocated_port: u16,
+        timestamp: DateTime<Utc>,
+    },
+    /// A tunnel's health check started or stopped failing
+    TunnelHealthChanged {
+        session_id: Uuid,
+        profile_name: String,
+        /// Index of the tunnel in the profile
+        tunnel_index: usize,
+        healthy: bool,
+        /// Why the check failed (when unhealthy)
+        error: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Session is connecting to a different server
+    SessionEndpointChanged {
+        session_id: Uuid,
+        profile_name: String,
+        previous: Option<RelayEndpoint>,
+        endpoint: RelayEndpoint,
+        reason: EndpointChangeReason,
+        timestamp: DateTime<Utc>,
+    },
     /// SSH process output (stdout/stderr)
     SessionOutput {
         session_id: Uuid,
@@ -126,12 +190,27 @@ impl Event {
         profile_name: impl Into<String>,
         attempt: u32,
         max_attempts: u32,
+        delay: Duration,
     ) -> Self {
         Self::SessionReconnecting {
             session_id,
             profile_name: profile_name.into(),
             attempt,
             max_attempts,
+            delay_ms: delay.as_millis() as u64,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn session_spawn_failed(
+        session_id: Uuid,
+        profile_name: impl Into<String>,
+        error: impl Into<String>,
+    ) -> Self {
+        Self::SessionSpawnFailed {
+            session_id,
+            profile_name: profile_name.into(),
+            error: error.into(),
             timestamp: Utc::now(),
         }
     }
@@ -149,6 +228,55 @@ impl Event {
         }
     }
 
+    pub fn tunnel_port_allocated(
+        session_id: Uuid,
+        profile_name: impl Into<String>,
+        tunnel_index: usize,
+        allocated_port: u16,
+    ) -> Self {
+        Self::TunnelPortAllocated {
+            session_id,
+            profile_name: profile_name.into(),
+            tunnel_index,
+            allocated_port,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn tunnel_health_changed(
+        session_id: Uuid,
+        profile_name: impl Into<String>,
+        tunnel_index: usize,
+        healthy: bool,
+        error: Option<String>,
+    ) -> Self {
+        Self::TunnelHealthChanged {
+            session_id,
+            profile_name: profile_name.into(),
+            tunnel_index,
+            healthy,
+            error,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn session_endpoint_changed(
+        session_id: Uuid,
+        profile_name: impl Into<String>,
+        previous: Option<RelayEndpoint>,
+        endpoint: RelayEndpoint,
+        reason: EndpointChangeReason,
+    ) -> Self {
+        Self::SessionEndpointChanged {
+            session_id,
+            profile_name: profile_name.into(),
+            previous,
+            endpoint,
+            reason,
+            timestamp: Utc::now(),
+        }
+    }
+
     pub fn session_output(
         session_id: Uuid,
         profile_name: impl Into<String>,
@@ -164,6 +292,30 @@ impl Event {
         }
     }
 
+    pub fn profile_created(profile_id: Uuid, profile_name: impl Into<String>) -> Self {
+        Self::ProfileCreated {
+            profile_id,
+            profile_name: profile_name.into(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn profile_updated(profile_id: Uuid, profile_name: impl Into<String>) -> Self {
+        Self::ProfileUpdated {
+            profile_id,
+            profile_name: profile_name.into(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn profile_deleted(profile_id: Uuid, profile_name: impl Into<String>) -> Self {
+        Self::ProfileDeleted {
+            profile_id,
+            profile_name: profile_name.into(),
+            timestamp: Utc::now(),
+        }
+    }
+
     pub fn error(message: impl Into<String>, context: Option<String>) -> Self {
         Self::Error {
             message: message.into(),
@@ -179,7 +331,11 @@ impl Event {
             Event::SessionConnected { timestamp, .. } => *timestamp,
             Event::SessionDisconnected { timestamp, .. } => *timestamp,
             Event::SessionReconnecting { timestamp, .. } => *timestamp,
+            Event::SessionSpawnFailed { timestamp, .. } => *timestamp,
             Event::SessionFailed { timestamp, .. } => *timestamp,
+            Event::TunnelPortAllocated { timestamp, .. } => *timestamp,
+            Event::TunnelHealthChanged { timestamp, .. } => *timestamp,
+            Event::SessionEndpointChanged { timestamp, .. } => *timestamp,
             Event::SessionOutput { timestamp, .. } => *timestamp,
             Event::ProfileCreated { timestamp, .. } => *timestamp,
             Event::ProfileUpdated { timestamp, .. } => *timestamp,
@@ -188,6 +344,64 @@ impl Event {
             Event::Error { timestamp, .. } => *timestamp,
         }
     }
+
+    /// The serialized `type` tag, e.g. `session_connected`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::SessionStatusChanged { .. } => "session_status_changed",
+            Event::SessionConnected { .. } => "session_connected",
+            Event::SessionDisconnected { .. } => "session_disconnected",
+            Event::SessionReconnecting { .. } => "session_reconnecting",
+            Event::SessionSpawnFailed { .. } => "session_spawn_failed",
+            Event::SessionFailed { .. } => "session_failed",
+            Event::TunnelPortAllocated { .. } => "tunnel_port_allocated",
+            Event::TunnelHealthChanged { .. } => "tunnel_health_changed",
+            Event::SessionEndpointChanged { .. } => "session_endpoint_changed",
+            Event::SessionOutput { .. } => "session_output",
+            Event::ProfileCreated { .. } => "profile_created",
+            Event::ProfileUpdated { .. } => "profile_updated",
+            Event::ProfileDeleted { .. } => "profile_deleted",
+            Event::SshBinaryChanged { .. } => "ssh_binary_changed",
+            Event::Error { .. } => "error",
+        }
+    }
+
+    /// The session this event is about, if any
+    pub fn session_id(&self) -> Option<Uuid> {
+        match self {
+            Event::SessionStatusChanged { session_id, .. }
+            | Event::SessionConnected { session_id, .. }
+            | Event::SessionDisconnected { session_id, .. }
+            | Event::SessionReconnecting { session_id, .. }
+            | Event::SessionSpawnFailed { session_id, .. }
+            | Event::SessionFailed { session_id, .. }
+            | Event::TunnelPortAllocated { session_id, .. }
+            | Event::TunnelHealthChanged { session_id, .. }
+            | Event::SessionEndpointChanged { session_id, .. }
+            | Event::SessionOutput { session_id, .. } => Some(*session_id),
+            _ => None,
+        }
+    }
+
+    /// The profile this event is about, if any
+    pub fn profile_name(&self) -> Option<&str> {
+        match self {
+            Event::SessionStatusChanged { profile_name, .. }
+            | Event::SessionConnected { profile_name, .. }
+            | Event::SessionDisconnected { profile_name, .. }
+            | Event::SessionReconnecting { profile_name, .. }
+            | Event::SessionSpawnFailed { profile_name, .. }
+            | Event::SessionFailed { profile_name, .. }
+            | Event::TunnelPortAllocated { profile_name, .. }
+            | Event::TunnelHealthChanged { profile_name, .. }
+            | Event::SessionEndpointChanged { profile_name, .. }
+            | Event::SessionOutput { profile_name, .. }
+            | Event::ProfileCreated { profile_name, .. }
+            | Event::ProfileUpdated { profile_name, .. }
+            | Event::ProfileDeleted { profile_name, .. } => Some(profile_name),
+            Event::SshBinaryChanged { .. } | Event::Error { .. } => None,
+        }
+    }
 }
 
 /// Event channel sender type
@@ -199,3 +413,29 @@ pub type EventReceiver = tokio::sync::broadcast::Receiver<Event>;
 pub fn event_channel(capacity: usize) -> (EventSender, EventReceiver) {
     tokio::sync::broadcast::channel(capacity)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_matches_serialized_type() {
+        let session_id = Uuid::new_v4();
+        let events = [
+            Event::session_connected(session_id, "web"),
+            Event::session_output(session_id, "web", "hello", false),
+            Event::profile_deleted(Uuid::new_v4(), "web"),
+            Event::error("boom", None),
+        ];
+
+        for event in &events {
+            let json = serde_json::to_value(event).unwrap();
+            assert_eq!(json["type"], event.kind());
+        }
+
+        assert_eq!(events[0].session_id(), Some(session_id));
+        assert_eq!(events[2].session_id(), None);
+        assert_eq!(events[2].profile_name(), Some("web"));
+        assert_eq!(events[3].profile_name(), None);
+    }
+}
@@ -1,7 +1,12 @@
 pub mod events;
 pub mod profile;
 pub mod session;
+pub mod validation;
 
-pub use events::{Event, EventReceiver, EventSender, event_channel};
-pub use profile::{AuthMethod, Profile, TunnelSpec};
-pub use session::{Session, SessionHandle, SessionStatus, new_session_handle};
+pub use events::{EndpointChangeReason, Event, EventReceiver, EventSender, event_channel};
+pub use profile::{
+    AuthMethod, BackendKind, FailoverStrategy, HealthCheck, HealthCheckKind, HookCommand, HookPoint, JitterStrategy,
+    JumpHost, Profile, ProfileHooks, ReconnectPolicy, RelayEndpoint, TunnelEndpoint, TunnelKind, TunnelSpec,
+};
+pub use session::{Session, SessionHandle, SessionStatus, TunnelHealth, TunnelState, TunnelTraffic, new_session_handle};
+pub use validation::{Diagnostic, Severity};
@@ -2,47 +2,433 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-/// A tunnel specification for reverse port forwarding (-R)
+use crate::error::{CoreError, Result};
+
+/// Direction of a forwarded tunnel
+///
+/// Every tunnel has a remote side (`remote_bind:remote_port` or
+/// `remote_socket`, on or as seen from the SSH server) and a local side
+/// (`local_host:local_port` or `local_socket`, on or as seen from this
+/// machine). The kind decides which side listens.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelKind {
+    /// Remote side listens, connections go to the local side (-R)
+    #[default]
+    Remote,
+    /// Local side listens, connections go to the remote side (-L)
+    Local,
+    /// Local side listens as a SOCKS proxy exiting at the server (-D)
+    Dynamic,
+    /// Remote side listens as a SOCKS proxy exiting at this machine (-R without target)
+    RemoteDynamic,
+}
+
+impl TunnelKind {
+    /// The ssh flag that establishes this kind of forward
+    pub fn ssh_flag(&self) -> &'static str {
+        match self {
+            TunnelKind::Remote | TunnelKind::RemoteDynamic => "-R",
+            TunnelKind::Local => "-L",
+            TunnelKind::Dynamic => "-D",
+        }
+    }
+
+    /// Whether the listening socket lives on the SSH server
+    pub fn listens_remotely(&self) -> bool {
+        matches!(self, TunnelKind::Remote | TunnelKind::RemoteDynamic)
+    }
+}
+
+impl std::fmt::Display for TunnelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TunnelKind::Remote => write!(f, "remote"),
+            TunnelKind::Local => write!(f, "local"),
+            TunnelKind::Dynamic => write!(f, "dynamic"),
+            TunnelKind::RemoteDynamic => write!(f, "remote_dynamic"),
+        }
+    }
+}
+
+/// One side of a tunnel: a TCP address or a Unix domain socket path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TunnelEndpoint {
+    Tcp { host: String, port: u16 },
+    Socket(String),
+}
+
+impl std::fmt::Display for TunnelEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TunnelEndpoint::Tcp { host, port } => write!(f, "{}:{}", host, port),
+            TunnelEndpoint::Socket(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+/// A tunnel specification (-R, -L or -D)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "RawTunnelSpec")]
 pub struct TunnelSpec {
-    /// Remote bind address (default: localhost)
+    /// Forwarding direction (default: remote)
+    #[serde(default)]
+    pub kind: TunnelKind,
+    /// Remote side address: bind address for remote/remote_dynamic,
+    /// destination host for local (default: 0.0.0.0)
     #[serde(default = "default_bind_address")]
     pub remote_bind: String,
-    /// Remote port on the SSH server
+    /// Remote side port (unused for dynamic; required for the other kinds
+    /// unless remote_socket is set)
     pub remote_port: u16,
-    /// Local host to forward to (default: localhost)
+    /// Local side address: destination host for remote,
+    /// bind address for local/dynamic (default: 0.0.0.0)
     #[serde(default = "default_bind_address")]
     pub local_host: String,
-    /// Local port to forward to
+    /// Local side port (unused for remote_dynamic)
+    #[serde(default)]
     pub local_port: u16,
+    /// Unix socket path on the server, used instead of remote_bind:remote_port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_socket: Option<String>,
+    /// Unix socket path on this machine, used instead of local_host:local_port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_socket: Option<String>,
+    /// Probe run periodically while the session is connected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+}
+
+/// A tunnel as written, before a missing `remote_port` is checked
+#[derive(Deserialize)]
+struct RawTunnelSpec {
+    #[serde(default)]
+    kind: TunnelKind,
+    #[serde(default = "default_bind_address")]
+    remote_bind: String,
+    remote_port: Option<u16>,
+    #[serde(default = "default_bind_address")]
+    local_host: String,
+    #[serde(default)]
+    local_port: u16,
+    #[serde(default)]
+    remote_socket: Option<String>,
+    #[serde(default)]
+    local_socket: Option<String>,
+    #[serde(default)]
+    health_check: Option<HealthCheck>,
+}
+
+impl TryFrom<RawTunnelSpec> for TunnelSpec {
+    type Error = String;
+
+    fn try_from(raw: RawTunnelSpec) -> std::result::Result<Self, Self::Error> {
+        // A forgotten port must not quietly become port 0 (server-allocated);
+        // an explicit `remote_port = 0` still asks for one
+        let remote_port = match raw.remote_port {
+            Some(port) => port,
+            None if raw.kind == TunnelKind::Dynamic || raw.remote_socket.is_some() => 0,
+            None => return Err(format!("{} tunnel is missing remote_port", raw.kind)),
+        };
+
+        Ok(Self {
+            kind: raw.kind,
+            remote_bind: raw.remote_bind,
+            remote_port,
+            local_host: raw.local_host,
+            local_port: raw.local_port,
+            remote_socket: raw.remote_socket,
+            local_socket: raw.local_socket,
+            health_check: raw.health_check,
+        })
+    }
 }
 
 fn default_bind_address() -> String {
     "0.0.0.0".to_string()
 }
 
+/// What a tunnel health check connects to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthCheckKind {
+    /// TCP (or Unix socket) connect to the tunnel's local side
+    LocalTcp,
+    /// TCP connect to the tunnel's remote side, made from the server over a
+    /// second ssh connection (`ssh -W`)
+    RemoteTcp,
+    /// HTTP GET to the tunnel's local side, expecting `expected_status`
+    Http,
+}
+
+impl std::fmt::Display for HealthCheckKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealthCheckKind::LocalTcp => write!(f, "local_tcp"),
+            HealthCheckKind::RemoteTcp => write!(f, "remote_tcp"),
+            HealthCheckKind::Http => write!(f, "http"),
+        }
+    }
+}
+
+/// A periodic check that a tunnel carries traffic, not just that ssh runs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HealthCheck {
+    pub kind: HealthCheckKind,
+    /// Seconds between probes (default: 30)
+    #[serde(default = "default_health_interval")]
+    pub interval_secs: u64,
+    /// Seconds a probe may take before it counts as failed (default: 5)
+    #[serde(default = "default_health_timeout")]
+    pub timeout_secs: u64,
+    /// Reconnect after this many failed probes in a row (0 = never)
+    #[serde(default)]
+    pub reconnect_after: u32,
+    /// Request path for `http` checks (default: /)
+    #[serde(default = "default_health_path")]
+    pub path: String,
+    /// Response status `http` checks expect (default: 200)
+    #[serde(default = "default_health_status")]
+    pub expected_status: u16,
+}
+
+fn default_health_interval() -> u64 {
+    30
+}
+
+fn default_health_timeout() -> u64 {
+    5
+}
+
+fn default_health_path() -> String {
+    "/".to_string()
+}
+
+fn default_health_status() -> u16 {
+    200
+}
+
+impl HealthCheck {
+    pub fn new(kind: HealthCheckKind) -> Self {
+        Self {
+            kind,
+            interval_secs: default_health_interval(),
+            timeout_secs: default_health_timeout(),
+            reconnect_after: 0,
+            path: default_health_path(),
+            expected_status: default_health_status(),
+        }
+    }
+
+    /// Why the check cannot probe `tunnel`, if it cannot
+    fn problem(&self, tunnel: &TunnelSpec) -> Option<String> {
+        if self.interval_secs == 0 || self.timeout_secs == 0 {
+            return Some("health check interval_secs and timeout_secs must not be 0".to_string());
+        }
+
+        match self.kind {
+            HealthCheckKind::LocalTcp if tunnel.kind == TunnelKind::RemoteDynamic => {
+                Some("local_tcp health checks need a local side; remote dynamic tunnels have none".to_string())
+            }
+            HealthCheckKind::RemoteTcp if tunnel.kind == TunnelKind::Dynamic => {
+                Some("remote_tcp health checks need a remote side; dynamic tunnels have none".to_string())
+            }
+            HealthCheckKind::RemoteTcp if tunnel.remote_socket.is_some() => {
+                Some("remote_tcp health checks cannot reach a Unix socket".to_string())
+            }
+            HealthCheckKind::Http if !matches!(tunnel.kind, TunnelKind::Remote | TunnelKind::Local) => {
+                Some(format!("http health checks do not apply to {} tunnels", tunnel.kind))
+            }
+            HealthCheckKind::Http if tunnel.local_socket.is_some() => {
+                Some("http health checks cannot reach a Unix socket".to_string())
+            }
+            HealthCheckKind::Http if !self.path.starts_with('/') => {
+                Some(format!("health check path '{}' must start with '/'", self.path))
+            }
+            HealthCheckKind::Http if !(100..=599).contains(&self.expected_status) => {
+                Some(format!("health check expected_status {} is not an HTTP status", self.expected_status))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl TunnelSpec {
+    /// Create a remote (-R) tunnel exposing `local_port` as `remote_port` on the server
     pub fn new(remote_port: u16, local_port: u16) -> Self {
         Self {
+            kind: TunnelKind::Remote,
             remote_bind: default_bind_address(),
             remote_port,
             local_host: default_bind_address(),
             local_port,
+            remote_socket: None,
+            local_socket: None,
+            health_check: None,
+        }
+    }
+
+    /// Create a local (-L) tunnel reaching `remote_host:remote_port` via `local_port`
+    pub fn local(local_port: u16, remote_host: impl Into<String>, remote_port: u16) -> Self {
+        Self {
+            kind: TunnelKind::Local,
+            remote_bind: remote_host.into(),
+            remote_port,
+            local_host: default_bind_address(),
+            local_port,
+            remote_socket: None,
+            local_socket: None,
+            health_check: None,
+        }
+    }
+
+    /// Create a dynamic (-D) SOCKS proxy listening on `local_port`
+    pub fn dynamic(local_port: u16) -> Self {
+        Self {
+            kind: TunnelKind::Dynamic,
+            remote_bind: default_bind_address(),
+            remote_port: 0,
+            local_host: default_bind_address(),
+            local_port,
+            remote_socket: None,
+            local_socket: None,
+            health_check: None,
         }
     }
 
-    /// Format as SSH -R argument: [bind_address:]port:host:hostport
+    /// Create a remote dynamic (-R) SOCKS proxy listening on `remote_port` on the server
+    pub fn remote_dynamic(remote_port: u16) -> Self {
+        Self {
+            kind: TunnelKind::RemoteDynamic,
+            remote_bind: default_bind_address(),
+            remote_port,
+            local_host: default_bind_address(),
+            local_port: 0,
+            remote_socket: None,
+            local_socket: None,
+            health_check: None,
+        }
+    }
+
+    /// The server side of the tunnel
+    pub fn remote_endpoint(&self) -> TunnelEndpoint {
+        match &self.remote_socket {
+            Some(path) => TunnelEndpoint::Socket(path.clone()),
+            None => {
+                // Sanitize the bind address (remove http://, etc) in case user pasted a URL
+                let host = self.remote_bind
+                    .trim_start_matches("http://")
+                    .trim_start_matches("https://")
+                    .trim_end_matches('/');
+                TunnelEndpoint::Tcp { host: host.to_string(), port: self.remote_port }
+            }
+        }
+    }
+
+    /// The client side of the tunnel
+    pub fn local_endpoint(&self) -> TunnelEndpoint {
+        match &self.local_socket {
+            Some(path) => TunnelEndpoint::Socket(path.clone()),
+            None => TunnelEndpoint::Tcp { host: self.local_host.clone(), port: self.local_port },
+        }
+    }
+
+    /// Replace the server side of the tunnel
+    pub fn with_remote_endpoint(mut self, endpoint: TunnelEndpoint) -> Self {
+        match endpoint {
+            TunnelEndpoint::Tcp { host, port } => {
+                self.remote_bind = host;
+                self.remote_port = port;
+                self.remote_socket = None;
+            }
+            TunnelEndpoint::Socket(path) => self.remote_socket = Some(path),
+        }
+        self
+    }
+
+    /// Replace the client side of the tunnel
+    pub fn with_local_endpoint(mut self, endpoint: TunnelEndpoint) -> Self {
+        match endpoint {
+            TunnelEndpoint::Tcp { host, port } => {
+                self.local_host = host;
+                self.local_port = port;
+                self.local_socket = None;
+            }
+            TunnelEndpoint::Socket(path) => self.local_socket = Some(path),
+        }
+        self
+    }
+
+    /// Probe the tunnel periodically while connected
+    pub fn with_health_check(mut self, check: HealthCheck) -> Self {
+        self.health_check = Some(check);
+        self
+    }
+
+    /// Whether the listening side of this tunnel is a Unix socket
+    pub fn listens_on_socket(&self) -> bool {
+        if self.kind.listens_remotely() {
+            self.remote_socket.is_some()
+        } else {
+            self.local_socket.is_some()
+        }
+    }
+
+    /// Check that socket endpoints are usable for this tunnel's kind
+    pub fn validate(&self) -> Result<()> {
+        match self.problem() {
+            Some(problem) => Err(CoreError::ProfileInvalid(format!("tunnel '{}': {}", self, problem))),
+            None => Ok(()),
+        }
+    }
+
+    /// What is wrong with the tunnel, if anything
+    pub(crate) fn problem(&self) -> Option<String> {
+        for path in [&self.remote_socket, &self.local_socket].into_iter().flatten() {
+            if !path.starts_with('/') {
+                return Some(format!("socket path '{}' must be absolute", path));
+            }
+            if path.contains(':') {
+                return Some(format!("socket path '{}' must not contain ':'", path));
+            }
+        }
+
+        if let Some(problem) = self.health_check.as_ref().and_then(|check| check.problem(self)) {
+            return Some(problem);
+        }
+
+        // SOCKS forwards only listen on TCP
+        match self.kind {
+            TunnelKind::Dynamic if self.local_socket.is_some() => {
+                Some("dynamic tunnels cannot listen on a Unix socket".to_string())
+            }
+            TunnelKind::RemoteDynamic if self.remote_socket.is_some() => {
+                Some("remote dynamic tunnels cannot listen on a Unix socket".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Format as the argument to the kind's ssh flag:
+    /// - remote: `remote_endpoint:local_endpoint`
+    /// - local: `local_endpoint:remote_endpoint`
+    /// - dynamic: `bind:port`
+    /// - remote_dynamic: `bind:port`
+    ///
+    /// where an endpoint is either `host:port` or a socket path.
     pub fn to_ssh_arg(&self) -> String {
-        // Sanitize the bind address (remove http://, etc) in case user pasted a URL
-        let bind = self.remote_bind
-            .trim_start_matches("http://")
-            .trim_start_matches("https://")
-            .trim_end_matches('/');
-            
-        format!(
-            "{}:{}:{}:{}",
-            bind, self.remote_port, self.local_host, self.local_port
-        )
+        match self.kind {
+            TunnelKind::Remote => format!("{}:{}", self.remote_endpoint(), self.local_endpoint()),
+            TunnelKind::Local => format!("{}:{}", self.local_endpoint(), self.remote_endpoint()),
+            TunnelKind::Dynamic => self.local_endpoint().to_string(),
+            TunnelKind::RemoteDynamic => self.remote_endpoint().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for TunnelSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind.ssh_flag(), self.to_ssh_arg())
     }
 }
 
@@ -61,6 +447,421 @@ pub enum AuthMethod {
     Password,
 }
 
+/// A bastion host the connection hops through on the way to the server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JumpHost {
+    /// Hostname or IP
+    pub host: String,
+    /// SSH port (default: 22)
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    /// Username (ssh's default when not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Authentication method (agent or key file)
+    #[serde(default)]
+    pub auth: AuthMethod,
+    /// Custom known_hosts file for this hop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_hosts_file: Option<String>,
+}
+
+impl JumpHost {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: default_ssh_port(),
+            user: None,
+            auth: AuthMethod::default(),
+            known_hosts_file: None,
+        }
+    }
+
+    /// `[user@]host[:port]` as accepted by `ssh -J`
+    pub fn destination(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        let mut destination = match &self.user {
+            Some(user) => format!("{}@{}", user, host),
+            None => host,
+        };
+        if self.port != default_ssh_port() {
+            destination.push_str(&format!(":{}", self.port));
+        }
+        destination
+    }
+
+    /// Whether this hop needs settings `-J` cannot express
+    pub fn needs_config(&self) -> bool {
+        !matches!(self.auth, AuthMethod::Agent) || self.known_hosts_file.is_some()
+    }
+
+    /// Check that the hop can be passed to ssh without being misread
+    pub fn validate(&self) -> Result<()> {
+        match self.problem() {
+            Some(problem) => Err(CoreError::ProfileInvalid(format!("jump host '{}': {}", self.host, problem))),
+            None => Ok(()),
+        }
+    }
+
+    /// What is wrong with the hop, if anything
+    pub(crate) fn problem(&self) -> Option<&'static str> {
+        // Characters with meaning to -J lists, ssh_config or ssh's % expansion
+        let unsafe_char = |c: char| c.is_whitespace() || c.is_control() || "\"',%@/\\[]".contains(c);
+
+        if self.host.is_empty() {
+            return Some("host is required");
+        }
+        if self.host.starts_with('-') || self.host.chars().any(unsafe_char) {
+            return Some("host contains characters ssh would misread");
+        }
+        if let Some(user) = &self.user {
+            if user.is_empty() || user.starts_with('-') || user.chars().any(|c| unsafe_char(c) || c == ':') {
+                return Some("user contains characters ssh would misread");
+            }
+        }
+        if self.port == 0 {
+            return Some("port must not be 0");
+        }
+
+        let path = match &self.auth {
+            AuthMethod::Agent => None,
+            AuthMethod::KeyFile { path } => Some(path),
+            AuthMethod::Password => return Some("password authentication is not supported for jump hosts"),
+        };
+        for path in path.into_iter().chain(&self.known_hosts_file) {
+            if path.is_empty() || path.chars().any(|c| c.is_control() || c == '"') {
+                return Some("file paths must not be empty or contain quotes or control characters");
+            }
+        }
+
+        None
+    }
+}
+
+impl std::fmt::Display for JumpHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.destination())?;
+        if let AuthMethod::KeyFile { path } = &self.auth {
+            write!(f, " (key {})", path)?;
+        }
+        if let Some(known_hosts) = &self.known_hosts_file {
+            write!(f, " (known_hosts {})", known_hosts)?;
+        }
+        Ok(())
+    }
+}
+
+/// An SSH server a profile can connect to: its `host` or a fallback relay
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RelayEndpoint {
+    /// Hostname or IP
+    pub host: String,
+    /// SSH port (default: 22)
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+}
+
+impl RelayEndpoint {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// Check that the endpoint can be passed to ssh as a destination
+    pub fn validate(&self) -> Result<()> {
+        match self.problem() {
+            Some(problem) => Err(CoreError::ProfileInvalid(format!("fallback host '{}': {}", self.host, problem))),
+            None => Ok(()),
+        }
+    }
+
+    /// What is wrong with the endpoint, if anything
+    pub(crate) fn problem(&self) -> Option<&'static str> {
+        if self.host.is_empty() {
+            Some("host is required")
+        } else if self.host.starts_with('-') || self.host.chars().any(|c| c.is_whitespace() || c.is_control() || c == '@') {
+            Some("host contains characters ssh would misread")
+        } else if self.port == 0 {
+            Some("port must not be 0")
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for RelayEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// How the server is picked among a profile's `host` and `fallback_hosts`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FailoverStrategy {
+    /// Try `host` first, then each fallback in order
+    #[default]
+    Ordered,
+    /// Move on to the next server in the list after every disconnect
+    RoundRobin,
+    /// Try servers in order of measured TCP connect time
+    LowestLatency,
+}
+
+impl std::fmt::Display for FailoverStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailoverStrategy::Ordered => write!(f, "ordered"),
+            FailoverStrategy::RoundRobin => write!(f, "round_robin"),
+            FailoverStrategy::LowestLatency => write!(f, "lowest_latency"),
+        }
+    }
+}
+
+/// How reconnection delays are randomized
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterStrategy {
+    /// Plain exponential delays
+    None,
+    /// A random delay between zero and the exponential delay
+    #[default]
+    Full,
+    /// A random delay between `initial_delay_secs` and `multiplier` times the
+    /// previous delay
+    Decorrelated,
+}
+
+impl std::fmt::Display for JitterStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JitterStrategy::None => write!(f, "none"),
+            JitterStrategy::Full => write!(f, "full"),
+            JitterStrategy::Decorrelated => write!(f, "decorrelated"),
+        }
+    }
+}
+
+/// How long a session waits between reconnection attempts, and when it gives up
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnection attempt (default: 1)
+    #[serde(default = "default_initial_delay")]
+    pub initial_delay_secs: u64,
+    /// Upper bound for any delay (default: 300)
+    #[serde(default = "default_max_delay")]
+    pub max_delay_secs: u64,
+    /// Growth of the delay per attempt (default: 2.0)
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    #[serde(default)]
+    pub jitter: JitterStrategy,
+    /// Start over from `initial_delay_secs` once a connection has stayed up
+    /// this long (0 = only after a clean exit)
+    #[serde(default)]
+    pub reset_after_stable_secs: u64,
+    /// Fail the session once it has been reconnecting this long without a
+    /// stable connection (0 = never)
+    #[serde(default)]
+    pub give_up_after_secs: u64,
+}
+
+fn default_initial_delay() -> u64 {
+    1
+}
+
+fn default_max_delay() -> u64 {
+    300
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_secs: default_initial_delay(),
+            max_delay_secs: default_max_delay(),
+            multiplier: default_multiplier(),
+            jitter: JitterStrategy::default(),
+            reset_after_stable_secs: 0,
+            give_up_after_secs: 0,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// What is wrong with the policy, if anything
+    pub(crate) fn problem(&self) -> Option<String> {
+        if self.initial_delay_secs == 0 {
+            Some("reconnect initial_delay_secs must not be 0".to_string())
+        } else if self.max_delay_secs < self.initial_delay_secs {
+            Some(format!(
+                "reconnect max_delay_secs ({}) is less than initial_delay_secs ({})",
+                self.max_delay_secs, self.initial_delay_secs
+            ))
+        } else if !(self.multiplier >= 1.0 && self.multiplier.is_finite()) {
+            Some(format!("reconnect multiplier {} must be at least 1", self.multiplier))
+        } else {
+            None
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self.problem() {
+            Some(problem) => Err(CoreError::ProfileInvalid(problem)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// How a profile's connection is established
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum BackendKind {
+    /// Spawn the system `ssh` binary and follow its output
+    #[default]
+    #[serde(rename = "openssh")]
+    OpenSsh,
+    /// In-process SSH client
+    #[serde(rename = "embedded")]
+    Embedded,
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackendKind::OpenSsh => write!(f, "openssh"),
+            BackendKind::Embedded => write!(f, "embedded"),
+        }
+    }
+}
+
+/// Point in a session's lifecycle where a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPoint {
+    /// Before the session makes its first connection
+    BeforeStart,
+    /// Each time the session connects with all tunnels up
+    OnConnected,
+    /// Each time an established or attempted connection ends
+    OnDisconnected,
+    /// Once, when the session gives up
+    OnFailed,
+}
+
+impl std::fmt::Display for HookPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookPoint::BeforeStart => write!(f, "before_start"),
+            HookPoint::OnConnected => write!(f, "on_connected"),
+            HookPoint::OnDisconnected => write!(f, "on_disconnected"),
+            HookPoint::OnFailed => write!(f, "on_failed"),
+        }
+    }
+}
+
+/// A command run by the supervisor at a hook point
+///
+/// Like ssh's own arguments, the command is an argument array handed to the
+/// program directly; it never goes through a shell.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HookCommand {
+    /// Program followed by its arguments
+    pub command: Vec<String>,
+    /// Seconds the command may run before it is killed (default: 30)
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_timeout() -> u64 {
+    30
+}
+
+impl HookCommand {
+    pub fn new<I, S>(command: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            command: command.into_iter().map(Into::into).collect(),
+            timeout_secs: default_hook_timeout(),
+        }
+    }
+
+    /// Check that the command names a program to run
+    pub fn validate(&self, point: HookPoint) -> Result<()> {
+        match self.problem() {
+            Some(problem) => Err(CoreError::ProfileInvalid(format!("{} hook: {}", point, problem))),
+            None => Ok(()),
+        }
+    }
+
+    /// What is wrong with the hook, if anything
+    pub(crate) fn problem(&self) -> Option<&'static str> {
+        match self.command.first() {
+            Some(program) if !program.trim().is_empty() => {}
+            _ => return Some("command is empty"),
+        }
+        if self.timeout_secs == 0 {
+            return Some("timeout_secs must not be 0");
+        }
+        None
+    }
+}
+
+/// Commands a profile runs as its sessions connect, disconnect and fail
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ProfileHooks {
+    /// Runs before the first connection; if it fails the session does not start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_start: Option<HookCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_connected: Option<HookCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_disconnected: Option<HookCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failed: Option<HookCommand>,
+}
+
+impl ProfileHooks {
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// The command configured for a hook point
+    pub fn get(&self, point: HookPoint) -> Option<&HookCommand> {
+        match point {
+            HookPoint::BeforeStart => self.before_start.as_ref(),
+            HookPoint::OnConnected => self.on_connected.as_ref(),
+            HookPoint::OnDisconnected => self.on_disconnected.as_ref(),
+            HookPoint::OnFailed => self.on_failed.as_ref(),
+        }
+    }
+
+    /// Configured hooks with their points
+    pub fn iter(&self) -> impl Iterator<Item = (HookPoint, &HookCommand)> {
+        [HookPoint::BeforeStart, HookPoint::OnConnected, HookPoint::OnDisconnected, HookPoint::OnFailed]
+            .into_iter()
+            .filter_map(|point| self.get(point).map(|hook| (point, hook)))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.iter().try_for_each(|(point, hook)| hook.validate(point))
+    }
+}
+
 /// Connection profile for a reverse SSH tunnel
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Profile {
@@ -69,6 +870,9 @@ pub struct Profile {
     pub id: Uuid,
     /// Human-readable name
     pub name: String,
+    /// Template or profile this one takes its unset fields from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
     /// SSH server hostname or IP
     pub host: String,
     /// SSH server port (default: 22)
@@ -81,6 +885,21 @@ pub struct Profile {
     pub auth: AuthMethod,
     /// Tunnels to establish
     pub tunnels: Vec<TunnelSpec>,
+    /// Jump hosts to hop through, first hop first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jump_hosts: Vec<JumpHost>,
+    /// Alternate servers used when `host` is unavailable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_hosts: Vec<RelayEndpoint>,
+    /// How the server is picked among `host` and `fallback_hosts` (default: ordered)
+    #[serde(default)]
+    pub failover_strategy: FailoverStrategy,
+    /// Return to `host` once it has been reachable for this many seconds (0 = stay)
+    #[serde(default)]
+    pub fail_back_after_secs: u64,
+    /// Backend that establishes the connection (default: openssh)
+    #[serde(default)]
+    pub backend: BackendKind,
     /// Keep-alive interval in seconds (default: 20)
     #[serde(default = "default_keepalive_interval")]
     pub keepalive_interval: u32,
@@ -93,6 +912,12 @@ pub struct Profile {
     /// Maximum reconnection attempts (0 = unlimited)
     #[serde(default)]
     pub max_reconnect_attempts: u32,
+    /// Reconnection delays; `[ssh.reconnect]` in config.toml when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconnect: Option<ReconnectPolicy>,
+    /// Commands run as sessions connect, disconnect and fail
+    #[serde(default, skip_serializing_if = "ProfileHooks::is_empty")]
+    pub hooks: ProfileHooks,
     /// Additional SSH options
     #[serde(default)]
     pub extra_options: HashMap<String, String>,
@@ -101,9 +926,9 @@ pub struct Profile {
     /// Custom known_hosts file path
     pub known_hosts_file: Option<String>,
     /// Custom identity file path
-    /// Custom identity file path
     pub identity_file: Option<String>,
-    /// Password for password auth (optional, saved in profile)
+    /// Password for password auth, normally a `secret://` reference; plain
+    /// text is moved to the secret store when profiles are loaded
     pub password: Option<String>,
 }
 
@@ -128,15 +953,23 @@ impl Profile {
         Self {
             id: Uuid::new_v4(),
             name: name.into(),
+            extends: None,
             host: host.into(),
             port: default_ssh_port(),
             user: user.into(),
             auth: AuthMethod::default(),
             tunnels: Vec::new(),
+            jump_hosts: Vec::new(),
+            fallback_hosts: Vec::new(),
+            failover_strategy: FailoverStrategy::default(),
+            fail_back_after_secs: 0,
+            backend: BackendKind::default(),
             keepalive_interval: default_keepalive_interval(),
             keepalive_count: default_keepalive_count(),
             auto_reconnect: true,
             max_reconnect_attempts: 0,
+            reconnect: None,
+            hooks: ProfileHooks::default(),
             extra_options: HashMap::new(),
             ssh_path: None,
             known_hosts_file: None,
@@ -155,6 +988,21 @@ impl Profile {
     pub fn destination(&self) -> String {
         format!("{}@{}", self.user, self.host)
     }
+
+    /// The primary server followed by the fallbacks
+    pub fn endpoints(&self) -> Vec<RelayEndpoint> {
+        std::iter::once(RelayEndpoint::new(self.host.clone(), self.port))
+            .chain(self.fallback_hosts.iter().cloned())
+            .collect()
+    }
+
+    /// This profile pointed at another server
+    pub fn with_endpoint(&self, endpoint: &RelayEndpoint) -> Profile {
+        let mut profile = self.clone();
+        profile.host = endpoint.host.clone();
+        profile.port = endpoint.port;
+        profile
+    }
 }
 
 #[cfg(test)]
@@ -164,7 +1012,156 @@ mod tests {
     #[test]
     fn test_tunnel_spec_to_ssh_arg() {
         let tunnel = TunnelSpec::new(8080, 3000);
-        assert_eq!(tunnel.to_ssh_arg(), "localhost:8080:localhost:3000");
+        assert_eq!(tunnel.to_ssh_arg(), "0.0.0.0:8080:0.0.0.0:3000");
+    }
+
+    #[test]
+    fn test_tunnel_kinds_to_ssh_arg() {
+        let local = TunnelSpec::local(5432, "db.internal", 5432);
+        assert_eq!(local.kind.ssh_flag(), "-L");
+        assert_eq!(local.to_ssh_arg(), "0.0.0.0:5432:db.internal:5432");
+
+        let dynamic = TunnelSpec::dynamic(1080);
+        assert_eq!(dynamic.kind.ssh_flag(), "-D");
+        assert_eq!(dynamic.to_ssh_arg(), "0.0.0.0:1080");
+
+        let remote_dynamic = TunnelSpec::remote_dynamic(1080);
+        assert_eq!(remote_dynamic.kind.ssh_flag(), "-R");
+        assert_eq!(remote_dynamic.to_ssh_arg(), "0.0.0.0:1080");
+    }
+
+    #[test]
+    fn test_tunnel_kind_defaults_to_remote() {
+        let tunnel: TunnelSpec = toml::from_str("remote_port = 8080\nlocal_port = 3000").unwrap();
+        assert_eq!(tunnel.kind, TunnelKind::Remote);
+        assert_eq!(tunnel, TunnelSpec::new(8080, 3000));
+
+        let tunnel: TunnelSpec = toml::from_str("kind = \"dynamic\"\nlocal_port = 1080").unwrap();
+        assert_eq!(tunnel, TunnelSpec::dynamic(1080));
+    }
+
+    #[test]
+    fn test_tunnel_requires_remote_port() {
+        let err = toml::from_str::<TunnelSpec>("local_port = 3000").unwrap_err();
+        assert!(err.to_string().contains("remote tunnel is missing remote_port"));
+        let err = toml::from_str::<TunnelSpec>("kind = \"local\"\nlocal_port = 15432\nremote_bind = \"db\"").unwrap_err();
+        assert!(err.to_string().contains("local tunnel is missing remote_port"));
+
+        // Written out, port 0 still asks the server to allocate one
+        let tunnel: TunnelSpec = toml::from_str("remote_port = 0\nlocal_port = 22").unwrap();
+        assert_eq!(tunnel.remote_port, 0);
+
+        // A socket replaces the port
+        let tunnel: TunnelSpec = toml::from_str("remote_socket = \"/run/docker.sock\"\nlocal_socket = \"/var/run/docker.sock\"").unwrap();
+        assert_eq!(tunnel.remote_socket.as_deref(), Some("/run/docker.sock"));
+    }
+
+    #[test]
+    fn test_tunnel_socket_endpoints() {
+        let tunnel = TunnelSpec::new(0, 0)
+            .with_remote_endpoint(TunnelEndpoint::Socket("/run/remote.sock".into()))
+            .with_local_endpoint(TunnelEndpoint::Socket("/var/run/docker.sock".into()));
+        assert_eq!(tunnel.to_ssh_arg(), "/run/remote.sock:/var/run/docker.sock");
+        assert!(tunnel.listens_on_socket());
+        assert!(tunnel.validate().is_ok());
+
+        let tunnel = TunnelSpec::new(2375, 0)
+            .with_local_endpoint(TunnelEndpoint::Socket("/var/run/docker.sock".into()));
+        assert_eq!(tunnel.to_ssh_arg(), "0.0.0.0:2375:/var/run/docker.sock");
+        assert!(!tunnel.listens_on_socket());
+
+        let tunnel = TunnelSpec::local(0, "db.internal", 5432)
+            .with_local_endpoint(TunnelEndpoint::Socket("/tmp/db.sock".into()));
+        assert_eq!(tunnel.to_ssh_arg(), "/tmp/db.sock:db.internal:5432");
+        assert!(tunnel.listens_on_socket());
+    }
+
+    #[test]
+    fn test_tunnel_socket_validation() {
+        let relative = TunnelSpec::new(8080, 0)
+            .with_local_endpoint(TunnelEndpoint::Socket("docker.sock".into()));
+        assert!(relative.validate().is_err());
+
+        let colon = TunnelSpec::new(0, 3000)
+            .with_remote_endpoint(TunnelEndpoint::Socket("/run/a:b.sock".into()));
+        assert!(colon.validate().is_err());
+
+        let socks = TunnelSpec::dynamic(0)
+            .with_local_endpoint(TunnelEndpoint::Socket("/tmp/socks.sock".into()));
+        assert!(socks.validate().is_err());
+
+        assert!(TunnelSpec::new(8080, 3000).validate().is_ok());
+    }
+
+    #[test]
+    fn test_tunnel_health_check() {
+        let tunnel: TunnelSpec = toml::from_str(
+            "remote_port = 8080\nlocal_port = 3000\n[health_check]\nkind = \"http\"\npath = \"/healthz\"\nreconnect_after = 3",
+        )
+        .unwrap();
+        let check = tunnel.health_check.clone().unwrap();
+        assert_eq!(check.kind, HealthCheckKind::Http);
+        assert_eq!((check.interval_secs, check.timeout_secs, check.expected_status), (30, 5, 200));
+        assert_eq!(check.reconnect_after, 3);
+        assert!(tunnel.validate().is_ok());
+
+        let socks = TunnelSpec::dynamic(1080).with_health_check(HealthCheck::new(HealthCheckKind::RemoteTcp));
+        assert!(socks.validate().is_err());
+        let socks = TunnelSpec::dynamic(1080).with_health_check(HealthCheck::new(HealthCheckKind::LocalTcp));
+        assert!(socks.validate().is_ok());
+
+        let mut check = HealthCheck::new(HealthCheckKind::Http);
+        check.path = "healthz".to_string();
+        assert!(TunnelSpec::new(8080, 3000).with_health_check(check).validate().is_err());
+    }
+
+    #[test]
+    fn test_profile_endpoints() {
+        let mut profile = Profile::new("test", "relay1.example.com", "user");
+        profile.fallback_hosts.push(RelayEndpoint::new("2001:db8::1", 2222));
+
+        let endpoints = profile.endpoints();
+        assert_eq!(endpoints[0], RelayEndpoint::new("relay1.example.com", 22));
+        assert_eq!(endpoints[1].to_string(), "[2001:db8::1]:2222");
+
+        let fallback = profile.with_endpoint(&endpoints[1]);
+        assert_eq!((fallback.host.as_str(), fallback.port), ("2001:db8::1", 2222));
+
+        assert!(RelayEndpoint::new("-oProxyCommand=x", 22).validate().is_err());
+        assert!(RelayEndpoint::new("relay2.example.com", 0).validate().is_err());
+    }
+
+    #[test]
+    fn test_profile_hooks() {
+        let profile: Profile = toml::from_str(
+            r#"
+            name = "web"
+            host = "relay.example.com"
+            user = "tunnel"
+            tunnels = []
+
+            [hooks.on_connected]
+            command = ["/usr/local/bin/register-port", "--name", "web"]
+
+            [hooks.on_failed]
+            command = ["systemctl", "restart", "app"]
+            timeout_secs = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            profile.hooks.get(HookPoint::OnConnected),
+            Some(&HookCommand::new(["/usr/local/bin/register-port", "--name", "web"]))
+        );
+        assert_eq!(profile.hooks.on_failed.as_ref().unwrap().timeout_secs, 5);
+        let points: Vec<_> = profile.hooks.iter().map(|(point, _)| point.to_string()).collect();
+        assert_eq!(points, ["on_connected", "on_failed"]);
+        assert!(profile.hooks.validate().is_ok());
+
+        let empty = ProfileHooks { before_start: Some(HookCommand::new(Vec::<String>::new())), ..Default::default() };
+        assert!(empty.validate().is_err());
+        assert!(!toml::to_string(&Profile::new("a", "b", "c")).unwrap().contains("hooks"));
     }
 
     #[test]
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use super::profile::Profile;
+use super::profile::{Profile, RelayEndpoint, TunnelSpec};
 
 /// Current status of an SSH session
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -14,12 +14,16 @@ pub enum SessionStatus {
     Starting,
     /// Session is connected and running
     Connected,
+    /// Session is connected but a tunnel health check is failing
+    Degraded,
     /// Session is reconnecting after a failure
     Reconnecting,
     /// Session has been stopped (intentionally)
     Stopped,
     /// Session has failed and won't retry
     Failed,
+    /// The connection did not become ready within the readiness timeout
+    TimedOut,
 }
 
 impl std::fmt::Display for SessionStatus {
@@ -27,13 +31,85 @@ impl std::fmt::Display for SessionStatus {
         match self {
             SessionStatus::Starting => write!(f, "starting"),
             SessionStatus::Connected => write!(f, "connected"),
+            SessionStatus::Degraded => write!(f, "degraded"),
             SessionStatus::Reconnecting => write!(f, "reconnecting"),
             SessionStatus::Stopped => write!(f, "stopped"),
             SessionStatus::Failed => write!(f, "failed"),
+            SessionStatus::TimedOut => write!(f, "timed_out"),
         }
     }
 }
 
+/// Runtime state of a single tunnel within a session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TunnelState {
+    /// The tunnel as configured in the profile
+    pub spec: TunnelSpec,
+    /// Port the server bound for a `remote_port = 0` forward (if reported)
+    pub allocated_port: Option<u16>,
+    /// Latest health check result, once the tunnel has been probed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<TunnelHealth>,
+    /// Bytes carried on the current connection, for backends that count them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traffic: Option<TunnelTraffic>,
+}
+
+/// Result of a tunnel's health checks on the current connection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TunnelHealth {
+    /// Whether the latest probe succeeded
+    pub healthy: bool,
+    /// Failed probes in a row
+    pub consecutive_failures: u32,
+    pub last_checked: DateTime<Utc>,
+    /// Why the latest probe failed
+    pub last_error: Option<String>,
+}
+
+/// Totals over the channels a tunnel has carried on the current connection
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct TunnelTraffic {
+    /// Bytes sent from this machine to the server
+    pub bytes_sent: u64,
+    /// Bytes received from the server
+    pub bytes_received: u64,
+    /// Channels (forwarded connections) opened
+    pub channels: u64,
+}
+
+impl TunnelState {
+    pub fn new(spec: TunnelSpec) -> Self {
+        Self {
+            spec,
+            allocated_port: None,
+            health: None,
+            traffic: None,
+        }
+    }
+
+    /// Whether the tunnel's latest health check failed
+    pub fn is_unhealthy(&self) -> bool {
+        self.health.as_ref().is_some_and(|health| !health.healthy)
+    }
+
+    /// Whether the server picks the listening port for this tunnel
+    pub fn wants_allocated_port(&self) -> bool {
+        self.spec.kind.listens_remotely()
+            && self.spec.remote_socket.is_none()
+            && self.spec.remote_port == 0
+    }
+
+    /// The tunnel with the allocated port (if any) substituted for port 0
+    pub fn effective_spec(&self) -> TunnelSpec {
+        let mut spec = self.spec.clone();
+        if let Some(port) = self.allocated_port {
+            spec.remote_port = port;
+        }
+        spec
+    }
+}
+
 /// Runtime state of an SSH tunnel session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
@@ -46,7 +122,6 @@ pub struct Session {
     /// Current status
     pub status: SessionStatus,
     /// Process ID of the SSH process (if running)
-    #[serde(skip)]
     pub pid: Option<u32>,
     /// When the session was started
     pub started_at: DateTime<Utc>,
@@ -56,6 +131,12 @@ pub struct Session {
     pub reconnect_count: u32,
     /// Last error message (if any)
     pub last_error: Option<String>,
+    /// Per-tunnel runtime state, in profile order
+    #[serde(default)]
+    pub tunnels: Vec<TunnelState>,
+    /// Server the session is connected or connecting to
+    #[serde(default)]
+    pub active_endpoint: Option<RelayEndpoint>,
 }
 
 impl Session {
@@ -70,6 +151,8 @@ impl Session {
             connected_at: None,
             reconnect_count: 0,
             last_error: None,
+            tunnels: profile.tunnels.iter().cloned().map(TunnelState::new).collect(),
+            active_endpoint: None,
         }
     }
 
@@ -77,13 +160,34 @@ impl Session {
     pub fn is_running(&self) -> bool {
         matches!(
             self.status,
-            SessionStatus::Starting | SessionStatus::Connected | SessionStatus::Reconnecting
+            SessionStatus::Starting | SessionStatus::Connected | SessionStatus::Degraded | SessionStatus::Reconnecting
         )
     }
 
-    /// Check if the session is connected
+    /// Check if the session is connected, healthy or not
     pub fn is_connected(&self) -> bool {
-        self.status == SessionStatus::Connected
+        matches!(self.status, SessionStatus::Connected | SessionStatus::Degraded)
+    }
+
+    /// Forget allocated ports (the next connection may be given different ones)
+    pub fn clear_allocated_ports(&mut self) {
+        for tunnel in &mut self.tunnels {
+            tunnel.allocated_port = None;
+        }
+    }
+
+    /// Forget health check results (they describe the previous connection)
+    pub fn clear_health(&mut self) {
+        for tunnel in &mut self.tunnels {
+            tunnel.health = None;
+        }
+    }
+
+    /// Forget traffic totals (they describe the previous connection)
+    pub fn clear_traffic(&mut self) {
+        for tunnel in &mut self.tunnels {
+            tunnel.traffic = None;
+        }
     }
 
     /// Get the uptime duration if connected
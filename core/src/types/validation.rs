@@ -0,0 +1,396 @@
+//! Checks of a whole profile, reported per field.
+//!
+//! Errors make a profile unusable and stop it from being saved or loaded.
+//! Warnings point at settings that are likely to fail once the session
+//! starts, such as a missing key file, but may be fine where it runs.
+
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::config::SECRET_PREFIX;
+use crate::error::{CoreError, Result};
+use crate::ssh::validate_args;
+
+use super::profile::{AuthMethod, BackendKind, Profile, TunnelEndpoint, TunnelKind, TunnelSpec};
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// One finding about a profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Path of the field, e.g. `tunnels[0].remote_port`
+    pub field: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, field: field.into(), message: message.into() }
+    }
+
+    pub fn warning(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, field: field.into(), message: message.into() }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+impl Profile {
+    /// Everything wrong with the profile on its own, errors first
+    ///
+    /// Fields holding `${VAR}` or `secret://` references are checked once
+    /// they are resolved, when a session starts.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut found = Vec::new();
+
+        if self.name.trim().is_empty() {
+            found.push(Diagnostic::error("name", "is required"));
+        }
+        if let Some(problem) = host_problem(&self.host) {
+            found.push(Diagnostic::error("host", problem));
+        }
+        if self.port == 0 {
+            found.push(Diagnostic::error("port", "must not be 0"));
+        }
+        if self.user.is_empty() {
+            found.push(Diagnostic::error("user", "is required"));
+        } else if !has_reference(&self.user) && misread_by_ssh(&self.user) {
+            found.push(Diagnostic::error("user", format!("'{}' contains characters ssh would misread", self.user)));
+        }
+
+        if let AuthMethod::KeyFile { path } = &self.auth {
+            key_file_problems(&mut found, "auth.path", path);
+        }
+        if let Some(path) = &self.identity_file {
+            key_file_problems(&mut found, "identity_file", path);
+        }
+
+        if self.tunnels.is_empty() {
+            found.push(Diagnostic::warning("tunnels", "no tunnels; sessions only keep a connection open"));
+        }
+        for (index, tunnel) in self.tunnels.iter().enumerate() {
+            self.tunnel_problems(&mut found, index, tunnel);
+        }
+
+        for (index, hop) in self.jump_hosts.iter().enumerate() {
+            if let Some(problem) = hop.problem() {
+                found.push(Diagnostic::error(format!("jump_hosts[{}]", index), problem));
+            } else if let AuthMethod::KeyFile { path } = &hop.auth {
+                key_file_problems(&mut found, &format!("jump_hosts[{}].auth.path", index), path);
+            }
+        }
+        for (index, endpoint) in self.fallback_hosts.iter().enumerate() {
+            if let Some(problem) = endpoint.problem() {
+                found.push(Diagnostic::error(format!("fallback_hosts[{}]", index), problem));
+            }
+        }
+        if let Some(problem) = self.reconnect.as_ref().and_then(|policy| policy.problem()) {
+            found.push(Diagnostic::error("reconnect", problem));
+        }
+        for (point, hook) in self.hooks.iter() {
+            if let Some(problem) = hook.problem() {
+                found.push(Diagnostic::error(format!("hooks.{}", point), problem));
+            }
+        }
+
+        if self.backend == BackendKind::Embedded && !self.extra_options.is_empty() {
+            found.push(Diagnostic::warning("extra_options", "the embedded backend does not use ssh options"));
+        }
+        let mut options: Vec<_> = self.extra_options.iter().collect();
+        options.sort();
+        for (key, value) in options {
+            let field = format!("extra_options.{}", key);
+            if key.is_empty() || key.contains('=') || key.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                found.push(Diagnostic::error(field, "option names must not be empty or contain spaces or '='"));
+            } else if let Err(problem) = validate_args(&[format!("{}={}", key, value)]) {
+                found.push(Diagnostic::error(field, problem));
+            }
+        }
+
+        // Stable: fields keep their order within each severity
+        found.sort_by_key(|diagnostic| diagnostic.severity);
+        found
+    }
+
+    /// [`validate`](Self::validate), plus remote ports this profile shares
+    /// with others connecting to the same server
+    pub fn validate_among(&self, others: &[Profile]) -> Vec<Diagnostic> {
+        let mut found = self.validate();
+
+        let servers = self.endpoints();
+        for (index, tunnel) in self.tunnels.iter().enumerate() {
+            let Some((true, bind, port)) = listener(tunnel) else { continue };
+            for other in others {
+                if other.id == self.id || other.name == self.name {
+                    continue;
+                }
+                let Some(server) = other.endpoints().into_iter().find(|endpoint| {
+                    servers.iter().any(|own| own.host.eq_ignore_ascii_case(&endpoint.host) && own.port == endpoint.port)
+                }) else {
+                    continue;
+                };
+                let clash = other
+                    .tunnels
+                    .iter()
+                    .filter_map(listener)
+                    .any(|(remote, other_bind, other_port)| remote && other_port == port && same_bind(&bind, &other_bind));
+                if clash {
+                    found.push(Diagnostic::warning(
+                        format!("tunnels[{}].remote_port", index),
+                        format!(
+                            "profile '{}' also listens on port {} of {}; only one of them can be connected at a time",
+                            other.name, port, server.host
+                        ),
+                    ));
+                }
+            }
+        }
+        found
+    }
+
+    /// Fail with `ProfileInvalid` listing the errors [`validate`](Self::validate) finds
+    pub fn ensure_valid(&self) -> Result<()> {
+        let errors: Vec<String> =
+            self.validate().iter().filter(|d| d.is_error()).map(ToString::to_string).collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::ProfileInvalid(errors.join("; ")))
+        }
+    }
+
+    fn tunnel_problems(&self, found: &mut Vec<Diagnostic>, index: usize, tunnel: &TunnelSpec) {
+        let field = |name: &str| format!("tunnels[{}].{}", index, name);
+
+        if let Some(problem) = tunnel.problem() {
+            found.push(Diagnostic::error(format!("tunnels[{}]", index), problem));
+            return;
+        }
+
+        // The side connections are forwarded to needs a real port
+        match tunnel.kind {
+            TunnelKind::Remote if tunnel.local_socket.is_none() && tunnel.local_port == 0 => {
+                found.push(Diagnostic::error(field("local_port"), "the port connections go to must not be 0"));
+            }
+            TunnelKind::Local if tunnel.remote_socket.is_none() && tunnel.remote_port == 0 => {
+                found.push(Diagnostic::error(field("remote_port"), "the port connections go to must not be 0"));
+            }
+            _ => {}
+        }
+
+        for (earlier, other) in self.tunnels[..index].iter().enumerate() {
+            if let (Some(own), Some(theirs)) = (listener(tunnel), listener(other)) {
+                if own.0 == theirs.0 && own.2 == theirs.2 && same_bind(&own.1, &theirs.1) {
+                    let name = if own.0 { "remote_port" } else { "local_port" };
+                    found.push(Diagnostic::error(
+                        field(name),
+                        format!("tunnels[{}] already listens on port {}", earlier, own.2),
+                    ));
+                }
+            }
+        }
+
+        if tunnel.remote_socket.is_none() && tunnel.remote_bind.contains("://") {
+            if let TunnelEndpoint::Tcp { host, .. } = tunnel.remote_endpoint() {
+                found.push(Diagnostic::warning(
+                    field("remote_bind"),
+                    format!("'{}' is a URL; ssh is given '{}'", tunnel.remote_bind, host),
+                ));
+            }
+        }
+
+        if let Some((true, _, port @ 1..=1023)) = listener(tunnel) {
+            if self.user != "root" && !has_reference(&self.user) {
+                found.push(Diagnostic::warning(
+                    field("remote_port"),
+                    format!("only root may listen on port {} of the server, and this profile logs in as '{}'", port, self.user),
+                ));
+            }
+        }
+    }
+}
+
+/// Where a tunnel listens on TCP: whether it is on the server, the bind
+/// address and the port (none for sockets and allocated ports)
+fn listener(tunnel: &TunnelSpec) -> Option<(bool, String, u16)> {
+    let remote = tunnel.kind.listens_remotely();
+    let endpoint = if remote { tunnel.remote_endpoint() } else { tunnel.local_endpoint() };
+    match endpoint {
+        TunnelEndpoint::Tcp { host, port } if port != 0 => Some((remote, host, port)),
+        _ => None,
+    }
+}
+
+/// Whether two bind addresses can collide on the same port
+fn same_bind(a: &str, b: &str) -> bool {
+    let wildcard = |host: &str| matches!(host, "" | "*" | "0.0.0.0" | "::");
+    wildcard(a) || wildcard(b) || a.eq_ignore_ascii_case(b)
+}
+
+fn has_reference(value: &str) -> bool {
+    value.contains("${") || value.starts_with(SECRET_PREFIX)
+}
+
+fn misread_by_ssh(value: &str) -> bool {
+    value.starts_with('-') || value.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '@' | '\'' | '"'))
+}
+
+/// What is wrong with the server's host name or address, if anything
+fn host_problem(host: &str) -> Option<String> {
+    if host.is_empty() {
+        return Some("is required".to_string());
+    }
+    if has_reference(host) || host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    if host.contains("://") {
+        return Some(format!("'{}' is a URL; give only the host name", host));
+    }
+    if misread_by_ssh(host) || host.contains('/') {
+        return Some(format!("'{}' contains characters ssh would misread", host));
+    }
+
+    let label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if host.len() > 253 || !host.strip_suffix('.').unwrap_or(host).split('.').all(label) {
+        return Some(format!("'{}' is not a valid host name or address", host));
+    }
+    None
+}
+
+fn key_file_problems(found: &mut Vec<Diagnostic>, field: &str, path: &str) {
+    if path.is_empty() {
+        found.push(Diagnostic::error(field, "a key file path is required"));
+    } else if !has_reference(path) && !expand_home(path).exists() {
+        found.push(Diagnostic::warning(field, format!("key file '{}' does not exist", path)));
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), directories::BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HookCommand, RelayEndpoint};
+
+    fn fields(diagnostics: &[Diagnostic], severity: Severity) -> Vec<&str> {
+        diagnostics.iter().filter(|d| d.severity == severity).map(|d| d.field.as_str()).collect()
+    }
+
+    #[test]
+    fn test_validate_profile() {
+        let profile = Profile::new("web", "relay.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 3000));
+        assert!(profile.validate().is_empty());
+        assert!(profile.ensure_valid().is_ok());
+
+        let mut broken = Profile::new("web", "https://relay.example.com", "-oProxyCommand=x");
+        broken.port = 0;
+        broken.tunnels = vec![
+            TunnelSpec::new(80, 3000),
+            TunnelSpec::new(80, 0),
+            TunnelSpec { remote_bind: "http://0.0.0.0/".to_string(), ..TunnelSpec::new(9000, 3000) },
+        ];
+        broken.fallback_hosts.push(RelayEndpoint::new("relay2.example.com", 0));
+        broken.hooks.on_failed = Some(HookCommand::new([""]));
+        broken.auth = AuthMethod::KeyFile { path: "/nonexistent/id_ed25519".to_string() };
+        broken.extra_options.insert("LocalCommand".to_string(), "touch /tmp/x".to_string());
+
+        let found = broken.validate();
+        assert_eq!(
+            fields(&found, Severity::Error),
+            [
+                "host",
+                "port",
+                "user",
+                "tunnels[1].local_port",
+                "tunnels[1].remote_port",
+                "fallback_hosts[0]",
+                "hooks.on_failed",
+                "extra_options.LocalCommand"
+            ]
+        );
+        assert_eq!(
+            fields(&found, Severity::Warning),
+            ["auth.path", "tunnels[0].remote_port", "tunnels[1].remote_port", "tunnels[2].remote_bind"]
+        );
+        assert_eq!(found[3].to_string(), "tunnels[1].local_port: the port connections go to must not be 0");
+        assert_eq!(found[4].message, "tunnels[0] already listens on port 80");
+
+        let error = broken.ensure_valid().unwrap_err().to_string();
+        assert!(error.starts_with("Invalid profile: host: 'https://relay.example.com' is a URL"), "{}", error);
+        assert!(!error.contains("auth.path"));
+
+        // References are checked once resolved
+        let templated = Profile::new("web", "${RELAY_HOST}", "${RELAY_USER}").with_tunnel(TunnelSpec::new(80, 3000));
+        assert!(templated.validate().is_empty());
+        assert!(host_problem("2001:db8::1").is_none());
+        assert!(host_problem("relay_1.example.com.").is_none());
+        assert!(host_problem("relay..example.com").is_some());
+
+        let mut embedded = Profile::new("web", "relay.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 3000));
+        embedded.backend = BackendKind::Embedded;
+        embedded.extra_options.insert("Compression".to_string(), "yes".to_string());
+        assert_eq!(fields(&embedded.validate(), Severity::Warning), ["extra_options"]);
+    }
+
+    #[test]
+    fn test_validate_among() {
+        let profile = Profile::new("web", "relay.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 3000));
+        let mut same_relay = Profile::new("api", "RELAY.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 4000));
+        let mut fallback = Profile::new("db", "other.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 5432));
+        fallback.fallback_hosts.push(RelayEndpoint::new("relay.example.com", 22));
+        let elsewhere = Profile::new("ci", "ci.example.com", "tunnel").with_tunnel(TunnelSpec::new(8080, 8080));
+
+        let found = profile.validate_among(&[profile.clone(), same_relay.clone(), fallback, elsewhere]);
+        let messages: Vec<_> = found.iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].starts_with("tunnels[0].remote_port: profile 'api' also listens on port 8080 of RELAY.example.com"));
+        assert!(messages[1].contains("profile 'db'"));
+
+        // Different bind addresses on the server do not collide
+        same_relay.tunnels[0].remote_bind = "10.0.0.1".to_string();
+        let mut bound = profile.clone();
+        bound.tunnels[0].remote_bind = "10.0.0.2".to_string();
+        assert!(bound.validate_among(&[same_relay]).is_empty());
+    }
+}
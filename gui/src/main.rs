@@ -15,9 +15,9 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use reverse_ssh_core::{
-    config::{load_config, profiles_dir, load_profiles, save_profile, update_profile as core_update_profile, delete_profile as core_delete_profile},
+    config::{load_config, profiles_dir, load_profiles, load_raw_profile, save_profile, update_profile as core_update_profile, delete_profile as core_delete_profile},
     supervisor::{SessionManager, SessionManagerHandle, StartSessionOptions},
-    types::{Profile, TunnelSpec, AuthMethod, Session, Event},
+    types::{BackendKind, FailoverStrategy, HealthCheck, JumpHost, Profile, ProfileHooks, RelayEndpoint, TunnelKind, TunnelSpec, AuthMethod, Session, SessionStatus, Event},
     error::CoreError,
 };
 
@@ -38,6 +38,14 @@ struct SessionInfo {
     started_at: String,
     pid: Option<u32>,
     reconnect_count: u32,
+    tunnels: Vec<SessionTunnelInfo>,
+    server: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SessionTunnelInfo {
+    forward: String,
+    allocated_port: Option<u16>,
 }
 
 impl From<&Session> for SessionInfo {
@@ -49,6 +57,11 @@ impl From<&Session> for SessionInfo {
             started_at: session.started_at.to_rfc3339(),
             pid: session.pid,
             reconnect_count: session.reconnect_count,
+            tunnels: session.tunnels.iter().map(|t| SessionTunnelInfo {
+                forward: t.effective_spec().to_string(),
+                allocated_port: t.allocated_port,
+            }).collect(),
+            server: session.active_endpoint.as_ref().map(ToString::to_string),
         }
     }
 }
@@ -61,21 +74,84 @@ impl From<&Session> for SessionInfo {
 pub struct ProfileInfo {
     pub id: String,
     pub name: String,
+    /// Template or profile the other fields were resolved from
+    #[serde(default)]
+    pub extends: Option<String>,
     pub host: String,
     pub port: u16,
     pub user: String,
     pub auth: String,
     pub tunnels: Vec<TunnelInfo>,
+    pub jump_hosts: Vec<JumpInfo>,
+    pub fallback_hosts: Vec<RelayEndpoint>,
+    pub failover_strategy: FailoverStrategy,
+    pub fail_back_after_secs: u64,
     pub auto_reconnect: bool,
     pub keepalive_interval: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TunnelInfo {
+    #[serde(default)]
+    pub kind: TunnelKind,
     pub remote_bind: String,
     pub remote_port: u16,
     pub local_host: String,
     pub local_port: u16,
+    #[serde(default)]
+    pub remote_socket: Option<String>,
+    #[serde(default)]
+    pub local_socket: Option<String>,
+    /// Kept as configured; the GUI does not edit health checks
+    #[serde(default)]
+    pub health_check: Option<HealthCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JumpInfo {
+    pub host: String,
+    #[serde(default = "default_jump_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub key_path: Option<String>,
+    #[serde(default)]
+    pub known_hosts_file: Option<String>,
+}
+
+fn default_jump_port() -> u16 {
+    22
+}
+
+impl From<&JumpHost> for JumpInfo {
+    fn from(hop: &JumpHost) -> Self {
+        Self {
+            host: hop.host.clone(),
+            port: hop.port,
+            user: hop.user.clone(),
+            key_path: match &hop.auth {
+                AuthMethod::KeyFile { path } => Some(path.clone()),
+                _ => None,
+            },
+            known_hosts_file: hop.known_hosts_file.clone(),
+        }
+    }
+}
+
+impl From<&JumpInfo> for JumpHost {
+    fn from(info: &JumpInfo) -> Self {
+        Self {
+            host: info.host.clone(),
+            port: info.port,
+            user: info.user.clone().filter(|u| !u.is_empty()),
+            auth: match info.key_path.as_deref().filter(|p| !p.trim().is_empty()) {
+                Some(path) => AuthMethod::KeyFile { path: path.to_string() },
+                None => AuthMethod::Agent,
+            },
+            known_hosts_file: info.known_hosts_file.clone().filter(|p| !p.trim().is_empty()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -87,6 +163,14 @@ pub struct CreateProfileRequest {
     pub auth: Option<String>,
     pub key_path: Option<String>,
     pub tunnels: Vec<TunnelInfo>,
+    #[serde(default)]
+    pub jump_hosts: Vec<JumpInfo>,
+    #[serde(default)]
+    pub fallback_hosts: Vec<RelayEndpoint>,
+    #[serde(default)]
+    pub failover_strategy: FailoverStrategy,
+    #[serde(default)]
+    pub fail_back_after_secs: u64,
     pub auto_reconnect: Option<bool>,
 }
 
@@ -100,6 +184,14 @@ pub struct UpdateProfileRequest {
     pub auth: Option<String>,
     pub key_path: Option<String>,
     pub tunnels: Vec<TunnelInfo>,
+    #[serde(default)]
+    pub jump_hosts: Vec<JumpInfo>,
+    #[serde(default)]
+    pub fallback_hosts: Vec<RelayEndpoint>,
+    #[serde(default)]
+    pub failover_strategy: FailoverStrategy,
+    #[serde(default)]
+    pub fail_back_after_secs: u64,
     pub auto_reconnect: Option<bool>,
 }
 
@@ -108,6 +200,7 @@ impl From<&Profile> for ProfileInfo {
         Self {
             id: profile.id.to_string(),
             name: profile.name.clone(),
+            extends: profile.extends.clone(),
             host: profile.host.clone(),
             port: profile.port,
             user: profile.user.clone(),
@@ -117,11 +210,19 @@ impl From<&Profile> for ProfileInfo {
                 AuthMethod::Password => "password".to_string(),
             },
             tunnels: profile.tunnels.iter().map(|t| TunnelInfo {
+                kind: t.kind,
                 remote_bind: t.remote_bind.clone(),
                 remote_port: t.remote_port,
                 local_host: t.local_host.clone(),
                 local_port: t.local_port,
+                remote_socket: t.remote_socket.clone(),
+                local_socket: t.local_socket.clone(),
+                health_check: t.health_check.clone(),
             }).collect(),
+            jump_hosts: profile.jump_hosts.iter().map(JumpInfo::from).collect(),
+            fallback_hosts: profile.fallback_hosts.clone(),
+            failover_strategy: profile.failover_strategy,
+            fail_back_after_secs: profile.fail_back_after_secs,
             auto_reconnect: profile.auto_reconnect,
             keepalive_interval: profile.keepalive_interval,
         }
@@ -158,6 +259,14 @@ async fn get_profile(name: String) -> Result<ProfileInfo, String> {
     Ok(ProfileInfo::from(&profile))
 }
 
+/// Get the settings in a profile's own file, without those it inherits
+#[tauri::command]
+async fn get_profile_raw(name: String) -> Result<serde_json::Value, String> {
+    let raw = load_raw_profile(&name)
+        .map_err(|e: CoreError| e.to_string())?;
+    serde_json::to_value(raw).map_err(|e| e.to_string())
+}
+
 /// Create a new profile
 #[tauri::command]
 async fn create_profile(request: CreateProfileRequest) -> Result<ProfileInfo, String> {
@@ -177,29 +286,42 @@ async fn create_profile(request: CreateProfileRequest) -> Result<ProfileInfo, St
 
     let tunnels: Vec<TunnelSpec> = request.tunnels.iter().map(|t| {
         TunnelSpec {
+            kind: t.kind,
             remote_bind: t.remote_bind.clone(),
             remote_port: t.remote_port,
             local_host: t.local_host.clone(),
             local_port: t.local_port,
+            remote_socket: t.remote_socket.clone(),
+            local_socket: t.local_socket.clone(),
+            health_check: t.health_check.clone(),
         }
     }).collect();
 
     let profile = Profile {
         id: Uuid::new_v4(),
         name: request.name.clone(),
+        extends: None,
         host: request.host,
         port: request.port.unwrap_or(22),
         user: request.user,
         auth,
         tunnels,
+        jump_hosts: request.jump_hosts.iter().map(JumpHost::from).collect(),
+        fallback_hosts: request.fallback_hosts,
+        failover_strategy: request.failover_strategy,
+        fail_back_after_secs: request.fail_back_after_secs,
+        backend: BackendKind::default(),
         keepalive_interval: 20,
         keepalive_count: 3,
         auto_reconnect: request.auto_reconnect.unwrap_or(true),
         max_reconnect_attempts: 0,
+        reconnect: None,
+        hooks: ProfileHooks::default(),
         extra_options: HashMap::new(),
         ssh_path: None,
         known_hosts_file: None,
         identity_file: None,
+        password: None,
     };
 
     save_profile(&profile)
@@ -243,10 +365,14 @@ async fn update_profile(request: UpdateProfileRequest) -> Result<ProfileInfo, St
         .tunnels
         .iter()
         .map(|t| TunnelSpec {
+            kind: t.kind,
             remote_bind: t.remote_bind.clone(),
             remote_port: t.remote_port,
             local_host: t.local_host.clone(),
             local_port: t.local_port,
+            remote_socket: t.remote_socket.clone(),
+            local_socket: t.local_socket.clone(),
+            health_check: t.health_check.clone(),
         })
         .collect();
 
@@ -256,6 +382,10 @@ async fn update_profile(request: UpdateProfileRequest) -> Result<ProfileInfo, St
     profile.user = request.user;
     profile.auth = auth;
     profile.tunnels = tunnels;
+    profile.jump_hosts = request.jump_hosts.iter().map(JumpHost::from).collect();
+    profile.fallback_hosts = request.fallback_hosts;
+    profile.failover_strategy = request.failover_strategy;
+    profile.fail_back_after_secs = request.fail_back_after_secs;
     if let Some(auto_reconnect) = request.auto_reconnect {
         profile.auto_reconnect = auto_reconnect;
     }
@@ -304,6 +434,11 @@ async fn start_session(
         if trimmed.is_empty() { None } else { Some(trimmed) }
     });
 
+    let tunnels = profile.tunnels.iter().map(|t| SessionTunnelInfo {
+        forward: t.to_string(),
+        allocated_port: None,
+    }).collect();
+
     let session_id = handle
         .start_with_options(profile, StartSessionOptions { password, sshpass_path })
         .await
@@ -316,6 +451,7 @@ async fn start_session(
         started_at: chrono::Utc::now().to_rfc3339(),
         pid: None,
         reconnect_count: 0,
+        tunnels,
     };
 
     // Store session
@@ -453,6 +589,26 @@ async fn setup_event_listener(
                         }
                         let _ = app_handle.emit("session-failed", event_data);
                     }
+                    Event::SessionStatusChanged { session_id, new_status: SessionStatus::TimedOut, .. } => {
+                        if let Some(session) = state.sessions.write().await.get_mut(session_id) {
+                            session.status = "TimedOut".to_string();
+                        }
+                        let _ = app_handle.emit("session-timed-out", event_data);
+                    }
+                    Event::TunnelPortAllocated { session_id, tunnel_index, allocated_port, .. } => {
+                        if let Some(session) = state.sessions.write().await.get_mut(session_id) {
+                            if let Some(tunnel) = session.tunnels.get_mut(*tunnel_index) {
+                                tunnel.allocated_port = Some(*allocated_port);
+                            }
+                        }
+                        let _ = app_handle.emit("tunnel-port-allocated", event_data);
+                    }
+                    Event::SessionEndpointChanged { session_id, endpoint, .. } => {
+                        if let Some(session) = state.sessions.write().await.get_mut(session_id) {
+                            session.server = Some(endpoint.to_string());
+                        }
+                        let _ = app_handle.emit("session-endpoint-changed", event_data);
+                    }
                     Event::SessionOutput { .. } => {
                         let _ = app_handle.emit("session-output", event_data);
                     }
@@ -583,6 +739,7 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             get_profiles,
             get_profile,
+            get_profile_raw,
             create_profile,
             update_profile,
             delete_profile,
@@ -112,6 +112,27 @@ function setupEventListeners() {
         loadSessions();
     });
 
+    listen('session-timed-out', (event) => {
+        addLog('warning', `Session timed out waiting for its tunnels to become ready`);
+        loadSessions();
+    });
+
+    listen('session-endpoint-changed', (event) => {
+        const data = event.payload;
+        if (data.previous) {
+            addLog('warning', `Switching server ${formatEndpoint(data.previous)} → ${formatEndpoint(data.endpoint)} (${data.reason.replace('_', '-')})`);
+        } else {
+            addLog('info', `Connecting to ${formatEndpoint(data.endpoint)}`);
+        }
+        loadSessions();
+    });
+
+    listen('tunnel-port-allocated', (event) => {
+        const data = event.payload;
+        addLog('info', `Server allocated port ${data.allocated_port} for tunnel ${data.tunnel_index + 1}`);
+        loadSessions();
+    });
+
     listen('session-stopped', (event) => {
         addLog('info', `Session stopped: ${event.payload}`);
         loadSessions();
@@ -236,8 +257,21 @@ async function showProfileDetail(name) {
 
         document.getElementById('profileDetailTitle').textContent = profile.name;
 
+        // Fields below are resolved; list the ones this profile sets itself
+        let overrides = [];
+        if (profile.extends) {
+            const raw = await invoke('get_profile_raw', { name });
+            overrides = Object.keys(raw).filter(key => !['id', 'name', 'extends'].includes(key));
+        }
+
         const body = document.getElementById('profileDetailBody');
         body.innerHTML = `
+            ${profile.extends ? `
+                <div class="profile-detail">
+                    <span class="profile-detail-label">Extends</span>
+                    <span class="profile-detail-value">${escapeHtml(profile.extends)}${overrides.length ? ` (overrides ${escapeHtml(overrides.join(', '))})` : ''}</span>
+                </div>
+            ` : ''}
             <div class="profile-detail">
                 <span class="profile-detail-label">Host</span>
                 <span class="profile-detail-value">${escapeHtml(profile.host)}:${profile.port}</span>
@@ -258,11 +292,27 @@ async function showProfileDetail(name) {
                 <span class="profile-detail-label">Keep-alive</span>
                 <span class="profile-detail-value">${profile.keepalive_interval}s</span>
             </div>
+            ${(profile.jump_hosts || []).length ? `
+                <div class="profile-detail">
+                    <span class="profile-detail-label">Jump Hosts</span>
+                    <span class="profile-detail-value">${escapeHtml(profile.jump_hosts.map(formatJumpHost).join(' → '))}</span>
+                </div>
+            ` : ''}
+            ${(profile.fallback_hosts || []).length ? `
+                <div class="profile-detail">
+                    <span class="profile-detail-label">Fallback Servers</span>
+                    <span class="profile-detail-value">${escapeHtml(profile.fallback_hosts.map(formatEndpoint).join(', '))}</span>
+                </div>
+                <div class="profile-detail">
+                    <span class="profile-detail-label">Failover</span>
+                    <span class="profile-detail-value">${FAILOVER_LABELS[profile.failover_strategy] || profile.failover_strategy}${profile.fail_back_after_secs ? `, fail back after ${profile.fail_back_after_secs}s` : ''}</span>
+                </div>
+            ` : ''}
             <h3 style="margin-top: 16px; margin-bottom: 8px;">Tunnels</h3>
             ${profile.tunnels.map(t => `
                 <div class="profile-detail">
-                    <span class="profile-detail-label">${t.remote_bind}:${t.remote_port}</span>
-                    <span class="profile-detail-value">→ ${t.local_host}:${t.local_port}</span>
+                    <span class="profile-detail-label">${TUNNEL_KIND_LABELS[t.kind] || TUNNEL_KIND_LABELS.remote}</span>
+                    <span class="profile-detail-value">${escapeHtml(formatTunnel(t))}</span>
                 </div>
             `).join('')}
         `;
@@ -389,19 +439,51 @@ function toggleEditKeyPath() {
     toggleAuthFieldsFor('editProfileAuth', 'editKeyPathGroup', 'editPasswordGroup');
 }
 
+const TUNNEL_KIND_LABELS = {
+    remote: 'Remote (-R)',
+    local: 'Local (-L)',
+    dynamic: 'SOCKS (-D)',
+    remote_dynamic: 'Remote SOCKS (-R)',
+};
+
+function formatTunnel(t) {
+    const remote = t.remote_socket || `${t.remote_bind}:${t.remote_port}`;
+    const local = t.local_socket || `${t.local_host}:${t.local_port}`;
+    switch (t.kind) {
+        case 'local':
+            return `${local} → ${remote}`;
+        case 'dynamic':
+            return `SOCKS on ${local}`;
+        case 'remote_dynamic':
+            return `SOCKS on remote ${remote}`;
+        default:
+            return `${remote} → ${local}`;
+    }
+}
+
+function tunnelKindOptionsHtml(selected) {
+    return Object.entries(TUNNEL_KIND_LABELS)
+        .map(([value, label]) => `<option value="${value}"${value === selected ? ' selected' : ''}>${label}</option>`)
+        .join('');
+}
+
 function tunnelRowHtml(tunnel, removeHandlerName) {
-    const remoteBind = tunnel?.remote_bind ?? '0.0.0.0';
-    const remotePort = tunnel?.remote_port ?? '';
-    const localHost = tunnel?.local_host ?? 'localhost';
-    const localPort = tunnel?.local_port ?? '';
+    const kind = tunnel?.kind ?? 'remote';
+    const remoteBind = tunnel?.remote_socket ?? tunnel?.remote_bind ?? '0.0.0.0';
+    const remotePort = tunnel?.remote_port || '';
+    const localHost = tunnel?.local_socket ?? tunnel?.local_host ?? 'localhost';
+    const localPort = tunnel?.local_port || '';
+    const healthCheck = JSON.stringify(tunnel?.health_check ?? null);
 
     return `
-        <input type="text" class="tunnel-remote-bind" placeholder="Remote Bind (0.0.0.0)" value="${escapeAttribute(remoteBind)}">
-        <input type="number" class="tunnel-remote" placeholder="Remote Port" min="1" max="65535" value="${remotePort}">
+        <select class="tunnel-kind" onchange="updateTunnelRow(this.parentElement)">${tunnelKindOptionsHtml(kind)}</select>
+        <input type="text" class="tunnel-remote-bind" placeholder="Remote Bind (0.0.0.0)" title="Host, or an absolute Unix socket path" value="${escapeAttribute(remoteBind)}">
+        <input type="number" class="tunnel-remote" placeholder="Remote Port (0 = any)" min="0" max="65535" value="${remotePort}">
         <span class="tunnel-arrow">→</span>
-        <input type="text" class="tunnel-local-host" placeholder="localhost" value="${escapeAttribute(localHost)}">
+        <input type="text" class="tunnel-local-host" placeholder="localhost" title="Host, or an absolute Unix socket path" value="${escapeAttribute(localHost)}">
         <span>:</span>
         <input type="number" class="tunnel-local-port" placeholder="Local Port" min="1" max="65535" value="${localPort}">
+        <input type="hidden" class="tunnel-health-check" value="${escapeAttribute(healthCheck)}">
         <button type="button" class="btn btn-ghost btn-sm" onclick="${removeHandlerName}(this)">×</button>
     `;
 }
@@ -411,9 +493,27 @@ function addTunnelRowTo(editorId, removeHandlerName, tunnel) {
     const row = document.createElement('div');
     row.className = 'tunnel-row';
     row.innerHTML = tunnelRowHtml(tunnel, removeHandlerName);
+    updateTunnelRow(row);
     editor.appendChild(row);
 }
 
+// Show only the endpoints a tunnel kind uses and point the arrow at the target
+function updateTunnelRow(row) {
+    const kind = row.querySelector('.tunnel-kind').value;
+    const usesRemote = kind !== 'dynamic';
+    const usesLocal = kind !== 'remote_dynamic';
+    for (const cls of ['.tunnel-remote-bind', '.tunnel-remote']) {
+        row.querySelector(cls).style.display = usesRemote ? '' : 'none';
+    }
+    for (const cls of ['.tunnel-local-host', '.tunnel-local-port']) {
+        row.querySelector(cls).style.display = usesLocal ? '' : 'none';
+    }
+    row.querySelectorAll('span').forEach(span => {
+        span.style.display = usesRemote && usesLocal ? '' : 'none';
+    });
+    row.querySelector('.tunnel-arrow').textContent = kind === 'local' ? '←' : '→';
+}
+
 function setTunnelsEditor(editorId, tunnels, removeHandlerName) {
     const editor = document.getElementById(editorId);
     editor.innerHTML = '';
@@ -454,17 +554,30 @@ function readTunnelsFrom(editorId) {
     const tunnels = [];
 
     for (const row of tunnelRows) {
+        const kind = row.querySelector('.tunnel-kind')?.value || 'remote';
         const remoteBind = (row.querySelector('.tunnel-remote-bind')?.value || '0.0.0.0').trim() || '0.0.0.0';
         const remotePort = row.querySelector('.tunnel-remote')?.value;
         const localHost = (row.querySelector('.tunnel-local-host')?.value || 'localhost').trim() || 'localhost';
         const localPort = row.querySelector('.tunnel-local-port')?.value;
+        const healthCheck = JSON.parse(row.querySelector('.tunnel-health-check')?.value || 'null');
 
-        if (remotePort && localPort) {
+        // A path in the host field makes that side a Unix socket
+        const remoteSocket = remoteBind.startsWith('/') ? remoteBind : null;
+        const localSocket = localHost.startsWith('/') ? localHost : null;
+        const complete = kind === 'dynamic' ? localPort
+            : kind === 'remote_dynamic' ? remotePort
+            : (remotePort || remoteSocket) && (localPort || localSocket);
+
+        if (complete) {
             tunnels.push({
-                remote_bind: remoteBind,
-                remote_port: parseInt(remotePort),
-                local_host: localHost,
-                local_port: parseInt(localPort),
+                kind,
+                remote_bind: remoteSocket ? '0.0.0.0' : remoteBind,
+                remote_port: parseInt(remotePort) || 0,
+                local_host: localSocket ? 'localhost' : localHost,
+                local_port: parseInt(localPort) || 0,
+                remote_socket: remoteSocket,
+                local_socket: localSocket,
+                health_check: healthCheck,
             });
         }
     }
@@ -472,6 +585,84 @@ function readTunnelsFrom(editorId) {
     return tunnels;
 }
 
+const FAILOVER_LABELS = {
+    ordered: 'Ordered',
+    round_robin: 'Round-robin',
+    lowest_latency: 'Lowest latency',
+};
+
+// Format a server as host:port
+function formatEndpoint(endpoint) {
+    const host = endpoint.host.includes(':') ? `[${endpoint.host}]` : endpoint.host;
+    return `${host}:${endpoint.port}`;
+}
+
+// Parse the fallback servers input (host[:port] separated by commas)
+function readFallbacksFrom(inputId) {
+    const text = document.getElementById(inputId).value;
+    const endpoints = [];
+    for (const entry of text.split(/[\s,]+/).filter(Boolean)) {
+        let host = entry;
+        let port = 22;
+        const bracketed = entry.match(/^\[(.+)\](?::(\d+))?$/);
+        if (bracketed) {
+            host = bracketed[1];
+            if (bracketed[2]) port = parseInt(bracketed[2]);
+        } else if (entry.split(':').length === 2) {
+            const parts = entry.split(':');
+            host = parts[0];
+            port = parseInt(parts[1]);
+        }
+        if (!host || !Number.isInteger(port) || port < 1 || port > 65535) {
+            return null;
+        }
+        endpoints.push({ host, port });
+    }
+    return endpoints;
+}
+
+// Format a jump host as [user@]host[:port]
+function formatJumpHost(hop) {
+    const host = hop.host.includes(':') ? `[${hop.host}]` : hop.host;
+    const user = hop.user ? `${hop.user}@` : '';
+    const port = hop.port && hop.port !== 22 ? `:${hop.port}` : '';
+    return `${user}${host}${port}`;
+}
+
+// Parse the jump hosts input. Hops whose destination is unchanged keep
+// the key and known_hosts settings they were configured with.
+function readJumpHostsFrom(inputId, existing = []) {
+    const text = document.getElementById(inputId).value;
+    const hops = [];
+    for (const entry of text.split(/[\s,]+/).filter(Boolean)) {
+        const previous = existing.find(h => formatJumpHost(h) === entry);
+        if (previous) {
+            hops.push(previous);
+            continue;
+        }
+
+        const at = entry.lastIndexOf('@');
+        const user = at >= 0 ? entry.slice(0, at) : null;
+        let host = at >= 0 ? entry.slice(at + 1) : entry;
+        let port = 22;
+        const bracketed = host.match(/^\[(.+)\](?::(\d+))?$/);
+        if (bracketed) {
+            host = bracketed[1];
+            if (bracketed[2]) port = parseInt(bracketed[2]);
+        } else if (host.split(':').length === 2) {
+            const parts = host.split(':');
+            host = parts[0];
+            port = parseInt(parts[1]);
+        }
+        if (!host || !Number.isInteger(port) || port < 1 || port > 65535) {
+            return null;
+        }
+
+        hops.push({ host, port, user, key_path: null, known_hosts_file: null });
+    }
+    return hops;
+}
+
 async function createProfile(event) {
     event.preventDefault();
 
@@ -490,6 +681,18 @@ async function createProfile(event) {
         return;
     }
 
+    const jumpHosts = readJumpHostsFrom('profileJumpHosts');
+    if (jumpHosts === null) {
+        showToast('warning', 'Warning', 'Jump hosts must look like [user@]host[:port]');
+        return;
+    }
+
+    const fallbackHosts = readFallbacksFrom('profileFallbacks');
+    if (fallbackHosts === null) {
+        showToast('warning', 'Warning', 'Fallback servers must look like host[:port]');
+        return;
+    }
+
     const tunnels = readTunnelsFrom('tunnelsEditor');
 
     if (tunnels.length === 0) {
@@ -523,6 +726,10 @@ async function createProfile(event) {
                 auth,
                 key_path: authType === 'key' ? keyPath : null,
                 tunnels,
+                jump_hosts: jumpHosts,
+                fallback_hosts: fallbackHosts,
+                failover_strategy: document.getElementById('profileFailover').value,
+                fail_back_after_secs: parseInt(document.getElementById('profileFailBack').value) || 0,
                 auto_reconnect: autoReconnect,
             }
         });
@@ -572,6 +779,14 @@ async function showEditProfileModal(name) {
         document.getElementById('editProfilePassword').value = authType === 'password' ? loadStoredPassword(name) : '';
         document.getElementById('editProfileSshpassPath').value = authType === 'password' ? loadStoredSshpassPath(name) : '';
 
+        // jump hosts
+        document.getElementById('editProfileJumpHosts').value = (profile.jump_hosts || []).map(formatJumpHost).join(', ');
+
+        // fallback servers
+        document.getElementById('editProfileFallbacks').value = (profile.fallback_hosts || []).map(formatEndpoint).join(', ');
+        document.getElementById('editProfileFailover').value = profile.failover_strategy || 'ordered';
+        document.getElementById('editProfileFailBack').value = profile.fail_back_after_secs || 0;
+
         // tunnels
         setTunnelsEditor('editTunnelsEditor', profile.tunnels, 'removeEditTunnelRow');
 
@@ -600,6 +815,18 @@ async function updateProfile(event) {
         return;
     }
 
+    const jumpHosts = readJumpHostsFrom('editProfileJumpHosts', state.currentProfile?.jump_hosts || []);
+    if (jumpHosts === null) {
+        showToast('warning', 'Warning', 'Jump hosts must look like [user@]host[:port]');
+        return;
+    }
+
+    const fallbackHosts = readFallbacksFrom('editProfileFallbacks');
+    if (fallbackHosts === null) {
+        showToast('warning', 'Warning', 'Fallback servers must look like host[:port]');
+        return;
+    }
+
     const tunnels = readTunnelsFrom('editTunnelsEditor');
     if (tunnels.length === 0) {
         showToast('warning', 'Warning', 'Please add at least one tunnel');
@@ -648,6 +875,10 @@ async function updateProfile(event) {
                 auth,
                 key_path: authType === 'key' ? keyPath : null,
                 tunnels,
+                jump_hosts: jumpHosts,
+                fallback_hosts: fallbackHosts,
+                failover_strategy: document.getElementById('editProfileFailover').value,
+                fail_back_after_secs: parseInt(document.getElementById('editProfileFailBack').value) || 0,
                 auto_reconnect: autoReconnect,
             }
         });
@@ -684,7 +915,7 @@ function renderSessions() {
     const tbody = document.getElementById('sessionsTableBody');
 
     if (state.sessions.length === 0) {
-        tbody.innerHTML = '<tr><td colspan="7" class="empty-state">No active sessions</td></tr>';
+        tbody.innerHTML = '<tr><td colspan="8" class="empty-state">No active sessions</td></tr>';
         return;
     }
 
@@ -696,11 +927,14 @@ function renderSessions() {
                     ${session.status}
                 </span>
             </td>
-            <td>${escapeHtml(session.profile_name)}</td>
+            <td>${escapeHtml(session.profile_name)}${session.server ? `<div><small>${escapeHtml(session.server)}</small></div>` : ''}</td>
             <td><code>${session.id.substring(0, 8)}...</code></td>
             <td>${formatTime(session.started_at)}</td>
             <td>${session.pid || '-'}</td>
             <td>${session.reconnect_count}</td>
+            <td>${(session.tunnels || []).map(t => `
+                <div><code>${escapeHtml(t.forward)}</code>${t.allocated_port ? ' <small>(allocated)</small>' : ''}</div>
+            `).join('') || '-'}</td>
             <td>
                 <button class="btn btn-danger btn-sm" onclick="stopSession('${session.id}')">
                     Stop
@@ -1003,10 +1237,11 @@ function formatTime(isoString) {
 function getStatusClass(status) {
     const s = status.toLowerCase();
     if (s === 'connected') return 'connected';
+    if (s === 'degraded') return 'degraded';
     if (s === 'starting') return 'starting';
     if (s === 'reconnecting') return 'reconnecting';
     if (s === 'disconnected') return 'disconnected';
-    if (s === 'failed') return 'failed';
+    if (s === 'failed' || s === 'timedout') return 'failed';
     return '';
 }
 
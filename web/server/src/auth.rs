@@ -0,0 +1,232 @@
+//! Authentication and role checks for the API.
+//!
+//! Callers authenticate either with a static API token sent as
+//! `Authorization: Bearer <token>` or with the session cookie set by
+//! `POST /api/auth/login`. When `WebConfig` has no tokens and no users,
+//! authentication is off and every caller is an operator.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use argon2::Argon2;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use password_hash::rand_core::{OsRng, RngCore};
+use password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use reverse_ssh_core::config::{ApiToken, WebConfig, WebRole, WebUser};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::state::AppState;
+
+/// Name of the cookie holding a web UI login
+pub const SESSION_COOKIE: &str = "rssh_session";
+
+/// Who made a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// Token name or username
+    pub name: String,
+    pub role: WebRole,
+}
+
+impl Principal {
+    /// The caller when authentication is disabled
+    fn anonymous() -> Self {
+        Self { name: "anonymous".to_string(), role: WebRole::Operator }
+    }
+}
+
+struct Login {
+    principal: Principal,
+    expires_at: Instant,
+}
+
+/// Credentials from `WebConfig` plus the logins handed out since startup
+pub struct Auth {
+    tokens: Vec<ApiToken>,
+    users: Vec<WebUser>,
+    login_ttl: Duration,
+    logins: Mutex<HashMap<String, Login>>,
+}
+
+impl Auth {
+    pub fn new(config: &WebConfig) -> Self {
+        Self {
+            tokens: config.api_tokens.clone(),
+            users: config.users.clone(),
+            login_ttl: Duration::from_secs(config.login_ttl_secs),
+            logins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether requests have to authenticate at all
+    pub fn enabled(&self) -> bool {
+        !self.tokens.is_empty() || !self.users.is_empty()
+    }
+
+    pub fn login_ttl(&self) -> Duration {
+        self.login_ttl
+    }
+
+    /// Identify the caller from a bearer token or session cookie
+    ///
+    /// Returns `None` when authentication is enabled and neither is valid.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<Principal> {
+        if !self.enabled() {
+            return Some(Principal::anonymous());
+        }
+
+        if let Some(token) = bearer_token(headers) {
+            return self.authenticate_token(token);
+        }
+
+        session_cookie(headers).and_then(|id| self.session(id))
+    }
+
+    fn authenticate_token(&self, token: &str) -> Option<Principal> {
+        let hash = hash_token(token);
+        self.tokens
+            .iter()
+            .find(|t| constant_time_eq(t.sha256.trim().to_ascii_lowercase().as_bytes(), hash.as_bytes()))
+            .map(|t| Principal { name: t.name.clone(), role: t.role })
+    }
+
+    /// Check a username and password
+    ///
+    /// Runs Argon2, so call it off the async runtime.
+    pub fn verify_password(&self, username: &str, password: &str) -> Option<Principal> {
+        let user = self.users.iter().find(|u| u.username == username)?;
+        let hash = match PasswordHash::new(&user.password_hash) {
+            Ok(hash) => hash,
+            Err(e) => {
+                tracing::warn!("Unreadable password hash for web user {:?}: {}", user.username, e);
+                return None;
+            }
+        };
+
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .ok()
+            .map(|_| Principal { name: user.username.clone(), role: user.role })
+    }
+
+    /// Start a login and return its session id
+    pub fn create_session(&self, principal: Principal) -> String {
+        let id = random_hex(32);
+        let now = Instant::now();
+
+        let mut logins = self.logins.lock().unwrap();
+        logins.retain(|_, login| login.expires_at > now);
+        logins.insert(id.clone(), Login { principal, expires_at: now + self.login_ttl });
+        id
+    }
+
+    pub fn end_session(&self, id: &str) {
+        self.logins.lock().unwrap().remove(id);
+    }
+
+    fn session(&self, id: &str) -> Option<Principal> {
+        let mut logins = self.logins.lock().unwrap();
+        match logins.get(id) {
+            Some(login) if login.expires_at > Instant::now() => Some(login.principal.clone()),
+            Some(_) => {
+                logins.remove(id);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 of a token, as stored in `WebConfig::api_tokens`
+pub fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// Argon2id hash of a password, as stored in `WebConfig::users`
+pub fn hash_password(password: &str) -> Result<String, password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default().hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+/// A new random API token
+pub fn generate_token() -> String {
+    format!("rssh_{}", random_hex(32))
+}
+
+/// Let the request through when the caller has at least read-only access
+pub async fn require_read_only(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    authorize(&state, WebRole::ReadOnly, request, next).await
+}
+
+/// Let the request through when the caller is an operator
+pub async fn require_operator(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    authorize(&state, WebRole::Operator, request, next).await
+}
+
+async fn authorize(state: &AppState, required: WebRole, mut request: Request, next: Next) -> Response {
+    let Some(principal) = state.auth.authenticate(request.headers()) else {
+        return unauthorized();
+    };
+
+    if principal.role < required {
+        tracing::debug!("Denied {} {} to {:?} ({})", request.method(), request.uri().path(), principal.name, principal.role);
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": format!("This action requires the {} role", required) })),
+        )
+            .into_response();
+    }
+
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
+pub fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        Json(json!({ "error": "Authentication required" })),
+    )
+        .into_response()
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// The session id from the `Cookie` header(s)
+pub fn session_cookie(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            (name == SESSION_COOKIE).then_some(value)
+        })
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+    to_hex(&buf)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,105 @@
+//! Numbered history of recent core events.
+//!
+//! Every event from the session manager gets an increasing ID and is kept in
+//! a bounded ring buffer, so event stream clients can resume where they left
+//! off (`Last-Event-ID`) after a reconnect or after falling behind.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use reverse_ssh_core::types::{Event, EventReceiver};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+
+/// An event with its position in the log
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    pub id: u64,
+    pub event: Event,
+}
+
+/// Events after a given ID, as far as the buffer still has them
+#[derive(Debug, Default)]
+pub struct Backlog {
+    /// Events newer than the requested ID that were already dropped
+    pub missed: u64,
+    pub events: Vec<LoggedEvent>,
+}
+
+pub struct EventLog {
+    capacity: usize,
+    buffer: Mutex<VecDeque<LoggedEvent>>,
+    /// ID of the newest event, 0 before the first
+    latest: watch::Sender<u64>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            buffer: Mutex::new(VecDeque::new()),
+            latest: watch::channel(0).0,
+        }
+    }
+
+    /// Create a log fed from `events` by a background task
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn spawn(mut events: EventReceiver, capacity: usize) -> Arc<Self> {
+        let log = Arc::new(Self::new(capacity));
+
+        let feeder = log.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        feeder.push(event);
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("Event log fell behind; {} events were not recorded", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        log
+    }
+
+    /// Record an event and return its ID
+    pub fn push(&self, event: Event) -> u64 {
+        let mut buffer = self.buffer.lock().unwrap();
+        let id = *self.latest.borrow() + 1;
+
+        if buffer.len() == self.capacity {
+            buffer.pop_front();
+        }
+        buffer.push_back(LoggedEvent { id, event });
+        // Publish while holding the lock so IDs reach waiters in order
+        self.latest.send_replace(id);
+        id
+    }
+
+    /// ID of the newest event, 0 when there is none yet
+    pub fn latest_id(&self) -> u64 {
+        *self.latest.borrow()
+    }
+
+    /// Events with an ID greater than `after`
+    pub fn since(&self, after: u64) -> Backlog {
+        let buffer = self.buffer.lock().unwrap();
+        let Some(oldest) = buffer.front().map(|e| e.id) else {
+            return Backlog::default();
+        };
+
+        Backlog {
+            missed: oldest.saturating_sub(after + 1),
+            events: buffer.iter().filter(|e| e.id > after).cloned().collect(),
+        }
+    }
+
+    /// Receiver that changes whenever an event is recorded
+    pub fn watch(&self) -> watch::Receiver<u64> {
+        self.latest.subscribe()
+    }
+}
@@ -1,3 +1,6 @@
+pub mod auth;
+pub mod events;
 pub mod routes;
 pub mod state;
 pub mod static_files;
+pub mod tls;
@@ -1,15 +1,12 @@
-use clap::Parser;
-use tower_http::{
-    cors::CorsLayer,
-    trace::TraceLayer,
-};
+use clap::{Parser, Subcommand, ValueEnum};
+use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use std::net::SocketAddr;
 use reverse_ssh_core::{
-    config::init_config,
+    config::{init_config, WebRole},
     supervisor::SessionManager,
 };
-use reverse_ssh_web_server::{routes, state};
+use reverse_ssh_web_server::{auth, routes, state, tls};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -21,6 +18,76 @@ struct Args {
     /// Port to bind to
     #[arg(long, default_value = "3000", env = "PORT")]
     port: u16,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate an API token and print its `[[web.api_tokens]]` config entry
+    HashToken {
+        /// Name shown in logs for requests using the token
+        #[arg(long, default_value = "default")]
+        name: String,
+
+        #[arg(long, value_enum, default_value = "read-only")]
+        role: RoleArg,
+    },
+    /// Hash a password read from standard input and print its `[[web.users]]` config entry
+    HashPassword {
+        #[arg(long)]
+        username: String,
+
+        #[arg(long, value_enum, default_value = "read-only")]
+        role: RoleArg,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RoleArg {
+    ReadOnly,
+    Operator,
+}
+
+impl From<RoleArg> for WebRole {
+    fn from(role: RoleArg) -> Self {
+        match role {
+            RoleArg::ReadOnly => WebRole::ReadOnly,
+            RoleArg::Operator => WebRole::Operator,
+        }
+    }
+}
+
+/// Print a credential entry for the `[web]` section of config.toml
+fn run_command(command: Command) -> Result<(), String> {
+    match command {
+        Command::HashToken { name, role } => {
+            let token = auth::generate_token();
+            eprintln!("Token (shown once, give it to the client): {}", token);
+            println!("[[web.api_tokens]]");
+            println!("name = {:?}", name);
+            println!("sha256 = {:?}", auth::hash_token(&token));
+            println!("role = \"{}\"", WebRole::from(role));
+        }
+        Command::HashPassword { username, role } => {
+            let mut password = String::new();
+            std::io::stdin()
+                .read_line(&mut password)
+                .map_err(|e| format!("Failed to read password: {}", e))?;
+            let password = password.trim_end_matches(['\r', '\n']);
+            if password.is_empty() {
+                return Err("Password must not be empty".to_string());
+            }
+
+            let hash = auth::hash_password(password).map_err(|e| format!("Failed to hash password: {}", e))?;
+            println!("[[web.users]]");
+            println!("username = {:?}", username);
+            println!("password_hash = {:?}", hash);
+            println!("role = \"{}\"", WebRole::from(role));
+        }
+    }
+    Ok(())
 }
 
 #[tokio::main]
@@ -35,6 +102,14 @@ async fn main() {
 
     let args = Args::parse();
 
+    if let Some(command) = args.command {
+        if let Err(e) = run_command(command) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Initialize configuration
     let config = match init_config() {
         Ok(c) => c,
@@ -44,6 +119,8 @@ async fn main() {
         }
     };
 
+    let web = config.web.clone();
+
     // Create session manager
     let (mut manager, handle) = SessionManager::new(config);
     
@@ -60,11 +137,16 @@ async fn main() {
         }
     });
 
-    let state = state::AppState::new(handle);
+    let auth_enabled = web.auth_enabled();
+    let redirect_port = web.http_redirect_port;
+    let tls_files = tls::certificate_files(&web, &args.host).unwrap_or_else(|e| {
+        tracing::error!("Failed to set up TLS: {}", e);
+        std::process::exit(1);
+    });
+    let state = state::AppState::new(handle, web);
 
     let app = routes::create_routes(state)
-        .layer(TraceLayer::new_for_http())
-        .layer(CorsLayer::permissive());
+        .layer(TraceLayer::new_for_http());
 
     let addr_str = format!("{}:{}", args.host, args.port);
     
@@ -73,9 +155,54 @@ async fn main() {
         std::process::exit(1);
     });
 
-    tracing::info!("listening on {}", addr);
-    tracing::info!("Swagger UI available at http://{}/swagger-ui/", addr);
-    
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    if !auth_enabled && !addr.ip().is_loopback() {
+        tracing::error!(
+            "Refusing to listen on {} without authentication; add api_tokens or users to the [web] config (see `rssh-web hash-token --help`)",
+            addr
+        );
+        std::process::exit(1);
+    }
+
+    let Some((cert, key)) = tls_files else {
+        if redirect_port.is_some() {
+            tracing::warn!("Ignoring http_redirect_port: TLS is not configured");
+        }
+
+        tracing::info!("listening on {}", addr);
+        tracing::info!("Swagger UI available at http://{}/swagger-ui/", addr);
+
+        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+        axum::serve(listener, app).await.unwrap();
+        return;
+    };
+
+    // Only ring is compiled in, but rustls still wants it chosen explicitly
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls_config = tls::load_rustls_config(&cert, &key).await.unwrap_or_else(|e| {
+        tracing::error!("Failed to load TLS certificate: {}", e);
+        std::process::exit(1);
+    });
+
+    if let Some(port) = redirect_port {
+        let redirect_addr = SocketAddr::new(addr.ip(), port);
+        let listener = tokio::net::TcpListener::bind(redirect_addr).await.unwrap_or_else(|e| {
+            tracing::error!("Failed to bind HTTP redirect listener on {}: {}", redirect_addr, e);
+            std::process::exit(1);
+        });
+
+        tracing::info!("redirecting http://{} to HTTPS", redirect_addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, tls::redirect_routes(addr.port())).await {
+                tracing::error!("HTTP redirect listener error: {}", e);
+            }
+        });
+    }
+
+    tracing::info!("listening on {} (HTTPS, certificate {})", addr, cert.display());
+    tracing::info!("Swagger UI available at https://{}/swagger-ui/", addr);
+
+    axum_server::bind_rustls(addr, tls_config)
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
 }
@@ -0,0 +1,94 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use crate::auth::{self, SESSION_COOKIE};
+use crate::state::AppState;
+use serde_json::json;
+
+use super::types::{ApiIdentity, LoginRequest};
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Signed in; the session cookie is set", body = ApiIdentity),
+        (status = 401, description = "Unknown user or wrong password")
+    ),
+    tag = "auth"
+)]
+pub async fn login(State(state): State<AppState>, Json(req): Json<LoginRequest>) -> impl IntoResponse {
+    let auth = state.auth.clone();
+    let username = req.username.clone();
+    // Argon2 is deliberately slow; keep it off the async workers
+    let principal = tokio::task::spawn_blocking(move || auth.verify_password(&req.username, &req.password))
+        .await
+        .ok()
+        .flatten();
+
+    let Some(principal) = principal else {
+        tracing::warn!("Failed web login for {:?}", username);
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Invalid username or password" })),
+        ).into_response();
+    };
+
+    tracing::info!("Web login for {:?} ({})", principal.name, principal.role);
+    let identity = ApiIdentity {
+        name: principal.name.clone(),
+        role: principal.role.into(),
+        auth_enabled: true,
+    };
+    let session_id = state.auth.create_session(principal);
+    let cookie = format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+        SESSION_COOKIE,
+        session_id,
+        state.auth.login_ttl().as_secs(),
+        if state.web.tls_enabled() { "; Secure" } else { "" }
+    );
+
+    ([(header::SET_COOKIE, cookie)], Json(identity)).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    responses(
+        (status = 200, description = "Signed out; the session cookie is cleared")
+    ),
+    tag = "auth"
+)]
+pub async fn logout(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    if let Some(session_id) = auth::session_cookie(&headers) {
+        state.auth.end_session(session_id);
+    }
+
+    let cookie = format!("{}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0", SESSION_COOKIE);
+    ([(header::SET_COOKIE, cookie)], Json(json!({ "status": "logged_out" })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/me",
+    responses(
+        (status = 200, description = "The authenticated caller", body = ApiIdentity),
+        (status = 401, description = "Not authenticated")
+    ),
+    tag = "auth"
+)]
+pub async fn me(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    match state.auth.authenticate(&headers) {
+        Some(principal) => Json(ApiIdentity {
+            name: principal.name,
+            role: principal.role.into(),
+            auth_enabled: state.auth.enabled(),
+        })
+        .into_response(),
+        None => auth::unauthorized(),
+    }
+}
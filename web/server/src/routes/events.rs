@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Json,
+};
+use crate::events::EventLog;
+use crate::state::AppState;
+use futures_util::stream::{self, Stream, StreamExt};
+use reverse_ssh_core::types::{Event, Session};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+/// Which events a client receives; an empty set matches everything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventFilter {
+    /// Session IDs
+    #[serde(default)]
+    pub sessions: HashSet<Uuid>,
+    /// Profile names
+    #[serde(default)]
+    pub profiles: HashSet<String>,
+    /// Event types, e.g. `session_connected`
+    #[serde(default)]
+    pub events: HashSet<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.events.is_empty() || self.events.contains(event.kind()))
+            && (self.sessions.is_empty() || event.session_id().is_some_and(|id| self.sessions.contains(&id)))
+            && (self.profiles.is_empty() || event.profile_name().is_some_and(|name| self.profiles.contains(name)))
+    }
+
+    pub fn matches_session(&self, session: &Session) -> bool {
+        (self.sessions.is_empty() || self.sessions.contains(&session.id))
+            && (self.profiles.is_empty() || self.profiles.contains(&session.profile_name))
+    }
+
+    pub fn matches_profile(&self, name: &str) -> bool {
+        self.profiles.is_empty() || self.profiles.contains(name)
+    }
+}
+
+/// Event filter as comma-separated query parameters
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventQuery {
+    /// Session IDs to include
+    sessions: Option<String>,
+    /// Profile names to include
+    #[param(example = "web,db")]
+    profiles: Option<String>,
+    /// Event types to include
+    #[param(example = "session_connected,session_failed")]
+    events: Option<String>,
+}
+
+impl EventQuery {
+    pub fn into_filter(self) -> Result<EventFilter, String> {
+        fn split(list: Option<String>) -> impl Iterator<Item = String> {
+            list.into_iter()
+                .flat_map(|list| list.split(',').map(|item| item.trim().to_string()).collect::<Vec<_>>())
+                .filter(|item| !item.is_empty())
+        }
+
+        let sessions = split(self.sessions)
+            .map(|id| Uuid::parse_str(&id).map_err(|_| format!("Invalid session ID: {}", id)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(EventFilter {
+            sessions,
+            profiles: split(self.profiles).collect(),
+            events: split(self.events).collect(),
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    params(
+        EventQuery,
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event ID, replaying buffered events")
+    ),
+    responses(
+        (status = 200, description = "Server-sent event stream. Each message has the event ID as `id`, \
+            the event type as `event` and the event as JSON `data`. A `lagged` message reports events \
+            that left the buffer before they could be sent.", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Invalid filter")
+    ),
+    tag = "events"
+)]
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    match query.into_filter() {
+        Ok(filter) => event_stream_response(state.events.clone(), filter, &headers),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}/output",
+    params(
+        ("id" = String, Path, description = "Session ID"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event ID, replaying buffered events")
+    ),
+    responses(
+        (status = 200, description = "Server-sent event stream of the session's output lines \
+            (`session_output`) and lifecycle events, in the format of `/api/events`",
+            content_type = "text/event-stream", body = String),
+        (status = 400, description = "Invalid session ID")
+    ),
+    tag = "events"
+)]
+pub async fn stream_session_output(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let Ok(session_id) = Uuid::parse_str(&id) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Invalid session ID format" })),
+        ).into_response();
+    };
+
+    let filter = EventFilter {
+        sessions: HashSet::from([session_id]),
+        ..EventFilter::default()
+    };
+    event_stream_response(state.events.clone(), filter, &headers)
+}
+
+fn event_stream_response(log: Arc<EventLog>, filter: EventFilter, headers: &HeaderMap) -> axum::response::Response {
+    let latest = log.latest_id();
+    let after = match last_event_id(headers) {
+        // IDs restart with the server; a newer one is from before a restart
+        Some(id) if id <= latest => id,
+        Some(_) => 0,
+        None => latest,
+    };
+
+    Sse::new(event_stream(log, filter, after))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers.get("last-event-id")?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Matching events with an ID greater than `after`, then live ones as they are recorded
+fn event_stream(
+    log: Arc<EventLog>,
+    filter: EventFilter,
+    after: u64,
+) -> impl Stream<Item = Result<SseEvent, Infallible>> {
+    let changes = log.watch();
+
+    stream::unfold((log, changes, filter, after), |(log, mut changes, filter, mut after)| async move {
+        loop {
+            changes.borrow_and_update();
+            let backlog = log.since(after);
+
+            let mut messages = Vec::new();
+            if backlog.missed > 0 {
+                messages.push(
+                    SseEvent::default()
+                        .id((after + backlog.missed).to_string())
+                        .event("lagged")
+                        .data(json!({ "missed": backlog.missed }).to_string()),
+                );
+            }
+            for logged in backlog.events {
+                after = logged.id;
+                if filter.matches(&logged.event) {
+                    let data = serde_json::to_string(&logged.event).unwrap_or_default();
+                    messages.push(
+                        SseEvent::default()
+                            .id(logged.id.to_string())
+                            .event(logged.event.kind())
+                            .data(data),
+                    );
+                }
+            }
+
+            if !messages.is_empty() {
+                let messages = stream::iter(messages.into_iter().map(Ok));
+                return Some((messages, (log, changes, filter, after)));
+            }
+
+            if changes.changed().await.is_err() {
+                return None;
+            }
+        }
+    })
+    .flatten()
+}
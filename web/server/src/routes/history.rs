@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use crate::state::AppState;
+use chrono::Utc;
+use reverse_ssh_core::storage::{history, HistoryReport, HistoryStore};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::IntoParams;
+
+use super::types::ApiHistoryReport;
+
+/// Window for a history report
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// How far back to look: a number with `s`, `m`, `h`, `d` or `w` (default `7d`)
+    #[param(example = "24h")]
+    window: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/profiles/{name}/history",
+    params(
+        ("name" = String, Path, description = "Profile name"),
+        HistoryQuery
+    ),
+    responses(
+        (status = 200, description = "Uptime, outages and recorded events for the window", body = ApiHistoryReport),
+        (status = 400, description = "Invalid window"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "profiles"
+)]
+pub async fn profile_history(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> impl IntoResponse {
+    let length = match history::parse_window(query.window.as_deref().unwrap_or("7d")) {
+        Ok(length) => length,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string() }))).into_response(),
+    };
+    let to = Utc::now();
+    let from = to - chrono::Duration::from_std(length).unwrap_or(chrono::Duration::MAX);
+
+    let path = state.history_file.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        // Nothing has been recorded yet
+        if !path.exists() {
+            return Ok(HistoryReport::compute(&name, from, to, None, Vec::new()));
+        }
+        HistoryStore::open(&path)?.report(&name, from, to)
+    })
+    .await;
+
+    match report {
+        Ok(Ok(report)) => Json(ApiHistoryReport::from(report)).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
@@ -0,0 +1,23 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::IntoResponse,
+};
+use crate::state::AppState;
+use reverse_ssh_core::metrics::CONTENT_TYPE;
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Per-profile session and tunnel metrics in the Prometheus text format",
+            content_type = "text/plain", body = String)
+    ),
+    tag = "metrics"
+)]
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    if let Ok(sessions) = state.handle.status().await {
+        state.metrics.observe_sessions(&sessions);
+    }
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], state.metrics.render())
+}
@@ -1,11 +1,19 @@
+use axum::http::{header, HeaderValue, Method};
+use axum::middleware;
 use axum::Router;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
+use reverse_ssh_core::config::WebConfig;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 use crate::state::AppState;
 use crate::static_files;
 
+pub mod auth;
+pub mod events;
 pub mod health;
+pub mod history;
+pub mod metrics;
 pub mod profiles;
 pub mod sessions;
 pub mod ws;
@@ -17,25 +25,53 @@ use types::*;
 #[openapi(
     paths(
         health::check,
+        auth::login,
+        auth::logout,
+        auth::me,
         profiles::list_profiles,
         profiles::create_profile,
         profiles::get_profile,
+        profiles::get_raw_profile,
         profiles::delete_profile,
         profiles::update_profile,
+        profiles::import_ssh_config,
+        history::profile_history,
         sessions::list_sessions,
         sessions::start_session,
         sessions::stop_session,
+        events::stream_events,
+        events::stream_session_output,
+        metrics::metrics,
     ),
     components(
         schemas(
             ApiProfile, 
+            ApiTunnelKind,
             ApiTunnelSpec, 
+            ApiHealthCheckKind,
+            ApiHealthCheck,
             ApiAuthMethod,
+            ApiJumpHost,
+            ApiRelayEndpoint,
+            ApiFailoverStrategy,
             ApiSession,
+            ApiTunnelState,
+            ApiTunnelHealth,
             ApiSessionStatus,
             CreateProfileRequest,
             UpdateProfileRequest,
+            ImportSshConfigRequest,
+            ApiImportResult,
+            ApiSeverity,
+            ApiDiagnostic,
+            ApiValidationError,
             StartSessionRequest,
+            LoginRequest,
+            ApiWebRole,
+            ApiIdentity,
+            ApiHistoryKind,
+            ApiHistoryEntry,
+            ApiHistoryReport,
         )
     ),
     tags(
@@ -44,21 +80,82 @@ use types::*;
 )]
 pub struct ApiDoc;
 
+/// Build the application router
+///
+/// `/`, `/health`, the login endpoints and the API docs are public. Reading
+/// profiles, sessions and metrics needs the read-only role; everything that
+/// changes them needs the operator role.
 pub fn create_routes(state: AppState) -> Router {
-    Router::new()
-        .route("/", get(static_files::index))
-        .route("/health", get(health::check))
-        .route("/api/profiles", get(profiles::list_profiles).post(profiles::create_profile))
+    let read_only = Router::new()
+        .route("/api/profiles", get(profiles::list_profiles))
+        .route("/api/profiles/{name}", get(profiles::get_profile))
+        .route("/api/profiles/{name}/raw", get(profiles::get_raw_profile))
+        .route("/api/profiles/{name}/history", get(history::profile_history))
+        .route("/api/sessions", get(sessions::list_sessions))
+        .route("/api/sessions/{id}/output", get(events::stream_session_output))
+        .route("/api/events", get(events::stream_events))
+        .route("/ws", get(ws::ws_handler))
+        .route("/metrics", get(metrics::metrics))
+        .route_layer(middleware::from_fn_with_state(state.clone(), crate::auth::require_read_only));
+
+    let operator = Router::new()
+        .route("/api/profiles", post(profiles::create_profile))
+        .route("/api/profiles/import", post(profiles::import_ssh_config))
         .route(
             "/api/profiles/{name}",
-            get(profiles::get_profile)
-                .delete(profiles::delete_profile)
-                .put(profiles::update_profile),
+            delete(profiles::delete_profile).put(profiles::update_profile),
         )
-        .route("/api/sessions", get(sessions::list_sessions))
         .route("/api/sessions/{name}/start", post(sessions::start_session))
         .route("/api/sessions/{id}/stop", post(sessions::stop_session))
-        .route("/ws", get(ws::ws_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), crate::auth::require_operator));
+
+    let router = Router::new()
+        .route("/", get(static_files::index))
+        .route("/health", get(health::check))
+        .route("/api/auth/login", post(auth::login))
+        .route("/api/auth/logout", post(auth::logout))
+        .route("/api/auth/me", get(auth::me))
+        .merge(read_only)
+        .merge(operator)
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
-        .with_state(state)
+        .with_state(state.clone());
+
+    match cors_layer(&state.web) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    }
+}
+
+/// CORS for the origins in `cors_allowed_origins`, when `cors_enabled` is set
+///
+/// Credentials are allowed so a dashboard on another origin can use the
+/// session cookie; that is why origins are listed rather than wildcarded.
+fn cors_layer(web: &WebConfig) -> Option<CorsLayer> {
+    if !web.cors_enabled {
+        return None;
+    }
+
+    let origins: Vec<HeaderValue> = web
+        .cors_allowed_origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin.trim_end_matches('/')) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS origin {:?}", origin);
+                None
+            }
+        })
+        .collect();
+
+    if origins.is_empty() {
+        tracing::warn!("CORS is enabled but cors_allowed_origins is empty; cross-origin requests will be refused");
+    }
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+            .allow_credentials(true),
+    )
 }
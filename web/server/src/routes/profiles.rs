@@ -1,18 +1,38 @@
 use axum::{
-    extract::Path,
+    extract::{Path, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use reverse_ssh_core::{
-    config::{load_profiles, save_profile, delete_profile as core_delete_profile},
-    types::Profile,
+    config::{
+        ensure_not_extended, import_profiles, load_profiles, load_raw_profile, save_profile,
+        delete_profile as core_delete_profile, SshConfigFile,
+    },
+    types::{BackendKind, Event, Profile, ProfileHooks},
+    CoreError,
 };
 use serde_json::json;
 use uuid::Uuid;
 use std::collections::HashMap;
+use crate::state::AppState;
 
-use super::types::{ApiProfile, CreateProfileRequest, UpdateProfileRequest};
+use super::types::{
+    ApiImportResult, ApiProfile, ApiValidationError, CreateProfileRequest, ImportSshConfigRequest, UpdateProfileRequest,
+};
+
+/// A 400 listing what is wrong with the profile, if it has errors
+fn reject_invalid(profile: &Profile, others: &[Profile]) -> Option<Response> {
+    let diagnostics = profile.validate_among(others);
+    if !diagnostics.iter().any(|d| d.is_error()) {
+        return None;
+    }
+    let body = ApiValidationError {
+        error: "Invalid profile".to_string(),
+        details: diagnostics.into_iter().map(Into::into).collect(),
+    };
+    Some((StatusCode::BAD_REQUEST, Json(body)).into_response())
+}
 
 #[utoipa::path(
     get,
@@ -42,15 +62,18 @@ pub async fn list_profiles() -> impl IntoResponse {
     request_body = CreateProfileRequest,
     responses(
         (status = 201, description = "Profile created successfully", body = ApiProfile),
-        (status = 400, description = "Invalid request"),
+        (status = 400, description = "Invalid request or profile", body = ApiValidationError),
         (status = 409, description = "Profile already exists"),
         (status = 500, description = "Internal server error")
     ),
     tag = "profiles"
 )]
-pub async fn create_profile(Json(req): Json<CreateProfileRequest>) -> impl IntoResponse {
+pub async fn create_profile(
+    State(state): State<AppState>,
+    Json(req): Json<CreateProfileRequest>,
+) -> impl IntoResponse {
     // Check if profile already exists
-    match load_profiles() {
+    let profiles = match load_profiles() {
         Ok(profiles) => {
             if profiles.iter().any(|p| p.name == req.name) {
                 return (
@@ -58,6 +81,7 @@ pub async fn create_profile(Json(req): Json<CreateProfileRequest>) -> impl IntoR
                     Json(json!({ "error": format!("Profile '{}' already exists", req.name) })),
                 ).into_response();
             }
+            profiles
         }
         Err(e) => {
             return (
@@ -65,7 +89,7 @@ pub async fn create_profile(Json(req): Json<CreateProfileRequest>) -> impl IntoR
                 Json(json!({ "error": e.to_string() })),
             ).into_response();
         }
-    }
+    };
 
     if req.tunnels.is_empty() {
         return (
@@ -77,15 +101,23 @@ pub async fn create_profile(Json(req): Json<CreateProfileRequest>) -> impl IntoR
     let profile = Profile {
         id: Uuid::new_v4(),
         name: req.name.clone(),
+        extends: None,
         host: req.host,
         port: req.port.unwrap_or(22),
         user: req.user,
         auth: req.auth.map(Into::into).unwrap_or_default(),
         tunnels: req.tunnels.into_iter().map(Into::into).collect(),
+        jump_hosts: req.jump_hosts.into_iter().map(Into::into).collect(),
+        fallback_hosts: req.fallback_hosts.into_iter().map(Into::into).collect(),
+        failover_strategy: req.failover_strategy.into(),
+        fail_back_after_secs: req.fail_back_after_secs,
+        backend: BackendKind::default(),
         keepalive_interval: 20,
         keepalive_count: 3,
         auto_reconnect: true,
         max_reconnect_attempts: 0,
+        reconnect: None,
+        hooks: ProfileHooks::default(),
         extra_options: HashMap::new(),
         ssh_path: None,
         known_hosts_file: None,
@@ -93,13 +125,21 @@ pub async fn create_profile(Json(req): Json<CreateProfileRequest>) -> impl IntoR
         password: None,
     };
 
+    if let Some(rejection) = reject_invalid(&profile, &profiles) {
+        return rejection;
+    }
     if let Err(e) = save_profile(&profile) {
+        let status = match e {
+            CoreError::ProfileInvalid(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
         return (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            status,
             Json(json!({ "error": format!("Failed to save profile: {}", e) })),
         ).into_response();
     }
 
+    state.handle.emit(Event::profile_created(profile.id, &profile.name));
     let api_profile: ApiProfile = profile.into();
     (StatusCode::CREATED, Json(api_profile)).into_response()
 }
@@ -134,6 +174,32 @@ pub async fn get_profile(Path(name): Path<String>) -> impl IntoResponse {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/profiles/{name}/raw",
+    params(
+        ("name" = String, Path, description = "Profile name")
+    ),
+    responses(
+        (status = 200, description = "The settings in the profile's own file, without inherited ones", body = Object),
+        (status = 404, description = "Profile not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "profiles"
+)]
+pub async fn get_raw_profile(Path(name): Path<String>) -> impl IntoResponse {
+    match load_raw_profile(&name) {
+        Ok(settings) => (StatusCode::OK, Json(settings)).into_response(),
+        Err(CoreError::ProfileNotFound(_)) => {
+            (StatusCode::NOT_FOUND, Json(json!({ "error": "Profile not found" }))).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": e.to_string() })),
+        ).into_response(),
+    }
+}
+
 #[utoipa::path(
     delete,
     path = "/api/profiles/{name}",
@@ -143,20 +209,29 @@ pub async fn get_profile(Path(name): Path<String>) -> impl IntoResponse {
     responses(
         (status = 200, description = "Profile deleted successfully"),
         (status = 404, description = "Profile not found"),
+        (status = 409, description = "Other profiles extend this profile"),
         (status = 500, description = "Internal server error")
     ),
     tag = "profiles"
 )]
-pub async fn delete_profile(Path(name): Path<String>) -> impl IntoResponse {
+pub async fn delete_profile(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
     match load_profiles() {
         Ok(profiles) => {
             if let Some(profile) = profiles.iter().find(|p| p.name == name) {
                 if let Err(e) = core_delete_profile(profile) {
+                    let status = match e {
+                        CoreError::ProfileInUse(_) => StatusCode::CONFLICT,
+                        _ => StatusCode::INTERNAL_SERVER_ERROR,
+                    };
                     return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
+                        status,
                         Json(json!({ "error": format!("Failed to delete profile: {}", e) })),
                     ).into_response();
                 }
+                state.handle.emit(Event::profile_deleted(profile.id, &profile.name));
                 (StatusCode::OK, Json(json!({ "status": "deleted" }))).into_response()
             } else {
                 (StatusCode::NOT_FOUND, Json(json!({ "error": "Profile not found" }))).into_response()
@@ -169,6 +244,70 @@ pub async fn delete_profile(Path(name): Path<String>) -> impl IntoResponse {
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/profiles/import",
+    request_body = ImportSshConfigRequest,
+    responses(
+        (status = 200, description = "Hosts with port forwards imported as profiles", body = ApiImportResult),
+        (status = 400, description = "Invalid ssh_config or host"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "profiles"
+)]
+pub async fn import_ssh_config(
+    State(state): State<AppState>,
+    Json(req): Json<ImportSshConfigRequest>,
+) -> impl IntoResponse {
+    // The server's own login name means nothing to the relay, so a missing
+    // User has to come from the request
+    let import = match SshConfigFile::parse(&req.ssh_config)
+        .and_then(|config| import_profiles(&config, &req.hosts, req.default_user.as_deref()))
+    {
+        Ok(import) => import,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": e.to_string() })),
+            ).into_response();
+        }
+    };
+
+    let existing = match load_profiles() {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            ).into_response();
+        }
+    };
+
+    let mut result = ApiImportResult { created: Vec::new(), skipped: Vec::new(), warnings: import.warnings };
+    for profile in import.profiles {
+        if existing.iter().any(|p| p.name == profile.name) {
+            result.skipped.push(profile.name);
+            continue;
+        }
+
+        if let Err(e) = save_profile(&profile) {
+            let status = match e {
+                CoreError::ProfileInvalid(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return (
+                status,
+                Json(json!({ "error": format!("Failed to save profile '{}': {}", profile.name, e) })),
+            ).into_response();
+        }
+
+        state.handle.emit(Event::profile_created(profile.id, &profile.name));
+        result.created.push(profile.into());
+    }
+
+    (StatusCode::OK, Json(result)).into_response()
+}
+
 #[utoipa::path(
     put,
     path = "/api/profiles/{name}",
@@ -178,14 +317,15 @@ pub async fn delete_profile(Path(name): Path<String>) -> impl IntoResponse {
     request_body = UpdateProfileRequest,
     responses(
         (status = 200, description = "Profile updated successfully", body = ApiProfile),
-        (status = 400, description = "Invalid request"),
+        (status = 400, description = "Invalid request or profile", body = ApiValidationError),
         (status = 404, description = "Profile not found"),
-        (status = 409, description = "Profile name already exists"),
+        (status = 409, description = "Profile name already exists, or other profiles extend a renamed profile"),
         (status = 500, description = "Internal server error")
     ),
     tag = "profiles"
 )]
 pub async fn update_profile(
+    State(state): State<AppState>,
     Path(name): Path<String>,
     Json(req): Json<UpdateProfileRequest>,
 ) -> impl IntoResponse {
@@ -238,6 +378,18 @@ pub async fn update_profile(
         }
         updated.tunnels = tunnels.into_iter().map(Into::into).collect();
     }
+    if let Some(jump_hosts) = req.jump_hosts {
+        updated.jump_hosts = jump_hosts.into_iter().map(Into::into).collect();
+    }
+    if let Some(fallback_hosts) = req.fallback_hosts {
+        updated.fallback_hosts = fallback_hosts.into_iter().map(Into::into).collect();
+    }
+    if let Some(strategy) = req.failover_strategy {
+        updated.failover_strategy = strategy.into();
+    }
+    if let Some(secs) = req.fail_back_after_secs {
+        updated.fail_back_after_secs = secs;
+    }
 
     // Rename collision check
     if updated.name != name && profiles.iter().any(|p| p.name == updated.name) {
@@ -248,9 +400,25 @@ pub async fn update_profile(
             .into_response();
     }
 
+    if let Some(rejection) = reject_invalid(&updated, &profiles) {
+        return rejection;
+    }
+    if updated.name != name {
+        if let Err(e) = ensure_not_extended(&name) {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    }
     if let Err(e) = save_profile(&updated) {
+        let status = match e {
+            CoreError::ProfileInvalid(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
         return (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            status,
             Json(json!({ "error": format!("Failed to save profile: {}", e) })),
         )
             .into_response();
@@ -261,6 +429,7 @@ pub async fn update_profile(
         let _ = core_delete_profile(&existing);
     }
 
+    state.handle.emit(Event::profile_updated(updated.id, &updated.name));
     let api_profile: ApiProfile = updated.into();
     (StatusCode::OK, Json(api_profile)).into_response()
 }
@@ -6,21 +6,104 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+/// API representation of a tunnel direction
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiTunnelKind {
+    /// Remote port forwards to a local service (-R)
+    #[default]
+    Remote,
+    /// Local port forwards to a host reachable from the server (-L)
+    Local,
+    /// Local SOCKS proxy exiting at the server (-D)
+    Dynamic,
+    /// Remote SOCKS proxy exiting at this machine (-R)
+    RemoteDynamic,
+}
+
 /// API representation of a tunnel specification
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ApiTunnelSpec {
-    /// Remote bind address
+    /// Forwarding direction (defaults to remote)
+    #[serde(default)]
+    pub kind: ApiTunnelKind,
+    /// Remote bind address (destination host for local tunnels)
     #[schema(example = "localhost")]
     pub remote_bind: String,
-    /// Remote port
+    /// Remote port (unused for dynamic tunnels, which send 0)
     #[schema(example = 8080)]
     pub remote_port: u16,
-    /// Local host
+    /// Local host (bind address for local and dynamic tunnels)
     #[schema(example = "localhost")]
     pub local_host: String,
-    /// Local port
+    /// Local port (unused for remote dynamic tunnels)
+    #[serde(default)]
     #[schema(example = 3000)]
     pub local_port: u16,
+    /// Unix socket path on the server, replaces remote_bind/remote_port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "/run/user/1000/docker.sock")]
+    pub remote_socket: Option<String>,
+    /// Unix socket path on this machine, replaces local_host/local_port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "/var/run/docker.sock")]
+    pub local_socket: Option<String>,
+    /// Probe run periodically while the session is connected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<ApiHealthCheck>,
+}
+
+/// API representation of what a tunnel health check connects to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiHealthCheckKind {
+    /// TCP connect to the tunnel's local side
+    LocalTcp,
+    /// TCP connect to the tunnel's remote side, made from the server
+    RemoteTcp,
+    /// HTTP GET to the tunnel's local side
+    Http,
+}
+
+/// API representation of a tunnel health check
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiHealthCheck {
+    pub kind: ApiHealthCheckKind,
+    /// Seconds between probes
+    #[serde(default = "default_health_interval")]
+    #[schema(example = 30)]
+    pub interval_secs: u64,
+    /// Seconds a probe may take
+    #[serde(default = "default_health_timeout")]
+    #[schema(example = 5)]
+    pub timeout_secs: u64,
+    /// Reconnect after this many failed probes in a row (0 = never)
+    #[serde(default)]
+    pub reconnect_after: u32,
+    /// Request path for http checks
+    #[serde(default = "default_health_path")]
+    #[schema(example = "/healthz")]
+    pub path: String,
+    /// Response status http checks expect
+    #[serde(default = "default_health_status")]
+    #[schema(example = 200)]
+    pub expected_status: u16,
+}
+
+fn default_health_interval() -> u64 {
+    30
+}
+
+fn default_health_timeout() -> u64 {
+    5
+}
+
+fn default_health_path() -> String {
+    "/".to_string()
+}
+
+fn default_health_status() -> u16 {
+    200
 }
 
 /// API representation of authentication method
@@ -35,6 +118,61 @@ pub enum ApiAuthMethod {
     Password,
 }
 
+/// API representation of a jump host hop
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiJumpHost {
+    /// Jump host address
+    #[schema(example = "bastion.example.com")]
+    pub host: String,
+    /// SSH port (default: 22)
+    #[serde(default = "default_ssh_port")]
+    #[schema(example = 22)]
+    pub port: u16,
+    /// SSH user (defaults to ssh's own choice)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "ops")]
+    pub user: Option<String>,
+    /// Authentication method for this hop (agent or key file)
+    #[serde(default = "default_jump_auth")]
+    pub auth: ApiAuthMethod,
+    /// Known hosts file used to verify this hop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_hosts_file: Option<String>,
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+fn default_jump_auth() -> ApiAuthMethod {
+    ApiAuthMethod::Agent
+}
+
+/// API representation of a server a profile can connect to
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiRelayEndpoint {
+    /// Hostname or IP
+    #[schema(example = "relay2.example.com")]
+    pub host: String,
+    /// SSH port (default: 22)
+    #[serde(default = "default_ssh_port")]
+    #[schema(example = 22)]
+    pub port: u16,
+}
+
+/// API representation of how a profile picks among its servers
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiFailoverStrategy {
+    /// Primary host first, then fallbacks in order
+    #[default]
+    Ordered,
+    /// Next server in the list after every disconnect
+    RoundRobin,
+    /// Servers in order of measured TCP connect time
+    LowestLatency,
+}
+
 /// API representation of a profile
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ApiProfile {
@@ -43,6 +181,11 @@ pub struct ApiProfile {
     /// Profile name
     #[schema(example = "my-server")]
     pub name: String,
+    /// Template or profile this one inherits from; the other fields are the
+    /// resolved values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "device")]
+    pub extends: Option<String>,
     /// SSH host
     #[schema(example = "example.com")]
     pub host: String,
@@ -56,6 +199,15 @@ pub struct ApiProfile {
     pub auth: ApiAuthMethod,
     /// Tunnel specifications
     pub tunnels: Vec<ApiTunnelSpec>,
+    /// Jump hosts traversed in order before the SSH host
+    pub jump_hosts: Vec<ApiJumpHost>,
+    /// Alternate servers used when the host is unavailable
+    pub fallback_hosts: Vec<ApiRelayEndpoint>,
+    /// How the server is picked among host and fallbacks
+    pub failover_strategy: ApiFailoverStrategy,
+    /// Seconds the host must be reachable before leaving a fallback for it (0 = never)
+    #[schema(example = 300)]
+    pub fail_back_after_secs: u64,
 }
 
 /// Request to create a new profile
@@ -77,6 +229,18 @@ pub struct CreateProfileRequest {
     pub auth: Option<ApiAuthMethod>,
     /// Tunnel specifications
     pub tunnels: Vec<ApiTunnelSpec>,
+    /// Jump hosts traversed in order before the SSH host
+    #[serde(default)]
+    pub jump_hosts: Vec<ApiJumpHost>,
+    /// Alternate servers used when the host is unavailable
+    #[serde(default)]
+    pub fallback_hosts: Vec<ApiRelayEndpoint>,
+    /// How the server is picked among host and fallbacks (default: ordered)
+    #[serde(default)]
+    pub failover_strategy: ApiFailoverStrategy,
+    /// Seconds the host must be reachable before leaving a fallback for it (default: 0 = never)
+    #[serde(default)]
+    pub fail_back_after_secs: u64,
 }
 
 /// Request to update an existing profile
@@ -94,6 +258,70 @@ pub struct UpdateProfileRequest {
     pub auth: Option<ApiAuthMethod>,
     /// Tunnel specifications (replaces existing when provided)
     pub tunnels: Option<Vec<ApiTunnelSpec>>,
+    /// Jump hosts (replaces existing when provided; empty connects directly)
+    pub jump_hosts: Option<Vec<ApiJumpHost>>,
+    /// Fallback servers (replaces existing when provided)
+    pub fallback_hosts: Option<Vec<ApiRelayEndpoint>>,
+    /// How the server is picked among host and fallbacks
+    pub failover_strategy: Option<ApiFailoverStrategy>,
+    /// Seconds the host must be reachable before leaving a fallback for it (0 = never)
+    pub fail_back_after_secs: Option<u64>,
+}
+
+/// Request to create profiles from OpenSSH client configuration
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportSshConfigRequest {
+    /// ssh_config text; `Include` lines are skipped
+    #[schema(example = "Host web\n    HostName relay.example.com\n    RemoteForward 8080 localhost:3000\n")]
+    pub ssh_config: String,
+    /// Only import these hosts (default: every host with port forwards)
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// User for hosts without `User` (default: such hosts are rejected)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "tunnel")]
+    pub default_user: Option<String>,
+}
+
+/// Outcome of an ssh_config import
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiImportResult {
+    /// Profiles that were created
+    pub created: Vec<ApiProfile>,
+    /// Hosts left alone because a profile with their name exists
+    pub skipped: Vec<String>,
+    /// Options and blocks that were not carried over
+    pub warnings: Vec<String>,
+}
+
+/// How serious a profile diagnostic is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiSeverity {
+    /// The profile cannot be saved
+    Error,
+    /// Likely to fail when a session starts
+    Warning,
+}
+
+/// A problem found in a profile
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiDiagnostic {
+    pub severity: ApiSeverity,
+    /// Path of the field
+    #[schema(example = "tunnels[0].remote_port")]
+    pub field: String,
+    #[schema(example = "must not be 0")]
+    pub message: String,
+}
+
+/// Response to a profile that fails validation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiValidationError {
+    #[schema(example = "Invalid profile")]
+    pub error: String,
+    /// Everything found, errors first
+    pub details: Vec<ApiDiagnostic>,
 }
 
 /// Request to start a session.
@@ -111,15 +339,47 @@ pub struct StartSessionRequest {
     pub sshpass_path: Option<String>,
 }
 
+/// Request to sign in to the web UI
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    #[schema(example = "admin")]
+    pub username: String,
+    pub password: String,
+}
+
+/// What a caller may do
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiWebRole {
+    /// View profiles and sessions
+    ReadOnly,
+    /// Also change profiles and start and stop sessions
+    Operator,
+}
+
+/// The authenticated caller
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiIdentity {
+    /// Username or API token name
+    #[schema(example = "admin")]
+    pub name: String,
+    pub role: ApiWebRole,
+    /// Whether the server requires authentication
+    pub auth_enabled: bool,
+}
+
 /// API representation of session status
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ApiSessionStatus {
     Starting,
     Connected,
+    /// Connected, but a tunnel is failing its health check
+    Degraded,
     Reconnecting,
     Stopped,
     Failed,
+    TimedOut,
 }
 
 /// API representation of a session
@@ -137,16 +397,117 @@ pub struct ApiSession {
     pub pid: Option<u32>,
     /// Last error message
     pub last_error: Option<String>,
+    /// Per-tunnel runtime state
+    pub tunnels: Vec<ApiTunnelState>,
+    /// Server the session is connected or connecting to
+    pub active_endpoint: Option<ApiRelayEndpoint>,
+}
+
+/// API representation of a tunnel's runtime state
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiTunnelState {
+    /// The tunnel as configured in the profile
+    pub spec: ApiTunnelSpec,
+    /// Port the server bound for a tunnel configured with remote_port 0
+    #[schema(example = 41234)]
+    pub allocated_port: Option<u16>,
+    /// Latest health check result, once the tunnel has been probed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<ApiTunnelHealth>,
+}
+
+/// API representation of a tunnel's health check results
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiTunnelHealth {
+    /// Whether the latest probe succeeded
+    pub healthy: bool,
+    /// Failed probes in a row
+    pub consecutive_failures: u32,
+    pub last_checked: DateTime<Utc>,
+    /// Why the latest probe failed
+    pub last_error: Option<String>,
+}
+
+/// Kind of session history entry
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiHistoryKind {
+    Started,
+    Connected,
+    Disconnected,
+    SpawnFailed,
+    TimedOut,
+    Failed,
+    Stopped,
+}
+
+/// A recorded session lifecycle change
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiHistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Uuid,
+    pub kind: ApiHistoryKind,
+    /// Disconnect reason or error, when known
+    pub reason: Option<String>,
+}
+
+/// Uptime and outages of a profile over a window
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiHistoryReport {
+    pub profile_name: String,
+    /// Start of the window
+    pub from: DateTime<Utc>,
+    /// End of the window (now)
+    pub to: DateTime<Utc>,
+    /// Seconds a session for the profile was running, connected or not
+    pub active_secs: u64,
+    /// Seconds a session was connected
+    pub connected_secs: u64,
+    /// Connected share of the running time; null when nothing ran
+    #[schema(example = 99.5)]
+    pub uptime_percent: Option<f64>,
+    /// Connections that dropped, timed out or failed
+    pub outages: u32,
+    /// Mean seconds from a dropped connection to the next successful one
+    pub mean_time_to_reconnect_secs: Option<f64>,
+    /// Entries within the window, oldest first
+    pub entries: Vec<ApiHistoryEntry>,
 }
 
 // Conversion functions
+impl From<reverse_ssh_core::types::TunnelKind> for ApiTunnelKind {
+    fn from(k: reverse_ssh_core::types::TunnelKind) -> Self {
+        match k {
+            reverse_ssh_core::types::TunnelKind::Remote => Self::Remote,
+            reverse_ssh_core::types::TunnelKind::Local => Self::Local,
+            reverse_ssh_core::types::TunnelKind::Dynamic => Self::Dynamic,
+            reverse_ssh_core::types::TunnelKind::RemoteDynamic => Self::RemoteDynamic,
+        }
+    }
+}
+
+impl From<ApiTunnelKind> for reverse_ssh_core::types::TunnelKind {
+    fn from(k: ApiTunnelKind) -> Self {
+        match k {
+            ApiTunnelKind::Remote => Self::Remote,
+            ApiTunnelKind::Local => Self::Local,
+            ApiTunnelKind::Dynamic => Self::Dynamic,
+            ApiTunnelKind::RemoteDynamic => Self::RemoteDynamic,
+        }
+    }
+}
+
 impl From<reverse_ssh_core::types::TunnelSpec> for ApiTunnelSpec {
     fn from(t: reverse_ssh_core::types::TunnelSpec) -> Self {
         Self {
+            kind: t.kind.into(),
             remote_bind: t.remote_bind,
             remote_port: t.remote_port,
             local_host: t.local_host,
             local_port: t.local_port,
+            remote_socket: t.remote_socket,
+            local_socket: t.local_socket,
+            health_check: t.health_check.map(Into::into),
         }
     }
 }
@@ -154,10 +515,60 @@ impl From<reverse_ssh_core::types::TunnelSpec> for ApiTunnelSpec {
 impl From<ApiTunnelSpec> for reverse_ssh_core::types::TunnelSpec {
     fn from(t: ApiTunnelSpec) -> Self {
         Self {
+            kind: t.kind.into(),
             remote_bind: t.remote_bind,
             remote_port: t.remote_port,
             local_host: t.local_host,
             local_port: t.local_port,
+            remote_socket: t.remote_socket,
+            local_socket: t.local_socket,
+            health_check: t.health_check.map(Into::into),
+        }
+    }
+}
+
+impl From<reverse_ssh_core::types::HealthCheckKind> for ApiHealthCheckKind {
+    fn from(k: reverse_ssh_core::types::HealthCheckKind) -> Self {
+        match k {
+            reverse_ssh_core::types::HealthCheckKind::LocalTcp => Self::LocalTcp,
+            reverse_ssh_core::types::HealthCheckKind::RemoteTcp => Self::RemoteTcp,
+            reverse_ssh_core::types::HealthCheckKind::Http => Self::Http,
+        }
+    }
+}
+
+impl From<ApiHealthCheckKind> for reverse_ssh_core::types::HealthCheckKind {
+    fn from(k: ApiHealthCheckKind) -> Self {
+        match k {
+            ApiHealthCheckKind::LocalTcp => Self::LocalTcp,
+            ApiHealthCheckKind::RemoteTcp => Self::RemoteTcp,
+            ApiHealthCheckKind::Http => Self::Http,
+        }
+    }
+}
+
+impl From<reverse_ssh_core::types::HealthCheck> for ApiHealthCheck {
+    fn from(c: reverse_ssh_core::types::HealthCheck) -> Self {
+        Self {
+            kind: c.kind.into(),
+            interval_secs: c.interval_secs,
+            timeout_secs: c.timeout_secs,
+            reconnect_after: c.reconnect_after,
+            path: c.path,
+            expected_status: c.expected_status,
+        }
+    }
+}
+
+impl From<ApiHealthCheck> for reverse_ssh_core::types::HealthCheck {
+    fn from(c: ApiHealthCheck) -> Self {
+        Self {
+            kind: c.kind.into(),
+            interval_secs: c.interval_secs,
+            timeout_secs: c.timeout_secs,
+            reconnect_after: c.reconnect_after,
+            path: c.path,
+            expected_status: c.expected_status,
         }
     }
 }
@@ -182,16 +593,105 @@ impl From<ApiAuthMethod> for reverse_ssh_core::types::AuthMethod {
     }
 }
 
+impl From<reverse_ssh_core::types::JumpHost> for ApiJumpHost {
+    fn from(j: reverse_ssh_core::types::JumpHost) -> Self {
+        Self {
+            host: j.host,
+            port: j.port,
+            user: j.user,
+            auth: j.auth.into(),
+            known_hosts_file: j.known_hosts_file,
+        }
+    }
+}
+
+impl From<ApiJumpHost> for reverse_ssh_core::types::JumpHost {
+    fn from(j: ApiJumpHost) -> Self {
+        Self {
+            host: j.host,
+            port: j.port,
+            user: j.user,
+            auth: j.auth.into(),
+            known_hosts_file: j.known_hosts_file,
+        }
+    }
+}
+
+impl From<reverse_ssh_core::types::RelayEndpoint> for ApiRelayEndpoint {
+    fn from(e: reverse_ssh_core::types::RelayEndpoint) -> Self {
+        Self {
+            host: e.host,
+            port: e.port,
+        }
+    }
+}
+
+impl From<ApiRelayEndpoint> for reverse_ssh_core::types::RelayEndpoint {
+    fn from(e: ApiRelayEndpoint) -> Self {
+        Self {
+            host: e.host,
+            port: e.port,
+        }
+    }
+}
+
+impl From<reverse_ssh_core::types::FailoverStrategy> for ApiFailoverStrategy {
+    fn from(s: reverse_ssh_core::types::FailoverStrategy) -> Self {
+        match s {
+            reverse_ssh_core::types::FailoverStrategy::Ordered => Self::Ordered,
+            reverse_ssh_core::types::FailoverStrategy::RoundRobin => Self::RoundRobin,
+            reverse_ssh_core::types::FailoverStrategy::LowestLatency => Self::LowestLatency,
+        }
+    }
+}
+
+impl From<ApiFailoverStrategy> for reverse_ssh_core::types::FailoverStrategy {
+    fn from(s: ApiFailoverStrategy) -> Self {
+        match s {
+            ApiFailoverStrategy::Ordered => Self::Ordered,
+            ApiFailoverStrategy::RoundRobin => Self::RoundRobin,
+            ApiFailoverStrategy::LowestLatency => Self::LowestLatency,
+        }
+    }
+}
+
+impl From<reverse_ssh_core::types::Diagnostic> for ApiDiagnostic {
+    fn from(d: reverse_ssh_core::types::Diagnostic) -> Self {
+        Self {
+            severity: match d.severity {
+                reverse_ssh_core::types::Severity::Error => ApiSeverity::Error,
+                reverse_ssh_core::types::Severity::Warning => ApiSeverity::Warning,
+            },
+            field: d.field,
+            message: d.message,
+        }
+    }
+}
+
+impl From<reverse_ssh_core::config::WebRole> for ApiWebRole {
+    fn from(role: reverse_ssh_core::config::WebRole) -> Self {
+        match role {
+            reverse_ssh_core::config::WebRole::ReadOnly => Self::ReadOnly,
+            reverse_ssh_core::config::WebRole::Operator => Self::Operator,
+        }
+    }
+}
+
 impl From<reverse_ssh_core::types::Profile> for ApiProfile {
     fn from(p: reverse_ssh_core::types::Profile) -> Self {
         Self {
             id: p.id,
             name: p.name,
+            extends: p.extends,
             host: p.host,
             port: p.port,
             user: p.user,
             auth: p.auth.into(),
             tunnels: p.tunnels.into_iter().map(Into::into).collect(),
+            jump_hosts: p.jump_hosts.into_iter().map(Into::into).collect(),
+            fallback_hosts: p.fallback_hosts.into_iter().map(Into::into).collect(),
+            failover_strategy: p.failover_strategy.into(),
+            fail_back_after_secs: p.fail_back_after_secs,
         }
     }
 }
@@ -201,9 +701,11 @@ impl From<reverse_ssh_core::types::SessionStatus> for ApiSessionStatus {
         match s {
             reverse_ssh_core::types::SessionStatus::Starting => Self::Starting,
             reverse_ssh_core::types::SessionStatus::Connected => Self::Connected,
+            reverse_ssh_core::types::SessionStatus::Degraded => Self::Degraded,
             reverse_ssh_core::types::SessionStatus::Reconnecting => Self::Reconnecting,
             reverse_ssh_core::types::SessionStatus::Stopped => Self::Stopped,
             reverse_ssh_core::types::SessionStatus::Failed => Self::Failed,
+            reverse_ssh_core::types::SessionStatus::TimedOut => Self::TimedOut,
         }
     }
 }
@@ -217,6 +719,68 @@ impl From<reverse_ssh_core::types::Session> for ApiSession {
             started_at: s.started_at,
             pid: s.pid,
             last_error: s.last_error,
+            tunnels: s.tunnels.into_iter().map(Into::into).collect(),
+            active_endpoint: s.active_endpoint.map(Into::into),
+        }
+    }
+}
+
+impl From<reverse_ssh_core::types::TunnelState> for ApiTunnelState {
+    fn from(t: reverse_ssh_core::types::TunnelState) -> Self {
+        Self {
+            spec: t.spec.into(),
+            allocated_port: t.allocated_port,
+            health: t.health.map(Into::into),
+        }
+    }
+}
+
+impl From<reverse_ssh_core::types::TunnelHealth> for ApiTunnelHealth {
+    fn from(h: reverse_ssh_core::types::TunnelHealth) -> Self {
+        Self {
+            healthy: h.healthy,
+            consecutive_failures: h.consecutive_failures,
+            last_checked: h.last_checked,
+            last_error: h.last_error,
+        }
+    }
+}
+
+impl From<reverse_ssh_core::storage::HistoryKind> for ApiHistoryKind {
+    fn from(k: reverse_ssh_core::storage::HistoryKind) -> Self {
+        match k {
+            reverse_ssh_core::storage::HistoryKind::Started => Self::Started,
+            reverse_ssh_core::storage::HistoryKind::Connected => Self::Connected,
+            reverse_ssh_core::storage::HistoryKind::Disconnected => Self::Disconnected,
+            reverse_ssh_core::storage::HistoryKind::SpawnFailed => Self::SpawnFailed,
+            reverse_ssh_core::storage::HistoryKind::TimedOut => Self::TimedOut,
+            reverse_ssh_core::storage::HistoryKind::Failed => Self::Failed,
+            reverse_ssh_core::storage::HistoryKind::Stopped => Self::Stopped,
+        }
+    }
+}
+
+impl From<reverse_ssh_core::storage::HistoryReport> for ApiHistoryReport {
+    fn from(r: reverse_ssh_core::storage::HistoryReport) -> Self {
+        Self {
+            profile_name: r.profile_name,
+            from: r.from,
+            to: r.to,
+            active_secs: r.active_secs,
+            connected_secs: r.connected_secs,
+            uptime_percent: r.uptime_percent,
+            outages: r.outages,
+            mean_time_to_reconnect_secs: r.mean_time_to_reconnect_secs,
+            entries: r
+                .entries
+                .into_iter()
+                .map(|e| ApiHistoryEntry {
+                    timestamp: e.timestamp,
+                    session_id: e.session_id,
+                    kind: e.kind.into(),
+                    reason: e.reason,
+                })
+                .collect(),
         }
     }
 }
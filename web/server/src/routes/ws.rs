@@ -1,50 +1,129 @@
 use axum::{
-    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Query, State},
     response::IntoResponse,
 };
 use crate::state::AppState;
-use tokio::time::{self, Duration};
-use serde_json::json;
-use futures_util::{SinkExt, StreamExt};
+use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use reverse_ssh_core::config::load_profiles;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
 
-use super::types::ApiSession;
+use super::events::{EventFilter, EventQuery};
+use super::types::{ApiProfile, ApiSession};
+
+/// Messages a client may send
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// Replace the filter
+    Subscribe {
+        #[serde(flatten)]
+        filter: EventFilter,
+    },
+    /// Ask for a fresh snapshot
+    Snapshot,
+}
+
+type Sender = SplitSink<WebSocket, Message>;
 
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, query))
 }
 
-async fn handle_socket(socket: WebSocket, state: AppState) {
+/// Stream core events to the client
+///
+/// The client first gets a `snapshot` of the sessions and profiles it
+/// subscribed to, then every matching event as `{"type": "event", "event":
+/// ...}`. If the client falls behind and the broadcast channel drops events,
+/// it gets a `lagged` message with the number missed, followed by a new
+/// snapshot to resynchronise from.
+async fn handle_socket(socket: WebSocket, state: AppState, query: EventQuery) {
     let (mut sender, mut receiver) = socket.split();
-    let mut interval = time::interval(Duration::from_secs(2));
+    // Subscribe before taking the snapshot so nothing falls in between
+    let mut events = state.handle.subscribe();
+
+    let mut filter = match query.into_filter() {
+        Ok(filter) => filter,
+        Err(e) => {
+            let _ = send(&mut sender, json!({ "type": "error", "message": e })).await;
+            return;
+        }
+    };
+
+    if send_snapshot(&mut sender, &state, &filter).await.is_err() {
+        return;
+    }
 
     loop {
         tokio::select! {
-            _ = interval.tick() => {
-                let sessions_result = state.handle.status().await;
-                let msg = match sessions_result {
-                    Ok(sessions) => {
-                        let api_sessions: Vec<ApiSession> = sessions.into_iter().map(Into::into).collect();
-                        json!({ "type": "sessions_update", "data": api_sessions }).to_string()
+            received = events.recv() => {
+                let sent = match received {
+                    Ok(event) if filter.matches(&event) => {
+                        send(&mut sender, json!({ "type": "event", "event": event })).await
                     }
-                    Err(e) => {
-                        json!({ "type": "error", "message": e.to_string() }).to_string()
+                    Ok(_) => Ok(()),
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!("WebSocket client missed {} events", missed);
+                        match send(&mut sender, json!({ "type": "lagged", "missed": missed })).await {
+                            Ok(()) => send_snapshot(&mut sender, &state, &filter).await,
+                            Err(e) => Err(e),
+                        }
                     }
+                    Err(RecvError::Closed) => break,
                 };
-
-                if sender.send(Message::Text(msg.into())).await.is_err() {
+                if sent.is_err() {
                     break;
                 }
             }
-            // Keep the connection alive by reading (even if we ignore input)
-            // If the client closes, this returns None and we break
             msg = receiver.next() => {
-                if msg.is_none() {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+
+                let sent = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Subscribe { filter: new_filter }) => {
+                        filter = new_filter;
+                        send(&mut sender, json!({ "type": "subscribed", "filter": filter })).await
+                    }
+                    Ok(ClientMessage::Snapshot) => send_snapshot(&mut sender, &state, &filter).await,
+                    Err(e) => send(&mut sender, json!({ "type": "error", "message": format!("Invalid message: {}", e) })).await,
+                };
+                if sent.is_err() {
                     break;
                 }
             }
         }
     }
 }
+
+async fn send_snapshot(sender: &mut Sender, state: &AppState, filter: &EventFilter) -> Result<(), axum::Error> {
+    let sessions = match state.handle.status().await {
+        Ok(sessions) => sessions,
+        Err(e) => return send(sender, json!({ "type": "error", "message": e.to_string() })).await,
+    };
+    let sessions: Vec<ApiSession> = sessions
+        .into_iter()
+        .filter(|s| filter.matches_session(s))
+        .map(Into::into)
+        .collect();
+
+    let profiles: Vec<ApiProfile> = load_profiles()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| filter.matches_profile(&p.name))
+        .map(Into::into)
+        .collect();
+
+    send(sender, json!({ "type": "snapshot", "sessions": sessions, "profiles": profiles })).await
+}
+
+async fn send(sender: &mut Sender, message: Value) -> Result<(), axum::Error> {
+    sender.send(Message::Text(message.to_string().into())).await
+}
@@ -1,15 +1,33 @@
+use std::path::PathBuf;
 use std::sync::Arc;
+use reverse_ssh_core::config::{paths, WebConfig};
+use reverse_ssh_core::metrics::Metrics;
 use reverse_ssh_core::supervisor::SessionManagerHandle;
 
+use crate::auth::Auth;
+use crate::events::EventLog;
+
 #[derive(Clone)]
 pub struct AppState {
     pub handle: Arc<SessionManagerHandle>,
+    pub auth: Arc<Auth>,
+    pub web: Arc<WebConfig>,
+    pub events: Arc<EventLog>,
+    pub metrics: Arc<Metrics>,
+    /// Session history database
+    pub history_file: PathBuf,
 }
 
 impl AppState {
-    pub fn new(handle: SessionManagerHandle) -> Self {
+    /// Must be called from within a Tokio runtime
+    pub fn new(handle: SessionManagerHandle, web: WebConfig) -> Self {
         Self {
+            events: EventLog::spawn(handle.subscribe(), web.event_buffer_size),
+            metrics: Metrics::spawn(handle.subscribe()),
+            history_file: paths::history_file(),
             handle: Arc::new(handle),
+            auth: Arc::new(Auth::new(&web)),
+            web: Arc::new(web),
         }
     }
 }
@@ -167,7 +167,7 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             background: #854d0e;
             color: #fef08a;
         }
-        .status-stopped, .status-failed {
+        .status-stopped, .status-failed, .status-timed_out {
             background: #991b1b;
             color: #fecaca;
         }
@@ -328,6 +328,10 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                     <span id="wsStatusText">Disconnected</span>
                 </div>
                 <a href="/swagger-ui/" class="api-link">API Docs </a>
+                <div class="ws-status" id="userStatus" style="display:none;">
+                    <span id="userName"></span>
+                    <button class="btn btn-sm btn-secondary" onclick="logout()">Sign Out</button>
+                </div>
             </div>
         </div>
     </header>
@@ -363,6 +367,30 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
         </div>
     </div>
 
+    <!-- Login Modal -->
+    <div class="modal-overlay" id="loginModal">
+        <div class="modal">
+            <div class="modal-header">
+                <h3>Sign In</h3>
+            </div>
+            <form id="loginForm" onsubmit="handleLogin(event)">
+                <div class="modal-body">
+                    <div class="form-group">
+                        <label for="loginUsername">Username</label>
+                        <input type="text" id="loginUsername" required autocomplete="username">
+                    </div>
+                    <div class="form-group">
+                        <label for="loginPassword">Password</label>
+                        <input type="password" id="loginPassword" required autocomplete="current-password">
+                    </div>
+                </div>
+                <div class="modal-footer">
+                    <button type="submit" class="btn btn-success">Sign In</button>
+                </div>
+            </form>
+        </div>
+    </div>
+
     <!-- Add Profile Modal -->
     <div class="modal-overlay" id="addProfileModal">
         <div class="modal">
@@ -411,14 +439,42 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                         <input type="text" id="profileSshpassPath" placeholder="/usr/bin/sshpass or C:\\Program Files\\PuTTY\\plink.exe">
                         <small>Windows: Use PuTTY's plink.exe (accepts password directly). Linux/Mac: Use sshpass. Leave empty if already in PATH.</small>
                     </div>
+                    <div class="form-group">
+                        <label for="profileFallbacks">Fallback Servers</label>
+                        <input type="text" id="profileFallbacks" placeholder="relay2.example.com, relay3.example.com:2222">
+                        <small>Optional. Used when the host is unavailable, as host[:port] separated by commas.</small>
+                    </div>
+                    <div class="form-group">
+                        <label for="profileFailover">Failover Strategy</label>
+                        <select id="profileFailover">
+                            <option value="ordered">Ordered</option>
+                            <option value="round_robin">Round-robin</option>
+                            <option value="lowest_latency">Lowest latency</option>
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="profileFailBack">Fail Back After (seconds)</label>
+                        <input type="number" id="profileFailBack" value="0" min="0" placeholder="0 = never">
+                    </div>
+                    <div class="form-group">
+                        <label for="profileJumpHosts">Jump Hosts</label>
+                        <input type="text" id="profileJumpHosts" placeholder="ops@bastion.example.com, 10.0.0.5:2222">
+                        <small>Optional. Hops traversed in order before the SSH host, as [user@]host[:port] separated by commas.</small>
+                    </div>
                     <div class="form-group">
                         <label>Tunnels</label>
                         <div id="tunnelsEditor">
                             <div class="tunnel-row" style="display:flex; gap:8px; align-items:center; margin-bottom:8px;">
-                                <input type="text" class="tunnel-remote-bind" placeholder="localhost" value="localhost" style="width:110px;">
-                                <input type="number" class="tunnel-remote-port" placeholder="Remote" min="1" max="65535" style="width:110px;">
-                                <span>→</span>
-                                <input type="text" class="tunnel-local-host" placeholder="localhost" value="localhost" style="flex:1; min-width:120px;">
+                                <select class="tunnel-kind" onchange="updateTunnelRow(this.parentElement)" style="width:130px;" title="Forwarding direction">
+                                    <option value="remote">Remote (-R)</option>
+                                    <option value="local">Local (-L)</option>
+                                    <option value="dynamic">SOCKS (-D)</option>
+                                    <option value="remote_dynamic">Remote SOCKS (-R)</option>
+                                </select>
+                                <input type="text" class="tunnel-remote-bind" placeholder="localhost or /socket" title="Host, or an absolute Unix socket path" value="localhost" style="width:110px;">
+                                <input type="number" class="tunnel-remote-port" placeholder="Remote (0 = any)" min="0" max="65535" style="width:110px;">
+                                <span class="tunnel-arrow">→</span>
+                                <input type="text" class="tunnel-local-host" placeholder="localhost or /socket" title="Host, or an absolute Unix socket path" value="localhost" style="flex:1; min-width:120px;">
                                 <span>:</span>
                                 <input type="number" class="tunnel-local-port" placeholder="Local" min="1" max="65535" style="width:110px;">
                                 <button type="button" class="btn btn-sm btn-danger" onclick="removeTunnelRow(this, 'tunnelsEditor')">×</button>
@@ -485,6 +541,28 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                         <input type="text" id="editProfileSshpassPath" placeholder="/usr/bin/sshpass or C:\\Program Files\\PuTTY\\plink.exe">
                         <small>Windows: Use PuTTY's plink.exe (accepts password directly). Linux/Mac: Use sshpass. Leave empty if already in PATH.</small>
                     </div>
+                    <div class="form-group">
+                        <label for="editProfileFallbacks">Fallback Servers</label>
+                        <input type="text" id="editProfileFallbacks" placeholder="relay2.example.com, relay3.example.com:2222">
+                        <small>Optional. Used when the host is unavailable, as host[:port] separated by commas.</small>
+                    </div>
+                    <div class="form-group">
+                        <label for="editProfileFailover">Failover Strategy</label>
+                        <select id="editProfileFailover">
+                            <option value="ordered">Ordered</option>
+                            <option value="round_robin">Round-robin</option>
+                            <option value="lowest_latency">Lowest latency</option>
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="editProfileFailBack">Fail Back After (seconds)</label>
+                        <input type="number" id="editProfileFailBack" value="0" min="0" placeholder="0 = never">
+                    </div>
+                    <div class="form-group">
+                        <label for="editProfileJumpHosts">Jump Hosts</label>
+                        <input type="text" id="editProfileJumpHosts" placeholder="ops@bastion.example.com, 10.0.0.5:2222">
+                        <small>Optional. Hops traversed in order before the SSH host, as [user@]host[:port] separated by commas.</small>
+                    </div>
                     <div class="form-group">
                         <label>Tunnels</label>
                         <div id="editTunnelsEditor"></div>
@@ -505,18 +583,96 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
         const API_BASE = '';
         let ws = null;
         let profilesCache = [];
+        let authenticated = false;
 
         // Initialize
-        document.addEventListener('DOMContentLoaded', () => {
+        document.addEventListener('DOMContentLoaded', checkAuth);
+
+        // Find out who we are; the server answers 401 when a login is needed
+        async function checkAuth() {
+            try {
+                const response = await fetch(`${API_BASE}/api/auth/me`);
+                if (response.status === 401) {
+                    showLogin();
+                    return;
+                }
+                onAuthenticated(await response.json());
+            } catch (error) {
+                console.error('Failed to check authentication:', error);
+            }
+        }
+
+        function onAuthenticated(identity) {
+            authenticated = true;
+            document.getElementById('loginModal').classList.remove('active');
+            if (identity.auth_enabled) {
+                document.getElementById('userName').textContent = `${identity.name} (${identity.role === 'operator' ? 'operator' : 'read-only'})`;
+                document.getElementById('userStatus').style.display = 'flex';
+            }
             loadProfiles();
             loadSessions();
-            connectWebSocket();
-        });
+            if (!ws) {
+                connectWebSocket();
+            }
+        }
+
+        function showLogin() {
+            authenticated = false;
+            document.getElementById('userStatus').style.display = 'none';
+            document.getElementById('loginModal').classList.add('active');
+            document.getElementById('loginUsername').focus();
+        }
+
+        async function handleLogin(event) {
+            event.preventDefault();
+            try {
+                const response = await fetch(`${API_BASE}/api/auth/login`, {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({
+                        username: document.getElementById('loginUsername').value,
+                        password: document.getElementById('loginPassword').value,
+                    }),
+                });
+                const result = await response.json();
+                document.getElementById('loginPassword').value = '';
+                if (response.ok) {
+                    onAuthenticated(result);
+                } else {
+                    showToast(result.error || 'Sign in failed', 'error');
+                }
+            } catch (error) {
+                showToast('Sign in failed', 'error');
+            }
+        }
+
+        async function logout() {
+            await fetch(`${API_BASE}/api/auth/logout`, { method: 'POST' });
+            if (ws) {
+                ws.close();
+            }
+            showLogin();
+        }
+
+        // fetch for API calls: a 401 means the login expired
+        async function apiFetch(url, options) {
+            const response = await fetch(url, options);
+            if (response.status === 401) {
+                showLogin();
+            }
+            return response;
+        }
 
         // WebSocket connection
         function connectWebSocket() {
             const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-            ws = new WebSocket(`${protocol}//${window.location.host}/ws`);
+            // ssh output is not shown here, so leave it out of the stream
+            const events = [
+                'session_status_changed', 'session_connected', 'session_disconnected',
+                'session_reconnecting', 'session_failed', 'tunnel_port_allocated',
+                'session_endpoint_changed', 'profile_created', 'profile_updated', 'profile_deleted',
+            ];
+            ws = new WebSocket(`${protocol}//${window.location.host}/ws?events=${events.join(',')}`);
             
             ws.onopen = () => {
                 document.getElementById('wsStatus').classList.add('connected');
@@ -526,22 +682,48 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             ws.onclose = () => {
                 document.getElementById('wsStatus').classList.remove('connected');
                 document.getElementById('wsStatusText').textContent = 'Disconnected';
-                // Reconnect after 3 seconds
-                setTimeout(connectWebSocket, 3000);
+                ws = null;
+                // Reconnect after 3 seconds, unless signed out
+                setTimeout(() => {
+                    if (authenticated && !ws) {
+                        connectWebSocket();
+                    }
+                }, 3000);
             };
             
             ws.onmessage = (event) => {
                 const data = JSON.parse(event.data);
-                if (data.type === 'sessions_update') {
-                    renderSessions(data.data);
+                if (data.type === 'snapshot') {
+                    profilesCache = data.profiles;
+                    renderProfiles(data.profiles);
+                    renderSessions(data.sessions);
+                } else if (data.type === 'event') {
+                    if (data.event.type.startsWith('profile_')) {
+                        loadProfiles();
+                    } else {
+                        scheduleSessionsRefresh();
+                    }
                 }
             };
         }
 
+        // Events come in bursts (status change, connect, port allocation);
+        // reload the sessions once per burst
+        let sessionsRefreshTimer = null;
+        function scheduleSessionsRefresh() {
+            if (sessionsRefreshTimer) {
+                return;
+            }
+            sessionsRefreshTimer = setTimeout(() => {
+                sessionsRefreshTimer = null;
+                loadSessions();
+            }, 250);
+        }
+
         // Load profiles
         async function loadProfiles() {
             try {
-                const response = await fetch(`${API_BASE}/api/profiles`);
+                const response = await apiFetch(`${API_BASE}/api/profiles`);
                 const profiles = await response.json();
                 profilesCache = Array.isArray(profiles) ? profiles : [];
                 renderProfiles(profiles);
@@ -558,7 +740,7 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
         // Load sessions
         async function loadSessions() {
             try {
-                const response = await fetch(`${API_BASE}/api/sessions`);
+                const response = await apiFetch(`${API_BASE}/api/sessions`);
                 const sessions = await response.json();
                 renderSessions(sessions);
             } catch (error) {
@@ -585,6 +767,8 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                     <div class="profile-info">
                         <h3>${escapeHtml(profile.name)}</h3>
                         <p>${escapeHtml(profile.user)}@${escapeHtml(profile.host)}:${profile.port}</p>
+                        ${(profile.fallback_hosts || []).length ? `<p>fallbacks: ${escapeHtml(profile.fallback_hosts.map(formatEndpoint).join(', '))}</p>` : ''}
+                        ${(profile.jump_hosts || []).length ? `<p>via ${escapeHtml(profile.jump_hosts.map(formatJumpHost).join(' → '))}</p>` : ''}
                         <p>${profile.tunnels.length} tunnel(s)</p>
                     </div>
                     <div style="display: flex; gap: 8px;">
@@ -669,23 +853,47 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             row.className = 'tunnel-row';
             row.style.cssText = 'display:flex; gap:8px; align-items:center; margin-bottom:8px;';
 
-            const remoteBind = preset?.remote_bind ?? 'localhost';
-            const remotePort = preset?.remote_port ?? '';
-            const localHost = preset?.local_host ?? 'localhost';
-            const localPort = preset?.local_port ?? '';
+            const kind = preset?.kind ?? 'remote';
+            const remoteBind = preset?.remote_socket ?? preset?.remote_bind ?? 'localhost';
+            const remotePort = preset?.remote_port || '';
+            const localHost = preset?.local_socket ?? preset?.local_host ?? 'localhost';
+            const localPort = preset?.local_port || '';
 
             row.innerHTML = `
-                <input type="text" class="tunnel-remote-bind" placeholder="localhost" value="${escapeAttribute(String(remoteBind))}" style="width:110px;">
-                <input type="number" class="tunnel-remote-port" placeholder="Remote" min="1" max="65535" value="${escapeAttribute(String(remotePort))}" style="width:110px;">
-                <span>→</span>
-                <input type="text" class="tunnel-local-host" placeholder="localhost" value="${escapeAttribute(String(localHost))}" style="flex:1; min-width:120px;">
+                <select class="tunnel-kind" onchange="updateTunnelRow(this.parentElement)" style="width:130px;" title="Forwarding direction">
+                    <option value="remote">Remote (-R)</option>
+                    <option value="local">Local (-L)</option>
+                    <option value="dynamic">SOCKS (-D)</option>
+                    <option value="remote_dynamic">Remote SOCKS (-R)</option>
+                </select>
+                <input type="text" class="tunnel-remote-bind" placeholder="localhost or /socket" title="Host, or an absolute Unix socket path" value="${escapeAttribute(String(remoteBind))}" style="width:110px;">
+                <input type="number" class="tunnel-remote-port" placeholder="Remote (0 = any)" min="0" max="65535" value="${escapeAttribute(String(remotePort))}" style="width:110px;">
+                <span class="tunnel-arrow">→</span>
+                <input type="text" class="tunnel-local-host" placeholder="localhost or /socket" title="Host, or an absolute Unix socket path" value="${escapeAttribute(String(localHost))}" style="flex:1; min-width:120px;">
                 <span>:</span>
                 <input type="number" class="tunnel-local-port" placeholder="Local" min="1" max="65535" value="${escapeAttribute(String(localPort))}" style="width:110px;">
                 <button type="button" class="btn btn-sm btn-danger" onclick="removeTunnelRow(this, '${editorId}')">×</button>
             `;
+            row.querySelector('.tunnel-kind').value = kind;
+            updateTunnelRow(row);
             editor.appendChild(row);
         }
 
+        // Show only the endpoints a tunnel kind uses and point the arrow at the target
+        function updateTunnelRow(row) {
+            const kind = row.querySelector('.tunnel-kind').value;
+            const usesRemote = kind !== 'dynamic';
+            const usesLocal = kind !== 'remote_dynamic';
+            row.querySelector('.tunnel-remote-bind').style.display = usesRemote ? '' : 'none';
+            row.querySelector('.tunnel-remote-port').style.display = usesRemote ? '' : 'none';
+            row.querySelector('.tunnel-local-host').style.display = usesLocal ? '' : 'none';
+            row.querySelector('.tunnel-local-port').style.display = usesLocal ? '' : 'none';
+            row.querySelectorAll('span').forEach(span => {
+                span.style.display = usesRemote && usesLocal ? '' : 'none';
+            });
+            row.querySelector('.tunnel-arrow').textContent = kind === 'local' ? '←' : '→';
+        }
+
         function resetTunnelsEditor(editorId) {
             const editor = document.getElementById(editorId);
             editor.innerHTML = '';
@@ -703,17 +911,30 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             const rows = document.querySelectorAll(`#${editorId} .tunnel-row`);
             const tunnels = [];
             for (const row of rows) {
+                const kind = row.querySelector('.tunnel-kind').value;
                 const remoteBind = row.querySelector('.tunnel-remote-bind').value || 'localhost';
                 const remotePort = row.querySelector('.tunnel-remote-port').value;
                 const localHost = row.querySelector('.tunnel-local-host').value || 'localhost';
                 const localPort = row.querySelector('.tunnel-local-port').value;
-                if (remotePort && localPort) {
-                    tunnels.push({
-                        remote_bind: remoteBind,
-                        remote_port: parseInt(remotePort),
-                        local_host: localHost,
-                        local_port: parseInt(localPort),
-                    });
+                // A path in the host field makes that side a Unix socket
+                const remoteSocket = remoteBind.startsWith('/') ? remoteBind : null;
+                const localSocket = localHost.startsWith('/') ? localHost : null;
+                const remoteReady = remotePort || remoteSocket;
+                const localReady = localPort || localSocket;
+                const complete = kind === 'dynamic' ? localPort
+                    : kind === 'remote_dynamic' ? remotePort
+                    : remoteReady && localReady;
+                if (complete) {
+                    const tunnel = {
+                        kind,
+                        remote_bind: remoteSocket ? 'localhost' : remoteBind,
+                        remote_port: parseInt(remotePort) || 0,
+                        local_host: localSocket ? 'localhost' : localHost,
+                        local_port: parseInt(localPort) || 0,
+                    };
+                    if (remoteSocket) tunnel.remote_socket = remoteSocket;
+                    if (localSocket) tunnel.local_socket = localSocket;
+                    tunnels.push(tunnel);
                 }
             }
             return tunnels;
@@ -727,6 +948,78 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             return { type: 'key_file', path: keyPath };
         }
 
+        // Format a server as host:port
+        function formatEndpoint(endpoint) {
+            const host = endpoint.host.includes(':') ? `[${endpoint.host}]` : endpoint.host;
+            return `${host}:${endpoint.port}`;
+        }
+
+        // Parse the fallback servers input (host[:port] separated by commas)
+        function parseFallbacks(inputId) {
+            const text = document.getElementById(inputId).value;
+            const endpoints = [];
+            for (const entry of text.split(/[\s,]+/).filter(Boolean)) {
+                let host = entry;
+                let port = 22;
+                const bracketed = entry.match(/^\[(.+)\](?::(\d+))?$/);
+                if (bracketed) {
+                    host = bracketed[1];
+                    if (bracketed[2]) port = parseInt(bracketed[2]);
+                } else if (entry.split(':').length === 2) {
+                    [host, port] = [entry.split(':')[0], parseInt(entry.split(':')[1])];
+                }
+                if (!host || !Number.isInteger(port) || port < 1 || port > 65535) {
+                    return null;
+                }
+                endpoints.push({ host, port });
+            }
+            return endpoints;
+        }
+
+        // Format a jump host as [user@]host[:port]
+        function formatJumpHost(hop) {
+            const host = hop.host.includes(':') ? `[${hop.host}]` : hop.host;
+            const user = hop.user ? `${hop.user}@` : '';
+            const port = hop.port && hop.port !== 22 ? `:${hop.port}` : '';
+            return `${user}${host}${port}`;
+        }
+
+        // Parse the jump hosts input. Hops whose destination is unchanged keep
+        // the key and known_hosts settings they were configured with.
+        function parseJumpHosts(inputId, existing = []) {
+            const text = document.getElementById(inputId).value;
+            const hops = [];
+            for (const entry of text.split(/[\s,]+/).filter(Boolean)) {
+                const previous = existing.find(h => formatJumpHost(h) === entry);
+                if (previous) {
+                    hops.push(previous);
+                    continue;
+                }
+
+                const at = entry.lastIndexOf('@');
+                const user = at >= 0 ? entry.slice(0, at) : null;
+                let rest = at >= 0 ? entry.slice(at + 1) : entry;
+                let port = 22;
+                const bracketed = rest.match(/^\[(.+)\](?::(\d+))?$/);
+                if (bracketed) {
+                    rest = bracketed[1];
+                    if (bracketed[2]) port = parseInt(bracketed[2]);
+                } else if (rest.split(':').length === 2) {
+                    const [host, p] = rest.split(':');
+                    rest = host;
+                    port = parseInt(p);
+                }
+                if (!rest || !Number.isInteger(port) || port < 1 || port > 65535) {
+                    return null;
+                }
+
+                const hop = { host: rest, port, auth: { type: 'agent' } };
+                if (user) hop.user = user;
+                hops.push(hop);
+            }
+            return hops;
+        }
+
         // Render sessions list
         function renderSessions(sessions) {
             const list = document.getElementById('sessionsList');
@@ -746,8 +1039,12 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                     <div class="session-info">
                         <h3>${escapeHtml(session.profile_name)}</h3>
                         <p>ID: ${session.id.substring(0, 8)}...</p>
+                        ${session.active_endpoint ? `<p>Server: ${escapeHtml(formatEndpoint(session.active_endpoint))}</p>` : ''}
                         <p>Started: ${new Date(session.started_at).toLocaleString()}</p>
                         ${session.pid ? `<p>PID: ${session.pid}</p>` : ''}
+                        ${(session.tunnels || []).filter(t => t.allocated_port).map(t =>
+                            `<p>Allocated port: ${t.allocated_port} → ${escapeHtml(t.spec.local_socket || `${t.spec.local_host}:${t.spec.local_port}`)}</p>`
+                        ).join('')}
                         ${session.last_error ? `<p style="color: #f87171;">Error: ${escapeHtml(session.last_error)}</p>` : ''}
                     </div>
                     <div style="display: flex; flex-direction: column; align-items: flex-end; gap: 8px;">
@@ -784,7 +1081,7 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                     }
                 }
 
-                const response = await fetch(`${API_BASE}/api/sessions/${encodeURIComponent(profileName)}/start`, {
+                const response = await apiFetch(`${API_BASE}/api/sessions/${encodeURIComponent(profileName)}/start`, {
                     method: 'POST',
                     headers: { 'Content-Type': 'application/json' },
                     body: JSON.stringify(body)
@@ -805,7 +1102,7 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
         // Stop session
         async function stopSession(sessionId) {
             try {
-                const response = await fetch(`${API_BASE}/api/sessions/${sessionId}/stop`, {
+                const response = await apiFetch(`${API_BASE}/api/sessions/${sessionId}/stop`, {
                     method: 'POST'
                 });
                 const result = await response.json();
@@ -828,7 +1125,7 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             }
             
             try {
-                const response = await fetch(`${API_BASE}/api/profiles/${encodeURIComponent(profileName)}`, {
+                const response = await apiFetch(`${API_BASE}/api/profiles/${encodeURIComponent(profileName)}`, {
                     method: 'DELETE'
                 });
                 
@@ -874,6 +1171,18 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                 }
             }
 
+            const jumpHosts = parseJumpHosts('profileJumpHosts');
+            if (jumpHosts === null) {
+                showToast('Jump hosts must look like [user@]host[:port]', 'error');
+                return;
+            }
+
+            const fallbackHosts = parseFallbacks('profileFallbacks');
+            if (fallbackHosts === null) {
+                showToast('Fallback servers must look like host[:port]', 'error');
+                return;
+            }
+
             const profile = {
                 name: document.getElementById('profileName').value,
                 host: document.getElementById('profileHost').value,
@@ -881,10 +1190,14 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                 port: parseInt(document.getElementById('profilePort').value) || 22,
                 auth: buildAuth('profileAuth', 'profileKeyPath'),
                 tunnels,
+                jump_hosts: jumpHosts,
+                fallback_hosts: fallbackHosts,
+                failover_strategy: document.getElementById('profileFailover').value,
+                fail_back_after_secs: parseInt(document.getElementById('profileFailBack').value) || 0,
             };
             
             try {
-                const response = await fetch(`${API_BASE}/api/profiles`, {
+                const response = await apiFetch(`${API_BASE}/api/profiles`, {
                     method: 'POST',
                     headers: { 'Content-Type': 'application/json' },
                     body: JSON.stringify(profile)
@@ -906,9 +1219,12 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             }
         }
 
+        // Jump hosts of the profile being edited, to keep per-hop settings
+        let editJumpHosts = [];
+
         async function showEditProfileModal(profileName) {
             try {
-                const response = await fetch(`${API_BASE}/api/profiles/${encodeURIComponent(profileName)}`);
+                const response = await apiFetch(`${API_BASE}/api/profiles/${encodeURIComponent(profileName)}`);
                 const profile = await response.json();
                 if (!response.ok) {
                     showToast(profile.error || 'Failed to load profile', 'error');
@@ -929,6 +1245,15 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                 document.getElementById('editProfilePassword').value = authType === 'password' ? loadStoredPassword(profileName) : '';
                 document.getElementById('editProfileSshpassPath').value = authType === 'password' ? loadStoredSshpassPath(profileName) : '';
 
+                // jump hosts
+                editJumpHosts = profile.jump_hosts || [];
+                document.getElementById('editProfileJumpHosts').value = editJumpHosts.map(formatJumpHost).join(', ');
+
+                // fallback servers
+                document.getElementById('editProfileFallbacks').value = (profile.fallback_hosts || []).map(formatEndpoint).join(', ');
+                document.getElementById('editProfileFailover').value = profile.failover_strategy || 'ordered';
+                document.getElementById('editProfileFailBack').value = profile.fail_back_after_secs || 0;
+
                 // tunnels
                 const editor = document.getElementById('editTunnelsEditor');
                 editor.innerHTML = '';
@@ -953,6 +1278,18 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             event.preventDefault();
             const existingName = document.getElementById('editExistingName').value;
 
+            const jumpHosts = parseJumpHosts('editProfileJumpHosts', editJumpHosts);
+            if (jumpHosts === null) {
+                showToast('Jump hosts must look like [user@]host[:port]', 'error');
+                return;
+            }
+
+            const fallbackHosts = parseFallbacks('editProfileFallbacks');
+            if (fallbackHosts === null) {
+                showToast('Fallback servers must look like host[:port]', 'error');
+                return;
+            }
+
             const tunnels = readTunnels('editTunnelsEditor');
             if (tunnels.length === 0) {
                 showToast('Please add at least one valid tunnel', 'error');
@@ -1003,10 +1340,14 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
                 port: parseInt(document.getElementById('editProfilePort').value) || 22,
                 auth: buildAuth('editProfileAuth', 'editProfileKeyPath'),
                 tunnels,
+                jump_hosts: jumpHosts,
+                fallback_hosts: fallbackHosts,
+                failover_strategy: document.getElementById('editProfileFailover').value,
+                fail_back_after_secs: parseInt(document.getElementById('editProfileFailBack').value) || 0,
             };
 
             try {
-                const response = await fetch(`${API_BASE}/api/profiles/${encodeURIComponent(existingName)}`, {
+                const response = await apiFetch(`${API_BASE}/api/profiles/${encodeURIComponent(existingName)}`, {
                     method: 'PUT',
                     headers: { 'Content-Type': 'application/json' },
                     body: JSON.stringify(payload),
@@ -1032,6 +1373,10 @@ const INDEX_HTML: &str = r##"<!DOCTYPE html>
             document.getElementById('profileKeyPath').value = '';
             document.getElementById('profilePassword').value = '';
             document.getElementById('profileSshpassPath').value = '';
+            document.getElementById('profileJumpHosts').value = '';
+            document.getElementById('profileFallbacks').value = '';
+            document.getElementById('profileFailover').value = 'ordered';
+            document.getElementById('profileFailBack').value = '0';
             toggleAuthFields('profileAuth', 'profileKeyPathGroup', 'profilePasswordGroup', 'profileSshpassPathGroup');
             document.getElementById('addProfileModal').classList.add('active');
         }
@@ -0,0 +1,136 @@
+//! HTTPS for the web server.
+//!
+//! The certificate comes from `WebConfig::tls_cert_path`/`tls_key_path`, or,
+//! with `tls_self_signed`, from a certificate generated once and kept under
+//! `paths::tls_dir()`. A plain HTTP listener can redirect browsers to HTTPS.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Redirect},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use reverse_ssh_core::config::{paths, WebConfig};
+
+/// Why HTTPS could not be set up
+#[derive(Debug)]
+pub enum TlsError {
+    /// Only one of `tls_cert_path` and `tls_key_path` is set
+    IncompletePaths,
+    Generate(rcgen::Error),
+    Io(PathBuf, std::io::Error),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::IncompletePaths => write!(f, "tls_cert_path and tls_key_path must be set together"),
+            TlsError::Generate(e) => write!(f, "Failed to generate self-signed certificate: {}", e),
+            TlsError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for TlsError {}
+
+/// The certificate and key to serve, generating the self-signed pair if needed
+///
+/// Returns `None` when TLS is not configured. `host` is added to the names a
+/// newly generated certificate is valid for.
+pub fn certificate_files(web: &WebConfig, host: &str) -> Result<Option<(PathBuf, PathBuf)>, TlsError> {
+    match (&web.tls_cert_path, &web.tls_key_path) {
+        (Some(cert), Some(key)) => Ok(Some((cert.clone(), key.clone()))),
+        (None, None) if web.tls_self_signed => {
+            let cert = paths::self_signed_cert_file();
+            let key = paths::self_signed_key_file();
+            if !cert.is_file() || !key.is_file() {
+                generate_self_signed(&cert, &key, host)?;
+                tracing::info!("Generated self-signed certificate {}", cert.display());
+            }
+            Ok(Some((cert, key)))
+        }
+        (None, None) => Ok(None),
+        _ => Err(TlsError::IncompletePaths),
+    }
+}
+
+/// Load a PEM certificate chain and private key for serving
+pub async fn load_rustls_config(cert: &Path, key: &Path) -> Result<RustlsConfig, TlsError> {
+    RustlsConfig::from_pem_file(cert, key)
+        .await
+        .map_err(|e| TlsError::Io(cert.to_path_buf(), e))
+}
+
+/// Write a self-signed certificate for `localhost`, the loopback addresses and `host`
+pub fn generate_self_signed(cert_path: &Path, key_path: &Path, host: &str) -> Result<(), TlsError> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    // A wildcard bind address is not a name clients connect to
+    if !host.is_empty() && host != "0.0.0.0" && host != "::" && !names.iter().any(|n| n == host) {
+        names.push(host.to_string());
+    }
+
+    let certified = rcgen::generate_simple_self_signed(names).map_err(TlsError::Generate)?;
+    write_file(cert_path, certified.cert.pem().as_bytes(), false)?;
+    write_file(key_path, certified.key_pair.serialize_pem().as_bytes(), true)
+}
+
+fn write_file(path: &Path, contents: &[u8], private: bool) -> Result<(), TlsError> {
+    let io_error = |e| TlsError::Io(path.to_path_buf(), e);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(if private { 0o600 } else { 0o644 })
+            .open(path)
+            .map_err(io_error)?;
+        file.write_all(contents).map_err(io_error)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = private;
+        std::fs::write(path, contents).map_err(io_error)
+    }
+}
+
+/// Router answering every request with a permanent redirect to HTTPS on `https_port`
+pub fn redirect_routes(https_port: u16) -> Router {
+    Router::new().fallback(redirect_to_https).with_state(https_port)
+}
+
+async fn redirect_to_https(State(https_port): State<u16>, headers: HeaderMap, uri: Uri) -> impl IntoResponse {
+    let Some(host) = headers.get(header::HOST).and_then(|h| h.to_str().ok()) else {
+        return (StatusCode::BAD_REQUEST, "Missing Host header").into_response();
+    };
+
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let target = if https_port == 443 {
+        format!("https://{}{}", strip_port(host), path)
+    } else {
+        format!("https://{}:{}{}", strip_port(host), https_port, path)
+    };
+
+    Redirect::permanent(&target).into_response()
+}
+
+/// `host` without a trailing `:port`, keeping IPv6 brackets
+fn strip_port(host: &str) -> &str {
+    match host.rfind(':') {
+        Some(colon) if !host[colon..].contains(']') => &host[..colon],
+        _ => host,
+    }
+}
@@ -1,14 +1,23 @@
 use axum_test::TestServer;
-use reverse_ssh_web_server::{routes, state};
+use reverse_ssh_web_server::{auth, events::EventLog, routes, state, tls};
 use reverse_ssh_core::{
-    config::init_config,
+    config::{init_config, ApiToken, WebConfig, WebRole, WebUser},
+    storage::{HistoryEntry, HistoryKind, HistoryStore},
     supervisor::SessionManager,
+    types::Event,
 };
-use serde_json::json;
+use serde_json::{json, Value};
 
 async fn create_test_state() -> state::AppState {
+    create_test_state_with(WebConfig::default()).await
+}
+
+async fn create_test_state_with(web: WebConfig) -> state::AppState {
     let config = init_config().expect("Failed to init config");
     let (mut manager, handle) = SessionManager::new(config);
+    // Don't auto-start or record the user's sessions from tests
+    manager.set_persist_state(false);
+    manager.set_record_sessions(false);
     manager.init().await.expect("Failed to init manager");
     
     // Run manager in background
@@ -16,7 +25,23 @@ async fn create_test_state() -> state::AppState {
         let _ = manager.run().await;
     });
     
-    state::AppState::new(handle)
+    state::AppState::new(handle, web)
+}
+
+/// A config with a read-only token, an operator token and an operator login
+fn auth_config() -> WebConfig {
+    WebConfig {
+        api_tokens: vec![
+            ApiToken { name: "viewer".to_string(), sha256: auth::hash_token("read-token"), role: WebRole::ReadOnly },
+            ApiToken { name: "deployer".to_string(), sha256: auth::hash_token("operator-token"), role: WebRole::Operator },
+        ],
+        users: vec![WebUser {
+            username: "admin".to_string(),
+            password_hash: auth::hash_password("hunter2").unwrap(),
+            role: WebRole::Operator,
+        }],
+        ..WebConfig::default()
+    }
 }
 
 #[tokio::test]
@@ -49,3 +74,410 @@ async fn test_swagger_ui() {
     let response = server.get("/swagger-ui/").await;
     response.assert_status_ok();
 }
+
+#[tokio::test]
+async fn test_api_tokens_and_roles() {
+    let state = create_test_state_with(auth_config()).await;
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+
+    server.get("/health").await.assert_status_ok();
+    server.get("/api/profiles").await.assert_status_unauthorized();
+    server.get("/api/profiles").authorization_bearer("wrong-token").await.assert_status_unauthorized();
+    server.get("/api/profiles").authorization_bearer("read-token").await.assert_status_ok();
+
+    let response = server
+        .post("/api/sessions/no-such-profile/start")
+        .authorization_bearer("read-token")
+        .json(&json!({}))
+        .await;
+    response.assert_status_forbidden();
+
+    // The operator gets past the role check to the handler itself
+    let response = server
+        .post("/api/sessions/no-such-profile/start")
+        .authorization_bearer("operator-token")
+        .json(&json!({}))
+        .await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_login_session_cookie() {
+    let state = create_test_state_with(auth_config()).await;
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+
+    server
+        .post("/api/auth/login")
+        .json(&json!({ "username": "admin", "password": "wrong" }))
+        .await
+        .assert_status_unauthorized();
+
+    let response = server
+        .post("/api/auth/login")
+        .json(&json!({ "username": "admin", "password": "hunter2" }))
+        .await;
+    response.assert_status_ok();
+    let cookie = response.cookie(auth::SESSION_COOKIE);
+    assert!(cookie.http_only().unwrap_or(false));
+    let cookie = format!("{}={}", cookie.name(), cookie.value());
+
+    let response = server.get("/api/auth/me").add_header("cookie", cookie.as_str()).await;
+    response.assert_status_ok();
+    response.assert_json(&json!({ "name": "admin", "role": "operator", "auth_enabled": true }));
+    server.get("/api/sessions").add_header("cookie", cookie.as_str()).await.assert_status_ok();
+
+    server.post("/api/auth/logout").add_header("cookie", cookie.as_str()).await.assert_status_ok();
+    server.get("/api/auth/me").add_header("cookie", cookie.as_str()).await.assert_status_unauthorized();
+}
+
+#[tokio::test]
+async fn test_cors_allow_list() {
+    let web = WebConfig {
+        cors_enabled: true,
+        cors_allowed_origins: vec!["https://dashboard.example.com".to_string()],
+        ..WebConfig::default()
+    };
+    let state = create_test_state_with(web).await;
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+
+    let response = server.get("/health").add_header("origin", "https://dashboard.example.com").await;
+    assert_eq!(response.header("access-control-allow-origin"), "https://dashboard.example.com");
+
+    let response = server.get("/health").add_header("origin", "https://evil.example.com").await;
+    assert!(response.maybe_header("access-control-allow-origin").is_none());
+}
+
+#[tokio::test]
+async fn test_http_redirects_to_https() {
+    let server = TestServer::new(tls::redirect_routes(8443)).unwrap();
+
+    let response = server
+        .get("/api/profiles?name=web")
+        .add_header("host", "rssh.example.com:8080")
+        .await;
+    response.assert_status(axum::http::StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.header("location"), "https://rssh.example.com:8443/api/profiles?name=web");
+}
+
+#[tokio::test]
+async fn test_self_signed_certificate() {
+    let dir = std::env::temp_dir().join(format!("rssh-tls-{}", uuid::Uuid::new_v4()));
+    let cert = dir.join("cert.pem");
+    let key = dir.join("key.pem");
+
+    tls::generate_self_signed(&cert, &key, "rssh.example.com").unwrap();
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    tls::load_rustls_config(&cert, &key).await.unwrap();
+
+    let web = WebConfig { tls_cert_path: Some(cert), ..WebConfig::default() };
+    assert!(tls::certificate_files(&web, "127.0.0.1").is_err());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_websocket_streams_filtered_events() {
+    let state = create_test_state().await;
+    let handle = state.handle.clone();
+    let server = TestServer::builder()
+        .http_transport()
+        .build(routes::create_routes(state))
+        .unwrap();
+
+    let mut socket = server
+        .get_websocket("/ws?profiles=ws-test&events=profile_created,profile_deleted")
+        .await
+        .into_websocket()
+        .await;
+
+    let snapshot: Value = socket.receive_json().await;
+    assert_eq!(snapshot["type"], "snapshot");
+    assert!(snapshot["sessions"].as_array().unwrap().is_empty());
+
+    // Filtered out by profile, then by event type
+    handle.emit(Event::profile_created(uuid::Uuid::new_v4(), "other"));
+    handle.emit(Event::profile_updated(uuid::Uuid::new_v4(), "ws-test"));
+    handle.emit(Event::profile_created(uuid::Uuid::new_v4(), "ws-test"));
+
+    let message: Value = socket.receive_json().await;
+    assert_eq!(message["type"], "event");
+    assert_eq!(message["event"]["type"], "profile_created");
+    assert_eq!(message["event"]["profile_name"], "ws-test");
+
+    socket.send_json(&json!({ "type": "subscribe", "events": ["profile_updated"] })).await;
+    let message: Value = socket.receive_json().await;
+    assert_eq!(message["type"], "subscribed");
+    assert_eq!(message["filter"]["events"], json!(["profile_updated"]));
+
+    handle.emit(Event::profile_updated(uuid::Uuid::new_v4(), "elsewhere"));
+    let message: Value = socket.receive_json().await;
+    assert_eq!(message["event"]["profile_name"], "elsewhere");
+}
+
+#[tokio::test]
+async fn test_websocket_reports_lag() {
+    let state = create_test_state().await;
+    let handle = state.handle.clone();
+    let server = TestServer::builder()
+        .http_transport()
+        .build(routes::create_routes(state))
+        .unwrap();
+
+    let mut socket = server.get_websocket("/ws?events=profile_updated").await.into_websocket().await;
+    let snapshot: Value = socket.receive_json().await;
+    assert_eq!(snapshot["type"], "snapshot");
+
+    // Without yielding, the socket task cannot keep up with the channel
+    for index in 0..500 {
+        handle.emit(Event::profile_updated(uuid::Uuid::new_v4(), format!("profile {}", index)));
+    }
+
+    let lagged = loop {
+        let message: Value = socket.receive_json().await;
+        if message["type"] == "lagged" {
+            break message;
+        }
+    };
+    assert!(lagged["missed"].as_u64().unwrap() > 0);
+
+    let message: Value = socket.receive_json().await;
+    assert_eq!(message["type"], "snapshot");
+}
+
+#[test]
+fn test_event_log_ring_buffer() {
+    let log = EventLog::new(3);
+    assert!(log.since(0).events.is_empty());
+
+    for index in 0..5 {
+        log.push(Event::profile_updated(uuid::Uuid::new_v4(), format!("profile {}", index)));
+    }
+    assert_eq!(log.latest_id(), 5);
+
+    // Events 1 and 2 were dropped to make room
+    let backlog = log.since(0);
+    assert_eq!(backlog.missed, 2);
+    assert_eq!(backlog.events.iter().map(|e| e.id).collect::<Vec<_>>(), [3, 4, 5]);
+
+    let backlog = log.since(3);
+    assert_eq!(backlog.missed, 0);
+    assert_eq!(backlog.events.iter().map(|e| e.id).collect::<Vec<_>>(), [4, 5]);
+    assert!(log.since(5).events.is_empty());
+}
+
+/// Read a streaming body until it contains `needle`
+async fn read_stream_until(body: &mut axum::body::Body, needle: &str) -> String {
+    use http_body_util::BodyExt;
+
+    let mut text = String::new();
+    while !text.contains(needle) {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame())
+            .await
+            .expect("timed out waiting for event stream")
+            .expect("event stream ended")
+            .unwrap();
+        if let Ok(data) = frame.into_data() {
+            text.push_str(&String::from_utf8_lossy(&data));
+        }
+    }
+    text
+}
+
+#[tokio::test]
+async fn test_sse_session_output_resumes_from_last_event_id() {
+    use tower::ServiceExt;
+
+    let state = create_test_state().await;
+    // Straight into the log: going through the manager would write session logs
+    let log = state.events.clone();
+    let app = routes::create_routes(state);
+
+    let session_id = uuid::Uuid::new_v4();
+    log.push(Event::session_connected(session_id, "sse-test"));
+    log.push(Event::session_output(uuid::Uuid::new_v4(), "other", "not mine", false));
+    log.push(Event::session_output(session_id, "sse-test", "first line", false));
+
+    // Resume after the connect event: only the session's output line is replayed
+    let request = axum::http::Request::get(format!("/api/sessions/{}/output", session_id))
+        .header("last-event-id", "1")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    let mut body = response.into_body();
+    let text = read_stream_until(&mut body, "first line").await;
+    assert!(text.contains("id: 3\nevent: session_output\n"));
+    assert!(!text.contains("not mine"));
+
+    // Then live events
+    log.push(Event::session_output(session_id, "sse-test", "second line", true));
+    read_stream_until(&mut body, "second line").await;
+
+    // The general stream filters by event type
+    let request = axum::http::Request::get("/api/events?events=session_connected")
+        .header("last-event-id", "0")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let mut body = app.oneshot(request).await.unwrap().into_body();
+    let text = read_stream_until(&mut body, "session_connected").await;
+    assert!(text.starts_with("id: 1\n"));
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let state = create_test_state_with(auth_config()).await;
+    let metrics = state.metrics.clone();
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+
+    // Scrapers authenticate like any other API client
+    server.get("/metrics").await.assert_status_unauthorized();
+
+    let session_id = uuid::Uuid::new_v4();
+    metrics.record(&Event::session_reconnecting(session_id, "metrics-test", 3, 5, std::time::Duration::from_secs(8)));
+    metrics.record(&Event::session_spawn_failed(session_id, "metrics-test", "ssh not found"));
+
+    let response = server.get("/metrics").authorization_bearer("read-token").await;
+    response.assert_status_ok();
+    assert!(response.header("content-type").to_str().unwrap().starts_with("text/plain; version=0.0.4"));
+
+    let body = response.text();
+    assert!(body.contains("# TYPE rssh_session_reconnects_total counter"));
+    assert!(body.contains("rssh_session_status{profile=\"metrics-test\",status=\"reconnecting\"} 1"));
+    assert!(body.contains("rssh_session_reconnects_total{profile=\"metrics-test\"} 1"));
+    assert!(body.contains("rssh_session_backoff_delay_seconds{profile=\"metrics-test\"} 8"));
+    assert!(body.contains("rssh_session_spawn_failures_total{profile=\"metrics-test\"} 1"));
+    assert!(body.contains("rssh_session_last_failure_timestamp_seconds{profile=\"metrics-test\"}"));
+    assert!(body.contains("rssh_event_bus_lagged_events_total 0"));
+}
+
+#[tokio::test]
+async fn test_profile_history() {
+    let mut state = create_test_state().await;
+    let dir = std::env::temp_dir().join(format!("rssh-history-{}", uuid::Uuid::new_v4()));
+    state.history_file = dir.join("history.db");
+
+    let store = HistoryStore::open(&state.history_file).unwrap();
+    let session_id = uuid::Uuid::new_v4();
+    let now = chrono::Utc::now();
+    for (minutes_ago, kind, reason) in [
+        (50, HistoryKind::Started, None),
+        (50, HistoryKind::Connected, None),
+        (30, HistoryKind::Disconnected, Some("Connection reset")),
+        (28, HistoryKind::Connected, None),
+    ] {
+        store
+            .insert(&HistoryEntry {
+                timestamp: now - chrono::Duration::minutes(minutes_ago),
+                session_id,
+                profile_name: "history-test".to_string(),
+                kind,
+                reason: reason.map(String::from),
+            })
+            .unwrap();
+    }
+
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+
+    let response = server.get("/api/profiles/history-test/history").add_query_param("window", "1h").await;
+    response.assert_status_ok();
+    let report: Value = response.json();
+    assert_eq!(report["outages"], 1);
+    assert_eq!(report["mean_time_to_reconnect_secs"], 120.0);
+    assert_eq!(report["entries"].as_array().unwrap().len(), 4);
+    assert_eq!(report["entries"][2]["kind"], "disconnected");
+    assert_eq!(report["entries"][2]["reason"], "Connection reset");
+    let uptime = report["uptime_percent"].as_f64().unwrap();
+    assert!(uptime > 95.0 && uptime < 97.0, "uptime {}", uptime);
+
+    // The disconnect falls just outside a 29 minute window
+    let report: Value = server
+        .get("/api/profiles/history-test/history")
+        .add_query_param("window", "29m")
+        .await
+        .json();
+    assert_eq!(report["outages"], 0);
+    assert_eq!(report["entries"].as_array().unwrap().len(), 1);
+
+    server
+        .get("/api/profiles/history-test/history")
+        .add_query_param("window", "soon")
+        .await
+        .assert_status_bad_request();
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_import_ssh_config_rejects_bad_input() {
+    let state = create_test_state_with(auth_config()).await;
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+    let ssh_config = "Host web\n    HostName relay.example.com\n    RemoteForward 8080 localhost:3000\n";
+
+    let response = server
+        .post("/api/profiles/import")
+        .authorization_bearer("read-token")
+        .json(&json!({ "ssh_config": ssh_config }))
+        .await;
+    response.assert_status_forbidden();
+
+    // Requests that fail validation never write profiles
+    let response = server
+        .post("/api/profiles/import")
+        .authorization_bearer("operator-token")
+        .json(&json!({ "ssh_config": ssh_config, "hosts": ["db"] }))
+        .await;
+    response.assert_status_bad_request();
+    assert!(response.json::<Value>()["error"].as_str().unwrap().contains("'db' is not in the ssh config"));
+
+    // The server's login name is never used for a host without User
+    let response = server
+        .post("/api/profiles/import")
+        .authorization_bearer("operator-token")
+        .json(&json!({ "ssh_config": ssh_config, "hosts": ["web"] }))
+        .await;
+    response.assert_status_bad_request();
+    assert!(response.json::<Value>()["error"].as_str().unwrap().contains("no User set"));
+
+    let response = server
+        .post("/api/profiles/import")
+        .authorization_bearer("operator-token")
+        .json(&json!({ "ssh_config": "Host web\n    RemoteForward 8080 nowhere\n" }))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_create_profile_rejects_invalid_profile() {
+    let state = create_test_state_with(auth_config()).await;
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+
+    // Rejected before anything is written
+    let response = server
+        .post("/api/profiles")
+        .authorization_bearer("operator-token")
+        .json(&json!({
+            "name": "rssh-test-invalid-profile",
+            "host": "https://relay.example.com",
+            "user": "tunnel",
+            "port": 0,
+            "tunnels": [{ "remote_bind": "localhost", "remote_port": 8080, "local_host": "localhost", "local_port": 0 }],
+        }))
+        .await;
+    response.assert_status_bad_request();
+
+    let body = response.json::<Value>();
+    assert_eq!(body["error"], "Invalid profile");
+    let fields: Vec<_> = body["details"].as_array().unwrap().iter().map(|d| d["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["host", "port", "tunnels[0].local_port"]);
+    assert_eq!(body["details"][0]["severity"], "error");
+}
+
+#[tokio::test]
+async fn test_raw_profile_not_found() {
+    let state = create_test_state().await;
+    let server = TestServer::new(routes::create_routes(state)).unwrap();
+
+    server.get("/api/profiles/no-such-profile/raw").await.assert_status_not_found();
+}